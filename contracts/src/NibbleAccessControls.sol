@@ -12,6 +12,13 @@ contract NibbleAccessControls is Initializable {
     event RoleGranted(address indexed agent, string role);
     event RoleRevoked(address indexed agent, string role);
 
+    modifier onlyAdmin() {
+        if (msg.sender != _admin) {
+            revert NibbleLibrary.OnlyAdmin();
+        }
+        _;
+    }
+
     modifier onlyNibbleFactory(address nibbleFactory) {
         if (msg.sender != nibbleFactory) {
             revert NibbleLibrary.InvalidInitializer();
@@ -31,6 +38,20 @@ contract NibbleAccessControls is Initializable {
         _humanWriters[admin] = true;
     }
 
+    function version() external pure returns (uint256) {
+        return 2;
+    }
+
+    function setAgentWriter(address agent, bool writer) external onlyAdmin {
+        _agentWriters[agent] = writer;
+
+        if (writer) {
+            emit RoleGranted(agent, "agentWriter");
+        } else {
+            emit RoleRevoked(agent, "agentWriter");
+        }
+    }
+
     function getHumanWriter(address writer) public view returns (bool) {
         return _humanWriters[writer];
     }
@@ -16,6 +16,7 @@ contract NibbleLibrary {
     error ConnectorsInitializationFailed();
     error FHEGatesInitializationFailed();
     error AgentsInitializationFailed();
+    error IdempotencyKeyUsed();
 
     struct Nibble {
         address storageContract;
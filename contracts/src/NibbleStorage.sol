@@ -21,10 +21,13 @@ contract NibbleStorage is Initializable {
     NibbleFHEGates public nibbleFHEGates;
     NibbleWorkflows public nibbleWorkflows;
 
+    mapping(bytes32 => bool) private _idempotencyKeys;
+
     event AdaptersModified(address writer);
     event AdaptersDeleted(address writer);
     event WorkflowModified(bytes workflowId, address writer);
     event WorkflowDeleted(bytes workflowId, address writer);
+    event IdempotencyKeyRecorded(bytes32 indexed idempotencyKey, address writer);
 
     modifier onlyNibbleFactory(address nibbleFactory) {
         if (msg.sender != nibbleFactory) {
@@ -43,6 +46,16 @@ contract NibbleStorage is Initializable {
         _;
     }
 
+    modifier recordsIdempotencyKey(bytes32 idempotencyKey) {
+        if (_idempotencyKeys[idempotencyKey]) {
+            revert NibbleLibrary.IdempotencyKeyUsed();
+        }
+        _idempotencyKeys[idempotencyKey] = true;
+
+        emit IdempotencyKeyRecorded(idempotencyKey, msg.sender);
+        _;
+    }
+
     function initialize(
         address nibbleFactoryAddress,
         address nibbleAccessControlsAddress,
@@ -72,9 +85,14 @@ contract NibbleStorage is Initializable {
     function addOrModifyWorkflow(
         NibbleLibrary.Workflow memory workflow
     ) external onlyWriter {
-        nibbleWorkflows.addOrModifyWorkflow(workflow);
+        _addOrModifyWorkflow(workflow);
+    }
 
-        emit WorkflowModified(workflow.id, msg.sender);
+    function addOrModifyWorkflowWithKey(
+        NibbleLibrary.Workflow memory workflow,
+        bytes32 idempotencyKey
+    ) external onlyWriter recordsIdempotencyKey(idempotencyKey) {
+        _addOrModifyWorkflow(workflow);
     }
 
     function removeWorkflow(bytes memory workflowId) external onlyWriter {
@@ -85,61 +103,88 @@ contract NibbleStorage is Initializable {
 
     function addOrModifyAdaptersBatch(
         NibbleLibrary.ModifyAdapters memory adapters
+    ) external onlyWriter {
+        _addOrModifyAdaptersBatch(adapters);
+    }
+
+    function addOrModifyAdaptersBatchWithKey(
+        NibbleLibrary.ModifyAdapters memory adapters,
+        bytes32 idempotencyKey
+    ) external onlyWriter recordsIdempotencyKey(idempotencyKey) {
+        _addOrModifyAdaptersBatch(adapters);
+    }
+
+    function removeAdaptersBatch(
+        NibbleLibrary.RemoveAdapters memory adapters
     ) external onlyWriter {
         if (adapters.conditions.length > 0) {
-            nibbleConditions.addOrModifyConditionsBatch(adapters.conditions);
+            nibbleConditions.removeConditionsBatch(adapters.conditions);
         }
 
         if (adapters.listeners.length > 0) {
-            nibbleListeners.addOrModifyListenersBatch(adapters.listeners);
+            nibbleListeners.removeListenersBatch(adapters.listeners);
         }
 
         if (adapters.connectors.length > 0) {
-            nibbleConnectors.addOrModifyConnectorsBatch(adapters.connectors);
+            nibbleConnectors.removeConnectorsBatch(adapters.connectors);
         }
 
         if (adapters.agents.length > 0) {
-            nibbleAgents.addOrModifyAgentsBatch(adapters.agents);
+            nibbleAgents.removeAgentsBatch(adapters.agents);
         }
 
         if (adapters.evaluations.length > 0) {
-            nibbleEvaluations.addOrModifyEvaluationsBatch(adapters.evaluations);
+            nibbleEvaluations.removeEvaluationsBatch(adapters.evaluations);
         }
 
         if (adapters.fheGates.length > 0) {
-            nibbleFHEGates.addOrModifyFHEGatesBatch(adapters.fheGates);
+            nibbleFHEGates.removeFHEGatesBatch(adapters.fheGates);
         }
 
-        emit AdaptersModified(msg.sender);
+        emit AdaptersDeleted(msg.sender);
     }
 
-    function removeAdaptersBatch(
-        NibbleLibrary.RemoveAdapters memory adapters
-    ) external onlyWriter {
+    function getIdempotencyKeyRecorded(
+        bytes32 idempotencyKey
+    ) public view returns (bool) {
+        return _idempotencyKeys[idempotencyKey];
+    }
+
+    function _addOrModifyWorkflow(
+        NibbleLibrary.Workflow memory workflow
+    ) internal {
+        nibbleWorkflows.addOrModifyWorkflow(workflow);
+
+        emit WorkflowModified(workflow.id, msg.sender);
+    }
+
+    function _addOrModifyAdaptersBatch(
+        NibbleLibrary.ModifyAdapters memory adapters
+    ) internal {
         if (adapters.conditions.length > 0) {
-            nibbleConditions.removeConditionsBatch(adapters.conditions);
+            nibbleConditions.addOrModifyConditionsBatch(adapters.conditions);
         }
 
         if (adapters.listeners.length > 0) {
-            nibbleListeners.removeListenersBatch(adapters.listeners);
+            nibbleListeners.addOrModifyListenersBatch(adapters.listeners);
         }
 
         if (adapters.connectors.length > 0) {
-            nibbleConnectors.removeConnectorsBatch(adapters.connectors);
+            nibbleConnectors.addOrModifyConnectorsBatch(adapters.connectors);
         }
 
         if (adapters.agents.length > 0) {
-            nibbleAgents.removeAgentsBatch(adapters.agents);
+            nibbleAgents.addOrModifyAgentsBatch(adapters.agents);
         }
 
         if (adapters.evaluations.length > 0) {
-            nibbleEvaluations.removeEvaluationsBatch(adapters.evaluations);
+            nibbleEvaluations.addOrModifyEvaluationsBatch(adapters.evaluations);
         }
 
         if (adapters.fheGates.length > 0) {
-            nibbleFHEGates.removeFHEGatesBatch(adapters.fheGates);
+            nibbleFHEGates.addOrModifyFHEGatesBatch(adapters.fheGates);
         }
 
-        emit AdaptersDeleted(msg.sender);
+        emit AdaptersModified(msg.sender);
     }
 }
@@ -1,13 +1,21 @@
 use crate::{
+    constants::{MULTICALL3_ADDRESS, MULTICALL3_AGGREGATE3},
     nibble::{Adaptable, Nibble},
-    utils::generate_unique_id,
+    utils::{generate_unique_id, token_to_value},
 };
 use ethers::{
-    abi::{AbiParser, Address, Token},
+    abi::{
+        token::{LenientTokenizer, Tokenizer},
+        Address, Function, HumanReadableParser, Token,
+    },
+    providers::Middleware,
     types::{TransactionRequest, H160},
 };
-use serde_json::{from_value, Map, Value};
-use std::{error::Error, str::FromStr};
+use reqwest::{Client, Method};
+use serde_json::{Map, Value};
+use std::{collections::HashMap, error::Error, str::FromStr, sync::Mutex, time::Duration};
+
+const OFFCHAIN_CONDITION_TIMEOUT: Duration = Duration::from_secs(10);
 
 #[derive(Debug, Clone)]
 pub enum LogicalOperator {
@@ -29,6 +37,55 @@ impl FromStr for LogicalOperator {
     }
 }
 
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum ComparisonOperator {
+    #[default]
+    Eq,
+    Gt,
+    Lt,
+    Contains,
+}
+
+impl FromStr for ComparisonOperator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "eq" => Ok(ComparisonOperator::Eq),
+            "gt" => Ok(ComparisonOperator::Gt),
+            "lt" => Ok(ComparisonOperator::Lt),
+            "contains" => Ok(ComparisonOperator::Contains),
+            _ => Err(format!("Invalid ComparisonOperator: {}", s)),
+        }
+    }
+}
+
+impl ComparisonOperator {
+    pub fn compare(&self, value: &Value, expected: &Value) -> bool {
+        match self {
+            ComparisonOperator::Eq => values_match(value, expected),
+            ComparisonOperator::Gt => match (value_as_f64(value), value_as_f64(expected)) {
+                (Some(value), Some(expected)) => value > expected,
+                _ => false,
+            },
+            ComparisonOperator::Lt => match (value_as_f64(value), value_as_f64(expected)) {
+                (Some(value), Some(expected)) => value < expected,
+                _ => false,
+            },
+            ComparisonOperator::Contains => match (value, expected) {
+                (Value::String(value), Value::String(expected)) => {
+                    value.contains(expected.as_str())
+                }
+                (Value::Array(values), expected) => {
+                    values.iter().any(|value| values_match(value, expected))
+                }
+                (Value::Object(map), Value::String(key)) => map.contains_key(key),
+                _ => false,
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Condition {
     pub name: String,
@@ -46,6 +103,9 @@ pub enum ConditionType {
     },
     OffChain {
         api_url: String,
+        method: Method,
+        json_pointer: Option<String>,
+        comparison: ComparisonOperator,
     },
     ContextBased,
     TimeBased {
@@ -59,6 +119,16 @@ pub enum ConditionType {
 }
 
 impl ConditionType {
+    pub fn is_external(&self) -> bool {
+        match self {
+            ConditionType::OnChain { .. } | ConditionType::OffChain { .. } => true,
+            ConditionType::Composite { sub_conditions, .. } => sub_conditions
+                .iter()
+                .any(|condition| condition.condition_type.is_external()),
+            _ => false,
+        }
+    }
+
     pub fn from_json(value: &serde_json::Value) -> Result<Self, String> {
         if let Some(on_chain) = value.get("OnChain") {
             let contract_address = on_chain
@@ -85,7 +155,28 @@ impl ConditionType {
                 .ok_or("Missing or invalid `api_url`")?
                 .to_string();
 
-            Ok(ConditionType::OffChain { api_url })
+            let method = match off_chain.get("method").and_then(|v| v.as_str()) {
+                Some(method) => Method::from_bytes(method.to_uppercase().as_bytes())
+                    .map_err(|_| format!("Invalid `method`: {}", method))?,
+                None => Method::GET,
+            };
+
+            let json_pointer = off_chain
+                .get("json_pointer")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string());
+
+            let comparison = match off_chain.get("comparison").and_then(|v| v.as_str()) {
+                Some(comparison) => comparison.parse::<ComparisonOperator>()?,
+                None => ComparisonOperator::default(),
+            };
+
+            Ok(ConditionType::OffChain {
+                api_url,
+                method,
+                json_pointer,
+                comparison,
+            })
         } else if let Some(time_based) = value.get("TimeBased") {
             let comparison_time = time_based
                 .get("comparison_time")
@@ -171,7 +262,247 @@ impl ConditionCheck {
     }
 }
 
+#[derive(Debug)]
+pub struct ConditionBatcher {
+    pub multicall_address: Address,
+    results: Mutex<HashMap<(Address, Vec<u8>), (Vec<u8>, usize)>>,
+}
+
+impl Default for ConditionBatcher {
+    fn default() -> Self {
+        Self::new(
+            MULTICALL3_ADDRESS
+                .parse()
+                .expect("MULTICALL3_ADDRESS is a valid address"),
+        )
+    }
+}
+
+impl ConditionBatcher {
+    pub fn new(multicall_address: Address) -> Self {
+        Self {
+            multicall_address,
+            results: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn prefetch(
+        &self,
+        nibble_context: &Nibble,
+        conditions: &[(&Condition, Option<&Value>)],
+    ) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        let mut calls: Vec<(Address, Vec<u8>)> = Vec::new();
+        let mut reads: HashMap<(Address, Vec<u8>), usize> = HashMap::new();
+        for (condition, params) in conditions {
+            if let ConditionType::OnChain {
+                contract_address,
+                function_signature,
+            } = &condition.condition_type
+            {
+                if let Ok((_, call_data)) = onchain_call(function_signature, *params) {
+                    let call = (*contract_address, call_data);
+                    let count = reads.entry(call.clone()).or_insert(0);
+                    if *count == 0 {
+                        calls.push(call);
+                    }
+                    *count += 1;
+                }
+            }
+        }
+        if calls.is_empty() {
+            return Ok(0);
+        }
+
+        let aggregate = HumanReadableParser::parse_function(MULTICALL3_AGGREGATE3)?;
+        let call_data = aggregate.encode_input(&[Token::Array(
+            calls
+                .iter()
+                .map(|(target, call_data)| {
+                    Token::Tuple(vec![
+                        Token::Address(*target),
+                        Token::Bool(true),
+                        Token::Bytes(call_data.clone()),
+                    ])
+                })
+                .collect(),
+        )])?;
+        let tx_request = TransactionRequest {
+            to: Some(self.multicall_address.into()),
+            data: Some(call_data.into()),
+            ..Default::default()
+        };
+
+        let output = nibble_context
+            .provider
+            .call(&tx_request.into(), None)
+            .await?;
+        let results = match aggregate.decode_output(&output)?.into_iter().next() {
+            Some(Token::Array(results)) => results,
+            _ => return Err("Unexpected Multicall3 response".into()),
+        };
+
+        let mut cached = self.results.lock().map_err(|e| e.to_string())?;
+        let mut resolved = 0;
+        for (call, result) in calls.into_iter().zip(results) {
+            if let Token::Tuple(fields) = result {
+                if let [Token::Bool(true), Token::Bytes(return_data)] = fields.as_slice() {
+                    let count = reads.get(&call).copied().unwrap_or(1);
+                    cached.insert(call, (return_data.clone(), count));
+                    resolved += 1;
+                }
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut results) = self.results.lock() {
+            results.clear();
+        }
+    }
+
+    fn take(&self, contract_address: &Address, call_data: &[u8]) -> Option<Vec<u8>> {
+        let mut results = self.results.lock().ok()?;
+        let call = (*contract_address, call_data.to_vec());
+        let (return_data, remaining) = results.get_mut(&call)?;
+        *remaining -= 1;
+        if *remaining > 0 {
+            return Some(return_data.clone());
+        }
+        results.remove(&call).map(|(return_data, _)| return_data)
+    }
+}
+
+fn onchain_call(
+    function_signature: &str,
+    params: Option<&Value>,
+) -> Result<(Function, Vec<u8>), Box<dyn Error + Send + Sync>> {
+    let func = HumanReadableParser::parse_function(function_signature)?;
+    let call_data = func.encode_input(&call_params(&func, params)?)?;
+    Ok((func, call_data))
+}
+
 impl ConditionCheck {
+    async fn check_onchain(
+        &self,
+        nibble_context: &Nibble,
+        contract_address: &Address,
+        function_signature: &str,
+        params: Option<&Value>,
+        batcher: Option<&ConditionBatcher>,
+    ) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        let (func, call_data) = onchain_call(function_signature, params)?;
+
+        let call_result =
+            match batcher.and_then(|batcher| batcher.take(contract_address, &call_data)) {
+                Some(return_data) => return_data,
+                None => {
+                    let tx_request = TransactionRequest {
+                        to: Some((*contract_address).into()),
+                        data: Some(call_data.into()),
+                        ..Default::default()
+                    };
+                    nibble_context
+                        .provider
+                        .call(&tx_request.into(), None)
+                        .await?
+                        .to_vec()
+                }
+            };
+        let mut outputs: Vec<Value> = func
+            .decode_output(&call_result)?
+            .into_iter()
+            .map(token_to_value)
+            .collect();
+        let result = if outputs.len() == 1 {
+            outputs.remove(0)
+        } else {
+            Value::Array(outputs)
+        };
+
+        match &self.expected_value {
+            Some(expected) => Ok(values_match(&result, expected)),
+            None => Ok((self.condition_fn)(result)),
+        }
+    }
+
+    async fn check_offchain(
+        &self,
+        api_url: &str,
+        method: &Method,
+        json_pointer: Option<&str>,
+        comparison: &ComparisonOperator,
+        params: Option<&Value>,
+    ) -> bool {
+        let mut url = api_url.to_string();
+        if let Some(map) = params.and_then(|params| params.as_object()) {
+            let query_string: Vec<String> = map
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value.as_str().unwrap_or("")))
+                .collect();
+            url = format!("{}?{}", api_url, query_string.join("&"));
+        }
+
+        let client = match Client::builder()
+            .timeout(OFFCHAIN_CONDITION_TIMEOUT)
+            .build()
+        {
+            Ok(client) => client,
+            Err(e) => {
+                eprintln!("OffChain condition could not build HTTP client: {}", e);
+                return false;
+            }
+        };
+
+        let response = match client.request(method.clone(), &url).send().await {
+            Ok(response) => response,
+            Err(e) => {
+                eprintln!("OffChain condition request to {} failed: {}", url, e);
+                return false;
+            }
+        };
+
+        if !response.status().is_success() {
+            eprintln!(
+                "OffChain condition request to {} returned status {}",
+                url,
+                response.status()
+            );
+            return false;
+        }
+
+        let json: Value = match response.json().await {
+            Ok(json) => json,
+            Err(e) => {
+                eprintln!(
+                    "OffChain condition response from {} is not JSON: {}",
+                    url, e
+                );
+                return false;
+            }
+        };
+
+        let value = match json_pointer {
+            Some(pointer) => match json.pointer(pointer) {
+                Some(value) => value.clone(),
+                None => {
+                    eprintln!(
+                        "OffChain condition field {} not found in response from {}",
+                        pointer, url
+                    );
+                    return false;
+                }
+            },
+            None => json,
+        };
+
+        match &self.expected_value {
+            Some(expected) => comparison.compare(&value, expected),
+            None => (self.condition_fn)(value),
+        }
+    }
+
     pub fn from_json(value: &Value) -> Result<Self, String> {
         let expected_value = value.get("expected_value").cloned();
 
@@ -221,6 +552,7 @@ impl Condition {
     pub fn to_json(&self) -> Map<String, Value> {
         let mut map = Map::new();
         map.insert("name".to_string(), Value::String(self.name.clone()));
+        map.insert("id".to_string(), Value::String(self.id.clone()));
         map.insert("public".to_string(), Value::Bool(self.encrypted));
 
         let condition_type_map = match &self.condition_type {
@@ -239,12 +571,28 @@ impl Condition {
                 );
                 Value::Object(sub_map)
             }
-            ConditionType::OffChain { api_url } => {
+            ConditionType::OffChain {
+                api_url,
+                method,
+                json_pointer,
+                comparison,
+            } => {
                 let mut sub_map = Map::new();
                 sub_map.insert("api_url".to_string(), Value::String(api_url.clone()));
+                sub_map.insert("method".to_string(), Value::String(method.to_string()));
+                if let Some(json_pointer) = json_pointer {
+                    sub_map.insert(
+                        "json_pointer".to_string(),
+                        Value::String(json_pointer.clone()),
+                    );
+                }
+                sub_map.insert(
+                    "comparison".to_string(),
+                    Value::String(format!("{:?}", comparison)),
+                );
                 Value::Object(sub_map)
             }
-            ConditionType::ContextBased {} => {
+            ConditionType::ContextBased => {
                 let sub_map = Map::new();
 
                 Value::Object(sub_map)
@@ -340,56 +688,49 @@ impl Condition {
         nibble_context: &Nibble,
         previous_node_result: Option<Value>,
         dynamic_params: Option<Value>,
+    ) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        self.check_condition_batched(nibble_context, previous_node_result, dynamic_params, None)
+            .await
+    }
+
+    pub async fn check_condition_batched(
+        &self,
+        nibble_context: &Nibble,
+        previous_node_result: Option<Value>,
+        dynamic_params: Option<Value>,
+        batcher: Option<&ConditionBatcher>,
     ) -> Result<bool, Box<dyn Error + Send + Sync>> {
         match &self.condition_type {
             ConditionType::OnChain {
                 contract_address,
                 function_signature,
             } => {
-                let abi =
-                    AbiParser::default().parse_str(&format!("function {};", function_signature))?;
-                let func = abi.functions().next().ok_or("Function not found in ABI")?;
-
-                let decoded_params: Vec<Token> = match dynamic_params {
-                    Some(params) => params
-                        .as_array()
-                        .ok_or("dynamic_params must be an array")?
-                        .iter()
-                        .map(|param| from_value::<Token>(param.clone()))
-                        .collect::<Result<Vec<_>, _>>()?,
-                    None => vec![],
-                };
-
-                let call_data = func.encode_input(&decoded_params)?;
-                let tx_request = TransactionRequest {
-                    to: Some(contract_address.clone().into()),
-                    data: Some(call_data.into()),
-                    ..Default::default()
-                };
-
-                let call_result = nibble_context.provider.call_raw(&tx_request.into()).await?;
-                let is_valid =
-                    (self.check.condition_fn)(Value::String(format!("{:?}", call_result)));
-                Ok(is_valid)
-            }
-            ConditionType::OffChain { api_url } => {
-                let mut url = api_url.clone();
-                if let Some(params) = &dynamic_params {
-                    if let Some(map) = params.as_object() {
-                        let query_string: Vec<String> = map
-                            .iter()
-                            .map(|(key, value)| format!("{}={}", key, value.as_str().unwrap_or("")))
-                            .collect();
-                        url = format!("{}?{}", api_url, query_string.join("&"));
-                    }
-                }
-
-                let response = reqwest::get(&url).await?;
-                let json: Value = response.json().await?;
-                let is_valid = (self.check.condition_fn)(json);
-                Ok(is_valid)
+                self.check
+                    .check_onchain(
+                        nibble_context,
+                        contract_address,
+                        function_signature,
+                        dynamic_params.as_ref().or(previous_node_result.as_ref()),
+                        batcher,
+                    )
+                    .await
             }
-            ConditionType::ContextBased {} => match previous_node_result {
+            ConditionType::OffChain {
+                api_url,
+                method,
+                json_pointer,
+                comparison,
+            } => Ok(self
+                .check
+                .check_offchain(
+                    api_url,
+                    method,
+                    json_pointer.as_deref(),
+                    comparison,
+                    dynamic_params.as_ref(),
+                )
+                .await),
+            ConditionType::ContextBased => match previous_node_result {
                 Some(context) => {
                     let is_valid = (self.check.condition_fn)(context);
                     Ok(is_valid)
@@ -420,6 +761,7 @@ impl Condition {
                     dynamic_params,
                     sub_conditions,
                     operator.clone(),
+                    batcher,
                 )
                 .await
             }
@@ -433,6 +775,7 @@ impl Condition {
         dynamic_params: Option<Value>,
         sub_conditions: &Vec<Condition>,
         operator: LogicalOperator,
+        batcher: Option<&ConditionBatcher>,
     ) -> Result<bool, Box<dyn Error + Send + Sync>> {
         let mut results = Vec::new();
 
@@ -442,47 +785,33 @@ impl Condition {
                     contract_address,
                     function_signature,
                 } => {
-                    let abi = AbiParser::default()
-                        .parse_str(&format!("function {};", function_signature))?;
-                    let func = abi.functions().next().ok_or("Function not found in ABI")?;
-
-                    let decoded_params: Vec<Token> = match &dynamic_params {
-                        Some(params) => params
-                            .as_array()
-                            .ok_or("dynamic_params must be an array")?
-                            .iter()
-                            .map(|param| from_value::<Token>(param.clone()))
-                            .collect::<Result<Vec<_>, _>>()?,
-                        None => vec![],
-                    };
-
-                    let call_data = func.encode_input(&decoded_params)?;
-                    let tx_request = TransactionRequest {
-                        to: Some(contract_address.clone().into()),
-                        data: Some(call_data.into()),
-                        ..Default::default()
-                    };
-
-                    let call_result = nibble_context.provider.call_raw(&tx_request.into()).await?;
-                    (sub_condition.check.condition_fn)(Value::String(format!("{:?}", call_result)))
+                    sub_condition
+                        .check
+                        .check_onchain(
+                            nibble_context,
+                            contract_address,
+                            function_signature,
+                            dynamic_params.as_ref().or(previous_node_result.as_ref()),
+                            batcher,
+                        )
+                        .await?
                 }
-                ConditionType::OffChain { api_url } => {
-                    let mut url = api_url.clone();
-                    if let Some(params) = &dynamic_params {
-                        if let Some(map) = params.as_object() {
-                            let query_string: Vec<String> = map
-                                .iter()
-                                .map(|(key, value)| {
-                                    format!("{}={}", key, value.as_str().unwrap_or(""))
-                                })
-                                .collect();
-                            url = format!("{}?{}", api_url, query_string.join("&"));
-                        }
-                    }
-
-                    let response = reqwest::get(&url).await?;
-                    let json: Value = response.json().await?;
-                    (sub_condition.check.condition_fn)(json)
+                ConditionType::OffChain {
+                    api_url,
+                    method,
+                    json_pointer,
+                    comparison,
+                } => {
+                    sub_condition
+                        .check
+                        .check_offchain(
+                            api_url,
+                            method,
+                            json_pointer.as_deref(),
+                            comparison,
+                            dynamic_params.as_ref(),
+                        )
+                        .await
                 }
                 ConditionType::ContextBased => {
                     if let Some(context) = &previous_node_result {
@@ -521,3 +850,88 @@ impl Condition {
         Ok(final_result)
     }
 }
+
+fn call_params(
+    func: &Function,
+    params: Option<&Value>,
+) -> Result<Vec<Token>, Box<dyn Error + Send + Sync>> {
+    if func.inputs.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let values: Vec<Value> = match params {
+        Some(Value::Array(values)) => values.clone(),
+        Some(Value::Object(map)) => func
+            .inputs
+            .iter()
+            .map(|input| {
+                map.get(&input.name)
+                    .cloned()
+                    .ok_or_else(|| format!("Missing parameter `{}` in context", input.name))
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        Some(value) => vec![value.clone()],
+        None => vec![],
+    };
+
+    if values.len() != func.inputs.len() {
+        return Err(format!(
+            "Function `{}` expects {} parameter(s), got {}",
+            func.name,
+            func.inputs.len(),
+            values.len()
+        )
+        .into());
+    }
+
+    let mut tokens = Vec::with_capacity(values.len());
+    for (input, value) in func.inputs.iter().zip(values.iter()) {
+        let token = LenientTokenizer::tokenize(&input.kind, &param_to_string(value))
+            .map_err(|e| format!("Invalid value for parameter `{}`: {}", input.name, e))?;
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+fn param_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(values) => format!(
+            "[{}]",
+            values
+                .iter()
+                .map(param_to_string)
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+        other => other.to_string(),
+    }
+}
+
+fn values_match(result: &Value, expected: &Value) -> bool {
+    if result == expected {
+        return true;
+    }
+
+    match (result, expected) {
+        (Value::String(result), Value::Number(expected)) => *result == expected.to_string(),
+        (Value::String(result), Value::String(expected)) => result.eq_ignore_ascii_case(expected),
+        (Value::Array(result), Value::Array(expected)) => {
+            result.len() == expected.len()
+                && result
+                    .iter()
+                    .zip(expected.iter())
+                    .all(|(result, expected)| values_match(result, expected))
+        }
+        _ => false,
+    }
+}
+
+fn value_as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(string) => string.parse::<f64>().ok(),
+        _ => None,
+    }
+}
@@ -1,17 +1,19 @@
 use crate::{
-    adapters::nodes::agents::{call_llm_api, Agent, LLMModel},
+    adapters::nodes::agents::{call_embeddings_api, call_llm_api, Agent, LLMModel},
     nibble::Adaptable,
     utils::generate_unique_id,
 };
 use ethers::{types::H160, utils::hex};
 use reqwest::Client;
-use serde_json::{Map, Number, Value};
+use serde_json::{json, Map, Number, Value};
 use std::{collections::HashMap, error::Error, fmt};
 use tokio::{
     sync::{oneshot, Mutex},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
+pub const DEFAULT_HUMAN_JUDGE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
 #[derive(Debug, Clone)]
 pub struct Evaluation {
     pub name: String,
@@ -27,6 +29,7 @@ pub enum EvaluationType {
         default: bool,
         endpoint: String,
         auth_key: Option<String>,
+        poll_interval: Duration,
     },
     LLMJudge {
         model_type: LLMModel,
@@ -38,12 +41,22 @@ pub enum EvaluationType {
         prompt: String,
         response_type: EvaluationResponseType,
     },
+    Panel {
+        judges: Vec<(EvaluationType, f64)>,
+        threshold: f64,
+    },
+    EmbeddingSimilarity {
+        reference: String,
+        threshold: f64,
+        model: LLMModel,
+    },
 }
 
 #[derive(Clone, Debug)]
 pub enum EvaluationResponseType {
     Boolean { expected: bool },
     Score { threshold: f64 },
+    BooleanWithData { expected: bool },
     Dynamic,
 }
 
@@ -59,11 +72,24 @@ impl EvaluationResponseType {
             }
             EvaluationResponseType::Score { threshold } => {
                 if let Some(score) = response.get("score").and_then(|v| v.as_f64()) {
-                    Ok(Value::Bool(score >= *threshold))
+                    Ok(serde_json::json!({
+                        "decision": score >= *threshold,
+                        "score": score,
+                    }))
                 } else {
                     Err("Response missing 'score' field.".into())
                 }
             }
+            EvaluationResponseType::BooleanWithData { expected } => {
+                if let Some(decision) = response.get("decision").and_then(|v| v.as_bool()) {
+                    Ok(serde_json::json!({
+                        "decision": decision == *expected,
+                        "data": response.get("data").cloned().unwrap_or(Value::Null),
+                    }))
+                } else {
+                    Err("Response missing boolean 'decision' field.".into())
+                }
+            }
             EvaluationResponseType::Dynamic => Ok(response.clone()),
         }
     }
@@ -74,6 +100,10 @@ impl EvaluationResponseType {
             EvaluationResponseType::Score { threshold } => {
                 Value::Number(serde_json::Number::from_f64(*threshold).unwrap())
             }
+            EvaluationResponseType::BooleanWithData { expected } => serde_json::json!({
+                "expected": expected,
+                "with_data": true,
+            }),
             EvaluationResponseType::Dynamic => Value::String("Dynamic".to_string()),
         }
     }
@@ -100,12 +130,14 @@ impl fmt::Debug for EvaluationType {
                 auth_key,
                 default,
                 endpoint,
+                poll_interval,
             } => f
                 .debug_struct("HumanJudge")
                 .field("timeout", timeout)
                 .field("default", default)
                 .field("endpoint", endpoint)
                 .field("auth_key", auth_key)
+                .field("poll_interval", poll_interval)
                 .finish(),
             EvaluationType::LLMJudge {
                 model_type,
@@ -127,6 +159,21 @@ impl fmt::Debug for EvaluationType {
                 .field("prompt", prompt)
                 .field("response_type", response_type)
                 .finish(),
+            EvaluationType::Panel { judges, threshold } => f
+                .debug_struct("Panel")
+                .field("judges", judges)
+                .field("threshold", threshold)
+                .finish(),
+            EvaluationType::EmbeddingSimilarity {
+                reference,
+                threshold,
+                model,
+            } => f
+                .debug_struct("EmbeddingSimilarity")
+                .field("reference", reference)
+                .field("threshold", threshold)
+                .field("model", model)
+                .finish(),
         }
     }
 }
@@ -163,6 +210,7 @@ impl EvaluationType {
                 auth_key,
                 default,
                 endpoint,
+                poll_interval,
             } => {
                 let mut map = Map::new();
                 map.insert("type".to_string(), Value::String("HumanJudge".to_string()));
@@ -176,6 +224,10 @@ impl EvaluationType {
                 );
                 map.insert("endpoint".to_string(), Value::String(endpoint.to_string()));
                 map.insert("default".to_string(), Value::Bool(*default));
+                map.insert(
+                    "poll_interval_ms".to_string(),
+                    Value::Number(Number::from(poll_interval.as_millis() as u64)),
+                );
                 Value::Object(map)
             }
             EvaluationType::LLMJudge {
@@ -205,41 +257,66 @@ impl EvaluationType {
                 map.insert("prompt".to_string(), Value::String(prompt.to_string()));
                 Value::Object(map)
             }
+            EvaluationType::Panel { judges, threshold } => {
+                let mut map = Map::new();
+                map.insert("type".to_string(), Value::String("Panel".to_string()));
+                map.insert(
+                    "judges".to_string(),
+                    Value::Array(
+                        judges
+                            .iter()
+                            .map(|(judge, weight)| {
+                                json!({
+                                    "judge": judge.to_json(),
+                                    "weight": weight,
+                                })
+                            })
+                            .collect(),
+                    ),
+                );
+                map.insert("threshold".to_string(), json!(threshold));
+                Value::Object(map)
+            }
+            EvaluationType::EmbeddingSimilarity {
+                reference,
+                threshold,
+                model,
+            } => {
+                let mut map = Map::new();
+                map.insert(
+                    "type".to_string(),
+                    Value::String("EmbeddingSimilarity".to_string()),
+                );
+                map.insert("reference".to_string(), Value::String(reference.clone()));
+                map.insert("threshold".to_string(), json!(threshold));
+                map.insert("model".to_string(), model.to_json());
+                Value::Object(map)
+            }
         }
     }
-}
-
-impl Evaluation {
-    pub fn to_json(&self) -> Map<String, Value> {
-        let mut map = Map::new();
-        map.insert("name".to_string(), Value::String(self.name.clone()));
-        map.insert("public".to_string(), Value::Bool(self.encrypted));
-        map.insert(
-            "evaluation_type".to_string(),
-            self.evaluation_type.to_json(),
-        );
-        map
-    }
 
-    pub async fn check_evaluation(
+    async fn judge(
         &self,
-        agents: Vec<Agent>,
+        agents: &[Agent],
         previous_node_context: Option<Value>,
         flow_previous_context: Option<&str>,
         flow_next_steps: Option<&str>,
-        interaction_id: String,
+        interaction_id: &str,
     ) -> Result<Value, Box<dyn Error + Send + Sync>> {
-        match &self.evaluation_type {
+        match self {
             EvaluationType::HumanJudge {
                 timeout,
                 default,
                 endpoint,
                 auth_key,
+                poll_interval,
             } => {
                 let client = Client::new();
+                let interaction_id = hex::encode(interaction_id);
+                let deadline = Instant::now() + *timeout;
 
                 let mut request = client.post(endpoint).json(&serde_json::json!({
-                    "interaction_id": hex::encode(&interaction_id),
+                    "interaction_id": interaction_id,
                     "context": flow_previous_context.unwrap_or("No previous context"),
                     "next_steps": flow_next_steps.unwrap_or("No next steps"),
                 }));
@@ -248,16 +325,50 @@ impl Evaluation {
                     request = request.header("Authorization", format!("Bearer {}", key));
                 }
 
-                let response = match tokio::time::timeout(*timeout, request.send()).await {
-                    Ok(Ok(resp)) if resp.status().is_success() => resp.text().await?,
+                match tokio::time::timeout(*timeout, request.send()).await {
+                    Ok(Ok(resp)) if resp.status().is_success() => {
+                        if let Some(verdict) = parse_human_verdict(&resp.text().await?) {
+                            return Ok(Value::Bool(verdict));
+                        }
+                    }
+                    Ok(Ok(resp)) => {
+                        eprintln!("HumanJudge endpoint returned status {}", resp.status());
+                        return Ok(Value::Bool(*default));
+                    }
                     _ => return Ok(Value::Bool(*default)),
-                };
+                }
+
+                let separator = if endpoint.contains('?') { '&' } else { '?' };
+                let poll_url =
+                    format!("{}{}interaction_id={}", endpoint, separator, interaction_id);
+
+                while Instant::now() < deadline {
+                    tokio::time::sleep_until((Instant::now() + *poll_interval).min(deadline)).await;
+
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        break;
+                    }
+
+                    let mut poll = client.get(&poll_url);
+                    if let Some(key) = auth_key {
+                        poll = poll.header("Authorization", format!("Bearer {}", key));
+                    }
 
-                match response.trim().to_lowercase().as_str() {
-                    "yes" => Ok(Value::Bool(true)),
-                    "no" => Ok(Value::Bool(false)),
-                    _ => Ok(Value::Bool(*default)),
+                    if let Ok(Ok(resp)) = tokio::time::timeout(remaining, poll.send()).await {
+                        if resp.status().is_success() {
+                            if let Some(verdict) = parse_human_verdict(&resp.text().await?) {
+                                return Ok(Value::Bool(verdict));
+                            }
+                        }
+                    }
                 }
+
+                println!(
+                    "HumanJudge timed out waiting for a verdict, using default: {}",
+                    default
+                );
+                Ok(Value::Bool(*default))
             }
 
             EvaluationType::LLMJudge {
@@ -319,6 +430,172 @@ impl Evaluation {
                     Err("Agent not found.".into())
                 }
             }
+            EvaluationType::Panel { .. } => {
+                Err("Panel evaluations cannot be nested inside a Panel.".into())
+            }
+            EvaluationType::EmbeddingSimilarity {
+                reference,
+                threshold,
+                model,
+            } => {
+                let candidate = match &previous_node_context {
+                    Some(Value::String(text)) => text.clone(),
+                    Some(context) => context.to_string(),
+                    None => return Err("No context to compare against the reference.".into()),
+                };
+
+                let reference_embedding = call_embeddings_api(model, reference).await?;
+                let candidate_embedding = call_embeddings_api(model, &candidate).await?;
+                let similarity = cosine_similarity(&reference_embedding, &candidate_embedding)?;
+
+                Ok(json!({
+                    "decision": similarity >= *threshold,
+                    "data": previous_node_context,
+                    "score": similarity,
+                    "threshold": threshold,
+                }))
+            }
+        }
+    }
+}
+
+impl Evaluation {
+    pub fn to_json(&self) -> Map<String, Value> {
+        let mut map = Map::new();
+        map.insert("name".to_string(), Value::String(self.name.clone()));
+        map.insert("id".to_string(), Value::String(self.id.clone()));
+        map.insert("public".to_string(), Value::Bool(self.encrypted));
+        map.insert(
+            "evaluation_type".to_string(),
+            self.evaluation_type.to_json(),
+        );
+        map
+    }
+
+    pub async fn check_evaluation(
+        &self,
+        agents: Vec<Agent>,
+        previous_node_context: Option<Value>,
+        flow_previous_context: Option<&str>,
+        flow_next_steps: Option<&str>,
+        interaction_id: String,
+    ) -> Result<Value, Box<dyn Error + Send + Sync>> {
+        match &self.evaluation_type {
+            EvaluationType::Panel { judges, threshold } => {
+                let mut votes = Vec::new();
+                let mut weighted_score = 0.0;
+                let mut total_weight = 0.0;
+
+                for (index, (judge, weight)) in judges.iter().enumerate() {
+                    let result = judge
+                        .judge(
+                            &agents,
+                            previous_node_context.clone(),
+                            flow_previous_context,
+                            flow_next_steps,
+                            &interaction_id,
+                        )
+                        .await;
+
+                    let (vote, result, error) = match result {
+                        Ok(result) => match judge_vote(&result) {
+                            Some(vote) => (vote, result, Value::Null),
+                            None => (
+                                0.0,
+                                result,
+                                Value::String("Judge result has no decision or score".to_string()),
+                            ),
+                        },
+                        Err(e) => {
+                            eprintln!("Panel judge {} failed: {}", index, e);
+                            (0.0, Value::Null, Value::String(e.to_string()))
+                        }
+                    };
+
+                    weighted_score += vote * weight;
+                    total_weight += weight;
+                    votes.push(json!({
+                        "index": index,
+                        "weight": weight,
+                        "vote": vote,
+                        "result": result,
+                        "error": error,
+                    }));
+                }
+
+                let score = if total_weight > 0.0 {
+                    weighted_score / total_weight
+                } else {
+                    0.0
+                };
+
+                Ok(json!({
+                    "decision": score >= *threshold,
+                    "data": previous_node_context.unwrap_or(Value::Null),
+                    "score": score,
+                    "threshold": threshold,
+                    "judges": votes,
+                }))
+            }
+            evaluation_type => {
+                evaluation_type
+                    .judge(
+                        &agents,
+                        previous_node_context,
+                        flow_previous_context,
+                        flow_next_steps,
+                        &interaction_id,
+                    )
+                    .await
+            }
         }
     }
 }
+
+fn parse_human_verdict(response: &str) -> Option<bool> {
+    if let Ok(Value::Object(map)) = serde_json::from_str::<Value>(response) {
+        return match map.get("decision").or_else(|| map.get("verdict")) {
+            Some(Value::Bool(decision)) => Some(*decision),
+            Some(Value::String(verdict)) => parse_human_verdict(verdict),
+            _ => None,
+        };
+    }
+
+    match response.trim().trim_matches('"').to_lowercase().as_str() {
+        "yes" | "true" => Some(true),
+        "no" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> Result<f64, Box<dyn Error + Send + Sync>> {
+    if a.len() != b.len() || a.is_empty() {
+        return Err(format!(
+            "Cannot compare embeddings of dimensions {} and {}",
+            a.len(),
+            b.len()
+        )
+        .into());
+    }
+
+    let dot: f64 = a.iter().zip(b).map(|(x, y)| *x as f64 * *y as f64).sum();
+    let norm_a = a.iter().map(|x| (*x as f64).powi(2)).sum::<f64>().sqrt();
+    let norm_b = b.iter().map(|y| (*y as f64).powi(2)).sum::<f64>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return Ok(0.0);
+    }
+
+    Ok(dot / (norm_a * norm_b))
+}
+
+fn judge_vote(result: &Value) -> Option<f64> {
+    match result {
+        Value::Bool(decision) => Some(if *decision { 1.0 } else { 0.0 }),
+        Value::Number(score) => score.as_f64(),
+        Value::Object(map) => match map.get("decision").and_then(|v| v.as_bool()) {
+            Some(decision) => Some(if decision { 1.0 } else { 0.0 }),
+            None => map.get("score").and_then(|v| v.as_f64()),
+        },
+        _ => None,
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{nibble::Adaptable, utils::generate_unique_id};
+use crate::{constants::FHE_GATE_ABI, nibble::Adaptable, utils::generate_unique_id};
 use bincode::{deserialize, serialize};
 use ethers::{
     abi::Abi,
@@ -6,11 +6,129 @@ use ethers::{
     middleware::SignerMiddleware,
     providers::{Http, Provider},
     signers::{LocalWallet, Signer},
-    types::{Chain, H160},
+    types::{Chain, H160, U256},
 };
 use serde::{Deserialize, Serialize};
-use std::{error::Error, fs::File, io::Read, path::Path, sync::Arc};
-use tfhe::{generate_keys, prelude::*, ClientKey, ConfigBuilder, FheUint8, ServerKey};
+use serde_json::{Map, Value};
+use std::{error::Error, fmt, str::FromStr, sync::Arc};
+use tfhe::{
+    generate_keys, prelude::*, set_server_key, ClientKey, ConfigBuilder, FheBool, FheUint64,
+    FheUint8, ServerKey,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FheOp {
+    Eq { criterion: u64 },
+    Ne { criterion: u64 },
+    Gt { criterion: u64 },
+    Lt { criterion: u64 },
+    InRange { min: u64, max: u64 },
+}
+
+impl fmt::Display for FheOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FheOp::Eq { .. } => "eq",
+            FheOp::Ne { .. } => "ne",
+            FheOp::Gt { .. } => "gt",
+            FheOp::Lt { .. } => "lt",
+            FheOp::InRange { .. } => "in_range",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FheOp {
+    pub fn requires_client_key(&self) -> bool {
+        matches!(self, FheOp::InRange { .. })
+    }
+
+    pub fn criteria(&self) -> Vec<u64> {
+        match self {
+            FheOp::Eq { criterion }
+            | FheOp::Ne { criterion }
+            | FheOp::Gt { criterion }
+            | FheOp::Lt { criterion } => vec![*criterion],
+            FheOp::InRange { min, max } => vec![*min, *max],
+        }
+    }
+
+    pub fn evaluate(
+        &self,
+        encrypted_value: &[u8],
+        client_key: &ClientKey,
+    ) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        set_server_key(ServerKey::new(client_key));
+        let value: FheUint64 = deserialize(encrypted_value)
+            .map_err(|e| format!("Invalid encrypted value for FHE gate: {}", e))?;
+
+        let result: FheBool = match self {
+            FheOp::Eq { criterion } => value.eq(*criterion),
+            FheOp::Ne { criterion } => value.ne(*criterion),
+            FheOp::Gt { criterion } => value.gt(*criterion),
+            FheOp::Lt { criterion } => value.lt(*criterion),
+            FheOp::InRange { min, max } => value.ge(*min) & value.le(*max),
+        };
+
+        Ok(result.decrypt(client_key))
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut map = Map::new();
+        map.insert("op".to_string(), Value::String(self.to_string()));
+        match self {
+            FheOp::InRange { min, max } => {
+                map.insert("min".to_string(), Value::from(*min));
+                map.insert("max".to_string(), Value::from(*max));
+            }
+            _ => {
+                map.insert("criterion".to_string(), Value::from(self.criteria()[0]));
+            }
+        }
+        Value::Object(map)
+    }
+
+    pub fn from_json(value: &Value) -> Result<Self, String> {
+        let op = value
+            .get("op")
+            .and_then(|v| v.as_str())
+            .ok_or("Missing FHE operation `op`")?;
+        let number = |key: &str| {
+            value
+                .get(key)
+                .and_then(|v| v.as_u64())
+                .ok_or_else(|| format!("Missing or invalid `{}` for FHE operation {}", key, op))
+        };
+
+        match op.to_lowercase().as_str() {
+            "in_range" | "inrange" => Ok(FheOp::InRange {
+                min: number("min")?,
+                max: number("max")?,
+            }),
+            _ => FheOp::from_criterion(op, number("criterion")?),
+        }
+    }
+
+    fn from_criterion(op: &str, criterion: u64) -> Result<Self, String> {
+        match op.to_lowercase().as_str() {
+            "eq" => Ok(FheOp::Eq { criterion }),
+            "ne" => Ok(FheOp::Ne { criterion }),
+            "gt" => Ok(FheOp::Gt { criterion }),
+            "lt" => Ok(FheOp::Lt { criterion }),
+            _ => Err(format!("Invalid FheOp: {}", op)),
+        }
+    }
+}
+
+impl FromStr for FheOp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value: Value =
+            serde_json::from_str(s).map_err(|e| format!("Invalid FheOp {}: {}", s, e))?;
+        FheOp::from_json(&value)
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct FHEGate {
@@ -19,7 +137,7 @@ pub struct FHEGate {
     pub key: String,
     pub encrypted: bool,
     pub contract_address: H160,
-    pub operation: String,
+    pub operation: FheOp,
     pub chain: Chain,
 }
 
@@ -29,7 +147,7 @@ pub fn configure_new_gate(
     encrypted: bool,
     address: &H160,
     contract_address: &H160,
-    operation: &str,
+    operation: FheOp,
     chain: Chain,
 ) -> Result<FHEGate, Box<dyn Error + Send + Sync>> {
     let fhe_gate = FHEGate {
@@ -38,7 +156,7 @@ pub fn configure_new_gate(
         key: key.to_string(),
         encrypted,
         contract_address: *contract_address,
-        operation: operation.to_string(),
+        operation,
         chain,
     };
     Ok(fhe_gate)
@@ -54,40 +172,59 @@ impl Adaptable for FHEGate {
 }
 
 impl FHEGate {
+    pub fn to_json(&self) -> Map<String, Value> {
+        let mut map = Map::new();
+        map.insert("name".to_string(), Value::String(self.name.clone()));
+        map.insert("id".to_string(), Value::String(self.id.clone()));
+        map.insert("key".to_string(), Value::String(self.key.clone()));
+        map.insert(
+            "contract_address".to_string(),
+            Value::String(format!("{:?}", self.contract_address)),
+        );
+        map.insert("operation".to_string(), self.operation.to_json());
+        map.insert("chain".to_string(), Value::String(self.chain.to_string()));
+        map
+    }
+
     pub async fn check_fhe_gate(
         &self,
         encrypted_value: Vec<u8>,
-        criterion: Option<Vec<u8>>,
-        client_key: ClientKey,
+        client_key: Option<ClientKey>,
         provider: Provider<Http>,
         wallet: LocalWallet,
     ) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        if let Some(client_key) = client_key {
+            let result = self.operation.evaluate(&encrypted_value, &client_key)?;
+            println!(
+                "FHE Gate operation '{}' evaluated locally: {}",
+                self.operation, result
+            );
+            return Ok(result);
+        }
+
+        if self.operation.requires_client_key() {
+            return Err(format!(
+                "FHE Gate operation '{}' requires a client_key",
+                self.operation
+            )
+            .into());
+        }
+
         let client = SignerMiddleware::new(provider, wallet.with_chain_id(self.chain));
         let client = Arc::new(client);
 
-        let mut abi_file = File::open(Path::new("./../../../abis/FHEGate.json"))?;
-        let mut abi_content = String::new();
-        abi_file.read_to_string(&mut abi_content)?;
-        let abi = serde_json::from_str::<Abi>(&abi_content)?;
+        let abi = serde_json::from_str::<Abi>(FHE_GATE_ABI)?;
 
         let contract = Contract::new(self.contract_address, abi, client.clone());
 
-        let operation_name = self.operation.clone();
-        let result_encrypted: Vec<u8> = if let Some(criterion_value) = criterion {
-            contract
-                .method::<_, Vec<u8>>(&operation_name, (encrypted_value.clone(), criterion_value))
-                .map_err(|e| format!("Error creating contract method: {}", e))?
-                .call()
-                .await
-                .map_err(|e| format!("Error calling contract method '{}': {}", operation_name, e))?
-        } else {
-            contract
-                .method::<_, Vec<u8>>(&operation_name, (encrypted_value.clone(),))
-                .map_err(|e| format!("Error creating contract method without criterion: {}", e))?
-                .call()
-                .await
-                .map_err(|e| format!("Error calling contract method '{}': {}", operation_name, e))?
-        };
+        let operation_name = self.operation.to_string();
+        let criterion = U256::from(self.operation.criteria()[0]);
+        let result_encrypted: Vec<u8> = contract
+            .method::<_, Vec<u8>>(&operation_name, (encrypted_value.clone(), criterion))
+            .map_err(|e| format!("Error creating contract method: {}", e))?
+            .call()
+            .await
+            .map_err(|e| format!("Error calling contract method '{}': {}", operation_name, e))?;
         println!(
             "Encrypted result from operation '{}': {:?}",
             operation_name, result_encrypted
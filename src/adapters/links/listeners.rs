@@ -1,19 +1,29 @@
-use crate::{nibble::Adaptable, utils::generate_unique_id};
+use crate::{
+    nibble::Adaptable,
+    utils::{generate_unique_id, token_to_value},
+};
+use axum::{body::Bytes, extract::State, http::StatusCode, routing::post, Router};
 use ethers::{
-    abi::{decode, Abi, Address, RawLog, Token},
+    abi::{decode, parse_abi, Abi, Address, RawLog, Token},
     contract::Contract,
     middleware::{Middleware, SignerMiddleware},
     providers::{Http, Provider},
     signers::{LocalWallet, Signer},
-    types::{Chain, Filter, Log, H160},
+    types::{transaction::eip2718::TypedTransaction, Chain, Filter, Log, TransactionRequest, H160},
 };
+use futures::{SinkExt, StreamExt};
 use reqwest::Client;
 use serde_json::{from_slice, to_value, Map, Value};
-use std::{error::Error, sync::Arc};
+use std::{error::Error, net::SocketAddr, sync::Arc};
 use tokio::{
-    sync::mpsc::Sender,
-    time::{sleep, Duration},
+    net::TcpListener,
+    sync::{
+        mpsc::{self, Sender},
+        oneshot,
+    },
+    time::{sleep, Duration, Instant},
 };
+use tokio_tungstenite::{connect_async, tungstenite::Message};
 
 #[derive(Debug, Clone)]
 pub struct Listener {
@@ -21,6 +31,8 @@ pub struct Listener {
     pub id: String,
     pub listener_type: ListenerType,
     pub encrypted: bool,
+    pub debounce: Option<Duration>,
+    pub dedup_key: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -37,15 +49,80 @@ pub enum ListenerType {
         webhook_url: String,
         sns_verification: bool,
     },
+    WebhookServer {
+        bind_addr: SocketAddr,
+        path: String,
+        sns_verification: bool,
+    },
     Timer {
         interval: Duration,
     },
+    WebSocket {
+        url: String,
+        subscribe_message: Option<String>,
+        reconnect: bool,
+    },
+    PollContract {
+        contract_address: Address,
+        function_signature: String,
+        provider: Provider<Http>,
+        chain: Chain,
+        interval: Duration,
+        trigger: TriggerCondition,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub enum TriggerCondition {
+    Changed,
+    Above { value: f64 },
+    Below { value: f64 },
+    Equals { value: Value },
+}
+
+impl TriggerCondition {
+    pub fn is_satisfied(&self, previous: Option<&Value>, current: &Value) -> bool {
+        match self {
+            TriggerCondition::Changed => previous.is_some_and(|previous| previous != current),
+            TriggerCondition::Above { value } => {
+                value_as_f64(current).is_some_and(|current| current > *value)
+            }
+            TriggerCondition::Below { value } => {
+                value_as_f64(current).is_some_and(|current| current < *value)
+            }
+            TriggerCondition::Equals { value } => current == value,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut map = Map::new();
+        match self {
+            TriggerCondition::Changed => {
+                map.insert("type".to_string(), Value::String("Changed".to_string()));
+            }
+            TriggerCondition::Above { value } => {
+                map.insert("type".to_string(), Value::String("Above".to_string()));
+                map.insert("value".to_string(), serde_json::json!(value));
+            }
+            TriggerCondition::Below { value } => {
+                map.insert("type".to_string(), Value::String("Below".to_string()));
+                map.insert("value".to_string(), serde_json::json!(value));
+            }
+            TriggerCondition::Equals { value } => {
+                map.insert("type".to_string(), Value::String("Equals".to_string()));
+                map.insert("value".to_string(), value.clone());
+            }
+        }
+        Value::Object(map)
+    }
 }
 
 pub fn configure_new_listener(
     name: &str,
     listener_type: ListenerType,
     encrypted: bool,
+    debounce: Option<Duration>,
+    dedup_key: Option<String>,
     address: &H160,
 ) -> Result<Listener, Box<dyn Error + Send + Sync>> {
     let listener = Listener {
@@ -53,6 +130,8 @@ pub fn configure_new_listener(
         id: generate_unique_id(address),
         listener_type,
         encrypted,
+        debounce,
+        dedup_key,
     };
 
     Ok(listener)
@@ -71,6 +150,7 @@ impl Listener {
     pub fn to_json(&self) -> Map<String, Value> {
         let mut map = Map::new();
         map.insert("name".to_string(), Value::String(self.name.clone()));
+        map.insert("id".to_string(), Value::String(self.id.clone()));
         map.insert("public".to_string(), Value::Bool(self.encrypted));
 
         let listener_type_map = match &self.listener_type {
@@ -108,7 +188,7 @@ impl Listener {
 
                 sub_map.insert(
                     "sns_verification".to_string(),
-                    Value::Bool(sns_verification.clone()),
+                    Value::Bool(*sns_verification),
                 );
                 sub_map.insert(
                     "webhook_url".to_string(),
@@ -117,6 +197,23 @@ impl Listener {
 
                 Value::Object(sub_map)
             }
+            ListenerType::WebhookServer {
+                bind_addr,
+                path,
+                sns_verification,
+            } => {
+                let mut sub_map = Map::new();
+                sub_map.insert(
+                    "bind_addr".to_string(),
+                    Value::String(bind_addr.to_string()),
+                );
+                sub_map.insert("path".to_string(), Value::String(path.clone()));
+                sub_map.insert(
+                    "sns_verification".to_string(),
+                    Value::Bool(*sns_verification),
+                );
+                Value::Object(sub_map)
+            }
             ListenerType::Timer { interval } => {
                 let mut sub_map = Map::new();
                 sub_map.insert(
@@ -125,8 +222,62 @@ impl Listener {
                 );
                 Value::Object(sub_map)
             }
+            ListenerType::WebSocket {
+                url,
+                subscribe_message,
+                reconnect,
+            } => {
+                let mut sub_map = Map::new();
+                sub_map.insert("url".to_string(), Value::String(url.clone()));
+                if let Some(message) = subscribe_message {
+                    sub_map.insert(
+                        "subscribe_message".to_string(),
+                        Value::String(message.clone()),
+                    );
+                }
+                sub_map.insert("reconnect".to_string(), Value::Bool(*reconnect));
+                Value::Object(sub_map)
+            }
+            ListenerType::PollContract {
+                contract_address,
+                function_signature,
+                provider,
+                chain,
+                interval,
+                trigger,
+            } => {
+                let mut sub_map = Map::new();
+                sub_map.insert(
+                    "contract_address".to_string(),
+                    Value::String(format!("{:?}", contract_address)),
+                );
+                sub_map.insert(
+                    "function_signature".to_string(),
+                    Value::String(function_signature.clone()),
+                );
+                sub_map.insert(
+                    "provider".to_string(),
+                    Value::String(format!("{:?}", provider)),
+                );
+                sub_map.insert("chain".to_string(), Value::String(format!("{:?}", chain)));
+                sub_map.insert(
+                    "interval".to_string(),
+                    Value::String(format!("{:?}", interval)),
+                );
+                sub_map.insert("trigger".to_string(), trigger.to_json());
+                Value::Object(sub_map)
+            }
         };
         map.insert("listener_type".to_string(), listener_type_map);
+        if let Some(debounce) = self.debounce {
+            map.insert(
+                "debounce_ms".to_string(),
+                Value::Number((debounce.as_millis() as u64).into()),
+            );
+        }
+        if let Some(dedup_key) = &self.dedup_key {
+            map.insert("dedup_key".to_string(), Value::String(dedup_key.clone()));
+        }
 
         map
     }
@@ -137,6 +288,7 @@ impl Listener {
         repetitions: Option<u64>,
     ) -> Result<(), Box<dyn Error + Send + Sync>> {
         let mut executed = 0;
+        let mut filter = EventFilter::new(self.debounce, self.dedup_key.clone());
 
         match &self.listener_type {
             ListenerType::OnChain {
@@ -147,10 +299,8 @@ impl Listener {
                 wallet,
                 chain,
             } => {
-                let client = SignerMiddleware::new(
-                    provider.clone(),
-                    wallet.clone().with_chain_id(chain.clone()),
-                );
+                let client =
+                    SignerMiddleware::new(provider.clone(), wallet.clone().with_chain_id(*chain));
                 let client = Arc::new(client);
 
                 loop {
@@ -172,7 +322,9 @@ impl Listener {
                     for log in logs {
                         println!("OnChain event detected: {:?}", log);
                         let decoded_event = decode_event(abi, &log, provider.clone())?;
-                        sender.send(decoded_event).await?;
+                        if filter.admit(&decoded_event) {
+                            sender.send(decoded_event).await?;
+                        }
                     }
 
                     executed += 1;
@@ -215,7 +367,9 @@ impl Listener {
                                 }
                                 "Notification" => {
                                     println!("SNS Notification received: {:?}", result);
-                                    sender.send(result.clone()).await?;
+                                    if filter.admit(&result) {
+                                        sender.send(result.clone()).await?;
+                                    }
                                 }
                                 "UnsubscribeConfirmation" => {
                                     println!("Received UnsubscribeConfirmation: {:?}", result);
@@ -229,7 +383,9 @@ impl Listener {
                         }
                     } else {
                         println!("Webhook data received: {:?}", result);
-                        sender.send(result.clone()).await?;
+                        if filter.admit(&result) {
+                            sender.send(result.clone()).await?;
+                        }
                     }
 
                     executed += 1;
@@ -237,6 +393,61 @@ impl Listener {
                 }
             }
 
+            ListenerType::WebhookServer {
+                bind_addr,
+                path,
+                sns_verification,
+            } => {
+                let (event_sender, mut events) = mpsc::channel::<Value>(32);
+                let (shutdown_sender, shutdown_receiver) = oneshot::channel::<()>();
+                let state = WebhookState {
+                    events: event_sender,
+                    sns_verification: *sns_verification,
+                    client: Client::new(),
+                };
+                let app = Router::new()
+                    .route(path, post(receive_webhook))
+                    .with_state(state);
+
+                let tcp_listener = TcpListener::bind(bind_addr).await?;
+                println!("Webhook server listening on {}{}", bind_addr, path);
+                let server = tokio::spawn(async move {
+                    axum::serve(tcp_listener, app)
+                        .with_graceful_shutdown(async {
+                            let _ = shutdown_receiver.await;
+                        })
+                        .await
+                });
+
+                let mut forward_error = None;
+                while let Some(event) = events.recv().await {
+                    println!("Webhook data received: {:?}", event);
+                    if !filter.admit(&event) {
+                        continue;
+                    }
+                    if let Err(e) = sender.send(event).await {
+                        forward_error = Some(e);
+                        break;
+                    }
+                    executed += 1;
+
+                    if let Some(max_reps) = repetitions {
+                        if executed >= max_reps && max_reps > 0 {
+                            println!("Max repetitions reached for WebhookServer listener.");
+                            break;
+                        }
+                    }
+                }
+
+                let _ = shutdown_sender.send(());
+                server.await??;
+                println!("Webhook server on {} shut down.", bind_addr);
+
+                if let Some(e) = forward_error {
+                    return Err(e.into());
+                }
+            }
+
             ListenerType::Timer { interval } => loop {
                 if let Some(max_reps) = repetitions {
                     if executed >= max_reps && max_reps > 0 {
@@ -250,16 +461,250 @@ impl Listener {
                 let timer = Value::String(chrono::Utc::now().to_string());
 
                 println!("Timer check completed at: {:?}", timer);
-                sender.send(timer).await?;
+                if filter.admit(&timer) {
+                    sender.send(timer).await?;
+                }
 
                 executed += 1;
             },
+
+            ListenerType::WebSocket {
+                url,
+                subscribe_message,
+                reconnect,
+            } => {
+                let mut backoff = Duration::from_secs(1);
+
+                'connection: loop {
+                    if let Some(max_reps) = repetitions {
+                        if executed >= max_reps && max_reps > 0 {
+                            println!("Max repetitions reached for WebSocket listener.");
+                            break;
+                        }
+                    }
+
+                    let mut stream = match connect_async(url.as_str()).await {
+                        Ok((stream, _)) => stream,
+                        Err(e) => {
+                            if !*reconnect {
+                                return Err(e.into());
+                            }
+                            eprintln!(
+                                "WebSocket connection failed: {:?}, retrying in {:?}",
+                                e, backoff
+                            );
+                            sleep(backoff).await;
+                            backoff = (backoff * 2).min(Duration::from_secs(60));
+                            continue;
+                        }
+                    };
+                    backoff = Duration::from_secs(1);
+
+                    if let Some(message) = subscribe_message {
+                        stream.send(Message::Text(message.clone())).await?;
+                    }
+
+                    while let Some(frame) = stream.next().await {
+                        match frame {
+                            Ok(Message::Text(text)) => {
+                                let event = serde_json::from_str::<Value>(&text)
+                                    .unwrap_or_else(|_| Value::String(text.to_string()));
+                                println!("WebSocket message received: {:?}", event);
+                                if !filter.admit(&event) {
+                                    continue;
+                                }
+                                sender.send(event).await?;
+                                executed += 1;
+
+                                if let Some(max_reps) = repetitions {
+                                    if executed >= max_reps && max_reps > 0 {
+                                        println!("Max repetitions reached for WebSocket listener.");
+                                        let _ = stream.close(None).await;
+                                        break 'connection;
+                                    }
+                                }
+                            }
+                            Ok(Message::Close(_)) => break,
+                            Ok(_) => {}
+                            Err(e) => {
+                                eprintln!("WebSocket error: {:?}", e);
+                                break;
+                            }
+                        }
+                    }
+
+                    if !*reconnect {
+                        println!("WebSocket disconnected.");
+                        break;
+                    }
+
+                    println!("WebSocket disconnected, reconnecting in {:?}", backoff);
+                    sleep(backoff).await;
+                    backoff = (backoff * 2).min(Duration::from_secs(60));
+                }
+            }
+
+            ListenerType::PollContract {
+                contract_address,
+                function_signature,
+                provider,
+                chain: _,
+                interval,
+                trigger,
+            } => {
+                let abi = parse_abi(&[function_signature.as_str()])?;
+                let function = abi
+                    .functions()
+                    .next()
+                    .ok_or("No function found in function_signature")?
+                    .clone();
+                let call_data = function.encode_input(&[])?;
+                let mut previous_value: Option<Value> = None;
+
+                loop {
+                    if let Some(max_reps) = repetitions {
+                        if executed >= max_reps && max_reps > 0 {
+                            println!("Max repetitions reached for PollContract listener.");
+                            break;
+                        }
+                    }
+
+                    let call: TypedTransaction = TransactionRequest::new()
+                        .to(*contract_address)
+                        .data(call_data.clone())
+                        .into();
+                    let raw_output = provider.call(&call, None).await?;
+                    let tokens = function.decode_output(&raw_output)?;
+
+                    let current_value = if tokens.len() == 1 {
+                        token_to_value(tokens[0].clone())
+                    } else {
+                        Value::Array(tokens.into_iter().map(token_to_value).collect())
+                    };
+
+                    if trigger.is_satisfied(previous_value.as_ref(), &current_value) {
+                        println!("PollContract trigger satisfied: {:?}", current_value);
+                        if filter.admit(&current_value) {
+                            sender.send(current_value.clone()).await?;
+                        }
+                    }
+                    previous_value = Some(current_value);
+
+                    executed += 1;
+                    sleep(*interval).await;
+                }
+            }
         }
 
         Ok(())
     }
 }
 
+#[derive(Clone)]
+struct WebhookState {
+    events: Sender<Value>,
+    sns_verification: bool,
+    client: Client,
+}
+
+async fn receive_webhook(State(state): State<WebhookState>, body: Bytes) -> StatusCode {
+    let payload = serde_json::from_slice::<Value>(&body)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&body).to_string()));
+
+    if state.sns_verification {
+        match payload["Type"].as_str() {
+            Some("SubscriptionConfirmation") => {
+                let subscribe_url = match payload["SubscribeURL"].as_str() {
+                    Some(subscribe_url) => subscribe_url,
+                    None => {
+                        eprintln!("SubscriptionConfirmation without SubscribeURL");
+                        return StatusCode::BAD_REQUEST;
+                    }
+                };
+                return match state.client.get(subscribe_url).send().await {
+                    Ok(response) if response.status().is_success() => {
+                        println!("Subscription confirmed: {}", subscribe_url);
+                        StatusCode::OK
+                    }
+                    _ => {
+                        eprintln!("Failed to confirm subscription: {}", subscribe_url);
+                        StatusCode::BAD_GATEWAY
+                    }
+                };
+            }
+            Some("Notification") => {
+                println!("SNS Notification received: {:?}", payload);
+            }
+            Some("UnsubscribeConfirmation") => {
+                println!("Received UnsubscribeConfirmation: {:?}", payload);
+                return StatusCode::OK;
+            }
+            Some(payload_type) => {
+                println!("Unhandled SNS Type: {:?}", payload_type);
+                return StatusCode::OK;
+            }
+            None => {
+                eprintln!("Invalid SNS payload: {:?}", payload);
+                return StatusCode::BAD_REQUEST;
+            }
+        }
+    }
+
+    match state.events.send(payload).await {
+        Ok(()) => StatusCode::OK,
+        Err(_) => StatusCode::SERVICE_UNAVAILABLE,
+    }
+}
+
+struct EventFilter {
+    debounce: Option<Duration>,
+    dedup_key: Option<String>,
+    last_emitted: Option<Instant>,
+    last_key: Option<Value>,
+}
+
+impl EventFilter {
+    fn new(debounce: Option<Duration>, dedup_key: Option<String>) -> Self {
+        Self {
+            debounce,
+            dedup_key,
+            last_emitted: None,
+            last_key: None,
+        }
+    }
+
+    fn admit(&mut self, event: &Value) -> bool {
+        if let (Some(debounce), Some(last_emitted)) = (self.debounce, self.last_emitted) {
+            if last_emitted.elapsed() < debounce {
+                println!("Listener event debounced: {:?}", event);
+                return false;
+            }
+        }
+
+        let key = self
+            .dedup_key
+            .as_ref()
+            .and_then(|pointer| event.pointer(pointer))
+            .cloned();
+        if key.is_some() && key == self.last_key {
+            println!("Listener event deduplicated: {:?}", event);
+            return false;
+        }
+
+        self.last_emitted = Some(Instant::now());
+        self.last_key = key;
+        true
+    }
+}
+
+fn value_as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(string) => string.parse::<f64>().ok(),
+        _ => None,
+    }
+}
+
 fn decode_event(
     abi: &str,
     log: &Log,
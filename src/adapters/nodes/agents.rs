@@ -1,10 +1,26 @@
-use crate::{nibble::Adaptable, utils::generate_unique_id};
+use crate::{
+    constants::{
+        ANTHROPIC_BASE_URL, DEFAULT_OBJECTIVE_SIMILARITY, OLLAMA_BASE_URL, OPENAI_BASE_URL,
+    },
+    nibble::Adaptable,
+    utils::generate_unique_id,
+};
 use ethers::{core::rand::thread_rng, prelude::*};
 use regex::Regex;
 use serde_json::{from_str, json, to_string, Map, Number, Value};
-use std::{collections, error::Error, iter::Iterator, str::FromStr};
-
-#[derive(Debug, Clone)]
+use std::{
+    cmp::Reverse,
+    collections::{self, HashSet, VecDeque},
+    error::Error,
+    fmt,
+    iter::Iterator,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+const DEFAULT_LLM_CACHE_CAPACITY: usize = 256;
+
+#[derive(Clone)]
 pub enum LLMModel {
     OpenAI {
         api_key: String,
@@ -25,7 +41,10 @@ pub enum LLMModel {
         response_format: Option<Value>,
         stream: Option<bool>,
         parallel_tool_calls: Option<bool>,
+        tools: Option<Vec<Value>>,
+        tool_choice: Option<Value>,
         user: Option<String>,
+        base_url: Option<String>,
     },
     Claude {
         api_key: String,
@@ -41,6 +60,7 @@ pub enum LLMModel {
         metadata: Option<Value>,
         tool_choice: Option<Value>,
         tools: Option<Vec<Value>>,
+        base_url: Option<String>,
     },
     Ollama {
         model: String,
@@ -69,6 +89,149 @@ pub enum LLMModel {
     },
 }
 
+pub trait LlmCache: Send + Sync {
+    fn get(&self, key: &str) -> Option<String>;
+    fn put(&self, key: &str, completion: String);
+}
+
+pub trait LlmObserver: fmt::Debug + Send + Sync {
+    fn on_request(&self, model_name: &str, prompt: &Value);
+    fn on_response(&self, raw: &str);
+}
+
+pub struct InMemoryLlmCache {
+    capacity: usize,
+    entries: Mutex<VecDeque<(String, String)>>,
+}
+
+impl InMemoryLlmCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: Mutex::new(VecDeque::new()),
+        }
+    }
+}
+
+impl Default for InMemoryLlmCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_LLM_CACHE_CAPACITY)
+    }
+}
+
+impl LlmCache for InMemoryLlmCache {
+    fn get(&self, key: &str) -> Option<String> {
+        let mut entries = self.entries.lock().ok()?;
+        let position = entries.iter().position(|(cached, _)| cached == key)?;
+        let entry = entries.remove(position)?;
+        let completion = entry.1.clone();
+        entries.push_back(entry);
+        Some(completion)
+    }
+
+    fn put(&self, key: &str, completion: String) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.retain(|(cached, _)| cached != key);
+            while entries.len() >= self.capacity {
+                entries.pop_front();
+            }
+            entries.push_back((key.to_string(), completion));
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AgentError {
+    Truncated {
+        finish_reason: String,
+        partial: String,
+    },
+}
+
+impl fmt::Display for AgentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AgentError::Truncated { finish_reason, .. } => write!(
+                f,
+                "LLM response was truncated (finish reason: {}); raise max_tokens and retry",
+                finish_reason
+            ),
+        }
+    }
+}
+
+impl Error for AgentError {}
+
+fn check_truncation(response_json: &Value, completion: &str) -> Result<(), AgentError> {
+    let finish_reason = response_json["choices"][0]["finish_reason"]
+        .as_str()
+        .or_else(|| response_json["finish_reason"].as_str())
+        .or_else(|| response_json["stop_reason"].as_str())
+        .or_else(|| response_json["done_reason"].as_str());
+
+    match finish_reason {
+        Some(reason @ ("length" | "max_tokens")) => Err(AgentError::Truncated {
+            finish_reason: reason.to_string(),
+            partial: completion.to_string(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(feature = "metrics")]
+fn record_token_usage(model_type: &LLMModel, response_json: &Value) {
+    let usage = &response_json["usage"];
+    let tokens = usage["total_tokens"]
+        .as_u64()
+        .or_else(|| Some(usage["input_tokens"].as_u64()? + usage["output_tokens"].as_u64()?))
+        .or_else(|| {
+            Some(
+                response_json["prompt_eval_count"].as_u64()?
+                    + response_json["eval_count"].as_u64()?,
+            )
+        });
+
+    if let Some(tokens) = tokens {
+        crate::metrics::record_llm_tokens(model_type.model_name().unwrap_or_default(), tokens);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheStatus {
+    Hit,
+    Miss,
+    Bypassed,
+}
+
+impl fmt::Display for CacheStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheStatus::Hit => write!(f, "hit"),
+            CacheStatus::Miss => write!(f, "miss"),
+            CacheStatus::Bypassed => write!(f, "bypassed"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LlmCompletion {
+    pub completion: String,
+    pub cache: CacheStatus,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolCall {
+    pub id: Option<String>,
+    pub name: String,
+    pub arguments: Value,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LlmResponse {
+    pub text: String,
+    pub tool_calls: Vec<ToolCall>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Objective {
     pub description: String,
@@ -120,6 +283,9 @@ pub struct Agent {
     pub lens_account: Option<String>,
     pub farcaster_account: Option<String>,
     pub objectives: Vec<Objective>,
+    pub objective_similarity: f64,
+    pub examples: Vec<(String, String)>,
+    pub observer: Option<Arc<dyn LlmObserver>>,
 }
 
 pub fn configure_new_agent(
@@ -157,6 +323,9 @@ pub fn configure_new_agent(
         lens_account: lens_account.map(|s| s.to_string()),
         farcaster_account: farcaster_account.map(|s| s.to_string()),
         objectives,
+        objective_similarity: DEFAULT_OBJECTIVE_SIMILARITY,
+        examples: vec![],
+        observer: None,
     };
 
     Ok(agent)
@@ -171,9 +340,182 @@ impl Adaptable for Agent {
     }
 }
 
+const REDACTED: &str = "***";
+
+impl fmt::Debug for LLMModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LLMModel::OpenAI {
+                model,
+                temperature,
+                max_completion_tokens,
+                system_prompt,
+                stream,
+                base_url,
+                ..
+            } => f
+                .debug_struct("OpenAI")
+                .field("api_key", &REDACTED)
+                .field("model", model)
+                .field("temperature", temperature)
+                .field("max_completion_tokens", max_completion_tokens)
+                .field("system_prompt", system_prompt)
+                .field("stream", stream)
+                .field("base_url", base_url)
+                .finish_non_exhaustive(),
+            LLMModel::Claude {
+                model,
+                temperature,
+                max_tokens,
+                system_prompt,
+                version,
+                stream,
+                base_url,
+                ..
+            } => f
+                .debug_struct("Claude")
+                .field("api_key", &REDACTED)
+                .field("model", model)
+                .field("temperature", temperature)
+                .field("max_tokens", max_tokens)
+                .field("system_prompt", system_prompt)
+                .field("version", version)
+                .field("stream", stream)
+                .field("base_url", base_url)
+                .finish_non_exhaustive(),
+            LLMModel::Ollama {
+                model,
+                temperature,
+                max_tokens,
+                system,
+                stream,
+                ..
+            } => f
+                .debug_struct("Ollama")
+                .field("model", model)
+                .field("temperature", temperature)
+                .field("max_tokens", max_tokens)
+                .field("system", system)
+                .field("stream", stream)
+                .finish_non_exhaustive(),
+            LLMModel::Other {
+                url,
+                api_key,
+                body,
+                result_path,
+                result_type,
+            } => f
+                .debug_struct("Other")
+                .field("url", url)
+                .field("api_key", &api_key.as_ref().map(|_| REDACTED))
+                .field("body", body)
+                .field("result_path", result_path)
+                .field("result_type", result_type)
+                .finish(),
+        }
+    }
+}
+
+pub fn default_llm_prices() -> collections::HashMap<String, f64> {
+    [
+        ("gpt-4o", 0.01),
+        ("gpt-4o-mini", 0.0006),
+        ("gpt-4-turbo", 0.03),
+        ("gpt-4", 0.06),
+        ("gpt-3.5-turbo", 0.0015),
+        ("o1-preview", 0.06),
+        ("o1-mini", 0.012),
+        ("claude-3-5-sonnet", 0.015),
+        ("claude-3-5-haiku", 0.004),
+        ("claude-3-opus", 0.075),
+        ("claude-3-sonnet", 0.015),
+        ("claude-3-haiku", 0.00125),
+        ("gemini-1.5-pro", 0.005),
+        ("gemini-1.5-flash", 0.0003),
+        ("gemini-1.0-pro", 0.0015),
+    ]
+    .into_iter()
+    .map(|(model, price)| (model.to_string(), price))
+    .collect()
+}
+
 impl LLMModel {
     pub fn to_json(&self) -> Value {
+        self.to_json_with(false)
+    }
+
+    pub fn with_agent_system(&self, agent_system: &str) -> LLMModel {
+        let mut model = self.clone();
+        match &mut model {
+            LLMModel::OpenAI { system_prompt, .. } | LLMModel::Claude { system_prompt, .. } => {
+                *system_prompt = resolve_system_prompt(system_prompt.as_deref(), agent_system);
+            }
+            LLMModel::Ollama { system, .. } => {
+                *system = resolve_system_prompt(system.as_deref(), agent_system);
+            }
+            LLMModel::Other { .. } => {}
+        }
+        model
+    }
+
+    pub fn model_name(&self) -> Option<&str> {
+        match self {
+            LLMModel::OpenAI { model, .. }
+            | LLMModel::Claude { model, .. }
+            | LLMModel::Ollama { model, .. } => Some(model),
+            LLMModel::Other { body, .. } => body.get("model").map(|model| model.as_str()),
+        }
+    }
+
+    pub fn max_tokens(&self) -> Option<u32> {
         match self {
+            LLMModel::OpenAI {
+                max_completion_tokens,
+                ..
+            } => Some(*max_completion_tokens),
+            LLMModel::Claude { max_tokens, .. } | LLMModel::Ollama { max_tokens, .. } => {
+                Some(*max_tokens)
+            }
+            LLMModel::Other { body, .. } => body
+                .get("max_tokens")
+                .and_then(|max_tokens| max_tokens.parse::<u32>().ok()),
+        }
+    }
+
+    pub fn temperature(&self) -> Option<f32> {
+        match self {
+            LLMModel::OpenAI { temperature, .. }
+            | LLMModel::Claude { temperature, .. }
+            | LLMModel::Ollama { temperature, .. } => Some(*temperature),
+            LLMModel::Other { body, .. } => body
+                .get("temperature")
+                .and_then(|temperature| temperature.parse::<f32>().ok()),
+        }
+    }
+
+    pub fn cache_key(&self, input_prompt: &str) -> String {
+        let fingerprint = self.to_json_with(true).to_string();
+        let digest = ethers::utils::keccak256(format!("{}\n{}", fingerprint, input_prompt));
+        ethers::utils::hex::encode(digest)
+    }
+
+    pub fn price_per_1k_tokens(&self, prices: &collections::HashMap<String, f64>) -> Option<f64> {
+        if let LLMModel::Ollama { .. } = self {
+            return Some(0.0);
+        }
+
+        let model = self.model_name()?;
+        prices.get(model).copied().or_else(|| {
+            prices
+                .iter()
+                .filter(|(name, _)| model.starts_with(name.as_str()))
+                .max_by_key(|(name, _)| name.len())
+                .map(|(_, price)| *price)
+        })
+    }
+
+    pub fn to_json_with(&self, redacted: bool) -> Value {
+        let mut value = match self {
             LLMModel::OpenAI {
                 api_key,
                 model,
@@ -193,7 +535,10 @@ impl LLMModel {
                 response_format,
                 stream,
                 parallel_tool_calls,
+                tools,
+                tool_choice,
                 user,
+                base_url,
             } => {
                 let mut map = Map::new();
                 map.insert("type".to_string(), Value::String("OpenAI".to_string()));
@@ -259,6 +604,12 @@ impl LLMModel {
                         Value::Bool(*parallel_tool_calls),
                     );
                 }
+                if let Some(tools) = tools {
+                    map.insert("tools".to_string(), Value::Array(tools.clone()));
+                }
+                if let Some(tool_choice) = tool_choice {
+                    map.insert("tool_choice".to_string(), tool_choice.clone());
+                }
                 if let Some(user) = user {
                     map.insert("user".to_string(), Value::String(user.clone()));
                 }
@@ -266,6 +617,9 @@ impl LLMModel {
                 if let Some(prompt) = system_prompt {
                     map.insert("system_prompt".to_string(), Value::String(prompt.clone()));
                 }
+                if let Some(base_url) = base_url {
+                    map.insert("base_url".to_string(), Value::String(base_url.clone()));
+                }
                 Value::Object(map)
             }
             LLMModel::Claude {
@@ -282,6 +636,7 @@ impl LLMModel {
                 metadata,
                 tool_choice,
                 tools,
+                base_url,
             } => {
                 let mut map = Map::new();
                 map.insert("type".to_string(), Value::String("Claude".to_string()));
@@ -317,10 +672,10 @@ impl LLMModel {
                     map.insert("tool_choice".to_string(), tool_choice.clone());
                 }
                 if let Some(tools) = tools {
-                    map.insert(
-                        "tools".to_string(),
-                        Value::Array(tools.iter().map(|t| t.clone()).collect()),
-                    );
+                    map.insert("tools".to_string(), Value::Array(tools.to_vec()));
+                }
+                if let Some(base_url) = base_url {
+                    map.insert("base_url".to_string(), Value::String(base_url.clone()));
                 }
                 Value::Object(map)
             }
@@ -438,7 +793,14 @@ impl LLMModel {
                 map.insert("body".to_string(), Value::Object(body_map));
                 Value::Object(map)
             }
+        };
+
+        if redacted {
+            if let Some(api_key) = value.get_mut("api_key") {
+                *api_key = Value::String(REDACTED.to_string());
+            }
         }
+        value
     }
 }
 
@@ -446,6 +808,7 @@ impl Agent {
     pub fn to_json(&self) -> Map<String, Value> {
         let mut map = Map::new();
         map.insert("name".to_string(), Value::String(self.name.clone()));
+        map.insert("id".to_string(), Value::String(self.id.clone()));
         map.insert("role".to_string(), Value::String(self.role.clone()));
         map.insert(
             "personality".to_string(),
@@ -467,6 +830,17 @@ impl Agent {
         );
         map.insert("write_role".to_string(), Value::Bool(self.write_role));
         map.insert("admin_role".to_string(), Value::Bool(self.admin_role));
+        if !self.examples.is_empty() {
+            map.insert(
+                "examples".to_string(),
+                Value::Array(
+                    self.examples
+                        .iter()
+                        .map(|(input, output)| json!({"input": input, "output": output}))
+                        .collect(),
+                ),
+            );
+        }
         map
     }
 
@@ -474,7 +848,55 @@ impl Agent {
         &self,
         input_prompt: &str,
     ) -> Result<String, Box<dyn Error + Send + Sync>> {
-        Ok(call_llm_api(&self.model, input_prompt).await?)
+        Ok(call_llm_api_observed(
+            &self.model.with_agent_system(&self.system),
+            input_prompt,
+            &self.examples,
+            self.observer.as_deref(),
+        )
+        .await?
+        .text)
+    }
+
+    pub async fn execute_agent_with_value(
+        &self,
+        input: &Value,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        self.execute_agent(&prompt_from_value(input)).await
+    }
+
+    pub async fn execute_agent_with_tools(
+        &self,
+        input_prompt: &str,
+    ) -> Result<LlmResponse, Box<dyn Error + Send + Sync>> {
+        call_llm_api_observed(
+            &self.model.with_agent_system(&self.system),
+            input_prompt,
+            &self.examples,
+            self.observer.as_deref(),
+        )
+        .await
+    }
+
+    pub async fn execute_agent_with_cache(
+        &self,
+        input_prompt: &str,
+        cache: Option<&dyn LlmCache>,
+        force_cache: bool,
+    ) -> Result<LlmCompletion, Box<dyn Error + Send + Sync>> {
+        call_llm_api_with_cache(
+            &self.model.with_agent_system(&self.system),
+            input_prompt,
+            &self.examples,
+            cache,
+            force_cache,
+            self.observer.as_deref(),
+        )
+        .await
+    }
+
+    pub fn add_example(&mut self, input: &str, output: &str) {
+        self.examples.push((input.to_string(), output.to_string()));
     }
 
     pub fn add_objective(&mut self, description: &str, priority: u8, generated: bool) {
@@ -484,7 +906,30 @@ impl Agent {
             generated,
         };
         self.objectives.push(objective);
-        self.objectives.sort_by(|a, b| b.priority.cmp(&a.priority));
+        self.objectives
+            .sort_by_key(|objective| Reverse(objective.priority));
+    }
+
+    pub fn dedup_objectives(&mut self) {
+        self.objectives
+            .sort_by_key(|objective| Reverse(objective.priority));
+
+        let threshold = self.objective_similarity;
+        let mut kept: Vec<(Objective, HashSet<String>)> = Vec::new();
+        for objective in self.objectives.drain(..) {
+            let tokens = objective_tokens(&objective.description);
+            match kept
+                .iter_mut()
+                .find(|(_, kept_tokens)| jaccard(kept_tokens, &tokens) >= threshold)
+            {
+                Some((existing, _)) => {
+                    existing.generated = existing.generated && objective.generated;
+                }
+                None => kept.push((objective, tokens)),
+            }
+        }
+
+        self.objectives = kept.into_iter().map(|(objective, _)| objective).collect();
     }
 
     pub async fn generate_objectives(
@@ -498,6 +943,14 @@ impl Agent {
 
         let generated_objective = self.execute_agent(&prompt).await?;
 
+        let json_objectives = parse_json_objectives(&generated_objective);
+        if !json_objectives.is_empty() {
+            for (description, priority) in json_objectives {
+                self.add_objective(&description, priority, true);
+            }
+            self.dedup_objectives();
+            return Ok(());
+        }
 
         let re = Regex::new(
             r"(?i)(objective|goal|task|focus|priority):?\s*(?P<description>.+?)\s*(,|;|:|\.)?\s*(priority|rank|importance):?\s*(?P<priority>\d+)",
@@ -519,8 +972,9 @@ impl Agent {
 
         if !found_match {
             eprintln!("Regex did not match. Applying fallback strategy.");
+            let priority_regex = Regex::new(r"(?P<priority>\d+)").unwrap();
             for line in generated_objective.lines() {
-                if let Some(priority_match) = Regex::new(r"(?P<priority>\d+)").unwrap().find(line) {
+                if let Some(priority_match) = priority_regex.find(line) {
                     let priority: u8 = priority_match.as_str().parse().unwrap_or(1);
                     let description = line.replace(priority_match.as_str(), "").trim().to_string();
                     if !description.is_empty() {
@@ -532,14 +986,320 @@ impl Agent {
             }
         }
 
+        self.dedup_objectives();
+
         Ok(())
     }
 }
 
+fn parse_json_objectives(response: &str) -> Vec<(String, u8)> {
+    let (start, end) = match (response.find('['), response.rfind(']')) {
+        (Some(start), Some(end)) if start < end => (start, end),
+        _ => return vec![],
+    };
+
+    let items: Vec<Value> = match from_str(&response[start..=end]) {
+        Ok(items) => items,
+        Err(_) => return vec![],
+    };
+
+    items
+        .iter()
+        .filter_map(|item| {
+            let description = item.get("description")?.as_str()?.trim();
+            let priority = match item.get("priority")? {
+                Value::Number(number) => number.as_u64()?,
+                Value::String(priority) => priority.trim().parse().ok()?,
+                _ => return None,
+            };
+            if description.is_empty() {
+                return None;
+            }
+            Some((description.to_string(), priority.min(u8::MAX as u64) as u8))
+        })
+        .collect()
+}
+
+const OBJECTIVE_STOPWORDS: [&str; 10] =
+    ["a", "an", "the", "and", "or", "of", "to", "for", "in", "on"];
+
+fn objective_tokens(description: &str) -> HashSet<String> {
+    description
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty() && !OBJECTIVE_STOPWORDS.contains(token))
+        .map(|token| token.to_string())
+        .collect()
+}
+
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    a.intersection(b).count() as f64 / a.union(b).count() as f64
+}
+
+pub async fn call_llm_api_with_cache(
+    model_type: &LLMModel,
+    input_prompt: &str,
+    examples: &[(String, String)],
+    cache: Option<&dyn LlmCache>,
+    force_cache: bool,
+    observer: Option<&dyn LlmObserver>,
+) -> Result<LlmCompletion, Box<dyn Error + Send + Sync>> {
+    let cache = cache.filter(|_| force_cache || model_type.temperature() == Some(0.0));
+
+    let cache = match cache {
+        Some(cache) => cache,
+        None => {
+            return Ok(LlmCompletion {
+                completion: call_llm_api_observed(model_type, input_prompt, examples, observer)
+                    .await?
+                    .text,
+                cache: CacheStatus::Bypassed,
+            })
+        }
+    };
+
+    let key = if examples.is_empty() {
+        model_type.cache_key(input_prompt)
+    } else {
+        model_type.cache_key(&format!("{}\n{}", json!(examples), input_prompt))
+    };
+    if let Some(completion) = cache.get(&key) {
+        return Ok(LlmCompletion {
+            completion,
+            cache: CacheStatus::Hit,
+        });
+    }
+
+    let completion = call_llm_api_observed(model_type, input_prompt, examples, observer)
+        .await?
+        .text;
+    cache.put(&key, completion.clone());
+    Ok(LlmCompletion {
+        completion,
+        cache: CacheStatus::Miss,
+    })
+}
+
 pub async fn call_llm_api(
     model_type: &LLMModel,
     input_prompt: &str,
 ) -> Result<String, Box<dyn Error + Send + Sync>> {
+    call_llm_api_with_examples(model_type, input_prompt, &[]).await
+}
+
+pub async fn call_embeddings_api(
+    model_type: &LLMModel,
+    input: &str,
+) -> Result<Vec<f32>, Box<dyn Error + Send + Sync>> {
+    let client = reqwest::Client::new();
+
+    let (response, pointer) = match model_type {
+        LLMModel::OpenAI {
+            api_key,
+            model,
+            base_url,
+            ..
+        } => (
+            client
+                .post(format!(
+                    "{}/embeddings",
+                    base_url
+                        .as_deref()
+                        .unwrap_or(OPENAI_BASE_URL)
+                        .trim_end_matches('/')
+                ))
+                .header("Authorization", format!("Bearer {}", api_key))
+                .json(&json!({
+                    "model": model,
+                    "input": input,
+                }))
+                .send()
+                .await?,
+            "/data/0/embedding",
+        ),
+        LLMModel::Ollama { model, .. } => (
+            client
+                .post(format!("{}/api/embeddings", OLLAMA_BASE_URL))
+                .json(&json!({
+                    "model": model,
+                    "prompt": input,
+                }))
+                .send()
+                .await?,
+            "/embedding",
+        ),
+        _ => return Err("Embeddings are only supported for OpenAI and Ollama models".into()),
+    };
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_body = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(format!(
+            "Embeddings API returned an error: status = {}, body = {}",
+            status, error_body
+        )
+        .into());
+    }
+
+    let response_json: Value = response.json().await?;
+    response_json
+        .pointer(pointer)
+        .and_then(|v| v.as_array())
+        .ok_or("Embeddings response has no embedding")?
+        .iter()
+        .map(|v| {
+            v.as_f64()
+                .map(|v| v as f32)
+                .ok_or_else(|| "Embedding contains a non-numeric value".into())
+        })
+        .collect()
+}
+
+pub fn prompt_from_value(input: &Value) -> String {
+    match input {
+        Value::String(input) => input.clone(),
+        Value::Null => String::new(),
+        Value::Object(_) | Value::Array(_) => format!(
+            "Context:\n```json\n{}\n```",
+            serde_json::to_string_pretty(input).unwrap_or_else(|_| input.to_string())
+        ),
+        input => input.to_string(),
+    }
+}
+
+fn resolve_result_path<'a>(
+    response: &'a Value,
+    result_path: &str,
+) -> Result<&'a Value, Box<dyn Error + Send + Sync>> {
+    if result_path.starts_with('/') {
+        return response
+            .pointer(result_path)
+            .ok_or_else(|| format!("Result path '{}' not found in response", result_path).into());
+    }
+
+    let result_path = match result_path {
+        "" if response.get("choices").is_some() => "choices.0.message.content",
+        "" => return Ok(response),
+        result_path => result_path,
+    };
+
+    let mut current_value = response;
+    for segment in result_path.split('.') {
+        let next_value = match current_value {
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+            value => value.get(segment),
+        };
+        current_value = next_value
+            .ok_or_else(|| format!("Path segment '{}' not found in response", segment))?;
+    }
+    Ok(current_value)
+}
+
+fn example_messages(examples: &[(String, String)]) -> Vec<Value> {
+    examples
+        .iter()
+        .flat_map(|(input, output)| {
+            [
+                json!({
+                    "role": "user",
+                    "content": input
+                }),
+                json!({
+                    "role": "assistant",
+                    "content": output
+                }),
+            ]
+        })
+        .collect()
+}
+
+pub async fn call_llm_api_with_examples(
+    model_type: &LLMModel,
+    input_prompt: &str,
+    examples: &[(String, String)],
+) -> Result<String, Box<dyn Error + Send + Sync>> {
+    Ok(call_llm_api_with_tools(model_type, input_prompt, examples)
+        .await?
+        .text)
+}
+
+/// The model's system prompt comes first and the agent's `system` is appended after a blank
+/// line; whichever is empty is dropped.
+pub fn resolve_system_prompt(model_system: Option<&str>, agent_system: &str) -> Option<String> {
+    let parts: Vec<&str> = [model_system.unwrap_or_default(), agent_system]
+        .into_iter()
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect();
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join("\n\n"))
+    }
+}
+
+pub async fn call_llm_api_with_tools(
+    model_type: &LLMModel,
+    input_prompt: &str,
+    examples: &[(String, String)],
+) -> Result<LlmResponse, Box<dyn Error + Send + Sync>> {
+    call_llm_api_observed(model_type, input_prompt, examples, None).await
+}
+
+fn redact_secrets(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let lowercase = key.to_lowercase();
+                    if ["api_key", "apikey", "secret", "authorization"]
+                        .iter()
+                        .any(|secret| lowercase.contains(secret))
+                    {
+                        (key.clone(), Value::String(REDACTED.to_string()))
+                    } else {
+                        (key.clone(), redact_secrets(value))
+                    }
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(redact_secrets).collect()),
+        value => value.clone(),
+    }
+}
+
+fn observe_request(observer: Option<&dyn LlmObserver>, model_type: &LLMModel, body: &Value) {
+    if let Some(observer) = observer {
+        observer.on_request(
+            model_type.model_name().unwrap_or_default(),
+            &redact_secrets(body),
+        );
+    }
+}
+
+async fn observed_text(
+    observer: Option<&dyn LlmObserver>,
+    response: reqwest::Response,
+) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let raw = response.text().await?;
+    if let Some(observer) = observer {
+        observer.on_response(&raw);
+    }
+    Ok(raw)
+}
+
+pub async fn call_llm_api_observed(
+    model_type: &LLMModel,
+    input_prompt: &str,
+    examples: &[(String, String)],
+    observer: Option<&dyn LlmObserver>,
+) -> Result<LlmResponse, Box<dyn Error + Send + Sync>> {
     match &model_type {
         LLMModel::OpenAI {
             api_key,
@@ -560,7 +1320,10 @@ pub async fn call_llm_api(
             response_format,
             stream,
             parallel_tool_calls,
+            tools,
+            tool_choice,
             user,
+            base_url,
         } => {
             let mut messages = vec![];
 
@@ -571,6 +1334,7 @@ pub async fn call_llm_api(
                 }));
             }
 
+            messages.extend(example_messages(examples));
             messages.push(json!({
                 "role": "user",
                 "content": input_prompt
@@ -618,12 +1382,25 @@ pub async fn call_llm_api(
             if let Some(parallel_tool_calls) = parallel_tool_calls {
                 request_body["parallel_tool_calls"] = json!(parallel_tool_calls);
             }
+            if let Some(tools) = tools {
+                request_body["tools"] = json!(tools);
+            }
+            if let Some(tool_choice) = tool_choice {
+                request_body["tool_choice"] = tool_choice.clone();
+            }
             if let Some(user) = user {
                 request_body["user"] = json!(user);
             }
 
+            observe_request(observer, model_type, &request_body);
             let response = client
-                .post("https://api.openai.com/v1/chat/completions")
+                .post(format!(
+                    "{}/chat/completions",
+                    base_url
+                        .as_deref()
+                        .unwrap_or(OPENAI_BASE_URL)
+                        .trim_end_matches('/')
+                ))
                 .header("Authorization", format!("Bearer {}", api_key))
                 .json(&request_body)
                 .send()
@@ -637,12 +1414,42 @@ pub async fn call_llm_api(
                 }
             };
 
-            let response_json: Value = response.json().await?;
+            let response_json: Value = from_str(&observed_text(observer, response).await?)?;
             let completion = response_json["choices"][0]["message"]["content"]
                 .as_str()
                 .unwrap_or("")
                 .to_string();
-            Ok(completion)
+            #[cfg(feature = "metrics")]
+            record_token_usage(model_type, &response_json);
+            check_truncation(&response_json, &completion)?;
+
+            let tool_calls = response_json["choices"][0]["message"]["tool_calls"]
+                .as_array()
+                .map(|calls| {
+                    calls
+                        .iter()
+                        .filter_map(|call| {
+                            let function = call.get("function")?;
+                            let arguments = match function.get("arguments") {
+                                Some(Value::String(arguments)) => from_str(arguments)
+                                    .unwrap_or_else(|_| Value::String(arguments.clone())),
+                                Some(arguments) => arguments.clone(),
+                                None => Value::Null,
+                            };
+                            Some(ToolCall {
+                                id: call.get("id").and_then(|v| v.as_str()).map(String::from),
+                                name: function.get("name")?.as_str()?.to_string(),
+                                arguments,
+                            })
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            Ok(LlmResponse {
+                text: completion,
+                tool_calls,
+            })
         }
         LLMModel::Claude {
             api_key,
@@ -658,15 +1465,19 @@ pub async fn call_llm_api(
             metadata,
             tool_choice,
             tools,
+            base_url,
         } => {
             let client = reqwest::Client::new();
 
+            let mut messages = example_messages(examples);
+            messages.push(json!({
+                "role": "user",
+                "content": input_prompt
+            }));
+
             let mut request_body = json!({
                 "model": model,
-                "messages": vec![json!({
-                    "role": "user",
-                    "content": input_prompt
-                })],
+                "messages": messages,
                 "temperature": temperature,
                 "max_tokens": max_tokens,
                 "system": system_prompt,
@@ -692,8 +1503,15 @@ pub async fn call_llm_api(
 
             request_body["stream"] = json!(stream);
 
+            observe_request(observer, model_type, &request_body);
             let response = client
-                .post("https://api.anthropic.com/v1/messages")
+                .post(format!(
+                    "{}/messages",
+                    base_url
+                        .as_deref()
+                        .unwrap_or(ANTHROPIC_BASE_URL)
+                        .trim_end_matches('/')
+                ))
                 .header("x-api-key", api_key)
                 .header("anthropic-version", version)
                 .json(&request_body)
@@ -708,7 +1526,7 @@ pub async fn call_llm_api(
                 }
             };
 
-            let response_json: Value = response.json().await?;
+            let response_json: Value = from_str(&observed_text(observer, response).await?)?;
             let completion = response_json["content"]
                 .as_array()
                 .and_then(|arr| {
@@ -717,8 +1535,31 @@ pub async fn call_llm_api(
                 })
                 .unwrap_or("")
                 .to_string();
+            #[cfg(feature = "metrics")]
+            record_token_usage(model_type, &response_json);
+            check_truncation(&response_json, &completion)?;
+
+            let tool_calls = response_json["content"]
+                .as_array()
+                .map(|blocks| {
+                    blocks
+                        .iter()
+                        .filter(|block| block["type"] == "tool_use")
+                        .filter_map(|block| {
+                            Some(ToolCall {
+                                id: block.get("id").and_then(|v| v.as_str()).map(String::from),
+                                name: block.get("name")?.as_str()?.to_string(),
+                                arguments: block.get("input").cloned().unwrap_or(Value::Null),
+                            })
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
 
-            Ok(completion)
+            Ok(LlmResponse {
+                text: completion,
+                tool_calls,
+            })
         }
         LLMModel::Ollama {
             model,
@@ -740,9 +1581,15 @@ pub async fn call_llm_api(
         } => {
             let client = reqwest::Client::new();
 
+            let prompt = examples
+                .iter()
+                .map(|(input, output)| format!("User: {}\nAssistant: {}\n\n", input, output))
+                .chain(std::iter::once(input_prompt.to_string()))
+                .collect::<String>();
+
             let mut request_body = json!({
                 "model": model,
-                "prompt": input_prompt,
+                "prompt": prompt,
                 "temperature": temperature,
                 "max_tokens": max_tokens,
                 "top_p": top_p,
@@ -781,8 +1628,9 @@ pub async fn call_llm_api(
                 request_body["images"] = json!(images);
             }
 
+            observe_request(observer, model_type, &request_body);
             let response = client
-                .post("http://localhost:11434/api/generate")
+                .post(format!("{}/api/generate", OLLAMA_BASE_URL))
                 .json(&request_body)
                 .send()
                 .await;
@@ -804,8 +1652,9 @@ pub async fn call_llm_api(
             }
 
             let mut completion = String::new();
+            let mut done_reason = Value::Null;
 
-            let raw_response = response.text().await?;
+            let raw_response = observed_text(observer, response).await?;
 
             for line in raw_response.lines() {
                 if line.trim().is_empty() {
@@ -817,14 +1666,23 @@ pub async fn call_llm_api(
                         if let Some(resp) = json.get("response").and_then(|r| r.as_str()) {
                             completion.push_str(resp);
                         }
+                        if json.get("done_reason").is_some() {
+                            done_reason = json;
+                        }
                     }
                     Err(e) => {
                         eprintln!("Error processing JSON: {}. Error: {}", line, e);
                     }
                 }
             }
-
-            Ok(completion)
+            #[cfg(feature = "metrics")]
+            record_token_usage(model_type, &done_reason);
+            check_truncation(&done_reason, &completion)?;
+
+            Ok(LlmResponse {
+                text: completion,
+                tool_calls: vec![],
+            })
         }
         LLMModel::Other {
             url,
@@ -837,7 +1695,24 @@ pub async fn call_llm_api(
 
             let mut body_json = Map::new();
             for (key, value) in body {
-                body_json.insert(key.clone(), Value::String(value.clone()));
+                body_json.insert(
+                    key.clone(),
+                    from_str(value).unwrap_or_else(|_| Value::String(value.clone())),
+                );
+            }
+            match body_json.get_mut("messages") {
+                Some(Value::Array(messages)) => {
+                    messages.extend(example_messages(examples));
+                    messages.push(json!({
+                        "role": "user",
+                        "content": input_prompt
+                    }));
+                }
+                _ => {
+                    body_json
+                        .entry("prompt")
+                        .or_insert_with(|| Value::String(input_prompt.to_string()));
+                }
             }
 
             let mut request = client.post(url);
@@ -845,9 +1720,9 @@ pub async fn call_llm_api(
                 request = request.header("Authorization", format!("Bearer {}", api_key));
             }
 
+            observe_request(observer, model_type, &Value::Object(body_json.clone()));
             let response = request.json(&body_json).send().await;
 
-
             let response = match response {
                 Ok(resp) => resp,
                 Err(e) => {
@@ -874,33 +1749,45 @@ pub async fn call_llm_api(
                 .into());
             }
 
+            let response_json: Value = from_str(&observed_text(observer, response).await?)?;
+            let current_value = resolve_result_path(&response_json, result_path)?;
 
-            let response_json: Value = response.json().await?;
-            let path_segments: Vec<&str> = result_path.split('.').collect();
-            let mut current_value = &response_json;
-
-            for segment in path_segments {
-                if let Some(next_value) = current_value.get(segment) {
-                    current_value = next_value;
-                } else {
-                    return Err(format!("Path segment '{}' not found in response", segment).into());
-                }
-            }
-
-            match result_type.as_str() {
-                "string" => Ok(current_value.as_str().unwrap_or("").to_string()),
-                "number" => Ok(current_value.as_f64().unwrap_or(0.0).to_string()),
-                "boolean" => Ok(current_value.as_bool().unwrap_or(false).to_string()),
-                "array" => Ok(current_value
+            let completion = match result_type.as_str() {
+                "string" | "" => match current_value {
+                    Value::String(value) => value.clone(),
+                    Value::Null => String::new(),
+                    value => value.to_string(),
+                },
+                "array-join" => current_value
+                    .as_array()
+                    .ok_or("Result is not an array")?
+                    .iter()
+                    .map(|item| match item {
+                        Value::String(item) => item.clone(),
+                        item => item.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                "number" => current_value.as_f64().unwrap_or(0.0).to_string(),
+                "boolean" => current_value.as_bool().unwrap_or(false).to_string(),
+                "array" => current_value
                     .as_array()
                     .map(|arr| to_string(arr).unwrap_or("[]".to_string()))
-                    .unwrap_or("[]".to_string())),
-                "object" => Ok(current_value
+                    .unwrap_or("[]".to_string()),
+                "object" => current_value
                     .as_object()
                     .map(|obj| to_string(obj).unwrap_or("{}".to_string()))
-                    .unwrap_or("{}".to_string())),
-                _ => Err("Unsupported result type or type not specified".into()),
-            }
+                    .unwrap_or("{}".to_string()),
+                _ => return Err("Unsupported result type or type not specified".into()),
+            };
+            #[cfg(feature = "metrics")]
+            record_token_usage(model_type, &response_json);
+            check_truncation(&response_json, &completion)?;
+
+            Ok(LlmResponse {
+                text: completion,
+                tool_calls: vec![],
+            })
         }
     }
 }
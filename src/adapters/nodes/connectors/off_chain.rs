@@ -1,12 +1,12 @@
 use crate::{
-    nibble::Adaptable,
+    nibble::{Adaptable, RateLimiter},
     tools::history::HistoryParse,
     utils::generate_unique_id,
     workflow::{SubflowManager, Workflow},
 };
 use core::fmt;
 use ethers::types::H160;
-use reqwest::{Client, Method};
+use reqwest::{Client, Method, RequestBuilder, Response, StatusCode};
 use serde_json::{json, Map, Value};
 use std::{collections::HashMap, error::Error, io, sync::Arc};
 use tokio::sync::Mutex;
@@ -20,6 +20,46 @@ pub enum ConnectorType {
         query: String,
         variables: Option<HashMap<String, String>>,
     },
+    ContextBody {
+        body_key: Option<String>,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct PaginationConfig {
+    pub items_path: Vec<String>,
+    pub cursor_path: Vec<String>,
+    pub cursor_variable: String,
+    pub max_pages: u32,
+}
+
+impl PaginationConfig {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "items_path": self.items_path,
+            "cursor_path": self.cursor_path,
+            "cursor_variable": self.cursor_variable,
+            "max_pages": self.max_pages,
+        })
+    }
+
+    pub fn from_json(value: &Value) -> Option<Self> {
+        let path = |key: &str| -> Option<Vec<String>> {
+            value
+                .get(key)?
+                .as_array()?
+                .iter()
+                .map(|segment| segment.as_str().map(|segment| segment.to_string()))
+                .collect()
+        };
+
+        Some(Self {
+            items_path: path("items_path")?,
+            cursor_path: path("cursor_path")?,
+            cursor_variable: value.get("cursor_variable")?.as_str()?.to_string(),
+            max_pages: u32::try_from(value.get("max_pages")?.as_u64()?).ok()?,
+        })
+    }
 }
 
 #[derive(Clone)]
@@ -36,6 +76,8 @@ pub struct OffChainConnector {
     pub auth_subflow: Option<Workflow>,
     pub result_processing_fn:
         Option<Arc<dyn Fn(Value) -> Result<Value, Box<dyn Error + Send + Sync>> + Send + Sync>>,
+    pub refreshed_auth_tokens: Arc<Mutex<Option<Value>>>,
+    pub pagination: Option<PaginationConfig>,
 }
 
 impl fmt::Debug for OffChainConnector {
@@ -50,6 +92,7 @@ impl fmt::Debug for OffChainConnector {
             .field("headers", &self.headers)
             .field("params", &self.params)
             .field("auth_tokens", &self.auth_tokens)
+            .field("pagination", &self.pagination)
             .field(
                 "result_processing_fn",
                 &self
@@ -70,9 +113,169 @@ impl OffChainConnector {
         dynamic_values: Option<Value>,
         subflow_manager: Option<&SubflowManager>,
         history_tool: Option<HistoryParse>,
+        rate_limiter: Option<&RateLimiter>,
     ) -> Result<Value, Box<dyn Error + Send + Sync>> {
         let client = Client::new();
-        let mut url = self.api_url.clone();
+        let mut auth_tokens = self.refreshed_auth_tokens.lock().await.clone();
+
+        if auth_tokens.is_none() && self.auth_tokens.is_none() && self.auth_subflow.is_some() {
+            auth_tokens = Some(
+                self.refresh_auth_tokens(subflow_manager, history_tool.as_ref())
+                    .await?,
+            );
+        }
+
+        let response_data = match &self.pagination {
+            Some(pagination) => {
+                self.fetch_all_pages(
+                    pagination,
+                    &client,
+                    dynamic_values.as_ref(),
+                    &mut auth_tokens,
+                    subflow_manager,
+                    history_tool.as_ref(),
+                    rate_limiter,
+                )
+                .await?
+            }
+            None => {
+                self.fetch(
+                    &client,
+                    dynamic_values.as_ref(),
+                    &mut auth_tokens,
+                    subflow_manager,
+                    history_tool.as_ref(),
+                    rate_limiter,
+                )
+                .await?
+            }
+        };
+
+        if let Some(exec_fn) = &self.result_processing_fn {
+            return exec_fn(response_data);
+        }
+
+        Ok(response_data)
+    }
+
+    pub fn with_pagination(mut self, pagination: PaginationConfig) -> Self {
+        self.pagination = Some(pagination);
+        self
+    }
+
+    async fn fetch(
+        &self,
+        client: &Client,
+        dynamic_values: Option<&Value>,
+        auth_tokens: &mut Option<Value>,
+        subflow_manager: Option<&SubflowManager>,
+        history_tool: Option<&HistoryParse>,
+        rate_limiter: Option<&RateLimiter>,
+    ) -> Result<Value, Box<dyn Error + Send + Sync>> {
+        let request = self.build_request(client, dynamic_values, auth_tokens.as_ref())?;
+        let mut response = self.send(client, request, rate_limiter).await?;
+
+        if response.status() == StatusCode::UNAUTHORIZED && self.auth_subflow.is_some() {
+            println!(
+                "Connector {} returned 401, refreshing auth tokens",
+                self.name
+            );
+            let refreshed = self
+                .refresh_auth_tokens(subflow_manager, history_tool)
+                .await?;
+            let request = self.build_request(client, dynamic_values, Some(&refreshed))?;
+            response = self.send(client, request, rate_limiter).await?;
+            *auth_tokens = Some(refreshed);
+        }
+
+        Ok(response.json().await?)
+    }
+
+    async fn send(
+        &self,
+        client: &Client,
+        request: RequestBuilder,
+        rate_limiter: Option<&RateLimiter>,
+    ) -> Result<Response, Box<dyn Error + Send + Sync>> {
+        let request = request.build()?;
+
+        if let (Some(rate_limiter), Some(host)) = (rate_limiter, request.url().host_str()) {
+            rate_limiter.acquire(host).await;
+        }
+
+        Ok(client.execute(request).await?)
+    }
+
+    async fn fetch_all_pages(
+        &self,
+        pagination: &PaginationConfig,
+        client: &Client,
+        dynamic_values: Option<&Value>,
+        auth_tokens: &mut Option<Value>,
+        subflow_manager: Option<&SubflowManager>,
+        history_tool: Option<&HistoryParse>,
+        rate_limiter: Option<&RateLimiter>,
+    ) -> Result<Value, Box<dyn Error + Send + Sync>> {
+        let items_pointer = json_pointer(&pagination.items_path);
+        let cursor_pointer = json_pointer(&pagination.cursor_path);
+        let mut merged: Option<Value> = None;
+        let mut items = Vec::new();
+        let mut cursor: Option<Value> = None;
+
+        for _ in 0..pagination.max_pages.max(1) {
+            let page_values = match &cursor {
+                Some(cursor) => {
+                    let mut values = dynamic_values.cloned().unwrap_or_else(|| json!({}));
+                    values
+                        .as_object_mut()
+                        .ok_or("Paginated connectors require an object context")?
+                        .insert(pagination.cursor_variable.clone(), cursor.clone());
+                    Some(values)
+                }
+                None => dynamic_values.cloned(),
+            };
+
+            let page = self
+                .fetch(
+                    client,
+                    page_values.as_ref(),
+                    auth_tokens,
+                    subflow_manager,
+                    history_tool,
+                    rate_limiter,
+                )
+                .await?;
+
+            if let Some(page_items) = page.pointer(&items_pointer).and_then(|v| v.as_array()) {
+                items.extend(page_items.iter().cloned());
+            }
+            cursor = page
+                .pointer(&cursor_pointer)
+                .filter(|next| !next.is_null())
+                .cloned();
+            merged.get_or_insert(page);
+
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        let mut merged = merged.ok_or("Pagination fetched no pages")?;
+        if let Some(slot) = merged.pointer_mut(&items_pointer) {
+            *slot = Value::Array(items);
+        }
+        if let Some(slot) = merged.pointer_mut(&cursor_pointer) {
+            *slot = cursor.unwrap_or(Value::Null);
+        }
+
+        Ok(merged)
+    }
+
+    async fn refresh_auth_tokens(
+        &self,
+        subflow_manager: Option<&SubflowManager>,
+        history_tool: Option<&HistoryParse>,
+    ) -> Result<Value, Box<dyn Error + Send + Sync>> {
         let mut auth_tokens: Option<Value> = None;
 
         if let Some(subflow) = &self.auth_subflow {
@@ -91,7 +294,7 @@ impl OffChainConnector {
                     Some(Ok(execution_history)) => {
                         println!("Auth subflow executed. History: {:?}", execution_history);
 
-                        if let Some(tool) = &history_tool {
+                        if let Some(tool) = history_tool {
                             match tool.process(execution_history) {
                                 Ok(parsed_value) => {
                                     auth_tokens = Some(parsed_value.clone());
@@ -101,8 +304,12 @@ impl OffChainConnector {
                                     );
                                 }
                                 Err(e) => {
-                                    eprintln!("Error processing history with tool: {}", e);
-                                    return Err(Box::new(io::Error::new(io::ErrorKind::Other, e)));
+                                    if let HistoryParse::Invalid { .. } = tool {
+                                        eprintln!("History tool is misconfigured: {}", e);
+                                    } else {
+                                        eprintln!("Error processing history with tool: {}", e);
+                                    }
+                                    return Err(Box::new(io::Error::other(e)));
                                 }
                             }
                         } else {
@@ -114,7 +321,7 @@ impl OffChainConnector {
                     }
                     Some(Err(e)) => {
                         eprintln!("Auth subflow execution failed: {}", e);
-                        return Err(Box::new(io::Error::new(io::ErrorKind::Other, e)));
+                        return Err(Box::new(io::Error::other(e)));
                     }
                     None => {
                         eprintln!("Auth subflow returned no history.");
@@ -126,32 +333,65 @@ impl OffChainConnector {
             }
         }
 
-        if let Some(params) = &self.params {
-            let query: Vec<String> = params.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
-            url = format!("{}?{}", url, query.join("&"));
+        let auth_tokens = auth_tokens.ok_or("Auth subflow did not produce tokens")?;
+        *self.refreshed_auth_tokens.lock().await = Some(auth_tokens.clone());
+        Ok(auth_tokens)
+    }
+
+    fn build_request(
+        &self,
+        client: &Client,
+        dynamic_values: Option<&Value>,
+        auth_tokens: Option<&Value>,
+    ) -> Result<RequestBuilder, Box<dyn Error + Send + Sync>> {
+        let context_values = dynamic_values
+            .and_then(|v| v.as_object())
+            .cloned()
+            .unwrap_or_default();
+        let is_get = self.http_method == Method::GET;
+        let context_as_query =
+            is_get && !matches!(self.connector_type, ConnectorType::GraphQL { .. });
+
+        let template_values =
+            template_values(self.auth_tokens.as_ref(), auth_tokens, &context_values);
+        let url = render_template(&self.api_url, &template_values)?;
+
+        let mut query_params: Vec<(String, String)> = self
+            .params
+            .iter()
+            .flatten()
+            .map(|(key, value)| Ok((key.clone(), render_template(value, &template_values)?)))
+            .collect::<Result<_, Box<dyn Error + Send + Sync>>>()?;
+        query_params.sort();
+
+        if context_as_query && self.params.is_none() {
+            for (key, value) in &context_values {
+                query_params.push((key.clone(), query_value(value)));
+            }
         }
 
         let mut request = client.request(self.http_method.clone(), &url);
 
-        if let Some(headers) = &self.headers {
-            for (key, value) in headers {
-                request = request.header(key, value);
-            }
+        if !query_params.is_empty() {
+            request = request.query(&query_params);
         }
 
-        if let Some(auth_tokens) = auth_tokens {
-            if let Some(token) = auth_tokens.get("access_token").and_then(|t| t.as_str()) {
-                request = request.header("Authorization", format!("Bearer {}", token));
+        if let Some(headers) = &self.headers {
+            for (key, value) in headers {
+                request = request.header(key, render_template(value, &template_values)?);
             }
         }
 
-        if let Some(auth_tokens) = &self.auth_tokens {
-            if let Some(token) = auth_tokens.get("access_token").and_then(|t| t.as_str()) {
-                request = request.header("Authorization", format!("Bearer {}", token));
-            }
+        if let Some(token) = auth_tokens
+            .or(self.auth_tokens.as_ref())
+            .and_then(|tokens| tokens.get("access_token"))
+            .and_then(|t| t.as_str())
+        {
+            request = request.header("Authorization", format!("Bearer {}", token));
         }
 
         match &self.connector_type {
+            _ if context_as_query => {}
             ConnectorType::REST { base_payload } => {
                 let mut payload = base_payload.clone().unwrap_or(json!({}));
                 if let Some(dynamic) = dynamic_values {
@@ -166,40 +406,56 @@ impl OffChainConnector {
                     .body(payload.to_string());
             }
             ConnectorType::GraphQL { query, variables } => {
-                let mut merged_variables = variables.clone().unwrap_or_default();
+                let query = render_template(query, &template_values)?;
+                let mut merged_variables = HashMap::new();
+                for (key, value) in variables.iter().flatten() {
+                    merged_variables.insert(key.clone(), render_template(value, &template_values)?);
+                }
 
                 if let Some(dynamic) = dynamic_values {
                     if let Some(dynamic_map) = dynamic.as_object() {
                         for (key, value) in dynamic_map {
-                            merged_variables.insert(key.clone(), value.to_string());
+                            merged_variables.insert(key.clone(), query_value(value));
                         }
                     }
                 }
 
-                let graphql_payload = json!({
-                    "query": query,
-                    "variables": merged_variables
-                });
+                if is_get {
+                    request = request.query(&[
+                        ("query", query),
+                        ("variables", json!(merged_variables).to_string()),
+                    ]);
+                } else {
+                    let graphql_payload = json!({
+                        "query": query,
+                        "variables": merged_variables
+                    });
+
+                    request = request
+                        .header("Content-Type", "application/json")
+                        .body(graphql_payload.to_string());
+                }
+            }
+            ConnectorType::ContextBody { body_key } => {
+                let context = dynamic_values.cloned().unwrap_or(Value::Null);
+                let payload = match body_key {
+                    Some(key) => json!({ key: context }),
+                    None => context,
+                };
 
                 request = request
                     .header("Content-Type", "application/json")
-                    .body(graphql_payload.to_string());
+                    .body(payload.to_string());
             }
         }
 
-        let response = request.send().await?;
-        let response_data: Value = response.json().await?;
-
-        if let Some(exec_fn) = &self.result_processing_fn {
-            return exec_fn(response_data);
-        }
-
-        Ok(response_data)
+        Ok(request)
     }
 
     pub fn to_json(&self) -> Map<String, Value> {
         let mut map = Map::new();
         map.insert("name".to_string(), Value::String(self.name.clone()));
+        map.insert("id".to_string(), Value::String(self.id.clone()));
         map.insert("api_url".to_string(), Value::String(self.api_url.clone()));
         map.insert("public".to_string(), Value::Bool(self.encrypted));
         map.insert(
@@ -225,12 +481,21 @@ impl OffChainConnector {
                 map.insert("query".to_string(), Value::String(query.clone()));
                 if let Some(vars) = variables {
                     let vars_json = vars
-                        .into_iter()
+                        .iter()
                         .map(|(k, v)| (k.to_string(), Value::String(v.to_string())))
                         .collect::<Map<String, Value>>();
                     map.insert("variables".to_string(), Value::Object(vars_json));
                 }
             }
+            ConnectorType::ContextBody { body_key } => {
+                map.insert(
+                    "connector_type".to_string(),
+                    Value::String("ContextBody".to_string()),
+                );
+                if let Some(key) = body_key {
+                    map.insert("body_key".to_string(), Value::String(key.clone()));
+                }
+            }
         }
 
         if let Some(headers) = &self.headers {
@@ -253,6 +518,10 @@ impl OffChainConnector {
             map.insert("auth_tokens".to_string(), auth_tokens.clone());
         }
 
+        if let Some(pagination) = &self.pagination {
+            map.insert("pagination".to_string(), pagination.to_json());
+        }
+
         if self.result_processing_fn.is_some() {
             map.insert(
                 "result_processing_fn".to_string(),
@@ -264,6 +533,63 @@ impl OffChainConnector {
     }
 }
 
+fn template_values(
+    stored_tokens: Option<&Value>,
+    subflow_tokens: Option<&Value>,
+    context: &Map<String, Value>,
+) -> Map<String, Value> {
+    // Later sources win: stored auth_tokens, then auth subflow tokens, then the processed context.
+    let mut values = Map::new();
+    for tokens in [stored_tokens, subflow_tokens].into_iter().flatten() {
+        if let Some(tokens) = tokens.as_object() {
+            values.extend(tokens.clone());
+        }
+    }
+    values.extend(context.clone());
+    values
+}
+
+fn render_template(
+    template: &str,
+    values: &Map<String, Value>,
+) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let end = match rest[start + 2..].find("}}") {
+            Some(end) => start + 2 + end,
+            None => break,
+        };
+        let key = rest[start + 2..end].trim();
+        let value = values.get(key).ok_or_else(|| {
+            format!(
+                "No value for placeholder {{{{{}}}}} in context or auth_tokens",
+                key
+            )
+        })?;
+        rendered.push_str(&rest[..start]);
+        rendered.push_str(&query_value(value));
+        rest = &rest[end + 2..];
+    }
+    rendered.push_str(rest);
+
+    Ok(rendered)
+}
+
+fn json_pointer(path: &[String]) -> String {
+    path.iter()
+        .map(|segment| format!("/{}", segment.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+fn query_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
 pub fn configure_new_offchain_connector(
     name: &str,
     connector_type: ConnectorType,
@@ -291,6 +617,8 @@ pub fn configure_new_offchain_connector(
         auth_tokens,
         result_processing_fn,
         auth_subflow,
+        refreshed_auth_tokens: Arc::default(),
+        pagination: None,
     };
     Ok(off_chain)
 }
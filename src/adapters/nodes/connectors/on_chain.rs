@@ -1,12 +1,16 @@
-use crate::{nibble::Adaptable, utils::generate_unique_id};
+use crate::{
+    nibble::{Adaptable, FeeMode, NonceManager},
+    utils::{generate_unique_id, token_to_value},
+};
 use ethers::{
     abi,
+    contract::multicall_contract::Call3Value,
     prelude::*,
-    types::{Address, Eip1559TransactionRequest, NameOrAddress, U256},
+    types::{Address, Eip1559TransactionRequest, NameOrAddress, I256, U256},
     utils::hex,
 };
 use serde::{Deserialize, Serialize};
-use serde_json::{Map, Value};
+use serde_json::{json, Map, Value};
 use std::{error::Error, io, sync::Arc};
 use transaction::eip2718::TypedTransaction;
 
@@ -20,9 +24,62 @@ pub struct OnChainConnector {
     pub bytecode: Option<Bytes>,
     pub chain: Chain,
     pub gas_options: Option<GasOptions>,
+    pub transactions: Vec<OnChainTransaction>,
+    pub batch_mode: BatchMode,
+    pub gas_oracle: GasOracle,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OnChainTransaction {
+    pub method_name: String,
+    pub params: Vec<Value>,
+    pub value: Option<U256>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub enum BatchMode {
+    /// Sends every transaction in one Multicall3 `aggregate3Value` call. The target
+    /// contract sees Multicall3 as `msg.sender`, not the connector's wallet, so
+    /// batches that call caller-dependent methods are rejected.
+    AllOrNothing,
+    SequentialContinue,
+    #[default]
+    SequentialAbort,
+}
+
+const CALLER_DEPENDENT_METHODS: [&str; 8] = [
+    "transfer",
+    "transferFrom",
+    "safeTransferFrom",
+    "approve",
+    "increaseAllowance",
+    "decreaseAllowance",
+    "setApprovalForAll",
+    "renounceOwnership",
+];
+
+fn check_multicall_batch(
+    batch_mode: &BatchMode,
+    transactions: &[OnChainTransaction],
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if *batch_mode != BatchMode::AllOrNothing {
+        return Ok(());
+    }
+
+    match transactions
+        .iter()
+        .find(|transaction| CALLER_DEPENDENT_METHODS.contains(&transaction.method_name.as_str()))
+    {
+        Some(transaction) => Err(format!(
+            "{} depends on msg.sender, which is Multicall3 in AllOrNothing mode; use a sequential batch mode",
+            transaction.method_name
+        )
+        .into()),
+        None => Ok(()),
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
 pub struct GasOptions {
     pub max_fee_per_gas: Option<U256>,
     pub max_priority_fee_per_gas: Option<U256>,
@@ -30,17 +87,89 @@ pub struct GasOptions {
     pub nonce: Option<U256>,
 }
 
-impl Default for GasOptions {
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub enum GasOracle {
+    FeeHistory {
+        block_count: u64,
+        reward_percentile: f64,
+        base_fee_multiplier: u64,
+    },
+    External {
+        url: String,
+    },
+}
+
+impl Default for GasOracle {
     fn default() -> Self {
-        GasOptions {
-            max_fee_per_gas: None,
-            max_priority_fee_per_gas: None,
-            gas_limit: None,
-            nonce: None,
+        GasOracle::FeeHistory {
+            block_count: 10,
+            reward_percentile: 50.0,
+            base_fee_multiplier: 2,
+        }
+    }
+}
+
+impl GasOracle {
+    pub async fn estimate_fees(
+        &self,
+        provider: &Provider<Http>,
+    ) -> Result<(U256, U256), Box<dyn Error + Send + Sync>> {
+        match self {
+            GasOracle::FeeHistory {
+                block_count,
+                reward_percentile,
+                base_fee_multiplier,
+            } => {
+                let history = provider
+                    .fee_history(*block_count, BlockNumber::Latest, &[*reward_percentile])
+                    .await?;
+
+                let base_fee = *history
+                    .base_fee_per_gas
+                    .last()
+                    .ok_or("feeHistory returned no base fees")?;
+
+                let rewards: Vec<U256> = history
+                    .reward
+                    .iter()
+                    .filter_map(|block| block.first().copied())
+                    .collect();
+                let max_priority_fee_per_gas = if rewards.is_empty() {
+                    U256::from(DEFAULT_PRIORITY_FEE)
+                } else {
+                    rewards
+                        .iter()
+                        .fold(U256::zero(), |sum, reward| sum + reward)
+                        / U256::from(rewards.len())
+                };
+
+                Ok((
+                    base_fee * U256::from(*base_fee_multiplier) + max_priority_fee_per_gas,
+                    max_priority_fee_per_gas,
+                ))
+            }
+            GasOracle::External { url } => {
+                let response: Value = reqwest::get(url).await?.json().await?;
+                let fee = |key: &str| -> Result<U256, Box<dyn Error + Send + Sync>> {
+                    match response.get(key) {
+                        Some(Value::String(fee)) => Ok(U256::from_dec_str(fee)?),
+                        Some(Value::Number(fee)) => Ok(U256::from(
+                            fee.as_u64()
+                                .ok_or(format!("Invalid {} from gas oracle", key))?,
+                        )),
+                        _ => Err(format!("Missing {} from gas oracle", key).into()),
+                    }
+                };
+
+                Ok((fee("max_fee_per_gas")?, fee("max_priority_fee_per_gas")?))
+            }
         }
     }
 }
 
+const DEFAULT_MAX_FEE: u64 = 100_000_000_000;
+const DEFAULT_PRIORITY_FEE: u64 = 2_000_000_000;
+
 pub fn configure_new_onchain_connector(
     name: &str,
     address: Option<Address>,
@@ -50,7 +179,11 @@ pub fn configure_new_onchain_connector(
     abi: Option<abi::Abi>,
     chain: Chain,
     gas_options: Option<GasOptions>,
+    transactions: Vec<OnChainTransaction>,
+    batch_mode: BatchMode,
 ) -> Result<OnChainConnector, Box<dyn Error + Send + Sync>> {
+    check_multicall_batch(&batch_mode, &transactions)?;
+
     let on_chain = OnChainConnector {
         name: name.to_string(),
         id: generate_unique_id(owner_address),
@@ -60,6 +193,9 @@ pub fn configure_new_onchain_connector(
         abi,
         chain,
         gas_options,
+        transactions,
+        batch_mode,
+        gas_oracle: GasOracle::default(),
     };
     Ok(on_chain)
 }
@@ -69,7 +205,7 @@ impl OnChainConnector {
         let mut map = Map::new();
 
         map.insert("name".to_string(), Value::String(self.name.clone()));
-        map.insert("id".to_string(), Value::String(hex::encode(&self.id)));
+        map.insert("id".to_string(), Value::String(self.id.clone()));
         map.insert(
             "address".to_string(),
             Value::String(
@@ -118,90 +254,145 @@ impl OnChainConnector {
             map.insert("gas_options".to_string(), Value::Object(gas_map));
         }
 
+        if !self.transactions.is_empty() {
+            map.insert(
+                "transactions".to_string(),
+                serde_json::to_value(&self.transactions).unwrap_or(Value::Null),
+            );
+        }
+        map.insert(
+            "batch_mode".to_string(),
+            Value::String(format!("{:?}", self.batch_mode)),
+        );
+        map.insert(
+            "gas_oracle".to_string(),
+            serde_json::to_value(&self.gas_oracle).unwrap_or(Value::Null),
+        );
+
         map
     }
 
+    /// View and pure calls return `{"return_values": [...]}` read with `eth_call`.
+    /// State-changing calls return the `"Transaction Hash: ..."` string, or, when the
+    /// function declares outputs, `{"transaction": ..., "simulated_return_values": [...]}`
+    /// where the values come from an `eth_call` made before sending and may differ
+    /// from what the mined transaction produced.
     pub async fn execute_onchain_connector(
         &self,
         provider: Provider<Http>,
         wallet: LocalWallet,
         method_name: Option<&str>,
         params: Option<Vec<Value>>,
+        nonce_manager: &NonceManager,
+        fee_mode: &FeeMode,
+        confirmations: usize,
     ) -> Result<Option<Value>, Box<dyn Error + Send + Sync>> {
-        let client = SignerMiddleware::new(provider.clone(), wallet.clone());
+        let client = SignerMiddleware::new(provider.clone(), wallet.with_chain_id(self.chain));
         let client = Arc::new(client);
 
+        if method_name.is_none() && !self.transactions.is_empty() {
+            return self
+                .execute_onchain_batch(client, nonce_manager, fee_mode, confirmations)
+                .await
+                .map(Some);
+        }
+
         if let Some(method) = method_name {
             if let (Some(address), Some(abi)) = (&self.address, &self.abi) {
-                let contract = Contract::new(*address, abi.clone(), client.clone());
+                let decoded_params =
+                    decode_params(params.unwrap_or_default(), &param_types(abi, Some(method)))?;
 
-                let decoded_params: Vec<abi::Token> = params
-                    .unwrap_or_default()
-                    .into_iter()
-                    .map(|param| {
-                        serde_json::from_value::<abi::Token>(param).map_err(|e| e.to_string())
-                    })
-                    .collect::<Result<Vec<_>, _>>()?;
-
-                let method_call = contract.method::<_, Vec<abi::Token>>(method, decoded_params)?;
-                let tx_request = method_call.tx;
-
-                let tx_request = if let Some(gas) = &self.gas_options {
-                    Eip1559TransactionRequest {
-                        from: Some(client.address()),
-                        to: Some(NameOrAddress::Address(*address)),
-                        gas: gas.gas_limit.or(tx_request.gas().copied()),
-                        value: tx_request.value().copied(),
-                        data: tx_request.data().cloned(),
-                        max_priority_fee_per_gas: gas
-                            .max_priority_fee_per_gas
-                            .or_else(|| Some(2_000_000_000u64.into())),
-                        max_fee_per_gas: gas
-                            .max_fee_per_gas
-                            .or_else(|| Some(100_000_000_000u64.into())),
-                        nonce: gas.nonce.or_else(|| None),
-                        chain_id: Some(self.chain.into()),
-                        ..Default::default()
-                    }
+                let function = abi.function(method)?;
+                let tx_request: TypedTransaction = Eip1559TransactionRequest::new()
+                    .to(*address)
+                    .data(function.encode_input(&decoded_params)?)
+                    .into();
+                if !function.outputs.is_empty()
+                    && matches!(
+                        function.state_mutability,
+                        abi::StateMutability::View | abi::StateMutability::Pure
+                    )
+                {
+                    let return_values = call_return_values(
+                        client.provider(),
+                        client.address(),
+                        function,
+                        &tx_request,
+                    )
+                    .await?;
+                    return Ok(Some(json!({ "return_values": return_values })));
+                }
+
+                let return_values = if function.outputs.is_empty() {
+                    None
                 } else {
-                    Eip1559TransactionRequest {
-                        from: Some(client.address()),
-                        to: Some(NameOrAddress::Address(*address)),
-                        gas: tx_request.gas().copied(),
-                        value: tx_request.value().copied(),
-                        data: tx_request.data().cloned(),
-                        max_priority_fee_per_gas: Some(2_000_000_000u64.into()),
-                        max_fee_per_gas: Some(100_000_000_000u64.into()),
-                        nonce: None,
-                        chain_id: Some(self.chain.into()),
-                        ..Default::default()
+                    match call_return_values(
+                        client.provider(),
+                        client.address(),
+                        function,
+                        &tx_request,
+                    )
+                    .await
+                    {
+                        Ok(return_values) => Some(return_values),
+                        Err(e) => {
+                            eprintln!("Failed to simulate {} for return values: {:?}", method, e);
+                            None
+                        }
                     }
                 };
+                let fees = self.resolve_fees(client.provider(), fee_mode).await;
+                let nonce = self
+                    .reserve_nonce(nonce_manager, client.provider(), client.address(), 0)
+                    .await?;
+
+                let tx_request = fee_mode
+                    .build_transaction(
+                        client.provider(),
+                        self.build_transaction_request(
+                            client.address(),
+                            *address,
+                            &tx_request,
+                            tx_request.value().copied(),
+                            nonce,
+                            fees,
+                        ),
+                    )
+                    .await;
 
                 let pending_tx = client
                     .send_transaction(tx_request, None)
                     .await
                     .map_err(|e| {
                         eprintln!("Error sending the transaction: {:?}", e);
+                        nonce_manager.reset(client.address());
                         Box::<dyn Error + Send + Sync>::from(format!(
                             "Error sending the transaction: {}",
                             e
                         ))
                     })?;
 
-                let receipt = pending_tx.await?;
+                let receipt = pending_tx.confirmations(confirmations).await?;
                 if let Some(receipt) = receipt {
                     if receipt.status == Some(U64::from(1)) {
                         println!("Transaction succeeded: {:?}", receipt.transaction_hash);
-                        Ok(Some(Value::String(format!(
+                        let transaction = Value::String(format!(
                             "Transaction Hash: {:?}",
                             receipt.transaction_hash
-                        ))))
+                        ));
+                        Ok(Some(match return_values {
+                            Some(return_values) => json!({
+                                "transaction": transaction,
+                                "simulated_return_values": return_values,
+                            }),
+                            None => transaction,
+                        }))
                     } else {
                         eprintln!("Transaction failed: {:?}", receipt);
                         Err("Transaction execution failed".into())
                     }
                 } else {
+                    nonce_manager.reset(client.address());
                     Err("Transaction was not mined".into())
                 }
             } else {
@@ -211,43 +402,42 @@ impl OnChainConnector {
             if let (Some(abi), Some(bytecode)) = (&self.abi, &self.bytecode) {
                 let factory = ContractFactory::new(abi.clone(), bytecode.clone(), client.clone());
 
-                let constructor_args: Vec<abi::Token> = match params {
-                    Some(param_list) => param_list
-                        .into_iter()
-                        .map(|param| {
-                            serde_json::from_value::<abi::Token>(param)
-                                .map_err(|e| format!("Error decoding arguments: {}", e))
-                        })
-                        .collect::<Result<Vec<_>, _>>()?,
-                    None => vec![],
-                };
+                let constructor_args =
+                    decode_params(params.unwrap_or_default(), &param_types(abi, None))
+                        .map_err(|e| format!("Error decoding arguments: {}", e))?;
 
                 let deployer = factory.deploy(constructor_args)?;
+                let (max_fee_per_gas, max_priority_fee_per_gas) =
+                    self.resolve_fees(client.provider(), fee_mode).await;
+                let nonce = self
+                    .reserve_nonce(nonce_manager, client.provider(), client.address(), 0)
+                    .await?;
 
                 let mut tx = deployer.tx.clone();
                 if let TypedTransaction::Eip1559(ref mut request) = tx {
-                    if let Some(ref gas_options) = self.gas_options {
-                        request.max_fee_per_gas = gas_options
-                            .max_fee_per_gas
-                            .or_else(|| Some(100_000_000_000u64.into()));
-                        request.max_priority_fee_per_gas = gas_options
-                            .max_priority_fee_per_gas
-                            .or_else(|| Some(2_000_000_000u64.into()));
-                        request.gas = gas_options.gas_limit.or_else(|| Some(2_000_000u64.into()));
-                        request.nonce = gas_options.nonce;
-                    } else {
-                        request.max_fee_per_gas = Some(100_000_000_000u64.into());
-                        request.max_priority_fee_per_gas = Some(2_000_000_000u64.into());
-                        request.gas = Some(2_000_000u64.into());
-                        request.nonce = None;
-                    }
+                    request.max_fee_per_gas = Some(max_fee_per_gas);
+                    request.max_priority_fee_per_gas = Some(max_priority_fee_per_gas);
+                    request.gas = self
+                        .gas_options
+                        .as_ref()
+                        .and_then(|gas_options| gas_options.gas_limit)
+                        .or_else(|| Some(2_000_000u64.into()));
+                    request.nonce = Some(nonce);
                 } else {
                     panic!("The transaction is not of type EIP-1559");
                 }
+                let tx = match tx {
+                    TypedTransaction::Eip1559(request) => {
+                        fee_mode.build_transaction(client.provider(), request).await
+                    }
+                    tx => tx,
+                };
 
-                let pending_tx = client.send_transaction(tx, None).await?;
+                let pending_tx = client.send_transaction(tx, None).await.inspect_err(|_e| {
+                    nonce_manager.reset(client.address());
+                })?;
 
-                match pending_tx.await {
+                match pending_tx.confirmations(confirmations).await {
                     Ok(contract) => match contract {
                         Some(tx) => {
                             println!("Contract deployed at: {:?}", tx.contract_address);
@@ -257,14 +447,13 @@ impl OnChainConnector {
                         }
                         None => {
                             eprintln!("Error getting contract address");
-                            Err(Box::new(io::Error::new(
-                                io::ErrorKind::Other,
-                                "Error getting contract address",
-                            )))
+                            nonce_manager.reset(client.address());
+                            Err(Box::new(io::Error::other("Error getting contract address")))
                         }
                     },
                     Err(e) => {
                         eprintln!("Error deploying contract: {:?}", e);
+                        nonce_manager.reset(client.address());
                         Err(Box::new(e))
                     }
                 }
@@ -273,6 +462,497 @@ impl OnChainConnector {
             }
         }
     }
+
+    async fn execute_onchain_batch(
+        &self,
+        client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+        nonce_manager: &NonceManager,
+        fee_mode: &FeeMode,
+        confirmations: usize,
+    ) -> Result<Value, Box<dyn Error + Send + Sync>> {
+        let (address, abi) = match (&self.address, &self.abi) {
+            (Some(address), Some(abi)) => (*address, abi.clone()),
+            _ => return Err("Contract address or ABI is missing".into()),
+        };
+        let contract = Contract::new(address, abi, client.clone());
+
+        if self.batch_mode == BatchMode::AllOrNothing {
+            check_multicall_batch(&self.batch_mode, &self.transactions)?;
+            println!(
+                "Sending {} transactions through Multicall3, msg.sender will be the Multicall3 contract",
+                self.transactions.len()
+            );
+            let multicall = Multicall::<SignerMiddleware<Provider<Http>, LocalWallet>>::new(
+                client.clone(),
+                None,
+            )
+            .await
+            .map_err(|e| format!("Multicall is not available on this chain: {}", e))?;
+
+            let mut calls = Vec::new();
+            let mut total_value = U256::zero();
+            for transaction in &self.transactions {
+                let decoded_params = decode_params(
+                    transaction.params.clone(),
+                    &param_types(contract.abi(), Some(&transaction.method_name)),
+                )?;
+                let call_data = contract
+                    .abi()
+                    .function(&transaction.method_name)?
+                    .encode_input(&decoded_params)?;
+                let value = transaction.value.unwrap_or_default();
+                total_value += value;
+                calls.push(Call3Value {
+                    target: address,
+                    allow_failure: false,
+                    value,
+                    call_data: call_data.into(),
+                });
+            }
+
+            let mut call = multicall
+                .contract
+                .aggregate_3_value(calls)
+                .value(total_value);
+            let (max_fee_per_gas, max_priority_fee_per_gas) =
+                self.resolve_fees(client.provider(), fee_mode).await;
+            if let TypedTransaction::Eip1559(ref mut request) = call.tx {
+                request.max_fee_per_gas = Some(max_fee_per_gas);
+                request.max_priority_fee_per_gas = Some(max_priority_fee_per_gas);
+            }
+            let nonce = self
+                .reserve_nonce(nonce_manager, client.provider(), client.address(), 0)
+                .await?;
+            call = call.nonce(nonce);
+            if let TypedTransaction::Eip1559(request) = &call.tx {
+                call.tx = fee_mode
+                    .build_transaction(client.provider(), request.clone())
+                    .await;
+            }
+
+            let pending_tx = call.send().await.map_err(|e| {
+                eprintln!("Error sending the multicall transaction: {:?}", e);
+                nonce_manager.reset(client.address());
+                Box::<dyn Error + Send + Sync>::from(format!(
+                    "Error sending the multicall transaction: {}",
+                    e
+                ))
+            })?;
+
+            let receipt = match pending_tx.confirmations(confirmations).await? {
+                Some(receipt) => receipt,
+                None => {
+                    nonce_manager.reset(client.address());
+                    return Err("Transaction was not mined".into());
+                }
+            };
+            if receipt.status != Some(U64::from(1)) {
+                eprintln!("Multicall transaction failed: {:?}", receipt);
+                return Err("Multicall transaction execution failed".into());
+            }
+
+            println!("Multicall succeeded: {:?}", receipt.transaction_hash);
+            return Ok(Value::Array(
+                self.transactions
+                    .iter()
+                    .map(|transaction| {
+                        batch_result(
+                            &transaction.method_name,
+                            Ok(format!("{:?}", receipt.transaction_hash)),
+                        )
+                    })
+                    .collect(),
+            ));
+        }
+
+        let mut results = Vec::new();
+
+        for (index, transaction) in self.transactions.iter().enumerate() {
+            let result = self
+                .send_batch_transaction(
+                    &contract,
+                    client.clone(),
+                    address,
+                    index,
+                    transaction,
+                    nonce_manager,
+                    fee_mode,
+                    confirmations,
+                )
+                .await;
+
+            match result {
+                Ok(hash) => {
+                    println!("Transaction succeeded: {}", hash);
+                    results.push(batch_result(&transaction.method_name, Ok(hash)));
+                }
+                Err(e) => {
+                    eprintln!(
+                        "Batch transaction {} ({}) failed: {}",
+                        index, transaction.method_name, e
+                    );
+                    if self.batch_mode == BatchMode::SequentialAbort {
+                        return Err(format!(
+                            "Batch aborted at transaction {} ({}): {}",
+                            index, transaction.method_name, e
+                        )
+                        .into());
+                    }
+                    results.push(batch_result(&transaction.method_name, Err(e.to_string())));
+                }
+            }
+        }
+
+        Ok(Value::Array(results))
+    }
+
+    async fn send_batch_transaction(
+        &self,
+        contract: &Contract<SignerMiddleware<Provider<Http>, LocalWallet>>,
+        client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+        address: Address,
+        index: usize,
+        transaction: &OnChainTransaction,
+        nonce_manager: &NonceManager,
+        fee_mode: &FeeMode,
+        confirmations: usize,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let decoded_params = decode_params(
+            transaction.params.clone(),
+            &param_types(contract.abi(), Some(&transaction.method_name)),
+        )?;
+        let tx_request: TypedTransaction = Eip1559TransactionRequest::new()
+            .to(address)
+            .data(
+                contract
+                    .abi()
+                    .function(&transaction.method_name)?
+                    .encode_input(&decoded_params)?,
+            )
+            .into();
+        let fees = self.resolve_fees(client.provider(), fee_mode).await;
+        let nonce = self
+            .reserve_nonce(nonce_manager, client.provider(), client.address(), index)
+            .await?;
+        let tx_request = fee_mode
+            .build_transaction(
+                client.provider(),
+                self.build_transaction_request(
+                    client.address(),
+                    address,
+                    &tx_request,
+                    transaction.value,
+                    nonce,
+                    fees,
+                ),
+            )
+            .await;
+
+        let pending_tx = client
+            .send_transaction(tx_request, None)
+            .await
+            .map_err(|e| {
+                nonce_manager.reset(client.address());
+                format!("Error sending the transaction: {}", e)
+            })?;
+
+        match pending_tx.confirmations(confirmations).await? {
+            Some(receipt) if receipt.status == Some(U64::from(1)) => {
+                Ok(format!("{:?}", receipt.transaction_hash))
+            }
+            Some(receipt) => Err(format!(
+                "Transaction execution failed: {:?}",
+                receipt.transaction_hash
+            )
+            .into()),
+            None => {
+                nonce_manager.reset(client.address());
+                Err("Transaction was not mined".into())
+            }
+        }
+    }
+
+    async fn reserve_nonce(
+        &self,
+        nonce_manager: &NonceManager,
+        provider: &Provider<Http>,
+        address: Address,
+        offset: usize,
+    ) -> Result<U256, Box<dyn Error + Send + Sync>> {
+        if let Some(nonce) = self.gas_options.as_ref().and_then(|gas| gas.nonce) {
+            return Ok(nonce + offset);
+        }
+
+        nonce_manager.next_nonce(provider, address).await
+    }
+
+    async fn resolve_fees(&self, provider: &Provider<Http>, fee_mode: &FeeMode) -> (U256, U256) {
+        let (max_fee_per_gas, max_priority_fee_per_gas) = match &self.gas_options {
+            Some(gas) => (gas.max_fee_per_gas, gas.max_priority_fee_per_gas),
+            None => (None, None),
+        };
+
+        if let (Some(max_fee), Some(max_priority_fee)) = (max_fee_per_gas, max_priority_fee_per_gas)
+        {
+            return (max_fee, max_priority_fee);
+        }
+
+        let oracle = match fee_mode {
+            FeeMode::DynamicFees(oracle) => oracle,
+            FeeMode::Legacy(_) => {
+                return (
+                    max_fee_per_gas.unwrap_or(U256::from(DEFAULT_MAX_FEE)),
+                    max_priority_fee_per_gas.unwrap_or(U256::from(DEFAULT_PRIORITY_FEE)),
+                )
+            }
+            FeeMode::Static => &self.gas_oracle,
+        };
+        let (estimated_max_fee, estimated_priority_fee) = match oracle.estimate_fees(provider).await
+        {
+            Ok(fees) => fees,
+            Err(e) => {
+                eprintln!("Gas oracle failed, using default fees: {:?}", e);
+                (
+                    U256::from(DEFAULT_MAX_FEE),
+                    U256::from(DEFAULT_PRIORITY_FEE),
+                )
+            }
+        };
+
+        (
+            max_fee_per_gas.unwrap_or(estimated_max_fee),
+            max_priority_fee_per_gas.unwrap_or(estimated_priority_fee),
+        )
+    }
+
+    fn build_transaction_request(
+        &self,
+        from: Address,
+        to: Address,
+        tx_request: &TypedTransaction,
+        value: Option<U256>,
+        nonce: U256,
+        (max_fee_per_gas, max_priority_fee_per_gas): (U256, U256),
+    ) -> Eip1559TransactionRequest {
+        Eip1559TransactionRequest {
+            from: Some(from),
+            to: Some(NameOrAddress::Address(to)),
+            gas: self
+                .gas_options
+                .as_ref()
+                .and_then(|gas| gas.gas_limit)
+                .or(tx_request.gas().copied()),
+            value,
+            data: tx_request.data().cloned(),
+            max_priority_fee_per_gas: Some(max_priority_fee_per_gas),
+            max_fee_per_gas: Some(max_fee_per_gas),
+            nonce: Some(nonce),
+            chain_id: Some(self.chain.into()),
+            ..Default::default()
+        }
+    }
+}
+
+async fn call_return_values(
+    provider: &Provider<Http>,
+    from: Address,
+    function: &abi::Function,
+    tx: &TypedTransaction,
+) -> Result<Value, Box<dyn Error + Send + Sync>> {
+    let mut tx = tx.clone();
+    tx.set_from(from);
+    let output = provider.call(&tx, None).await?;
+
+    Ok(Value::Array(
+        function
+            .decode_output(&output)?
+            .into_iter()
+            .map(token_to_value)
+            .collect(),
+    ))
+}
+
+fn param_types(abi: &abi::Abi, method_name: Option<&str>) -> Vec<abi::ParamType> {
+    let inputs = match method_name {
+        Some(method_name) => abi
+            .function(method_name)
+            .map(|function| &function.inputs)
+            .ok(),
+        None => abi.constructor().map(|constructor| &constructor.inputs),
+    };
+
+    inputs
+        .map(|inputs| inputs.iter().map(|input| input.kind.clone()).collect())
+        .unwrap_or_default()
+}
+
+fn decode_params(
+    params: Vec<Value>,
+    kinds: &[abi::ParamType],
+) -> Result<Vec<abi::Token>, Box<dyn Error + Send + Sync>> {
+    params
+        .iter()
+        .enumerate()
+        .map(|(index, param)| convert_value_to_token(param, kinds.get(index)))
+        .collect()
+}
+
+pub fn convert_value_to_token(
+    value: &Value,
+    hint: Option<&abi::ParamType>,
+) -> Result<abi::Token, Box<dyn Error + Send + Sync>> {
+    match (hint, value) {
+        (Some(abi::ParamType::Address), Value::String(address)) => {
+            Ok(abi::Token::Address(address.parse::<Address>()?))
+        }
+        (Some(abi::ParamType::Bool), Value::String(flag)) => {
+            Ok(abi::Token::Bool(flag.parse::<bool>()?))
+        }
+        (Some(abi::ParamType::Uint(_)), Value::String(number)) => {
+            Ok(abi::Token::Uint(parse_uint(number)?))
+        }
+        (Some(abi::ParamType::Int(_)), Value::Number(number)) => number
+            .as_i64()
+            .map(|number| abi::Token::Int(I256::from(number).into_raw()))
+            .ok_or_else(|| format!("Unsupported int parameter: {}", number).into()),
+        (Some(abi::ParamType::Int(_)), Value::String(number)) => {
+            Ok(abi::Token::Int(parse_int(number)?.into_raw()))
+        }
+        (Some(abi::ParamType::Bytes), Value::String(bytes)) => {
+            Ok(abi::Token::Bytes(decode_hex(bytes)?))
+        }
+        (Some(abi::ParamType::FixedBytes(size)), Value::String(bytes)) => {
+            let bytes = decode_hex(bytes)?;
+            if bytes.len() != *size {
+                return Err(format!(
+                    "Expected {} bytes for bytes{} parameter, got {}",
+                    size,
+                    size,
+                    bytes.len()
+                )
+                .into());
+            }
+            Ok(abi::Token::FixedBytes(bytes))
+        }
+        (Some(abi::ParamType::String), Value::String(text)) => Ok(abi::Token::String(text.clone())),
+        (Some(abi::ParamType::String), Value::Number(number)) => {
+            Ok(abi::Token::String(number.to_string()))
+        }
+        (Some(abi::ParamType::Array(inner)), Value::Array(items)) => Ok(abi::Token::Array(
+            items
+                .iter()
+                .map(|item| convert_value_to_token(item, Some(inner.as_ref())))
+                .collect::<Result<_, _>>()?,
+        )),
+        (Some(abi::ParamType::FixedArray(inner, size)), Value::Array(items)) => {
+            if items.len() != *size {
+                return Err(format!(
+                    "Expected {} items for fixed array parameter, got {}",
+                    size,
+                    items.len()
+                )
+                .into());
+            }
+            Ok(abi::Token::FixedArray(
+                items
+                    .iter()
+                    .map(|item| convert_value_to_token(item, Some(inner.as_ref())))
+                    .collect::<Result<_, _>>()?,
+            ))
+        }
+        (Some(abi::ParamType::Tuple(kinds)), Value::Array(items)) => {
+            if items.len() != kinds.len() {
+                return Err(format!(
+                    "Expected {} items for tuple parameter, got {}",
+                    kinds.len(),
+                    items.len()
+                )
+                .into());
+            }
+            Ok(abi::Token::Tuple(
+                items
+                    .iter()
+                    .zip(kinds)
+                    .map(|(item, kind)| convert_value_to_token(item, Some(kind)))
+                    .collect::<Result<_, _>>()?,
+            ))
+        }
+        (_, Value::Bool(flag)) => Ok(abi::Token::Bool(*flag)),
+        (_, Value::Number(number)) => match (number.as_u64(), number.as_i64()) {
+            (Some(number), _) => Ok(abi::Token::Uint(U256::from(number))),
+            (None, Some(number)) => Ok(abi::Token::Int(I256::from(number).into_raw())),
+            _ => Err(format!("Unsupported parameter type: {}", number).into()),
+        },
+        (None, Value::String(text)) => {
+            if text.len() == 42 && text.starts_with("0x") {
+                if let Ok(address) = text.parse::<Address>() {
+                    return Ok(abi::Token::Address(address));
+                }
+            }
+            if !text.is_empty()
+                && text.chars().all(|c| c.is_ascii_digit())
+                && text.parse::<u64>().is_err()
+            {
+                return Ok(abi::Token::Uint(U256::from_dec_str(text)?));
+            }
+            Ok(abi::Token::String(text.clone()))
+        }
+        (None, Value::Array(items)) => Ok(abi::Token::Array(
+            items
+                .iter()
+                .map(|item| convert_value_to_token(item, None))
+                .collect::<Result<_, _>>()?,
+        )),
+        (None, Value::Object(_)) => Ok(serde_json::from_value::<abi::Token>(value.clone())
+            .map_err(|e| format!("Unsupported parameter type: {}", e))?),
+        (hint, value) => Err(format!(
+            "Unsupported parameter type: cannot encode {} as {:?}",
+            value, hint
+        )
+        .into()),
+    }
+}
+
+fn parse_int(number: &str) -> Result<I256, Box<dyn Error + Send + Sync>> {
+    match number.strip_prefix('-') {
+        Some(magnitude) => Ok(I256::try_from(parse_uint(magnitude)?)?
+            .checked_neg()
+            .ok_or("Int parameter is out of range")?),
+        None => Ok(I256::try_from(parse_uint(number)?)?),
+    }
+}
+
+fn decode_hex(bytes: &str) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    let digits = bytes
+        .strip_prefix("0x")
+        .ok_or_else(|| format!("Bytes parameter must be 0x-prefixed hex: {}", bytes))?;
+    Ok(hex::decode(digits)?)
+}
+
+fn parse_uint(number: &str) -> Result<U256, Box<dyn Error + Send + Sync>> {
+    match number.strip_prefix("0x") {
+        Some(hex_digits) => Ok(U256::from_str_radix(hex_digits, 16)?),
+        None => Ok(U256::from_dec_str(number)?),
+    }
+}
+
+fn batch_result(method_name: &str, result: Result<String, String>) -> Value {
+    let mut map = Map::new();
+    map.insert(
+        "method_name".to_string(),
+        Value::String(method_name.to_string()),
+    );
+    match result {
+        Ok(hash) => {
+            map.insert("success".to_string(), Value::Bool(true));
+            map.insert("transaction_hash".to_string(), Value::String(hash));
+        }
+        Err(e) => {
+            map.insert("success".to_string(), Value::Bool(false));
+            map.insert("error".to_string(), Value::String(e));
+        }
+    }
+    Value::Object(map)
 }
 
 impl Adaptable for OnChainConnector {
@@ -5,3 +5,42 @@ pub const GRAPH_ENDPOINT_PROD: &str = "https://gateway.thegraph.com/api/apikey/s
 
 pub const GRAPH_ENDPOINT_DEV: &str =
     "https://api.studio.thegraph.com/query/37770/nibble_test/version/latest";
+
+pub const DEFAULT_SUBGRAPH_PAGE_SIZE: usize = 100;
+
+pub const DEFAULT_ENCRYPTED_FIELDS: [&str; 4] = ["api_key", "auth_tokens", "private_key", "secret"];
+
+pub const DEFAULT_OBJECTIVE_SIMILARITY: f64 = 0.5;
+
+pub const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+
+pub const ANTHROPIC_BASE_URL: &str = "https://api.anthropic.com/v1";
+
+pub const OLLAMA_BASE_URL: &str = "http://localhost:11434";
+
+pub const PREFLIGHT_IPFS_PAYLOAD: &str = "{\"npc_workbench\":\"preflight\"}";
+
+pub const PERSIST_MAX_FEE_PER_GAS: u64 = 44_786_996_170;
+
+pub const PERSIST_MAX_PRIORITY_FEE_PER_GAS: u64 = 25_000_000_000;
+
+pub const DEFAULT_CONFIRMATIONS: usize = 1;
+
+pub const SUBGRAPH_POLL_INTERVAL_MS: u64 = 500;
+
+pub const NIBBLE_FACTORY_ABI: &str = include_str!("../abis/NibbleFactory.json");
+
+pub const NIBBLE_STORAGE_ABI: &str = include_str!("../abis/NibbleStorage.json");
+
+pub const FHE_GATE_ABI: &str = include_str!("../abis/FHEGate.json");
+
+pub const NIBBLE_ACCESS_CONTROLS_ABI: &str = include_str!("../abis/NibbleAccessControls.json");
+
+pub const AGENT_WRITER_ACCESS_CONTROLS_VERSION: u64 = 2;
+
+pub const DEFAULT_MAX_WHILE_ITERATIONS: u32 = 100;
+
+pub const MULTICALL3_ADDRESS: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+
+pub const MULTICALL3_AGGREGATE3: &str =
+    "function aggregate3((address,bool,bytes)[] calls) returns ((bool,bytes)[] returnData)";
@@ -1,21 +1,8 @@
 use ecies::{decrypt, encrypt};
-use ethers::signers::LocalWallet;
-use serde::{Deserialize, Serialize};
+use ethers::{signers::LocalWallet, utils::hex};
 use serde_json::Value;
 use std::{error::Error, io};
 
-#[derive(Serialize, Deserialize)]
-struct EncryptParams {
-    access_control_conditions: Vec<String>,
-    evm_contract_conditions: Vec<String>,
-    sol_rpc_conditions: Vec<String>,
-    unified_access_control_conditions: Vec<String>,
-    chain: String,
-    data_to_encrypt: String,
-    public_key: Vec<u8>,
-    identity: Vec<u8>,
-}
-
 pub fn encrypt_with_public_key(
     metadata: Vec<u8>,
     wallet: LocalWallet,
@@ -32,10 +19,10 @@ pub fn encrypt_with_public_key(
     }
 
     let encrypted_data = encrypt(public_key_bytes, metadata.as_slice()).map_err(|e| {
-        Box::new(io::Error::new(
-            io::ErrorKind::Other,
-            format!("Error encrypting the data: {:?}", e),
-        ))
+        Box::new(io::Error::other(format!(
+            "Error encrypting the data: {:?}",
+            e
+        )))
     })?;
 
     Ok(encrypted_data)
@@ -55,10 +42,10 @@ pub fn decrypt_with_private_key(
     }
 
     let decrypted_data = decrypt(&private_key_bytes, encrypted_data.as_slice()).map_err(|e| {
-        Box::new(io::Error::new(
-            io::ErrorKind::Other,
-            format!("Error decrypting the data: {:?}", e),
-        ))
+        Box::new(io::Error::other(format!(
+            "Error decrypting the data: {:?}",
+            e
+        )))
     })?;
 
     let json_value: Value = serde_json::from_slice(&decrypted_data).map_err(|e| {
@@ -70,3 +57,61 @@ pub fn decrypt_with_private_key(
 
     Ok(json_value)
 }
+
+pub fn encrypt_fields(
+    metadata: &mut Value,
+    fields: &[String],
+    wallet: &LocalWallet,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match metadata {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if fields.contains(key) {
+                    if !value.is_null() {
+                        let encrypted =
+                            encrypt_with_public_key(serde_json::to_vec(value)?, wallet.clone())?;
+                        *value = Value::String(hex::encode(encrypted));
+                    }
+                } else {
+                    encrypt_fields(value, fields, wallet)?;
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                encrypt_fields(item, fields, wallet)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+pub fn decrypt_fields(
+    metadata: &mut Value,
+    fields: &[String],
+    wallet: &LocalWallet,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match metadata {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if fields.contains(key) {
+                    if let Some(encrypted) = value.as_str() {
+                        *value = decrypt_with_private_key(hex::decode(encrypted)?, wallet.clone())?;
+                    }
+                } else {
+                    decrypt_fields(value, fields, wallet)?;
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                decrypt_fields(item, fields, wallet)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use std::{error::Error, io};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum NpcError {
+    #[error("RPC error: {0}")]
+    Rpc(String),
+    #[error("IPFS error: {0}")]
+    Ipfs(String),
+    #[error("Subgraph error: {0}")]
+    Subgraph(String),
+    #[error("Encryption error: {0}")]
+    Encryption(String),
+    #[error("Validation error: {0}")]
+    Validation(String),
+    #[error("LLM error: {0}")]
+    Llm(String),
+    #[error("Timeout: {0}")]
+    Timeout(String),
+    #[error(transparent)]
+    Other(Box<dyn Error + Send + Sync>),
+}
+
+impl NpcError {
+    pub fn rpc(error: impl ToString) -> Self {
+        NpcError::Rpc(error.to_string())
+    }
+
+    pub fn ipfs(error: Box<dyn Error + Send + Sync>) -> Self {
+        if is_timeout(error.as_ref()) {
+            return NpcError::Timeout(error.to_string());
+        }
+        NpcError::Ipfs(error.to_string())
+    }
+
+    pub fn subgraph(error: Box<dyn Error + Send + Sync>) -> Self {
+        if is_timeout(error.as_ref()) {
+            return NpcError::Timeout(error.to_string());
+        }
+        NpcError::Subgraph(error.to_string())
+    }
+
+    pub fn encryption(error: impl ToString) -> Self {
+        NpcError::Encryption(error.to_string())
+    }
+
+    pub fn validation(error: impl ToString) -> Self {
+        NpcError::Validation(error.to_string())
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            NpcError::Rpc(_) | NpcError::Ipfs(_) | NpcError::Subgraph(_) | NpcError::Timeout(_)
+        )
+    }
+}
+
+fn is_timeout(error: &(dyn Error + Send + Sync + 'static)) -> bool {
+    if let Some(error) = error.downcast_ref::<reqwest::Error>() {
+        return error.is_timeout();
+    }
+    error
+        .downcast_ref::<tokio::time::error::Elapsed>()
+        .is_some()
+}
+
+impl From<Box<dyn Error + Send + Sync>> for NpcError {
+    fn from(error: Box<dyn Error + Send + Sync>) -> Self {
+        match error.downcast::<NpcError>() {
+            Ok(error) => *error,
+            Err(error) => NpcError::Other(error),
+        }
+    }
+}
+
+impl From<Box<dyn Error>> for NpcError {
+    fn from(error: Box<dyn Error>) -> Self {
+        NpcError::Other(error.to_string().into())
+    }
+}
+
+impl From<&str> for NpcError {
+    fn from(error: &str) -> Self {
+        NpcError::Other(error.into())
+    }
+}
+
+impl From<String> for NpcError {
+    fn from(error: String) -> Self {
+        NpcError::Other(error.into())
+    }
+}
+
+impl From<io::Error> for NpcError {
+    fn from(error: io::Error) -> Self {
+        NpcError::Other(Box::new(error))
+    }
+}
+
+impl From<serde_json::Error> for NpcError {
+    fn from(error: serde_json::Error) -> Self {
+        NpcError::Other(Box::new(error))
+    }
+}
+
+impl From<reqwest::Error> for NpcError {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_timeout() {
+            return NpcError::Timeout(error.to_string());
+        }
+        NpcError::Other(Box::new(error))
+    }
+}
@@ -1,3 +1,4 @@
+use crate::{nibble::RetryPolicy, utils::send_with_retry};
 use async_trait::async_trait;
 use base64::{engine::general_purpose::STANDARD as BASE64_STANDARD, Engine};
 use core::fmt;
@@ -9,6 +10,31 @@ use std::{collections::HashMap, error::Error, sync::Arc};
 #[async_trait]
 pub trait IPFSClient: Send + Sync {
     async fn upload(&self, file_data: Vec<u8>) -> Result<String, Box<dyn Error + Send + Sync>>;
+
+    async fn fetch(&self, hash: &str) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        fetch_from_gateway(DEFAULT_GATEWAY_URL, hash).await
+    }
+}
+
+const DEFAULT_GATEWAY_URL: &str = "https://thedial.infura-ipfs.io/ipfs";
+
+async fn fetch_from_gateway(
+    gateway_url: &str,
+    hash: &str,
+) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    let url = format!(
+        "{}/{}",
+        gateway_url.trim_end_matches('/'),
+        hash.trim_start_matches("ipfs://")
+    );
+    let client = Client::new();
+    let response = send_with_retry(&RetryPolicy::default(), || client.get(&url)).await?;
+
+    if !response.status().is_success() {
+        return Err(format!("Failed to fetch {} from IPFS: {}", hash, response.status()).into());
+    }
+
+    Ok(response.bytes().await?.to_vec())
 }
 
 #[derive(Debug)]
@@ -18,9 +44,47 @@ pub enum IPFSProvider {
     Custom,
 }
 
+impl IPFSProvider {
+    pub fn required_config_keys(&self) -> &'static [&'static str] {
+        match self {
+            IPFSProvider::Infura => &["project_id", "project_secret"],
+            IPFSProvider::Pinata => &["api_key", "secret_api_key"],
+            IPFSProvider::Custom => &["api_url"],
+        }
+    }
+
+    pub fn validate_config(
+        &self,
+        config: &HashMap<String, String>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let missing: Vec<&str> = self
+            .required_config_keys()
+            .iter()
+            .filter(|key| {
+                config
+                    .get(**key)
+                    .is_none_or(|value| value.trim().is_empty())
+            })
+            .copied()
+            .collect();
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Missing ipfs_config keys for {:?}: {}",
+                self,
+                missing.join(", ")
+            )
+            .into())
+        }
+    }
+}
+
 #[derive(Debug)]
 struct CustomIPFSClient {
     pub api_url: String,
+    pub gateway_url: Option<String>,
     pub headers: HashMap<String, String>,
 }
 
@@ -33,6 +97,8 @@ impl fmt::Debug for dyn IPFSClient + Send + Sync {
 #[async_trait]
 impl IPFSClient for CustomIPFSClient {
     async fn upload(&self, file_data: Vec<u8>) -> Result<String, Box<dyn Error + Send + Sync>> {
+        #[cfg(feature = "metrics")]
+        let _timer = crate::metrics::ipfs_upload_timer();
         let client = Client::new();
         let mut request = client.post(&self.api_url);
 
@@ -46,6 +112,14 @@ impl IPFSClient for CustomIPFSClient {
         let ipfs_hash = response_json["Hash"].as_str().unwrap().to_string();
         Ok(ipfs_hash)
     }
+
+    async fn fetch(&self, hash: &str) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        fetch_from_gateway(
+            self.gateway_url.as_deref().unwrap_or(DEFAULT_GATEWAY_URL),
+            hash,
+        )
+        .await
+    }
 }
 
 struct InfuraIPFSClient {
@@ -56,6 +130,8 @@ struct InfuraIPFSClient {
 #[async_trait]
 impl IPFSClient for InfuraIPFSClient {
     async fn upload(&self, file_data: Vec<u8>) -> Result<String, Box<dyn Error + Send + Sync>> {
+        #[cfg(feature = "metrics")]
+        let _timer = crate::metrics::ipfs_upload_timer();
         let client = Client::new();
         let response = client
             .post("https://ipfs.infura.io:5001/api/v0/add")
@@ -84,6 +160,8 @@ struct PinataIPFSClient {
 #[async_trait]
 impl IPFSClient for PinataIPFSClient {
     async fn upload(&self, file_data: Vec<u8>) -> Result<String, Box<dyn Error + Send + Sync>> {
+        #[cfg(feature = "metrics")]
+        let _timer = crate::metrics::ipfs_upload_timer();
         let client = Client::new();
         let response = client
             .post("https://api.pinata.cloud/pinning/pinFileToIPFS")
@@ -126,16 +204,75 @@ impl IPFSClientFactory {
             })),
             IPFSProvider::Custom => {
                 let api_url = config.get("api_url").ok_or("API URL missing")?.to_string();
+                let gateway_url = config.get("gateway_url").cloned();
                 let headers: HashMap<String, String> = config
                     .iter()
-                    .filter(|(k, _)| k != &"api_url")
+                    .filter(|(k, _)| k != &"api_url" && k != &"gateway_url")
                     .map(|(k, v)| (k.clone(), v.clone()))
                     .collect();
 
-                Ok(Arc::new(CustomIPFSClient { api_url, headers }))
+                Ok(Arc::new(CustomIPFSClient {
+                    api_url,
+                    gateway_url,
+                    headers,
+                }))
             }
         }
     }
 }
 
+#[cfg(feature = "mock-ipfs")]
+#[derive(Debug, Default)]
+pub struct MockIPFSClient {
+    files: std::sync::RwLock<HashMap<String, Vec<u8>>>,
+    uploads: std::sync::RwLock<Vec<String>>,
+}
+
+#[cfg(feature = "mock-ipfs")]
+impl MockIPFSClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn uploads(&self) -> Vec<String> {
+        self.uploads.read().unwrap().clone()
+    }
 
+    pub fn get(&self, hash: &str) -> Option<Vec<u8>> {
+        self.files
+            .read()
+            .unwrap()
+            .get(hash.trim_start_matches("ipfs://"))
+            .cloned()
+    }
+
+    pub fn get_json(&self, hash: &str) -> Option<Value> {
+        serde_json::from_slice(&self.get(hash)?).ok()
+    }
+
+    pub fn clear(&self) {
+        self.files.write().unwrap().clear();
+        self.uploads.write().unwrap().clear();
+    }
+}
+
+#[cfg(feature = "mock-ipfs")]
+#[async_trait]
+impl IPFSClient for MockIPFSClient {
+    async fn upload(&self, file_data: Vec<u8>) -> Result<String, Box<dyn Error + Send + Sync>> {
+        use sha2::{Digest, Sha256};
+
+        let hash = format!(
+            "mock{}",
+            ethers::utils::hex::encode(Sha256::digest(&file_data))
+        );
+        self.files.write().unwrap().insert(hash.clone(), file_data);
+        self.uploads.write().unwrap().push(hash.clone());
+        Ok(hash)
+    }
+
+    async fn fetch(&self, hash: &str) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        self.get(hash)
+            .ok_or_else(|| format!("Hash {} was not uploaded to MockIPFSClient", hash).into())
+    }
+}
@@ -1,9 +1,15 @@
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
 pub mod nibble;
 pub mod workflow;
 pub mod ipfs;
 pub mod adapters;
 pub mod tools;
+pub mod error;
 mod utils;
 mod constants;
 mod encrypt;
+mod telemetry;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 
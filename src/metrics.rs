@@ -0,0 +1,85 @@
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramTimer, IntCounter, IntCounterVec, Opts, Registry,
+    TextEncoder,
+};
+use std::{error::Error, sync::OnceLock};
+
+pub struct Metrics {
+    pub registry: Registry,
+    pub workflows_executed_total: IntCounter,
+    pub node_failures_total: IntCounterVec,
+    pub llm_tokens_total: IntCounterVec,
+    pub ipfs_upload_duration_seconds: Histogram,
+}
+
+impl Metrics {
+    fn new() -> Result<Self, prometheus::Error> {
+        let registry = Registry::new();
+        let workflows_executed_total = IntCounter::new(
+            "workflows_executed_total",
+            "Workflow executions that completed without an error",
+        )?;
+        let node_failures_total = IntCounterVec::new(
+            Opts::new("node_failures_total", "Workflow nodes that failed"),
+            &["adapter_type"],
+        )?;
+        let llm_tokens_total = IntCounterVec::new(
+            Opts::new("llm_tokens_total", "Tokens reported by LLM responses"),
+            &["model"],
+        )?;
+        let ipfs_upload_duration_seconds = Histogram::with_opts(HistogramOpts::new(
+            "ipfs_upload_duration_seconds",
+            "Time spent uploading to IPFS",
+        ))?;
+
+        registry.register(Box::new(workflows_executed_total.clone()))?;
+        registry.register(Box::new(node_failures_total.clone()))?;
+        registry.register(Box::new(llm_tokens_total.clone()))?;
+        registry.register(Box::new(ipfs_upload_duration_seconds.clone()))?;
+
+        Ok(Self {
+            registry,
+            workflows_executed_total,
+            node_failures_total,
+            llm_tokens_total,
+            ipfs_upload_duration_seconds,
+        })
+    }
+
+    pub fn render(&self) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+}
+
+pub fn metrics() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(|| Metrics::new().expect("metric definitions are valid"))
+}
+
+pub fn render() -> Result<String, Box<dyn Error + Send + Sync>> {
+    metrics().render()
+}
+
+pub(crate) fn record_workflow_executed() {
+    metrics().workflows_executed_total.inc();
+}
+
+pub(crate) fn record_node_failure(adapter_type: &str) {
+    metrics()
+        .node_failures_total
+        .with_label_values(&[adapter_type])
+        .inc();
+}
+
+pub(crate) fn record_llm_tokens(model: &str, tokens: u64) {
+    metrics()
+        .llm_tokens_total
+        .with_label_values(&[model])
+        .inc_by(tokens);
+}
+
+pub(crate) fn ipfs_upload_timer() -> HistogramTimer {
+    metrics().ipfs_upload_duration_seconds.start_timer()
+}
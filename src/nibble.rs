@@ -3,35 +3,62 @@ use crate::{
         links::{
             conditions::{configure_new_condition, Condition, ConditionType},
             evaluations::{configure_new_evaluation, Evaluation, EvaluationType},
-            fhe_gates::{configure_new_gate, FHEGate},
+            fhe_gates::{configure_new_gate, FHEGate, FheOp},
             listeners::{configure_new_listener, Listener, ListenerType},
         },
         nodes::{
-            agents::{self, Agent, LLMModel, Objective},
+            agents::{self, Agent, LLMModel, LlmObserver, Objective},
             connectors::{
                 off_chain::{configure_new_offchain_connector, ConnectorType, OffChainConnector},
-                on_chain::{configure_new_onchain_connector, GasOptions, OnChainConnector},
+                on_chain::{
+                    configure_new_onchain_connector, BatchMode, GasOptions, GasOracle,
+                    OnChainConnector, OnChainTransaction,
+                },
             },
         },
     },
-    constants::NIBBLE_FACTORY_CONTRACT,
-    encrypt::encrypt_with_public_key,
+    constants::{
+        AGENT_WRITER_ACCESS_CONTROLS_VERSION, DEFAULT_CONFIRMATIONS, DEFAULT_ENCRYPTED_FIELDS,
+        DEFAULT_SUBGRAPH_PAGE_SIZE, GRAPH_ENDPOINT_DEV, GRAPH_ENDPOINT_PROD,
+        NIBBLE_ACCESS_CONTROLS_ABI, NIBBLE_FACTORY_ABI, NIBBLE_FACTORY_CONTRACT,
+        NIBBLE_STORAGE_ABI, PERSIST_MAX_FEE_PER_GAS, PERSIST_MAX_PRIORITY_FEE_PER_GAS,
+        PREFLIGHT_IPFS_PAYLOAD, SUBGRAPH_POLL_INTERVAL_MS,
+    },
+    encrypt::{encrypt_fields, encrypt_with_public_key},
+    error::NpcError,
     ipfs::{IPFSClient, IPFSClientFactory, IPFSProvider},
-    utils::{generate_unique_id, load_nibble_from_subgraph, load_workflow_from_subgraph},
-    workflow::Workflow,
+    utils::{
+        generate_unique_id, id_token, idempotency_key_hash, load_nibble_from_subgraph,
+        load_workflow_from_subgraph, send_with_retry, subgraph_block_number,
+        subgraph_entity_indexed,
+    },
+    workflow::{ErrorPolicy, ExecutionHistory, Simulation, Workflow},
 };
 use abi::{decode, ParamType};
+use async_trait::async_trait;
 use ethers::{
     abi::{Abi, Token, Tokenize},
     prelude::*,
-    types::{Address, Eip1559TransactionRequest, NameOrAddress, U256},
+    types::{
+        transaction::eip2718::TypedTransaction, Address, Eip1559TransactionRequest, NameOrAddress,
+        TransactionRequest, U256,
+    },
     utils::hex,
 };
 use futures::stream::{self, StreamExt, TryStreamExt};
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::{collections::HashMap, error::Error, fs::File, io::Read, path::Path, sync::Arc, vec};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+    vec,
+};
+use zeroize::Zeroizing;
 
 pub struct AdapterHandle<'a, T>
 where
@@ -58,17 +85,18 @@ pub enum Adapter {
     Evaluation,
 }
 
-impl ToString for Adapter {
-    fn to_string(&self) -> String {
-        match self {
-            Adapter::Condition => "Condition".to_string(),
-            Adapter::OffChainConnector => "OffChainConnector".to_string(),
-            Adapter::OnChainConnector => "OnChainConnector".to_string(),
-            Adapter::Listener => "Listener".to_string(),
-            Adapter::FHEGate => "FHEGate".to_string(),
-            Adapter::Agent => "Agent".to_string(),
-            Adapter::Evaluation => "Evaluation".to_string(),
-        }
+impl fmt::Display for Adapter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Adapter::Condition => "Condition",
+            Adapter::OffChainConnector => "OffChainConnector",
+            Adapter::OnChainConnector => "OnChainConnector",
+            Adapter::Listener => "Listener",
+            Adapter::FHEGate => "FHEGate",
+            Adapter::Agent => "Agent",
+            Adapter::Evaluation => "Evaluation",
+        };
+        write!(f, "{}", name)
     }
 }
 
@@ -78,6 +106,62 @@ pub struct ContractInfo {
     pub address: Address,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum PreflightStatus {
+    Ok(String),
+    Failed(String),
+}
+
+impl PreflightStatus {
+    fn from_result(result: Result<String, Box<dyn Error + Send + Sync>>) -> Self {
+        match result {
+            Ok(detail) => PreflightStatus::Ok(detail),
+            Err(e) => PreflightStatus::Failed(e.to_string()),
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        matches!(self, PreflightStatus::Ok(_))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PreflightReport {
+    pub rpc: PreflightStatus,
+    pub ipfs: PreflightStatus,
+    pub subgraph: PreflightStatus,
+    pub balance: PreflightStatus,
+}
+
+impl PreflightReport {
+    pub fn is_ok(&self) -> bool {
+        [&self.rpc, &self.ipfs, &self.subgraph, &self.balance]
+            .iter()
+            .all(|status| status.is_ok())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LoadWarning {
+    pub entity_type: String,
+    pub entity_id: Option<String>,
+    pub error: String,
+}
+
+impl LoadWarning {
+    pub fn new(entity_type: &str, data: &Value, error: Box<dyn Error + Send + Sync>) -> Self {
+        Self {
+            entity_type: entity_type.to_string(),
+            entity_id: data
+                .get("id")
+                .and_then(|v| v.as_str())
+                .or_else(|| data.as_str())
+                .map(|id| id.to_string()),
+            error: error.to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Nibble {
     pub agents: Vec<Agent>,
@@ -102,7 +186,289 @@ pub struct Nibble {
     pub chain: Chain,
     pub ipfs_client: Arc<dyn IPFSClient + Send + Sync>,
     pub graph_api_key: Option<String>,
+    pub subgraph: SubgraphConfig,
+    pub load_warnings: Vec<LoadWarning>,
+    pub encrypted_fields: Vec<String>,
     pub debug: bool,
+    pub nonce_manager: Arc<NonceManager>,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub llm_observer: Option<Arc<dyn LlmObserver>>,
+    pub fee_mode: FeeMode,
+    pub confirmations: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum FeeMode {
+    #[default]
+    Static,
+    DynamicFees(GasOracle),
+    Legacy(Option<U256>),
+}
+
+impl FeeMode {
+    pub async fn build_transaction(
+        &self,
+        provider: &Provider<Http>,
+        request: Eip1559TransactionRequest,
+    ) -> TypedTransaction {
+        let gas_price = match self {
+            FeeMode::Legacy(Some(gas_price)) => *gas_price,
+            FeeMode::Legacy(None) => match provider.get_gas_price().await {
+                Ok(gas_price) => gas_price,
+                Err(e) => {
+                    eprintln!("Gas price lookup failed, using max fee per gas: {:?}", e);
+                    request
+                        .max_fee_per_gas
+                        .unwrap_or(U256::from(PERSIST_MAX_FEE_PER_GAS))
+                }
+            },
+            _ => return request.into(),
+        };
+
+        TransactionRequest {
+            from: request.from,
+            to: request.to,
+            gas: request.gas,
+            gas_price: Some(gas_price),
+            value: request.value,
+            data: request.data,
+            nonce: request.nonce,
+            chain_id: request.chain_id,
+        }
+        .into()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SubgraphConfig {
+    pub endpoint: String,
+    pub page_size: usize,
+    pub retry: RetryPolicy,
+    pub client: Arc<dyn SubgraphClient>,
+}
+
+#[async_trait]
+pub trait SubgraphClient: fmt::Debug + Send + Sync {
+    async fn query(
+        &self,
+        endpoint: &str,
+        retry: &RetryPolicy,
+        query: &Value,
+    ) -> Result<Value, Box<dyn Error + Send + Sync>>;
+}
+
+#[derive(Debug, Default)]
+pub struct HttpSubgraphClient {
+    client: reqwest::Client,
+}
+
+#[async_trait]
+impl SubgraphClient for HttpSubgraphClient {
+    async fn query(
+        &self,
+        endpoint: &str,
+        retry: &RetryPolicy,
+        query: &Value,
+    ) -> Result<Value, Box<dyn Error + Send + Sync>> {
+        let res = send_with_retry(retry, || {
+            self.client
+                .post(endpoint)
+                .header("Content-Type", "application/json")
+                .json(query)
+        })
+        .await?;
+
+        if !res.status().is_success() {
+            let error_text = res.text().await?;
+            return Err(error_text.into());
+        }
+
+        Ok(res.json().await?)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl SubgraphConfig {
+    pub fn new(api_key: Option<String>) -> Self {
+        Self {
+            endpoint: match api_key {
+                Some(key) => GRAPH_ENDPOINT_PROD.replace("apikey", &key),
+                None => GRAPH_ENDPOINT_DEV.to_string(),
+            },
+            page_size: DEFAULT_SUBGRAPH_PAGE_SIZE,
+            retry: RetryPolicy::default(),
+            client: Arc::new(HttpSubgraphClient::default()),
+        }
+    }
+
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn with_client(mut self, client: Arc<dyn SubgraphClient>) -> Self {
+        self.client = client;
+        self
+    }
+
+    pub async fn query(&self, query: &Value) -> Result<Value, Box<dyn Error + Send + Sync>> {
+        self.client.query(&self.endpoint, &self.retry, query).await
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct NonceManager {
+    nonces: Mutex<HashMap<Address, U256>>,
+}
+
+impl NonceManager {
+    pub async fn next_nonce(
+        &self,
+        provider: &Provider<Http>,
+        address: Address,
+    ) -> Result<U256, Box<dyn Error + Send + Sync>> {
+        let cached = self
+            .nonces
+            .lock()
+            .map_err(|e| e.to_string())?
+            .get(&address)
+            .copied();
+
+        let pending = match cached {
+            Some(nonce) => nonce,
+            None => {
+                provider
+                    .get_transaction_count(address, Some(BlockNumber::Pending.into()))
+                    .await?
+            }
+        };
+
+        let mut nonces = self.nonces.lock().map_err(|e| e.to_string())?;
+        let nonce = nonces.entry(address).or_insert(pending);
+        let next = *nonce;
+        *nonce = next + 1;
+
+        Ok(next)
+    }
+
+    pub fn reset(&self, address: Address) {
+        if let Ok(mut nonces) = self.nonces.lock() {
+            nonces.remove(&address);
+        }
+    }
+
+    pub fn reset_all(&self) {
+        if let Ok(mut nonces) = self.nonces.lock() {
+            nonces.clear();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    pub requests_per_second: f64,
+    pub burst: u32,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    default_limit: Mutex<Option<RateLimit>>,
+    host_limits: Mutex<HashMap<String, RateLimit>>,
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(default_limit: Option<RateLimit>) -> Self {
+        Self {
+            default_limit: Mutex::new(default_limit),
+            ..Self::default()
+        }
+    }
+
+    pub fn set_default_limit(&self, limit: Option<RateLimit>) {
+        if let Ok(mut default_limit) = self.default_limit.lock() {
+            *default_limit = limit;
+        }
+    }
+
+    pub fn set_host_limit(&self, host: &str, limit: RateLimit) {
+        if let Ok(mut host_limits) = self.host_limits.lock() {
+            host_limits.insert(host.to_lowercase(), limit);
+        }
+        if let Ok(mut buckets) = self.buckets.lock() {
+            buckets.remove(&host.to_lowercase());
+        }
+    }
+
+    pub async fn acquire(&self, host: &str) {
+        let host = host.to_lowercase();
+        while let Some(wait) = self.try_acquire(&host) {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    fn limit_for(&self, host: &str) -> Option<RateLimit> {
+        let host_limit = self
+            .host_limits
+            .lock()
+            .ok()
+            .and_then(|limits| limits.get(host).copied());
+
+        host_limit
+            .or_else(|| self.default_limit.lock().ok().and_then(|limit| *limit))
+            .filter(|limit| limit.requests_per_second > 0.0)
+    }
+
+    fn try_acquire(&self, host: &str) -> Option<Duration> {
+        let limit = self.limit_for(host)?;
+        let mut buckets = self.buckets.lock().ok()?;
+        let burst = limit.burst.max(1) as f64;
+        let now = Instant::now();
+
+        let bucket = buckets.entry(host.to_string()).or_insert(TokenBucket {
+            tokens: burst,
+            last_refill: now,
+        });
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * limit.requests_per_second).min(burst);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / limit.requests_per_second,
+            ))
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -114,7 +480,7 @@ pub struct ModifyAdapters {
     pub evaluations: Vec<ContractEvaluation>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
 pub struct RemoveAdapters {
     pub conditions: Vec<String>,
     pub listeners: Vec<String>,
@@ -125,13 +491,13 @@ pub struct RemoveAdapters {
 
 impl Tokenize for ModifyAdapters {
     fn into_tokens(self) -> Vec<Token> {
-        vec![
+        vec![Token::Tuple(vec![
             Token::Array(
                 self.conditions
                     .into_iter()
                     .map(|condition| {
                         Token::Tuple(vec![
-                            Token::String(condition.id),
+                            id_token(condition.id),
                             Token::String(condition.metadata),
                             Token::Bool(condition.encrypted),
                         ])
@@ -143,7 +509,7 @@ impl Tokenize for ModifyAdapters {
                     .into_iter()
                     .map(|listener| {
                         Token::Tuple(vec![
-                            Token::String(listener.id),
+                            id_token(listener.id),
                             Token::String(listener.metadata),
                             Token::Bool(listener.encrypted),
                         ])
@@ -155,7 +521,7 @@ impl Tokenize for ModifyAdapters {
                     .into_iter()
                     .map(|connector| {
                         Token::Tuple(vec![
-                            Token::String(connector.id),
+                            id_token(connector.id),
                             Token::String(connector.metadata),
                             Token::Bool(connector.encrypted),
                             Token::Bool(connector.onChain),
@@ -168,7 +534,7 @@ impl Tokenize for ModifyAdapters {
                     .into_iter()
                     .map(|agent| {
                         Token::Tuple(vec![
-                            Token::String(agent.id),
+                            id_token(agent.id),
                             Token::String(agent.metadata),
                             Token::Address(agent.wallet),
                             Token::Bool(agent.encrypted),
@@ -182,14 +548,33 @@ impl Tokenize for ModifyAdapters {
                     .into_iter()
                     .map(|evaluation| {
                         Token::Tuple(vec![
-                            Token::String(evaluation.id),
+                            id_token(evaluation.id),
                             Token::String(evaluation.metadata),
                             Token::Bool(evaluation.encrypted),
                         ])
                     })
                     .collect(),
             ),
-        ]
+            Token::Array(vec![]),
+        ])]
+    }
+}
+
+impl RemoveAdapters {
+    pub fn for_adapter(adapter_type: &Adapter, id: &str) -> Option<RemoveAdapters> {
+        let mut remove_adapters = RemoveAdapters::default();
+        let ids = match adapter_type {
+            Adapter::Condition => &mut remove_adapters.conditions,
+            Adapter::Listener => &mut remove_adapters.listeners,
+            Adapter::OnChainConnector | Adapter::OffChainConnector => {
+                &mut remove_adapters.connectors
+            }
+            Adapter::Agent => &mut remove_adapters.agents,
+            Adapter::Evaluation => &mut remove_adapters.evaluations,
+            Adapter::FHEGate => return None,
+        };
+        ids.push(id.to_string());
+        Some(remove_adapters)
     }
 }
 
@@ -249,20 +634,157 @@ enum Connector<'a> {
     OffChain(&'a OffChainConnector),
 }
 
-impl Nibble {
-    pub fn new(
-        owner_private_key: &str,
-        rpc_url: &str,
-        ipfs_provider: IPFSProvider,
-        ipfs_config: HashMap<String, String>,
-        chain: Chain,
-        graph_api_key: Option<String>,
-        debug: Option<bool>,
-    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
-        Ok(Self {
+#[derive(Default)]
+struct OwnerKey(Zeroizing<String>);
+
+impl fmt::Debug for OwnerKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("OwnerKey(***)")
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct NibbleBuilder {
+    owner_private_key: Option<OwnerKey>,
+    owner_wallet: Option<LocalWallet>,
+    rpc_url: Option<String>,
+    ipfs_provider: Option<IPFSProvider>,
+    ipfs_config: HashMap<String, String>,
+    chain: Option<Chain>,
+    graph_api_key: Option<String>,
+    subgraph: Option<SubgraphConfig>,
+    encrypted_fields: Option<Vec<String>>,
+    debug: bool,
+    llm_observer: Option<Arc<dyn LlmObserver>>,
+    fee_mode: FeeMode,
+    confirmations: Option<usize>,
+}
+
+impl NibbleBuilder {
+    pub fn owner_private_key(mut self, owner_private_key: &str) -> Self {
+        self.owner_private_key = Some(OwnerKey(Zeroizing::new(owner_private_key.to_string())));
+        self
+    }
+
+    pub fn owner_wallet(mut self, owner_wallet: LocalWallet) -> Self {
+        self.owner_wallet = Some(owner_wallet);
+        self
+    }
+
+    pub fn owner_keystore(
+        self,
+        path: impl AsRef<Path>,
+        password: impl AsRef<[u8]>,
+    ) -> Result<Self, NpcError> {
+        let owner_wallet = LocalWallet::decrypt_keystore(path, password)
+            .map_err(|e| NpcError::validation(format!("Invalid owner keystore: {}", e)))?;
+        Ok(self.owner_wallet(owner_wallet))
+    }
+
+    pub fn owner_private_key_env(self, var_name: &str) -> Result<Self, NpcError> {
+        let owner_private_key = Zeroizing::new(std::env::var(var_name).map_err(|e| {
+            NpcError::validation(format!(
+                "Owner private key {} not available: {}",
+                var_name, e
+            ))
+        })?);
+        Ok(self.owner_private_key(&owner_private_key))
+    }
+
+    pub fn rpc_url(mut self, rpc_url: &str) -> Self {
+        self.rpc_url = Some(rpc_url.to_string());
+        self
+    }
+
+    pub fn ipfs_provider(mut self, ipfs_provider: IPFSProvider) -> Self {
+        self.ipfs_provider = Some(ipfs_provider);
+        self
+    }
+
+    pub fn ipfs_config(mut self, ipfs_config: HashMap<String, String>) -> Self {
+        self.ipfs_config = ipfs_config;
+        self
+    }
+
+    pub fn ipfs_option(mut self, key: &str, value: &str) -> Self {
+        self.ipfs_config.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    pub fn chain(mut self, chain: Chain) -> Self {
+        self.chain = Some(chain);
+        self
+    }
+
+    pub fn graph_api_key(mut self, graph_api_key: &str) -> Self {
+        self.graph_api_key = Some(graph_api_key.to_string());
+        self
+    }
+
+    pub fn subgraph(mut self, subgraph: SubgraphConfig) -> Self {
+        self.subgraph = Some(subgraph);
+        self
+    }
+
+    pub fn encrypted_fields(mut self, encrypted_fields: Vec<String>) -> Self {
+        self.encrypted_fields = Some(encrypted_fields);
+        self
+    }
+
+    pub fn debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+
+    pub fn llm_observer(mut self, observer: Arc<dyn LlmObserver>) -> Self {
+        self.llm_observer = Some(observer);
+        self
+    }
+
+    pub fn fee_mode(mut self, fee_mode: FeeMode) -> Self {
+        self.fee_mode = fee_mode;
+        self
+    }
+
+    pub fn confirmations(mut self, confirmations: usize) -> Self {
+        self.confirmations = Some(confirmations.max(1));
+        self
+    }
+
+    pub fn build(self) -> Result<Nibble, NpcError> {
+        let owner_wallet: LocalWallet = match (self.owner_wallet, self.owner_private_key) {
+            (Some(owner_wallet), _) => owner_wallet,
+            (None, Some(owner_private_key)) => owner_private_key
+                .0
+                .parse()
+                .map_err(|e| NpcError::validation(format!("Invalid owner private key: {}", e)))?,
+            (None, None) => {
+                return Err(NpcError::validation("Nibble requires an owner private key"))
+            }
+        };
+
+        let rpc_url = self
+            .rpc_url
+            .ok_or_else(|| NpcError::validation("Nibble requires an RPC url"))?;
+        let provider = reqwest::Url::parse(&rpc_url)
+            .map_err(|e| e.to_string())
+            .and_then(|url| match url.scheme() {
+                "http" | "https" => Ok(Provider::<Http>::new(Http::new(url))),
+                scheme => Err(format!("unsupported scheme {}", scheme)),
+            })
+            .map_err(|e| NpcError::validation(format!("Invalid RPC url {}: {}", rpc_url, e)))?;
+
+        let ipfs_provider = self
+            .ipfs_provider
+            .ok_or_else(|| NpcError::validation("Nibble requires an IPFS provider"))?;
+        ipfs_provider
+            .validate_config(&self.ipfs_config)
+            .map_err(NpcError::validation)?;
+
+        Ok(Nibble {
             agents: vec![],
             contracts: vec![],
-            owner_wallet: owner_private_key.parse()?,
+            owner_wallet,
             id: None,
             count: U256::from(0),
             fhe_gates: vec![],
@@ -278,15 +800,120 @@ impl Nibble {
             saved_conditions: vec![],
             saved_listeners: vec![],
             saved_agents: vec![],
-            provider: Provider::<Http>::try_from(rpc_url)?,
+            provider,
+            chain: self.chain.unwrap_or(Chain::PolygonAmoy),
+            subgraph: self
+                .subgraph
+                .unwrap_or_else(|| SubgraphConfig::new(self.graph_api_key.clone())),
+            load_warnings: vec![],
+            encrypted_fields: self.encrypted_fields.unwrap_or_else(|| {
+                DEFAULT_ENCRYPTED_FIELDS
+                    .iter()
+                    .map(|field| field.to_string())
+                    .collect()
+            }),
+            graph_api_key: self.graph_api_key,
+            ipfs_client: IPFSClientFactory::create_client(ipfs_provider, self.ipfs_config)?,
+            debug: self.debug,
+            nonce_manager: Arc::new(NonceManager::default()),
+            rate_limiter: Arc::new(RateLimiter::default()),
+            llm_observer: self.llm_observer,
+            fee_mode: self.fee_mode,
+            confirmations: self.confirmations.unwrap_or(DEFAULT_CONFIRMATIONS),
+        })
+    }
+}
+
+impl Nibble {
+    pub fn builder() -> NibbleBuilder {
+        NibbleBuilder::default()
+    }
+
+    /// Builds a Nibble from a plaintext private key. Prefer `Nibble::from_keystore` or
+    /// `Nibble::from_env` so the key never has to live in application code or logs.
+    pub fn new(
+        owner_private_key: &str,
+        rpc_url: &str,
+        ipfs_provider: IPFSProvider,
+        ipfs_config: HashMap<String, String>,
+        chain: Chain,
+        graph_api_key: Option<String>,
+        debug: Option<bool>,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Nibble::from_builder(
+            Nibble::builder().owner_private_key(owner_private_key),
+            rpc_url,
+            ipfs_provider,
+            ipfs_config,
             chain,
             graph_api_key,
-            ipfs_client: IPFSClientFactory::create_client(ipfs_provider, ipfs_config)?,
-            debug: match debug {
-                Some(debug) => debug,
-                None => false,
-            },
-        })
+            debug,
+        )
+    }
+
+    /// Builds a Nibble from an encrypted JSON keystore, decrypted with `password`.
+    pub fn from_keystore(
+        path: impl AsRef<Path>,
+        password: impl AsRef<[u8]>,
+        rpc_url: &str,
+        ipfs_provider: IPFSProvider,
+        ipfs_config: HashMap<String, String>,
+        chain: Chain,
+        graph_api_key: Option<String>,
+        debug: Option<bool>,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Nibble::from_builder(
+            Nibble::builder().owner_keystore(path, password)?,
+            rpc_url,
+            ipfs_provider,
+            ipfs_config,
+            chain,
+            graph_api_key,
+            debug,
+        )
+    }
+
+    /// Builds a Nibble from a private key held in the `var_name` environment variable.
+    pub fn from_env(
+        var_name: &str,
+        rpc_url: &str,
+        ipfs_provider: IPFSProvider,
+        ipfs_config: HashMap<String, String>,
+        chain: Chain,
+        graph_api_key: Option<String>,
+        debug: Option<bool>,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Nibble::from_builder(
+            Nibble::builder().owner_private_key_env(var_name)?,
+            rpc_url,
+            ipfs_provider,
+            ipfs_config,
+            chain,
+            graph_api_key,
+            debug,
+        )
+    }
+
+    fn from_builder(
+        builder: NibbleBuilder,
+        rpc_url: &str,
+        ipfs_provider: IPFSProvider,
+        ipfs_config: HashMap<String, String>,
+        chain: Chain,
+        graph_api_key: Option<String>,
+        debug: Option<bool>,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut builder = builder
+            .rpc_url(rpc_url)
+            .ipfs_provider(ipfs_provider)
+            .ipfs_config(ipfs_config)
+            .chain(chain)
+            .debug(debug.unwrap_or(false));
+        if let Some(graph_api_key) = graph_api_key {
+            builder = builder.graph_api_key(&graph_api_key);
+        }
+
+        Ok(builder.build()?)
     }
 
     pub fn add_listener(
@@ -294,9 +921,17 @@ impl Nibble {
         name: &str,
         listener_type: ListenerType,
         encrypted: bool,
+        debounce: Option<Duration>,
+        dedup_key: Option<String>,
     ) -> Result<AdapterHandle<'_, Listener>, Box<dyn Error + Send + Sync>> {
-        let listener =
-            configure_new_listener(name, listener_type, encrypted, &self.owner_wallet.address())?;
+        let listener = configure_new_listener(
+            name,
+            listener_type,
+            encrypted,
+            debounce,
+            dedup_key,
+            &self.owner_wallet.address(),
+        )?;
         self.listeners.push(listener.clone());
         Ok(AdapterHandle {
             nibble: self,
@@ -335,7 +970,7 @@ impl Nibble {
         key: &str,
         encrypted: bool,
         contract_address: &H160,
-        operation: &str,
+        operation: FheOp,
         chain: Chain,
     ) -> Result<AdapterHandle<'_, FHEGate>, Box<dyn Error + Send + Sync>> {
         let fhe_gate: FHEGate = configure_new_gate(
@@ -385,6 +1020,8 @@ impl Nibble {
         abi: Option<abi::Abi>,
         chain: Chain,
         gas_options: Option<GasOptions>,
+        transactions: Vec<OnChainTransaction>,
+        batch_mode: BatchMode,
     ) -> Result<AdapterHandle<'_, OnChainConnector>, Box<dyn Error + Send + Sync>> {
         let on_chain = configure_new_onchain_connector(
             name,
@@ -395,6 +1032,8 @@ impl Nibble {
             abi,
             chain,
             gas_options,
+            transactions,
+            batch_mode,
         )?;
         self.onchain_connectors.push(on_chain.clone());
         Ok(AdapterHandle {
@@ -434,51 +1073,382 @@ impl Nibble {
             auth_subflow,
         )?;
 
-        self.offchain_connectors.push(off_chain.clone());
-        Ok(AdapterHandle {
-            nibble: self,
-            adapter: off_chain,
-            adapter_type: Adapter::OffChainConnector,
-        })
+        self.offchain_connectors.push(off_chain.clone());
+        Ok(AdapterHandle {
+            nibble: self,
+            adapter: off_chain,
+            adapter_type: Adapter::OffChainConnector,
+        })
+    }
+
+    pub fn add_agent(
+        &mut self,
+        name: &str,
+        role: &str,
+        personality: &str,
+        system: &str,
+        write_role: bool,
+        admin_role: bool,
+        model: LLMModel,
+        encrypted: bool,
+        agent_wallet: Option<&H160>,
+        lens_account: Option<&str>,
+        farcaster_account: Option<&str>,
+        objectives: Vec<Objective>,
+    ) -> Result<AdapterHandle<'_, Agent>, Box<dyn Error + Send + Sync>> {
+        let mut agent = agents::configure_new_agent(
+            name,
+            role,
+            personality,
+            system,
+            write_role,
+            admin_role,
+            encrypted,
+            model,
+            &self.owner_wallet.address(),
+            agent_wallet,
+            farcaster_account,
+            lens_account,
+            objectives,
+        )?;
+        agent.observer = self.llm_observer.clone();
+
+        self.agents.push(agent.clone());
+        Ok(AdapterHandle {
+            nibble: self,
+            adapter: agent,
+            adapter_type: Adapter::Agent,
+        })
+    }
+
+    pub fn set_llm_observer(&mut self, observer: Arc<dyn LlmObserver>) -> &mut Self {
+        self.llm_observer = Some(observer);
+        self.apply_llm_observer();
+        self
+    }
+
+    fn apply_llm_observer(&mut self) {
+        if let Some(observer) = &self.llm_observer {
+            for agent in self.agents.iter_mut().chain(self.saved_agents.iter_mut()) {
+                if agent.observer.is_none() {
+                    agent.observer = Some(observer.clone());
+                }
+            }
+        }
+    }
+
+    pub fn set_fee_mode(&mut self, fee_mode: FeeMode) -> &mut Self {
+        self.fee_mode = fee_mode;
+        self
+    }
+
+    pub fn set_confirmations(&mut self, confirmations: usize) -> &mut Self {
+        self.confirmations = confirmations.max(1);
+        self
+    }
+
+    pub async fn resolve_fees(
+        &self,
+        static_max_fee_per_gas: U256,
+        static_max_priority_fee_per_gas: U256,
+    ) -> (U256, U256) {
+        match &self.fee_mode {
+            FeeMode::Static | FeeMode::Legacy(_) => {
+                (static_max_fee_per_gas, static_max_priority_fee_per_gas)
+            }
+            FeeMode::DynamicFees(oracle) => match oracle.estimate_fees(&self.provider).await {
+                Ok(fees) => fees,
+                Err(e) => {
+                    eprintln!("Dynamic fee estimation failed, using static fees: {:?}", e);
+                    (static_max_fee_per_gas, static_max_priority_fee_per_gas)
+                }
+            },
+        }
+    }
+
+    pub async fn detect_fee_mode(&mut self) -> Result<&FeeMode, Box<dyn Error + Send + Sync>> {
+        let block = self
+            .provider
+            .get_block(BlockNumber::Latest)
+            .await?
+            .ok_or("Latest block not found")?;
+
+        if block.base_fee_per_gas.is_none() && !matches!(self.fee_mode, FeeMode::Legacy(_)) {
+            self.fee_mode = FeeMode::Legacy(None);
+        }
+
+        Ok(&self.fee_mode)
+    }
+
+    pub async fn build_transaction(&self, request: Eip1559TransactionRequest) -> TypedTransaction {
+        self.fee_mode
+            .build_transaction(&self.provider, request)
+            .await
+    }
+
+    pub fn adapter_is_saved(&self, adapter_id: &str) -> bool {
+        self.saved_agents.iter().any(|a| a.id == adapter_id)
+            || self.saved_conditions.iter().any(|c| c.id == adapter_id)
+            || self.saved_listeners.iter().any(|l| l.id == adapter_id)
+            || self.saved_fhe_gates.iter().any(|f| f.id == adapter_id)
+            || self.saved_evaluations.iter().any(|e| e.id == adapter_id)
+            || self
+                .saved_onchain_connectors
+                .iter()
+                .any(|c| c.id == adapter_id)
+            || self
+                .saved_offchain_connectors
+                .iter()
+                .any(|c| c.id == adapter_id)
+    }
+
+    pub fn adapter_exists_locally(&self, adapter_id: &str) -> bool {
+        self.agents.iter().any(|a| a.id == adapter_id)
+            || self.conditions.iter().any(|c| c.id == adapter_id)
+            || self.listeners.iter().any(|l| l.id == adapter_id)
+            || self.fhe_gates.iter().any(|f| f.id == adapter_id)
+            || self.evaluations.iter().any(|e| e.id == adapter_id)
+            || self.onchain_connectors.iter().any(|c| c.id == adapter_id)
+            || self.offchain_connectors.iter().any(|c| c.id == adapter_id)
+    }
+
+    pub fn find_agent(&self, name: &str) -> Option<&Agent> {
+        find_adapter(&self.agents, &self.saved_agents, |adapter| {
+            adapter.name() == name
+        })
+    }
+
+    pub fn find_agent_by_id(&self, id: &str) -> Option<&Agent> {
+        find_adapter(&self.agents, &self.saved_agents, |adapter| {
+            adapter.id() == id
+        })
+    }
+
+    pub fn find_condition(&self, name: &str) -> Option<&Condition> {
+        find_adapter(&self.conditions, &self.saved_conditions, |adapter| {
+            adapter.name() == name
+        })
+    }
+
+    pub fn find_condition_by_id(&self, id: &str) -> Option<&Condition> {
+        find_adapter(&self.conditions, &self.saved_conditions, |adapter| {
+            adapter.id() == id
+        })
+    }
+
+    pub fn find_listener(&self, name: &str) -> Option<&Listener> {
+        find_adapter(&self.listeners, &self.saved_listeners, |adapter| {
+            adapter.name() == name
+        })
+    }
+
+    pub fn find_listener_by_id(&self, id: &str) -> Option<&Listener> {
+        find_adapter(&self.listeners, &self.saved_listeners, |adapter| {
+            adapter.id() == id
+        })
+    }
+
+    pub fn find_evaluation(&self, name: &str) -> Option<&Evaluation> {
+        find_adapter(&self.evaluations, &self.saved_evaluations, |adapter| {
+            adapter.name() == name
+        })
+    }
+
+    pub fn find_evaluation_by_id(&self, id: &str) -> Option<&Evaluation> {
+        find_adapter(&self.evaluations, &self.saved_evaluations, |adapter| {
+            adapter.id() == id
+        })
+    }
+
+    pub fn find_onchain_connector(&self, name: &str) -> Option<&OnChainConnector> {
+        find_adapter(
+            &self.onchain_connectors,
+            &self.saved_onchain_connectors,
+            |adapter| adapter.name() == name,
+        )
+    }
+
+    pub fn find_onchain_connector_by_id(&self, id: &str) -> Option<&OnChainConnector> {
+        find_adapter(
+            &self.onchain_connectors,
+            &self.saved_onchain_connectors,
+            |adapter| adapter.id() == id,
+        )
+    }
+
+    pub fn find_offchain_connector(&self, name: &str) -> Option<&OffChainConnector> {
+        find_adapter(
+            &self.offchain_connectors,
+            &self.saved_offchain_connectors,
+            |adapter| adapter.name() == name,
+        )
+    }
+
+    pub fn find_offchain_connector_by_id(&self, id: &str) -> Option<&OffChainConnector> {
+        find_adapter(
+            &self.offchain_connectors,
+            &self.saved_offchain_connectors,
+            |adapter| adapter.id() == id,
+        )
+    }
+
+    pub fn find_fhe_gate(&self, name: &str) -> Option<&FHEGate> {
+        find_adapter(&self.fhe_gates, &self.saved_fhe_gates, |adapter| {
+            adapter.name() == name
+        })
+    }
+
+    pub fn find_fhe_gate_by_id(&self, id: &str) -> Option<&FHEGate> {
+        find_adapter(&self.fhe_gates, &self.saved_fhe_gates, |adapter| {
+            adapter.id() == id
+        })
+    }
+
+    pub fn set_agent_examples(
+        &mut self,
+        agent_id: &str,
+        examples: Vec<(String, String)>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut updated_agent = None;
+        for agent in self.agents.iter_mut().chain(self.saved_agents.iter_mut()) {
+            if agent.id == agent_id {
+                agent.examples = examples.clone();
+                updated_agent = Some(agent.clone());
+            }
+        }
+
+        let agent = updated_agent.ok_or("Agent not found")?;
+        if !self.agents.iter().any(|active| active.id == agent_id) {
+            self.agents.push(agent);
+        }
+        Ok(())
+    }
+
+    pub async fn set_agent_writer(
+        &mut self,
+        agent_id: &str,
+        writer: bool,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let agent_address = self
+            .agents
+            .iter()
+            .chain(self.saved_agents.iter())
+            .find(|agent| agent.id == agent_id)
+            .map(|agent| agent.wallet.address())
+            .ok_or("Agent not found")?;
+
+        let access_control_address = match self
+            .contracts
+            .iter()
+            .find(|c| c.name == "NibbleAccessControl")
+        {
+            Some(contract) => contract.address,
+            None => {
+                println!("NibbleAccessControl contract not found, writer status will be stored on the next persist.");
+                self.update_agent_writer(agent_id, writer);
+                return Ok(());
+            }
+        };
+
+        let client = SignerMiddleware::new(
+            self.provider.clone(),
+            self.owner_wallet.clone().with_chain_id(self.chain),
+        );
+        let client = Arc::new(client);
+
+        let abi = serde_json::from_str::<Abi>(NIBBLE_ACCESS_CONTROLS_ABI)?;
+        let contract_instance = Contract::new(access_control_address, abi, client.clone());
+
+        // NibbleAccessControls deployed before version 2 has no setAgentWriter and no version().
+        let version = contract_instance
+            .method::<_, U256>("version", ())?
+            .call()
+            .await
+            .unwrap_or_default();
+        if version < U256::from(AGENT_WRITER_ACCESS_CONTROLS_VERSION) {
+            return Err(format!(
+                "NibbleAccessControl at {:?} predates setAgentWriter, redeploy the Nibble to manage agent writers",
+                access_control_address
+            )
+            .into());
+        }
+
+        let method = contract_instance.method::<_, H256>("setAgentWriter", (agent_address, writer));
+
+        match method {
+            Ok(call) => {
+                let FunctionCall { tx, .. } = call;
+
+                if let Some(tx_request) = tx.as_eip1559_ref() {
+                    let cliente = contract_instance.client().clone();
+                    let nonce = self
+                        .nonce_manager
+                        .next_nonce(&self.provider, client.address())
+                        .await?;
+                    let (max_fee_per_gas, max_priority_fee_per_gas) = self
+                        .resolve_fees(
+                            U256::from(PERSIST_MAX_FEE_PER_GAS),
+                            U256::from(PERSIST_MAX_PRIORITY_FEE_PER_GAS),
+                        )
+                        .await;
+                    let req = Eip1559TransactionRequest {
+                        from: Some(client.address()),
+                        to: Some(NameOrAddress::Address(access_control_address)),
+                        gas: Some(U256::from(300_000)),
+                        value: tx_request.value,
+                        data: tx_request.data.clone(),
+                        max_fee_per_gas: Some(max_fee_per_gas),
+                        max_priority_fee_per_gas: Some(max_priority_fee_per_gas),
+                        nonce: Some(nonce),
+                        ..Default::default()
+                    };
+
+                    let req = self.build_transaction(req).await;
+                    let pending_tx = cliente.send_transaction(req, None).await.map_err(|e| {
+                        eprintln!("Error sending the transaction: {:?}", e);
+                        self.nonce_manager.reset(client.address());
+                        Box::<dyn Error + Send + Sync>::from(format!(
+                            "Error sending the transaction: {}",
+                            e
+                        ))
+                    })?;
+
+                    match pending_tx.confirmations(self.confirmations).await {
+                        Ok(Some(receipt)) if receipt.status == Some(1.into()) => {}
+                        Ok(Some(receipt)) => {
+                            eprintln!("Error with the transaction: {:?}", receipt.status);
+                            return Err("setAgentWriter transaction reverted".into());
+                        }
+                        Ok(None) => {
+                            self.nonce_manager.reset(client.address());
+                            return Err("Transaction not recieved".into());
+                        }
+                        Err(e) => {
+                            eprintln!("Error with the transaction: {:?}", e);
+                            self.nonce_manager.reset(client.address());
+                            return Err(e.into());
+                        }
+                    }
+                } else {
+                    return Err("EIP-1559 reference invalid.".into());
+                }
+            }
+            Err(e) => {
+                eprintln!("Error while preparing the method of setAgentWriter: {}", e);
+                return Err(e.into());
+            }
+        }
+
+        self.update_agent_writer(agent_id, writer);
+        Ok(())
     }
 
-    pub fn add_agent(
-        &mut self,
-        name: &str,
-        role: &str,
-        personality: &str,
-        system: &str,
-        write_role: bool,
-        admin_role: bool,
-        model: LLMModel,
-        encrypted: bool,
-        agent_wallet: Option<&H160>,
-        lens_account: Option<&str>,
-        farcaster_account: Option<&str>,
-        objectives: Vec<Objective>,
-    ) -> Result<AdapterHandle<'_, Agent>, Box<dyn Error + Send + Sync>> {
-        let agent = agents::configure_new_agent(
-            name,
-            role,
-            personality,
-            system,
-            write_role,
-            admin_role,
-            encrypted,
-            model,
-            &self.owner_wallet.address(),
-            agent_wallet,
-            farcaster_account,
-            lens_account,
-            objectives,
-        )?;
-
-        self.agents.push(agent.clone());
-        Ok(AdapterHandle {
-            nibble: self,
-            adapter: agent,
-            adapter_type: Adapter::Agent,
-        })
+    fn update_agent_writer(&mut self, agent_id: &str, writer: bool) {
+        for agent in self.agents.iter_mut().chain(self.saved_agents.iter_mut()) {
+            if agent.id == agent_id {
+                agent.write_role = writer;
+            }
+        }
     }
 
     pub async fn create_nibble(&mut self) -> Result<Nibble, Box<dyn Error + Send + Sync>> {
@@ -488,10 +1458,7 @@ impl Nibble {
         );
         let client = Arc::new(client);
 
-        let mut abi_file = File::open(Path::new("./abis/NibbleFactory.json"))?;
-        let mut abi_content = String::new();
-        abi_file.read_to_string(&mut abi_content)?;
-        let abi = serde_json::from_str::<Abi>(&abi_content)?;
+        let abi = serde_json::from_str::<Abi>(NIBBLE_FACTORY_ABI)?;
 
         let contract_instance = Contract::new(
             NIBBLE_FACTORY_CONTRACT.parse::<Address>().unwrap(),
@@ -500,7 +1467,7 @@ impl Nibble {
         );
 
         let method =
-            contract_instance.method::<_, ([Address; 9], String, U256)>("deployFromFactory", {});
+            contract_instance.method::<_, ([Address; 9], String, U256)>("deployFromFactory", ());
 
         match method {
             Ok(call) => {
@@ -509,6 +1476,12 @@ impl Nibble {
                 if let Some(tx_request) = tx.as_eip1559_ref() {
                     let cliente = contract_instance.client().clone();
 
+                    let (max_fee_per_gas, max_priority_fee_per_gas) = self
+                        .resolve_fees(
+                            U256::from(PERSIST_MAX_FEE_PER_GAS),
+                            U256::from(PERSIST_MAX_PRIORITY_FEE_PER_GAS),
+                        )
+                        .await;
                     let req = Eip1559TransactionRequest {
                         from: Some(client.address()),
                         to: Some(NameOrAddress::Address(
@@ -517,12 +1490,13 @@ impl Nibble {
                         gas: Some(U256::from(1252629)),
                         value: tx_request.value,
                         data: tx_request.data.clone(),
-                        max_fee_per_gas: Some(U256::from_dec_str("44786996170").unwrap()),
-                        max_priority_fee_per_gas: Some(U256::from_dec_str("25000000000").unwrap()),
+                        max_fee_per_gas: Some(max_fee_per_gas),
+                        max_priority_fee_per_gas: Some(max_priority_fee_per_gas),
                         chain_id: Some(Chain::PolygonAmoy.into()),
                         ..Default::default()
                     };
 
+                    let req = self.build_transaction(req).await;
                     let pending_tx = match cliente.send_transaction(req, None).await {
                         Ok(tx) => tx,
                         Err(e) => {
@@ -531,7 +1505,7 @@ impl Nibble {
                         }
                     };
 
-                    let receipt = match pending_tx.await {
+                    let receipt = match pending_tx.confirmations(self.confirmations).await {
                         Ok(Some(receipt)) => {
                             if receipt.status != Some(1.into()) {
                                 eprintln!("Error with the transaction: {:?}", receipt.status);
@@ -548,7 +1522,7 @@ impl Nibble {
                         }
                     };
 
-                    if let Some(log) = receipt.logs.get(0) {
+                    if let Some(log) = receipt.logs.first() {
                         let log_data_bytes = log.data.0.clone();
                         let decoded: Vec<Token> = decode(
                             &[
@@ -561,7 +1535,7 @@ impl Nibble {
 
                         let return_values: ([Address; 9], String, U256) = {
                             let addresses: [Address; 9] = decoded
-                                .get(0)
+                                .first()
                                 .and_then(|token| {
                                     if let Token::FixedArray(array) = token {
                                         array
@@ -577,7 +1551,7 @@ impl Nibble {
                                         None
                                     }
                                 })
-                                .ok_or_else(|| "Invalid address array")?;
+                                .ok_or("Invalid address array")?;
 
                             let id: String = decoded
                                 .get(1)
@@ -588,7 +1562,7 @@ impl Nibble {
                                         None
                                     }
                                 })
-                                .ok_or_else(|| "Invalid ID bytes")?;
+                                .ok_or("Invalid ID bytes")?;
 
                             let count: U256 = decoded
                                 .get(2)
@@ -599,7 +1573,7 @@ impl Nibble {
                                         None
                                     }
                                 })
-                                .ok_or_else(|| "Invalid count")?;
+                                .ok_or("Invalid count")?;
 
                             (addresses, id, count)
                         };
@@ -656,9 +1630,9 @@ impl Nibble {
                             contracts: self.contracts.clone(),
                             owner_wallet: self.owner_wallet.clone(),
                             id: self.id.clone(),
-                            count: self.count.clone(),
+                            count: self.count,
                             provider: self.provider.clone(),
-                            chain: self.chain.clone(),
+                            chain: self.chain,
                             saved_fhe_gates: vec![],
                             saved_evaluations: vec![],
                             saved_onchain_connectors: vec![],
@@ -668,7 +1642,15 @@ impl Nibble {
                             saved_agents: vec![],
                             ipfs_client: self.ipfs_client.clone(),
                             graph_api_key: self.graph_api_key.clone(),
+                            subgraph: self.subgraph.clone(),
+                            load_warnings: self.load_warnings.clone(),
+                            encrypted_fields: self.encrypted_fields.clone(),
                             debug: self.debug,
+                            nonce_manager: self.nonce_manager.clone(),
+                            rate_limiter: self.rate_limiter.clone(),
+                            llm_observer: self.llm_observer.clone(),
+                            fee_mode: self.fee_mode.clone(),
+                            confirmations: self.confirmations,
                         })
                     } else {
                         Err("No transaction logs received.".into())
@@ -687,14 +1669,21 @@ impl Nibble {
         }
     }
 
-    pub async fn load_nibble(&mut self, id: &str) -> Result<Nibble, Box<dyn Error + Send + Sync>> {
+    pub async fn load_nibble(&mut self, id: &str) -> Result<Nibble, NpcError> {
         let response = load_nibble_from_subgraph(
+            &self.subgraph,
             id.to_string(),
-            self.graph_api_key.clone(),
             self.owner_wallet.clone(),
             self.provider.clone(),
+            self.ipfs_client.as_ref(),
         )
-        .await?;
+        .await
+        .map_err(NpcError::subgraph)?;
+        if self.debug {
+            for (field, entities) in &response.entities {
+                println!("Loaded {} {} from subgraph", entities.len(), field);
+            }
+        }
         self.contracts = response.contracts;
         self.saved_conditions = response.conditions;
         self.saved_listeners = response.listeners;
@@ -702,8 +1691,10 @@ impl Nibble {
         self.saved_onchain_connectors = response.onchain_connectors;
         self.saved_evaluations = response.evaluations;
         self.saved_agents = response.agents;
+        self.apply_llm_observer();
         self.saved_fhe_gates = response.fhe_gates;
         self.count = response.count;
+        self.record_load_warnings(response.warnings);
 
         Ok(Nibble {
             fhe_gates: vec![],
@@ -723,17 +1714,82 @@ impl Nibble {
             contracts: self.contracts.clone(),
             owner_wallet: self.owner_wallet.clone(),
             id: self.id.clone(),
-            count: self.count.clone(),
+            count: self.count,
             provider: self.provider.clone(),
-            chain: self.chain.clone(),
+            chain: self.chain,
             ipfs_client: self.ipfs_client.clone(),
             graph_api_key: self.graph_api_key.clone(),
+            subgraph: self.subgraph.clone(),
+            load_warnings: self.load_warnings.clone(),
+            encrypted_fields: self.encrypted_fields.clone(),
             debug: self.debug,
+            nonce_manager: self.nonce_manager.clone(),
+            rate_limiter: self.rate_limiter.clone(),
+            llm_observer: self.llm_observer.clone(),
+            fee_mode: self.fee_mode.clone(),
+            confirmations: self.confirmations,
         })
     }
 
+    pub async fn preflight(&self) -> PreflightReport {
+        let rpc = async {
+            let block = self.provider.get_block_number().await?;
+            Ok::<_, Box<dyn Error + Send + Sync>>(format!("Block {}", block))
+        };
+        let ipfs = async {
+            let payload = PREFLIGHT_IPFS_PAYLOAD.as_bytes().to_vec();
+            let hash = self.ipfs_client.upload(payload.clone()).await?;
+            if self.ipfs_client.fetch(&hash).await? != payload {
+                return Err(
+                    format!("Fetched content for {} did not match the upload", hash).into(),
+                );
+            }
+            Ok::<_, Box<dyn Error + Send + Sync>>(hash)
+        };
+        let subgraph = async {
+            let block = subgraph_block_number(&self.subgraph).await?;
+            Ok::<_, Box<dyn Error + Send + Sync>>(format!("Indexed block {}", block))
+        };
+        let balance = async {
+            let balance = self
+                .provider
+                .get_balance(self.owner_wallet.address(), None)
+                .await?;
+            if balance.is_zero() {
+                return Err(
+                    format!("Wallet {:?} has no funds", self.owner_wallet.address()).into(),
+                );
+            }
+            Ok::<_, Box<dyn Error + Send + Sync>>(format!("{} wei", balance))
+        };
+
+        let (rpc, ipfs, subgraph, balance) = tokio::join!(rpc, ipfs, subgraph, balance);
+        let report = PreflightReport {
+            rpc: PreflightStatus::from_result(rpc),
+            ipfs: PreflightStatus::from_result(ipfs),
+            subgraph: PreflightStatus::from_result(subgraph),
+            balance: PreflightStatus::from_result(balance),
+        };
+        if self.debug {
+            println!("Preflight report: {:?}", report);
+        }
+        report
+    }
+
+    fn record_load_warnings(&mut self, warnings: Vec<LoadWarning>) {
+        for warning in &warnings {
+            eprintln!(
+                "Skipped {} {} while loading Nibble: {}",
+                warning.entity_type,
+                warning.entity_id.as_deref().unwrap_or("without id"),
+                warning.error
+            );
+        }
+        self.load_warnings = warnings;
+    }
+
     pub async fn remove_adapters(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
-        if self.contracts.len() < 1 {
+        if self.contracts.is_empty() {
             return Err("No contracts found. Load or create a Nibble.".into());
         }
 
@@ -750,10 +1806,7 @@ impl Nibble {
             .ok_or("NibbleStorage contract not found")?
             .address;
 
-        let mut abi_file = File::open(Path::new("./abis/NibbleStorage.json"))?;
-        let mut abi_content = String::new();
-        abi_file.read_to_string(&mut abi_content)?;
-        let abi = serde_json::from_str::<Abi>(&abi_content)?;
+        let abi = serde_json::from_str::<Abi>(NIBBLE_STORAGE_ABI)?;
         let contract_instance = Contract::new(storage_contract_address, abi, client.clone());
 
         let remove_adapters = self.build_remove_adapters()?;
@@ -765,18 +1818,25 @@ impl Nibble {
 
                 if let Some(tx_request) = tx.as_eip1559_ref() {
                     let cliente = contract_instance.client().clone();
+                    let (max_fee_per_gas, max_priority_fee_per_gas) = self
+                        .resolve_fees(
+                            U256::from(PERSIST_MAX_FEE_PER_GAS),
+                            U256::from(PERSIST_MAX_PRIORITY_FEE_PER_GAS),
+                        )
+                        .await;
                     let req = Eip1559TransactionRequest {
                         from: Some(client.address()),
                         to: Some(NameOrAddress::Address(storage_contract_address)),
                         gas: Some(U256::from(1252629)),
                         value: tx_request.value,
                         data: tx_request.data.clone(),
-                        max_fee_per_gas: Some(U256::from_dec_str("44786996170").unwrap()),
-                        max_priority_fee_per_gas: Some(U256::from_dec_str("25000000000").unwrap()),
+                        max_fee_per_gas: Some(max_fee_per_gas),
+                        max_priority_fee_per_gas: Some(max_priority_fee_per_gas),
                         chain_id: Some(Chain::PolygonAmoy.into()),
                         ..Default::default()
                     };
 
+                    let req = self.build_transaction(req).await;
                     let pending_tx = cliente.send_transaction(req, None).await.map_err(|e| {
                         eprintln!("Error sending the transaction: {:?}", e);
                         Box::<dyn Error + Send + Sync>::from(format!(
@@ -785,7 +1845,7 @@ impl Nibble {
                         ))
                     })?;
 
-                    match pending_tx.await {
+                    match pending_tx.confirmations(self.confirmations).await {
                         Ok(Some(receipt)) => receipt,
                         Ok(None) => {
                             return Err("Transaction not recieved".into());
@@ -817,10 +1877,11 @@ impl Nibble {
         self.agents.clear();
 
         let response = load_nibble_from_subgraph(
+            &self.subgraph,
             self.id.as_ref().unwrap().clone(),
-            self.graph_api_key.clone(),
             self.owner_wallet.clone(),
             self.provider.clone(),
+            self.ipfs_client.as_ref(),
         )
         .await?;
         self.contracts = response.contracts;
@@ -830,42 +1891,207 @@ impl Nibble {
         self.saved_onchain_connectors = response.onchain_connectors;
         self.saved_evaluations = response.evaluations;
         self.saved_agents = response.agents;
+        self.apply_llm_observer();
         self.saved_fhe_gates = response.fhe_gates;
         self.count = response.count;
+        self.record_load_warnings(response.warnings);
 
         Ok(())
     }
 
-    pub async fn persist_adapters(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
-        if self.contracts.len() < 1 {
+    pub async fn persist_adapters(
+        &mut self,
+        idempotency_key: Option<&str>,
+    ) -> Result<(), NpcError> {
+        if let Some(key) = idempotency_key {
+            if self.idempotency_key_landed(key).await? {
+                println!("Adapters already persisted with idempotency key {}", key);
+                return self.refresh_saved_adapters().await;
+            }
+        }
+
+        self.persist_adapters_signed_by(self.owner_wallet.clone(), idempotency_key)
+            .await
+    }
+
+    pub(crate) async fn idempotency_key_landed(
+        &self,
+        idempotency_key: &str,
+    ) -> Result<bool, NpcError> {
+        let storage_contract_address = self
+            .contracts
+            .iter()
+            .find(|c| c.name == "NibbleStorage")
+            .ok_or_else(|| NpcError::validation("NibbleStorage contract not found"))?
+            .address;
+
+        let abi = serde_json::from_str::<Abi>(NIBBLE_STORAGE_ABI)?;
+        let contract_instance = Contract::new(
+            storage_contract_address,
+            abi,
+            Arc::new(self.provider.clone()),
+        );
+
+        contract_instance
+            .method::<_, bool>(
+                "getIdempotencyKeyRecorded",
+                idempotency_key_hash(idempotency_key),
+            )
+            .map_err(NpcError::validation)?
+            .call()
+            .await
+            .map_err(NpcError::rpc)
+    }
+
+    pub async fn wait_for_subgraph(
+        &self,
+        field: &str,
+        entity_id: &str,
+        timeout: Duration,
+    ) -> Result<(), NpcError> {
+        let nibble_id = self
+            .id
+            .as_deref()
+            .ok_or_else(|| NpcError::validation("Nibble id not set"))?;
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if subgraph_entity_indexed(&self.subgraph, nibble_id, field, entity_id)
+                .await
+                .map_err(NpcError::subgraph)?
+            {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                return Err(NpcError::Timeout(format!(
+                    "{} {} was not indexed within {:?}",
+                    field, entity_id, timeout
+                )));
+            }
+            tokio::time::sleep(Duration::from_millis(SUBGRAPH_POLL_INTERVAL_MS).min(timeout)).await;
+        }
+    }
+
+    pub async fn reencrypt_all(
+        &mut self,
+        new_wallet: LocalWallet,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self.contracts.is_empty() {
             return Err("No contracts found. Load or create a Nibble.".into());
         }
 
-        let client = SignerMiddleware::new(
+        let response = load_nibble_from_subgraph(
+            &self.subgraph,
+            self.id.as_ref().ok_or("Nibble id not set")?.clone(),
+            self.owner_wallet.clone(),
             self.provider.clone(),
-            self.owner_wallet.clone().with_chain_id(self.chain),
-        );
+            self.ipfs_client.as_ref(),
+        )
+        .await?;
+        if !response.warnings.is_empty() {
+            self.record_load_warnings(response.warnings);
+            return Err(
+                "Some saved adapters could not be loaded with the current wallet; see load_warnings"
+                    .into(),
+            );
+        }
+
+        self.conditions = response
+            .conditions
+            .into_iter()
+            .filter(|condition| condition.encrypted)
+            .collect();
+        self.listeners = response
+            .listeners
+            .into_iter()
+            .filter(|listener| listener.encrypted)
+            .collect();
+        self.onchain_connectors = response
+            .onchain_connectors
+            .into_iter()
+            .filter(|connector| connector.encrypted)
+            .collect();
+        self.offchain_connectors = response
+            .offchain_connectors
+            .into_iter()
+            .filter(|connector| connector.encrypted)
+            .collect();
+        self.agents = response
+            .agents
+            .into_iter()
+            .filter(|agent| agent.encrypted)
+            .collect();
+        self.evaluations = response
+            .evaluations
+            .into_iter()
+            .filter(|evaluation| evaluation.encrypted)
+            .collect();
+
+        let nothing_encrypted = self.conditions.is_empty()
+            && self.listeners.is_empty()
+            && self.onchain_connectors.is_empty()
+            && self.offchain_connectors.is_empty()
+            && self.agents.is_empty()
+            && self.evaluations.is_empty();
+
+        let old_wallet = std::mem::replace(&mut self.owner_wallet, new_wallet);
+        if nothing_encrypted {
+            return Ok(());
+        }
+
+        if let Err(e) = self
+            .persist_adapters_signed_by(old_wallet.clone(), None)
+            .await
+        {
+            self.owner_wallet = old_wallet;
+            return Err(e.into());
+        }
+
+        Ok(())
+    }
+
+    async fn persist_adapters_signed_by(
+        &mut self,
+        signer: LocalWallet,
+        idempotency_key: Option<&str>,
+    ) -> Result<(), NpcError> {
+        if self.contracts.is_empty() {
+            return Err(NpcError::validation(
+                "No contracts found. Load or create a Nibble.",
+            ));
+        }
+
+        let client = SignerMiddleware::new(self.provider.clone(), signer.with_chain_id(self.chain));
         let client = Arc::new(client);
 
         let storage_contract_address = self
             .contracts
             .iter()
             .find(|c| c.name == "NibbleStorage")
-            .ok_or("NibbleStorage contract not found")?
+            .ok_or_else(|| NpcError::validation("NibbleStorage contract not found"))?
             .address;
 
-        let mut abi_file = File::open(Path::new("./abis/NibbleStorage.json"))?;
-        let mut abi_content = String::new();
-        abi_file.read_to_string(&mut abi_content)?;
-        let abi = serde_json::from_str::<Abi>(&abi_content)?;
+        let abi = serde_json::from_str::<Abi>(NIBBLE_STORAGE_ABI)?;
         let contract_instance = Contract::new(storage_contract_address, abi, client.clone());
 
         let modify_adapters = self
             .build_modify_adapters(self.ipfs_client.as_ref())
             .await?;
 
-        let method =
-            contract_instance.method::<_, H256>("addOrModifyAdaptersBatch", modify_adapters);
+        let method = match idempotency_key {
+            Some(key) => contract_instance.method::<_, H256>(
+                "addOrModifyAdaptersBatchWithKey",
+                [
+                    modify_adapters.into_tokens(),
+                    vec![Token::FixedBytes(idempotency_key_hash(key).to_vec())],
+                ]
+                .concat()
+                .as_slice(),
+            ),
+            None => {
+                contract_instance.method::<_, H256>("addOrModifyAdaptersBatch", modify_adapters)
+            }
+        };
 
         match method {
             Ok(call) => {
@@ -873,37 +2099,56 @@ impl Nibble {
 
                 if let Some(tx_request) = tx.as_eip1559_ref() {
                     let cliente = contract_instance.client().clone();
+                    let nonce = self
+                        .nonce_manager
+                        .next_nonce(&self.provider, client.address())
+                        .await
+                        .map_err(NpcError::rpc)?;
+                    let (max_fee_per_gas, max_priority_fee_per_gas) = self
+                        .resolve_fees(
+                            U256::from(PERSIST_MAX_FEE_PER_GAS),
+                            U256::from(PERSIST_MAX_PRIORITY_FEE_PER_GAS),
+                        )
+                        .await;
                     let req = Eip1559TransactionRequest {
                         from: Some(client.address()),
                         to: Some(NameOrAddress::Address(storage_contract_address)),
                         gas: Some(U256::from(1252629)),
                         value: tx_request.value,
                         data: tx_request.data.clone(),
-                        max_fee_per_gas: Some(U256::from_dec_str("44786996170").unwrap()),
-                        max_priority_fee_per_gas: Some(U256::from_dec_str("25000000000").unwrap()),
+                        max_fee_per_gas: Some(max_fee_per_gas),
+                        max_priority_fee_per_gas: Some(max_priority_fee_per_gas),
+                        nonce: Some(nonce),
                         ..Default::default()
                     };
 
+                    let req = self.build_transaction(req).await;
                     let pending_tx = cliente.send_transaction(req, None).await.map_err(|e| {
                         eprintln!("Error sending the transaction: {:?}", e);
-                        Box::<dyn Error + Send + Sync>::from(format!(
-                            "Error sending the transaction: {}",
-                            e
-                        ))
+                        self.nonce_manager.reset(client.address());
+                        NpcError::Rpc(format!("Error sending the transaction: {}", e))
                     })?;
 
-                    match pending_tx.await {
-                        Ok(Some(receipt)) => receipt,
+                    match pending_tx.confirmations(self.confirmations).await {
+                        Ok(Some(receipt)) if receipt.status == Some(1.into()) => receipt,
+                        Ok(Some(receipt)) => {
+                            eprintln!("Error with the transaction: {:?}", receipt.status);
+                            return Err(NpcError::rpc(
+                                "addOrModifyAdaptersBatch transaction reverted",
+                            ));
+                        }
                         Ok(None) => {
-                            return Err("Transaction not recieved".into());
+                            self.nonce_manager.reset(client.address());
+                            return Err(NpcError::rpc("Transaction not recieved"));
                         }
                         Err(e) => {
                             eprintln!("Error with the transaction: {:?}", e);
-                            return Err(e.into());
+                            self.nonce_manager.reset(client.address());
+                            return Err(NpcError::rpc(e));
                         }
                     };
                 } else {
-                    return Err("EIP-1559 reference invalid.".into());
+                    return Err(NpcError::validation("EIP-1559 reference invalid."));
                 }
             }
             Err(e) => {
@@ -911,10 +2156,14 @@ impl Nibble {
                     "Error while preparing the method of addOrModifyAdaptersBatch: {}",
                     e
                 );
-                return Err(e.into());
+                return Err(NpcError::validation(e));
             }
         }
 
+        self.refresh_saved_adapters().await
+    }
+
+    async fn refresh_saved_adapters(&mut self) -> Result<(), NpcError> {
         self.conditions.clear();
         self.listeners.clear();
         self.fhe_gates.clear();
@@ -924,13 +2173,14 @@ impl Nibble {
         self.agents.clear();
 
         let response = load_nibble_from_subgraph(
+            &self.subgraph,
             self.id.as_ref().unwrap().clone(),
-            self.graph_api_key.clone(),
             self.owner_wallet.clone(),
             self.provider.clone(),
+            self.ipfs_client.as_ref(),
         )
         .await
-        .map_err(|e| Box::<dyn Error + Send + Sync>::from(e))?;
+        .map_err(NpcError::subgraph)?;
         self.contracts = response.contracts;
         self.saved_conditions = response.conditions;
         self.saved_listeners = response.listeners;
@@ -938,33 +2188,82 @@ impl Nibble {
         self.saved_onchain_connectors = response.onchain_connectors;
         self.saved_evaluations = response.evaluations;
         self.saved_agents = response.agents;
+        self.apply_llm_observer();
         self.saved_fhe_gates = response.fhe_gates;
         self.count = response.count;
+        self.record_load_warnings(response.warnings);
 
         Ok(())
     }
 
+    #[deprecated(
+        note = "clones the whole Nibble into a new Arc; use `create_shared_workflow` on an `Arc<Nibble>`"
+    )]
     pub fn create_workflow(&self, name: &str, encrypted: bool) -> Workflow {
+        Arc::new(self.clone()).create_shared_workflow(name, encrypted)
+    }
+
+    pub fn create_shared_workflow(self: &Arc<Self>, name: &str, encrypted: bool) -> Workflow {
         Workflow {
             id: generate_unique_id(&self.owner_wallet.address()),
             name: name.to_string(),
             nodes: HashMap::new(),
             links: HashMap::new(),
-            nibble_context: Arc::new(self.clone()),
+            nibble_context: Arc::clone(self),
             encrypted,
             execution_history: Vec::new(),
+            context_middleware: Vec::new(),
+            repetition_retry: None,
+            checkpoint_config: None,
+            checkpoints: Vec::new(),
+            runtime_budget: None,
+            error_policy: ErrorPolicy::default(),
+            join_inputs: HashMap::new(),
+            simulation: None,
+            llm_prices: agents::default_llm_prices(),
+            llm_cache: None,
+            force_llm_cache: false,
+            executing: Arc::default(),
+            signer: None,
+            history_sink: None,
+            condition_batcher: None,
         }
     }
 
+    pub async fn simulate_workflow(
+        &self,
+        workflow: &Workflow,
+        stubs: HashMap<String, Value>,
+        repetitions: Option<u32>,
+    ) -> Result<Vec<ExecutionHistory>, NpcError> {
+        let mut simulated = workflow.clone();
+        simulated.nibble_context = Arc::new(self.clone());
+        simulated.execution_history = Vec::new();
+        simulated.executing = Arc::default();
+        simulated.enable_simulation(Simulation { stubs });
+
+        simulated.execute(repetitions, false).await
+    }
+
+    #[deprecated(
+        note = "clones the whole Nibble into a new Arc; use `load_shared_workflow` on an `Arc<Nibble>`"
+    )]
     pub async fn load_workflow(&self, id: &str) -> Result<Workflow, Box<dyn Error + Send + Sync>> {
-        if self.contracts.len() < 1 {
+        Arc::new(self.clone()).load_shared_workflow(id).await
+    }
+
+    pub async fn load_shared_workflow(
+        self: &Arc<Self>,
+        id: &str,
+    ) -> Result<Workflow, Box<dyn Error + Send + Sync>> {
+        if self.contracts.is_empty() {
             return Err("No contracts found. Load or create a Nibble firsty.".into());
         }
 
         let workflow = load_workflow_from_subgraph(
+            &self.subgraph,
             id.to_string(),
             self.id.as_ref().unwrap().clone(),
-            self.graph_api_key.clone(),
         )
         .await?;
 
@@ -973,9 +2272,24 @@ impl Nibble {
             name: workflow.name,
             nodes: workflow.nodes,
             links: workflow.links,
-            nibble_context: Arc::new(self.clone()),
+            nibble_context: Arc::clone(self),
             encrypted: workflow.encrypted,
             execution_history: workflow.execution_history,
+            context_middleware: Vec::new(),
+            repetition_retry: None,
+            checkpoint_config: None,
+            checkpoints: Vec::new(),
+            runtime_budget: None,
+            error_policy: ErrorPolicy::default(),
+            join_inputs: HashMap::new(),
+            simulation: None,
+            llm_prices: agents::default_llm_prices(),
+            llm_cache: None,
+            force_llm_cache: false,
+            executing: Arc::default(),
+            signer: None,
+            history_sink: None,
+            condition_batcher: None,
         })
     }
 
@@ -994,12 +2308,8 @@ impl Nibble {
             connectors: self
                 .onchain_connectors
                 .iter()
-                .map(|c| Connector::OnChain(c))
-                .chain(
-                    self.offchain_connectors
-                        .iter()
-                        .map(|c| Connector::OffChain(c)),
-                )
+                .map(Connector::OnChain)
+                .chain(self.offchain_connectors.iter().map(Connector::OffChain))
                 .map(|connector| {
                     let id = match connector {
                         Connector::OnChain(on_chain) => &on_chain.id,
@@ -1017,19 +2327,87 @@ impl Nibble {
         })
     }
 
-    async fn build_modify_adapters(
+    fn encode_metadata<S: Serialize>(
+        &self,
+        metadata: &S,
+        encrypted: bool,
+    ) -> Result<Vec<u8>, NpcError> {
+        if !encrypted {
+            return Ok(serde_json::to_vec(metadata)?);
+        }
+
+        if self.encrypted_fields.is_empty() {
+            return encrypt_with_public_key(
+                serde_json::to_vec(metadata)?,
+                self.owner_wallet.clone(),
+            )
+            .map_err(NpcError::encryption);
+        }
+
+        let mut metadata = serde_json::to_value(metadata)?;
+        encrypt_fields(&mut metadata, &self.encrypted_fields, &self.owner_wallet)
+            .map_err(NpcError::encryption)?;
+        if let Value::Object(map) = &mut metadata {
+            map.insert(
+                "encrypted_fields".to_string(),
+                Value::Array(
+                    self.encrypted_fields
+                        .iter()
+                        .map(|field| Value::String(field.clone()))
+                        .collect(),
+                ),
+            );
+        }
+        Ok(serde_json::to_vec(&metadata)?)
+    }
+
+    async fn build_modify_adapter(
+        &self,
+        adapter_id: &str,
+    ) -> Result<ModifyAdapters, Box<dyn Error + Send + Sync>> {
+        let mut scoped = self.clone();
+        scoped
+            .conditions
+            .retain(|adapter| adapter.id() == adapter_id);
+        scoped
+            .listeners
+            .retain(|adapter| adapter.id() == adapter_id);
+        scoped
+            .evaluations
+            .retain(|adapter| adapter.id() == adapter_id);
+        scoped
+            .onchain_connectors
+            .retain(|adapter| adapter.id() == adapter_id);
+        scoped
+            .offchain_connectors
+            .retain(|adapter| adapter.id() == adapter_id);
+        scoped.agents.retain(|adapter| adapter.id() == adapter_id);
+
+        let modify_adapters = scoped
+            .build_modify_adapters(self.ipfs_client.as_ref())
+            .await?;
+        if modify_adapters.conditions.is_empty()
+            && modify_adapters.listeners.is_empty()
+            && modify_adapters.evaluations.is_empty()
+            && modify_adapters.connectors.is_empty()
+            && modify_adapters.agents.is_empty()
+        {
+            return Err(format!("Adapter {} has no unpersisted changes", adapter_id).into());
+        }
+
+        Ok(modify_adapters)
+    }
+
+    pub async fn build_modify_adapters(
         &self,
         ipfs_client: &dyn IPFSClient,
     ) -> Result<ModifyAdapters, Box<dyn Error + Send + Sync>> {
         Ok(ModifyAdapters {
             conditions: stream::iter(&self.conditions)
                 .then(|condition| async {
-                    let mut metadata = serde_json::to_vec(&condition.to_json())?;
-
-                    if condition.encrypted {
-                        metadata = encrypt_with_public_key(metadata, self.owner_wallet.clone())?;
-                    }
-                    let ipfs_hash = ipfs_client.upload(metadata).await?;
+                    let metadata =
+                        self.encode_metadata(&condition.to_json(), condition.encrypted)?;
+                    let ipfs_hash = ipfs_client.upload(metadata).await.map_err(NpcError::ipfs)?;
                     Ok::<ContractCondition, Box<dyn Error + Send + Sync>>(ContractCondition {
                         id: condition.id().to_string(),
                         metadata: ipfs_hash,
@@ -1040,13 +2418,9 @@ impl Nibble {
                 .await?,
             listeners: stream::iter(&self.listeners)
                 .then(|listener| async {
-                    let mut metadata = serde_json::to_vec(&listener.to_json())?;
-
-                    if listener.encrypted {
-                        metadata = encrypt_with_public_key(metadata, self.owner_wallet.clone())?;
-                    }
+                    let metadata = self.encode_metadata(&listener.to_json(), listener.encrypted)?;
 
-                    let ipfs_hash = ipfs_client.upload(metadata).await?;
+                    let ipfs_hash = ipfs_client.upload(metadata).await.map_err(NpcError::ipfs)?;
                     Ok::<ContractListener, Box<dyn Error + Send + Sync>>(ContractListener {
                         id: listener.id().to_string(),
                         metadata: ipfs_hash,
@@ -1058,36 +2432,28 @@ impl Nibble {
             connectors: stream::iter(
                 self.onchain_connectors
                     .iter()
-                    .map(|c| Connector::OnChain(c))
-                    .chain(
-                        self.offchain_connectors
-                            .iter()
-                            .map(|c| Connector::OffChain(c)),
-                    ),
+                    .map(Connector::OnChain)
+                    .chain(self.offchain_connectors.iter().map(Connector::OffChain)),
             )
             .then(|connector| async move {
-                let (mut metadata, is_onchain) = match connector {
+                let encrypted = match connector {
+                    Connector::OnChain(on_chain) => &on_chain.encrypted,
+                    Connector::OffChain(off_chain) => &off_chain.encrypted,
+                };
+                let (metadata, is_onchain) = match connector {
                     Connector::OnChain(on_chain) => (
-                        serde_json::to_vec(&on_chain.to_json())
+                        self.encode_metadata(&on_chain.to_json(), *encrypted)
                             .map_err(|e| format!("Failed to serialize OnChainConnector: {}", e))?,
                         true,
                     ),
                     Connector::OffChain(off_chain) => (
-                        serde_json::to_vec(&off_chain.to_json())
+                        self.encode_metadata(&off_chain.to_json(), *encrypted)
                             .map_err(|e| format!("Failed to serialize OffChainConnector: {}", e))?,
                         false,
                     ),
                 };
-                let encrypted = match connector {
-                    Connector::OnChain(on_chain) => &on_chain.encrypted,
-                    Connector::OffChain(off_chain) => &off_chain.encrypted,
-                };
 
-                if encrypted.clone() {
-                    metadata = encrypt_with_public_key(metadata, self.owner_wallet.clone())?;
-                }
-
-                let ipfs_hash = ipfs_client.upload(metadata).await?;
+                let ipfs_hash = ipfs_client.upload(metadata).await.map_err(NpcError::ipfs)?;
 
                 let id = match connector {
                     Connector::OnChain(on_chain) => &on_chain.id,
@@ -1097,7 +2463,7 @@ impl Nibble {
                 Ok::<ContractConnector, Box<dyn Error + Send + Sync>>(ContractConnector {
                     id: id.clone(),
                     metadata: ipfs_hash,
-                    encrypted: encrypted.clone(),
+                    encrypted: *encrypted,
                     onChain: is_onchain,
                 })
             })
@@ -1105,13 +2471,9 @@ impl Nibble {
             .await?,
             agents: stream::iter(&self.agents)
                 .then(|agent| async {
-                    let mut metadata = serde_json::to_vec(&agent.to_json())?;
-
-                    if agent.encrypted {
-                        metadata = encrypt_with_public_key(metadata, self.owner_wallet.clone())?;
-                    }
+                    let metadata = self.encode_metadata(&agent.to_json(), agent.encrypted)?;
 
-                    let ipfs_hash = ipfs_client.upload(metadata).await?;
+                    let ipfs_hash = ipfs_client.upload(metadata).await.map_err(NpcError::ipfs)?;
                     Ok::<ContractAgent, Box<dyn Error + Send + Sync>>(ContractAgent {
                         id: agent.id().to_string(),
                         metadata: ipfs_hash,
@@ -1124,12 +2486,10 @@ impl Nibble {
                 .await?,
             evaluations: stream::iter(&self.evaluations)
                 .then(|evaluation| async {
-                    let mut metadata = serde_json::to_vec(&evaluation.to_json())?;
-                    if evaluation.encrypted {
-                        metadata = encrypt_with_public_key(metadata, self.owner_wallet.clone())?;
-                    }
+                    let metadata =
+                        self.encode_metadata(&evaluation.to_json(), evaluation.encrypted)?;
 
-                    let ipfs_hash = ipfs_client.upload(metadata).await?;
+                    let ipfs_hash = ipfs_client.upload(metadata).await.map_err(NpcError::ipfs)?;
                     Ok::<ContractEvaluation, Box<dyn Error + Send + Sync>>(ContractEvaluation {
                         id: evaluation.id().to_string(),
                         metadata: ipfs_hash,
@@ -1142,11 +2502,31 @@ impl Nibble {
     }
 }
 
+fn find_adapter<'a, T: Adaptable>(
+    local: &'a [T],
+    saved: &'a [T],
+    matches: impl Fn(&T) -> bool,
+) -> Option<&'a T> {
+    local
+        .iter()
+        .chain(saved.iter())
+        .find(|adapter| matches(adapter))
+}
+
 impl<'a, T> AdapterHandle<'a, T>
 where
-    T: Adaptable + Serialize + std::fmt::Debug,
+    T: Adaptable,
 {
     pub async fn persist_adapter(self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if let Adapter::FHEGate = self.adapter_type {
+            return Err("FHEGate adapters are not stored in NibbleStorage".into());
+        }
+        let nibble_id = self
+            .nibble
+            .id
+            .clone()
+            .ok_or("Nibble id is missing, persist the Nibble before its adapters")?;
+
         let client = SignerMiddleware::new(
             self.nibble.provider.clone(),
             self.nibble
@@ -1156,84 +2536,21 @@ where
         );
         let client = Arc::new(client);
 
-        let contract_address = match self.adapter_type {
-            Adapter::Condition => {
-                self.nibble
-                    .contracts
-                    .iter()
-                    .find(|c| c.name == "NibbleConditions")
-                    .ok_or("Condition contract not found")?
-                    .address
-            }
-            Adapter::Listener => {
-                self.nibble
-                    .contracts
-                    .iter()
-                    .find(|c| c.name == "NibbleListeners")
-                    .ok_or("Listener contract not found")?
-                    .address
-            }
-            Adapter::FHEGate => {
-                self.nibble
-                    .contracts
-                    .iter()
-                    .find(|c| c.name == "NibbleFHEGates")
-                    .ok_or("FHEGate contract not found")?
-                    .address
-            }
-            Adapter::Evaluation => {
-                self.nibble
-                    .contracts
-                    .iter()
-                    .find(|c| c.name == "NibbleEvaluations")
-                    .ok_or("Evaluation contract not found")?
-                    .address
-            }
-            Adapter::OnChainConnector => {
-                self.nibble
-                    .contracts
-                    .iter()
-                    .find(|c| c.name == "NibbleConnectors")
-                    .ok_or("OnChainConnector contract not found")?
-                    .address
-            }
-            Adapter::OffChainConnector => {
-                self.nibble
-                    .contracts
-                    .iter()
-                    .find(|c| c.name == "NibbleConnectors")
-                    .ok_or("OffChainConnector contract not found")?
-                    .address
-            }
-            Adapter::Agent => {
-                self.nibble
-                    .contracts
-                    .iter()
-                    .find(|c| c.name == "NibbleAgents")
-                    .ok_or("Agent contract not found")?
-                    .address
-            }
-        };
-
-        let serialized_adapter = serde_json::to_vec(&self.adapter)?;
+        let contract_address = self
+            .nibble
+            .contracts
+            .iter()
+            .find(|c| c.name == "NibbleStorage")
+            .ok_or("NibbleStorage contract not found")?
+            .address;
 
-        let mut abi_file = File::open(Path::new("./abis/NibbleStorage.json"))?;
-        let mut abi_content = String::new();
-        abi_file.read_to_string(&mut abi_content)?;
-        let abi = serde_json::from_str::<Abi>(&abi_content)?;
+        let abi = serde_json::from_str::<Abi>(NIBBLE_STORAGE_ABI)?;
         let contract_instance = Contract::new(contract_address, abi, client.clone());
 
-        let method_name = match self.adapter_type {
-            Adapter::Condition => "addOrModifyConditionsBatch",
-            Adapter::Listener => "addOrModifyListenersBatch",
-            Adapter::FHEGate => "addOrModifyFHEGatesBatch",
-            Adapter::Evaluation => "addOrModifyEvaluationsBatch",
-            Adapter::OnChainConnector => "addOrModifyConnectorsBatch",
-            Adapter::OffChainConnector => "addOrModifyConnectorsBatch",
-            Adapter::Agent => "addOrModifyAgentsBatch",
-        };
+        let method_name = "addOrModifyAdaptersBatch";
+        let modify_adapters = self.nibble.build_modify_adapter(self.adapter.id()).await?;
 
-        let method = contract_instance.method::<_, H256>(&method_name, vec![serialized_adapter]);
+        let method = contract_instance.method::<_, H256>(method_name, modify_adapters);
 
         match method {
             Ok(call) => {
@@ -1241,32 +2558,49 @@ where
 
                 if let Some(tx_request) = tx.as_eip1559_ref() {
                     let cliente = contract_instance.client().clone();
+                    let nonce = self
+                        .nibble
+                        .nonce_manager
+                        .next_nonce(&self.nibble.provider, client.address())
+                        .await?;
+                    let (max_fee_per_gas, max_priority_fee_per_gas) = self
+                        .nibble
+                        .resolve_fees(
+                            U256::from(PERSIST_MAX_FEE_PER_GAS),
+                            U256::from(PERSIST_MAX_PRIORITY_FEE_PER_GAS),
+                        )
+                        .await;
                     let req = Eip1559TransactionRequest {
                         from: Some(client.address()),
                         to: Some(NameOrAddress::Address(contract_address)),
                         gas: Some(U256::from(1252629)),
                         value: tx_request.value,
                         data: tx_request.data.clone(),
-                        max_fee_per_gas: Some(U256::from_dec_str("44786996170").unwrap()),
-                        max_priority_fee_per_gas: Some(U256::from_dec_str("25000000000").unwrap()),
+                        max_fee_per_gas: Some(max_fee_per_gas),
+                        max_priority_fee_per_gas: Some(max_priority_fee_per_gas),
+                        nonce: Some(nonce),
                         ..Default::default()
                     };
 
+                    let req = self.nibble.build_transaction(req).await;
                     let pending_tx = cliente.send_transaction(req, None).await.map_err(|e| {
                         eprintln!("Error sending the transaction: {:?}", e);
+                        self.nibble.nonce_manager.reset(client.address());
                         Box::<dyn Error + Send + Sync>::from(format!(
                             "Error sending the transaction: {}",
                             e
                         ))
                     })?;
 
-                    match pending_tx.await {
+                    match pending_tx.confirmations(self.nibble.confirmations).await {
                         Ok(Some(receipt)) => receipt,
                         Ok(None) => {
+                            self.nibble.nonce_manager.reset(client.address());
                             return Err("Transaction not recieved".into());
                         }
                         Err(e) => {
                             eprintln!("Error with the transaction: {:?}", e);
+                            self.nibble.nonce_manager.reset(client.address());
                             return Err(e.into());
                         }
                     };
@@ -1354,10 +2688,11 @@ where
         };
 
         let response = load_nibble_from_subgraph(
-            self.nibble.id.as_ref().unwrap().clone(),
-            self.nibble.graph_api_key.clone(),
+            &self.nibble.subgraph,
+            nibble_id,
             self.nibble.owner_wallet.clone(),
             self.nibble.provider.clone(),
+            self.nibble.ipfs_client.as_ref(),
         )
         .await?;
         self.nibble.contracts = response.contracts;
@@ -1374,6 +2709,9 @@ where
     }
 
     pub async fn remove_adapter(self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let remove_adapters = RemoveAdapters::for_adapter(&self.adapter_type, self.adapter.id())
+            .ok_or("FHEGate adapters are not stored in NibbleStorage")?;
+
         let client = SignerMiddleware::new(
             self.nibble.provider.clone(),
             self.nibble
@@ -1383,84 +2721,19 @@ where
         );
         let client = Arc::new(client);
 
-        let contract_address = match self.adapter_type {
-            Adapter::Condition => {
-                self.nibble
-                    .contracts
-                    .iter()
-                    .find(|c| c.name == "NibbleConditions")
-                    .ok_or("Condition contract not found")?
-                    .address
-            }
-            Adapter::Listener => {
-                self.nibble
-                    .contracts
-                    .iter()
-                    .find(|c| c.name == "NibbleListeners")
-                    .ok_or("Listener contract not found")?
-                    .address
-            }
-            Adapter::FHEGate => {
-                self.nibble
-                    .contracts
-                    .iter()
-                    .find(|c| c.name == "NibbleFHEGates")
-                    .ok_or("FHEGate contract not found")?
-                    .address
-            }
-            Adapter::Evaluation => {
-                self.nibble
-                    .contracts
-                    .iter()
-                    .find(|c| c.name == "NibbleEvaluations")
-                    .ok_or("Evaluation contract not found")?
-                    .address
-            }
-            Adapter::OnChainConnector => {
-                self.nibble
-                    .contracts
-                    .iter()
-                    .find(|c| c.name == "NibbleConnectors")
-                    .ok_or("OnChainConnector contract not found")?
-                    .address
-            }
-            Adapter::OffChainConnector => {
-                self.nibble
-                    .contracts
-                    .iter()
-                    .find(|c| c.name == "NibbleConnectors")
-                    .ok_or("OffChainConnector contract not found")?
-                    .address
-            }
-            Adapter::Agent => {
-                self.nibble
-                    .contracts
-                    .iter()
-                    .find(|c| c.name == "NibbleAgents")
-                    .ok_or("Agent contract not found")?
-                    .address
-            }
-        };
-
-        let mut abi_file = File::open(Path::new("./abis/NibbleStorage.json"))?;
-        let mut abi_content = String::new();
-        abi_file.read_to_string(&mut abi_content)?;
-        let abi = serde_json::from_str::<Abi>(&abi_content)?;
+        let contract_address = self
+            .nibble
+            .contracts
+            .iter()
+            .find(|c| c.name == "NibbleStorage")
+            .ok_or("NibbleStorage contract not found")?
+            .address;
 
+        let abi = serde_json::from_str::<Abi>(NIBBLE_STORAGE_ABI)?;
         let contract_instance = Contract::new(contract_address, abi, client.clone());
 
-        let method_name = match self.adapter_type {
-            Adapter::Condition => "removeListenersBatch",
-            Adapter::Listener => "removeListenersBatch",
-            Adapter::FHEGate => "removeFHEGatesBatch",
-            Adapter::Evaluation => "removeEvaluationsBatch",
-            Adapter::OnChainConnector => "removeConnectorsBatch",
-            Adapter::OffChainConnector => "removeConnectorsBatch",
-            Adapter::Agent => "removeAgentsBatch",
-        };
-
-        let method =
-            contract_instance.method::<_, H256>(&method_name, vec![self.adapter.id().to_string()]);
+        let method_name = "removeAdaptersBatch";
+        let method = contract_instance.method::<_, H256>(method_name, remove_adapters);
 
         match method {
             Ok(call) => {
@@ -1468,17 +2741,25 @@ where
 
                 if let Some(tx_request) = tx.as_eip1559_ref() {
                     let cliente = contract_instance.client().clone();
+                    let (max_fee_per_gas, max_priority_fee_per_gas) = self
+                        .nibble
+                        .resolve_fees(
+                            U256::from(PERSIST_MAX_FEE_PER_GAS),
+                            U256::from(PERSIST_MAX_PRIORITY_FEE_PER_GAS),
+                        )
+                        .await;
                     let req = Eip1559TransactionRequest {
                         from: Some(client.address()),
                         to: Some(NameOrAddress::Address(contract_address)),
                         gas: Some(U256::from(1252629)),
                         value: tx_request.value,
                         data: tx_request.data.clone(),
-                        max_fee_per_gas: Some(U256::from_dec_str("44786996170").unwrap()),
-                        max_priority_fee_per_gas: Some(U256::from_dec_str("25000000000").unwrap()),
+                        max_fee_per_gas: Some(max_fee_per_gas),
+                        max_priority_fee_per_gas: Some(max_priority_fee_per_gas),
                         ..Default::default()
                     };
 
+                    let req = self.nibble.build_transaction(req).await;
                     let pending_tx = cliente.send_transaction(req, None).await.map_err(|e| {
                         eprintln!("Error sending the transaction: {:?}", e);
                         Box::<dyn Error + Send + Sync>::from(format!(
@@ -1487,7 +2768,7 @@ where
                         ))
                     })?;
 
-                    match pending_tx.await {
+                    match pending_tx.confirmations(self.nibble.confirmations).await {
                         Ok(Some(receipt)) => receipt,
                         Ok(None) => {
                             return Err("Transaction not recieved".into());
@@ -1581,10 +2862,11 @@ where
         };
 
         let response = load_nibble_from_subgraph(
+            &self.nibble.subgraph,
             self.nibble.id.as_ref().unwrap().clone(),
-            self.nibble.graph_api_key.clone(),
             self.nibble.owner_wallet.clone(),
             self.nibble.provider.clone(),
+            self.nibble.ipfs_client.as_ref(),
         )
         .await?;
         self.nibble.contracts = response.contracts;
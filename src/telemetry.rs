@@ -0,0 +1,70 @@
+use std::{future::Future, time::Duration};
+
+#[cfg(feature = "otel")]
+#[derive(Debug, Clone)]
+pub(crate) struct TelemetrySpan(tracing::Span);
+
+#[cfg(feature = "otel")]
+impl TelemetrySpan {
+    pub(crate) fn workflow(workflow_id: &str, name: &str) -> Self {
+        Self(tracing::info_span!(
+            "workflow",
+            otel.name = %format!("workflow {}", name),
+            workflow.id = %workflow_id,
+            workflow.name = %name,
+            success = tracing::field::Empty,
+            duration_ms = tracing::field::Empty,
+        ))
+    }
+
+    pub(crate) fn element(kind: &str, element_id: &str, adapter_type: &str) -> Self {
+        Self(tracing::info_span!(
+            "element",
+            otel.name = %format!("{} {}", kind, adapter_type),
+            element.kind = %kind,
+            element.id = %element_id,
+            adapter_type = %adapter_type,
+            success = tracing::field::Empty,
+            duration_ms = tracing::field::Empty,
+        ))
+    }
+
+    pub(crate) fn current() -> Self {
+        Self(tracing::Span::current())
+    }
+
+    pub(crate) async fn scope<F: Future>(&self, future: F) -> F::Output {
+        use tracing::Instrument;
+        future.instrument(self.0.clone()).await
+    }
+
+    pub(crate) fn finish(&self, success: bool, duration: Duration) {
+        self.0.record("success", success);
+        self.0.record("duration_ms", duration.as_millis() as u64);
+    }
+}
+
+#[cfg(not(feature = "otel"))]
+#[derive(Debug, Clone)]
+pub(crate) struct TelemetrySpan;
+
+#[cfg(not(feature = "otel"))]
+impl TelemetrySpan {
+    pub(crate) fn workflow(_workflow_id: &str, _name: &str) -> Self {
+        Self
+    }
+
+    pub(crate) fn element(_kind: &str, _element_id: &str, _adapter_type: &str) -> Self {
+        Self
+    }
+
+    pub(crate) fn current() -> Self {
+        Self
+    }
+
+    pub(crate) async fn scope<F: Future>(&self, future: F) -> F::Output {
+        future.await
+    }
+
+    pub(crate) fn finish(&self, _success: bool, _duration: Duration) {}
+}
@@ -9,6 +9,13 @@ pub enum ContextParse {
     CustomProcessor {
         function: fn(Value) -> Result<Value, String>,
     },
+    JsonPath {
+        expression: String,
+        output_key: Option<String>,
+    },
+    Transform {
+        mapping: Vec<(String, String)>,
+    },
 }
 
 impl ContextParse {
@@ -41,6 +48,50 @@ impl ContextParse {
             }
 
             ContextParse::CustomProcessor { function } => function(input),
+
+            ContextParse::JsonPath {
+                expression,
+                output_key,
+            } => {
+                let mut matches = jsonpath_lib::select(&input, expression)
+                    .map_err(|e| format!("Invalid JSONPath expression: {:?}", e))?;
+
+                let result = match matches.len() {
+                    0 => return Err(format!("JSONPath Not Matched: {}", expression)),
+                    1 => matches.remove(0).clone(),
+                    _ => Value::Array(matches.into_iter().cloned().collect()),
+                };
+
+                match output_key {
+                    Some(key) => {
+                        let mut map = Map::new();
+                        map.insert(key.clone(), result);
+                        Ok(Value::Object(map))
+                    }
+                    None => Ok(result),
+                }
+            }
+
+            ContextParse::Transform { mapping } => {
+                let missing_sources: Vec<String> = mapping
+                    .iter()
+                    .filter(|(from, _)| input.pointer(from).is_none())
+                    .map(|(from, _)| from.clone())
+                    .collect();
+
+                if !missing_sources.is_empty() {
+                    return Err(format!("Source Field Not Found: {:?}", missing_sources));
+                }
+
+                let transformed = mapping
+                    .iter()
+                    .filter_map(|(from, to)| {
+                        input.pointer(from).map(|value| (to.clone(), value.clone()))
+                    })
+                    .collect::<Map<String, Value>>();
+
+                Ok(Value::Object(transformed))
+            }
         }
     }
 }
@@ -5,12 +5,49 @@ use crate::workflow::ExecutionHistory;
 #[derive(Clone, Debug)]
 pub enum HistoryParse {
     ExtractField {
-        index: usize,             
-        field_path: Vec<String>, 
+        index: usize,
+        field_path: Vec<String>,
     },
     CustomProcessor {
         function: fn(Vec<ExecutionHistory>) -> Result<Value, String>,
     },
+    Aggregate {
+        selector: FieldPath,
+        op: AggregateOp,
+    },
+    Invalid {
+        reason: String,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct FieldPath {
+    pub element_type: Option<String>,
+    pub field_path: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AggregateOp {
+    Concat,
+    Sum,
+    Max,
+    Collect,
+}
+
+impl FieldPath {
+    fn select<'a>(&self, entry: &'a ExecutionHistory) -> Option<&'a Value> {
+        if let Some(element_type) = &self.element_type {
+            if entry.element_type != *element_type {
+                return None;
+            }
+        }
+
+        let mut current_value = entry.result.as_ref()?;
+        for key in &self.field_path {
+            current_value = current_value.get(key)?;
+        }
+        Some(current_value)
+    }
 }
 
 impl HistoryParse {
@@ -40,7 +77,7 @@ impl HistoryParse {
                             }
                         }
 
-                        return Ok(current_value.clone());
+                        Ok(current_value.clone())
                     } else {
                         Err(format!(
                             "No result found in ExecutionHistory at index {}",
@@ -57,6 +94,55 @@ impl HistoryParse {
             }
 
             HistoryParse::CustomProcessor { function } => function(history),
+
+            HistoryParse::Invalid { reason } => {
+                Err(format!("Invalid history_tool configuration: {}", reason))
+            }
+
+            HistoryParse::Aggregate { selector, op } => {
+                let values: Vec<&Value> = history
+                    .iter()
+                    .filter_map(|entry| selector.select(entry))
+                    .collect();
+
+                match op {
+                    AggregateOp::Concat => Ok(Value::String(
+                        values
+                            .iter()
+                            .map(|value| match value {
+                                Value::String(text) => text.clone(),
+                                other => other.to_string(),
+                            })
+                            .collect::<Vec<String>>()
+                            .join("\n"),
+                    )),
+                    AggregateOp::Sum | AggregateOp::Max => {
+                        let numbers = values
+                            .iter()
+                            .map(|value| match value {
+                                Value::Number(number) => number.as_f64(),
+                                Value::String(text) => text.parse::<f64>().ok(),
+                                _ => None,
+                            })
+                            .collect::<Option<Vec<f64>>>()
+                            .ok_or("Non-numeric value found while aggregating history")?;
+
+                        let result = if *op == AggregateOp::Sum {
+                            numbers.iter().sum::<f64>()
+                        } else {
+                            numbers
+                                .into_iter()
+                                .reduce(f64::max)
+                                .ok_or("No matching history entries to aggregate")?
+                        };
+
+                        serde_json::Number::from_f64(result)
+                            .map(Value::Number)
+                            .ok_or_else(|| format!("Invalid aggregate result: {}", result))
+                    }
+                    AggregateOp::Collect => Ok(Value::Array(values.into_iter().cloned().collect())),
+                }
+            }
         }
     }
 }
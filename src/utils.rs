@@ -2,45 +2,55 @@ use crate::{
     adapters::{
         links::{
             conditions::{
-                Condition, ConditionCheck, ConditionType, LogicalOperator, TimeComparisonType,
+                ComparisonOperator, Condition, ConditionCheck, ConditionType, LogicalOperator,
+                TimeComparisonType,
             },
-            evaluations::{Evaluation, EvaluationResponseType, EvaluationType},
-            fhe_gates::FHEGate,
-            listeners::{Listener, ListenerType},
+            evaluations::{
+                Evaluation, EvaluationResponseType, EvaluationType,
+                DEFAULT_HUMAN_JUDGE_POLL_INTERVAL,
+            },
+            fhe_gates::{FHEGate, FheOp},
+            listeners::{Listener, ListenerType, TriggerCondition},
         },
         nodes::{
             agents::{Agent, LLMModel, Objective},
             connectors::{
-                off_chain::{ConnectorType, OffChainConnector},
-                on_chain::{GasOptions, OnChainConnector},
+                off_chain::{ConnectorType, OffChainConnector, PaginationConfig},
+                on_chain::{
+                    BatchMode, GasOptions, GasOracle, OnChainConnector, OnChainTransaction,
+                },
             },
         },
     },
-    constants::{GRAPH_ENDPOINT_DEV, GRAPH_ENDPOINT_PROD},
-    encrypt::decrypt_with_private_key,
-    nibble::ContractInfo,
-    tools::{context::ContextParse, history::HistoryParse},
+    constants::DEFAULT_OBJECTIVE_SIMILARITY,
+    encrypt::{decrypt_fields, decrypt_with_private_key},
+    ipfs::IPFSClient,
+    nibble::{ContractInfo, LoadWarning, RetryPolicy, SubgraphConfig},
+    tools::{
+        context::ContextParse,
+        history::{AggregateOp, FieldPath, HistoryParse},
+    },
     workflow::{
         ExecutionHistory, LinkAdapter, LinkTarget, NodeAdapter, WorkflowLink, WorkflowNode,
     },
 };
-use base64::{engine::general_purpose::STANDARD, Engine};
 use chrono::{DateTime, Utc};
 use ethers::{
-    abi,
+    abi::{self, Token},
     providers::{Http, Provider},
     signers::LocalWallet,
-    types::{Address, Bytes, Chain, H160, U256},
-    utils::hex,
+    types::{Address, Bytes, Chain, H160, I256, U256},
+    utils::{hex, keccak256},
 };
 use rand::Rng;
-use reqwest::{Client, Method};
-use serde_json::{from_str, from_value, json, to_vec, Map, Value};
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde_json::{from_slice, from_str, from_value, json, to_vec, Map, Value};
 use sha2::{Digest, Sha256};
 use std::{
-    collections::HashMap, convert::TryFrom, error::Error, iter::Iterator, str::FromStr, sync::Arc,
+    collections::HashMap, convert::TryFrom, error::Error, iter::Iterator, net::SocketAddr,
+    str::FromStr, sync::Arc,
 };
-use tokio::time::Duration;
+use tokio::time::{sleep, Duration};
 
 pub struct GraphWorkflowResponse {
     pub id: String,
@@ -61,6 +71,49 @@ pub struct GraphNibbleResponse {
     pub offchain_connectors: Vec<OffChainConnector>,
     pub contracts: Vec<ContractInfo>,
     pub count: U256,
+    pub entities: HashMap<String, Vec<Value>>,
+    pub warnings: Vec<LoadWarning>,
+}
+
+pub const NIBBLE_ENTITY_FIELDS: [&str; 8] = [
+    "agents",
+    "conditions",
+    "listeners",
+    "fhe_gates",
+    "evaluations",
+    "onchain_connectors",
+    "offchain_connectors",
+    "workflows",
+];
+
+pub fn token_to_value(token: Token) -> Value {
+    match token {
+        Token::Uint(u) => Value::String(u.to_string()),
+        Token::Int(i) => Value::String(I256::from_raw(i).to_string()),
+        Token::Address(a) => Value::String(format!("{:?}", a)),
+        Token::String(s) => Value::String(s),
+        Token::Bool(b) => Value::Bool(b),
+        Token::Bytes(b) | Token::FixedBytes(b) => Value::String(format!("0x{}", hex::encode(b))),
+        Token::Array(tokens) | Token::FixedArray(tokens) | Token::Tuple(tokens) => {
+            Value::Array(tokens.into_iter().map(token_to_value).collect())
+        }
+    }
+}
+
+pub fn decode_id(id: &str) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    let digits = id
+        .strip_prefix("0x")
+        .ok_or_else(|| format!("Invalid id {:?}: expected 0x-prefixed hex", id))?;
+    hex::decode(digits)
+        .map_err(|e| format!("Invalid id {:?}: expected 0x-prefixed hex ({})", id, e).into())
+}
+
+fn parse_id(data: &Value, key: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let id = data
+        .get(key)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| format!("Missing {}", key))?;
+    Ok(format!("0x{}", hex::encode(decode_id(id)?)))
 }
 
 pub fn generate_unique_id(address: &H160) -> String {
@@ -81,26 +134,20 @@ pub fn generate_unique_id(address: &H160) -> String {
 }
 
 pub async fn load_workflow_from_subgraph(
+    config: &SubgraphConfig,
     workflow_id: String,
     nibble_id: String,
-    api_key: Option<String>,
 ) -> Result<GraphWorkflowResponse, Box<dyn Error + Send + Sync>> {
-    let mut url = GRAPH_ENDPOINT_DEV.to_string();
-
-    if api_key.is_some() {
-        url = GRAPH_ENDPOINT_PROD.replace("apikey", &api_key.unwrap());
-    }
-
-    let client = Client::new();
-
     let query = json!({
         "query": r#"
-                    query Workflow($id: ID!, $nibble_id: nibble_id) {
+                    query Workflow($id: ID!, $nibble_id: ID!) {
                         workflow(id: $id, nibble_id: $nibble_id) {
                             id
                             name
                             nodes
                             links
+                            encrypted
+                            execution_history
                         }
                     }
                 "#,
@@ -109,266 +156,436 @@ pub async fn load_workflow_from_subgraph(
             "nibble_id": nibble_id
         }
     });
-    let res = client
-        .post(url)
-        .header("Content-Type", "application/json")
-        .json(&query)
-        .send()
-        .await?;
-
-    if res.status().is_success() {
-        let json: Value = res.json().await?;
-
-        if let Some(object) = json["data"]["nibble"].as_object() {
-            let id = object
-                .get("id")
-                .and_then(|v| v.as_str())
-                .ok_or("Missing or invalid id")?
-                .to_string();
+    let json = config.query(&query).await?;
 
-            return Ok(GraphWorkflowResponse {
-                id,
-                name: object
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .ok_or("Missing name")?
-                    .parse::<String>()?,
-                encrypted: object
-                    .get("encrypted")
-                    .and_then(|v| v.as_bool())
-                    .ok_or("Missing encrypted")?,
-                nodes: build_nodes(object.get("nodes").unwrap())?,
-                links: build_links(object.get("links").unwrap())?,
-                execution_history: build_execution_history(
-                    object.get("execution_history").unwrap(),
-                )?,
-            });
-        } else {
-            return Err("No data returned from Graph query".into());
-        }
+    if let Some(object) = json["data"]["workflow"].as_object() {
+        let id = parse_id(&json["data"]["workflow"], "id")?;
+
+        Ok(GraphWorkflowResponse {
+            id,
+            name: object
+                .get("name")
+                .and_then(|v| v.as_str())
+                .ok_or("Missing name")?
+                .parse::<String>()?,
+            encrypted: object
+                .get("encrypted")
+                .and_then(|v| v.as_bool())
+                .ok_or("Missing encrypted")?,
+            nodes: build_nodes(object.get("nodes").ok_or("Missing nodes")?)?,
+            links: build_links(object.get("links").ok_or("Missing links")?)?,
+            execution_history: build_execution_history(
+                object.get("execution_history").unwrap_or(&Value::Null),
+            )?,
+        })
     } else {
-        let error_text = res.text().await?;
-        Err(error_text.into())
+        Err("No data returned from Graph query".into())
+    }
+}
+
+pub(crate) fn id_token(id: String) -> Token {
+    match id.strip_prefix("0x").map(hex::decode) {
+        Some(Ok(bytes)) => Token::Bytes(bytes),
+        _ => Token::Bytes(id.into_bytes()),
     }
 }
 
+pub fn idempotency_key_hash(idempotency_key: &str) -> [u8; 32] {
+    keccak256(idempotency_key.as_bytes())
+}
+
+pub async fn subgraph_entity_indexed(
+    config: &SubgraphConfig,
+    nibble_id: &str,
+    field: &str,
+    entity_id: &str,
+) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let query = json!({
+        "query": format!(
+            r#"
+                query Indexed($id: ID!, $entity: ID!) {{
+                    nibbleDeployed(id: $id) {{
+                        {}(where: {{ id: $entity }}, first: 1) {{ id }}
+                    }}
+                }}
+            "#,
+            field
+        ),
+        "variables": {
+            "id": nibble_id,
+            "entity": entity_id
+        }
+    });
+    let json = config.query(&query).await?;
+    Ok(json["data"]["nibbleDeployed"][field]
+        .as_array()
+        .is_some_and(|records| !records.is_empty()))
+}
+
+pub async fn subgraph_block_number(
+    config: &SubgraphConfig,
+) -> Result<u64, Box<dyn Error + Send + Sync>> {
+    let query = json!({ "query": "{ _meta { block { number } } }" });
+    let json = config.query(&query).await?;
+    json["data"]["_meta"]["block"]["number"]
+        .as_u64()
+        .ok_or_else(|| format!("Unexpected subgraph response: {}", json).into())
+}
+
 pub async fn load_nibble_from_subgraph(
+    config: &SubgraphConfig,
     id: String,
-    api_key: Option<String>,
     wallet: LocalWallet,
     provider: Provider<Http>,
+    ipfs_client: &dyn IPFSClient,
 ) -> Result<GraphNibbleResponse, Box<dyn Error + Send + Sync>> {
-    let url = match api_key {
-        Some(key) => GRAPH_ENDPOINT_PROD.replace("apikey", &key),
-        None => GRAPH_ENDPOINT_DEV.to_string(),
-    };
-
-    let client = Client::new();
-
-    let query = json!({
-        "query": r#"
-                query Nibble($id: ID!) {
-                    nibbleDeployed(id: $id) {
-                        agents
-                        conditions
-                        listeners
-                        fhe_gates
-                        evaluations
-                        onchain_connectors
-                        offchain_connectors
-                        workflows
-                        contracts {
+    let page_size = config.page_size.max(1);
+    let entity_selection = NIBBLE_ENTITY_FIELDS
+        .iter()
+        .map(|field| format!("{}(first: $first, skip: $skip)", field))
+        .collect::<Vec<_>>()
+        .join("\n                        ");
+    let query_text = format!(
+        r#"
+                query Nibble($id: ID!, $first: Int!, $skip: Int!) {{
+                    nibbleDeployed(id: $id) {{
+                        {}
+                        contracts {{
                             name
                             address
-                        }
+                        }}
                         count
-                    }
-                }
+                    }}
+                }}
             "#,
-        "variables": {
-            "id": id
-
-
+        entity_selection
+    );
+
+    let mut entities: HashMap<String, Vec<Value>> = NIBBLE_ENTITY_FIELDS
+        .iter()
+        .map(|field| (field.to_string(), Vec::new()))
+        .collect();
+    let mut first_page: Option<Map<String, Value>> = None;
+    let mut skip = 0;
+
+    loop {
+        let query = json!({
+            "query": query_text,
+            "variables": {
+                "id": id,
+                "first": page_size,
+                "skip": skip,
+            }
+        });
+
+        let json = config.query(&query).await?;
+        let object = json["data"]["nibbleDeployed"]
+            .as_object()
+            .cloned()
+            .ok_or("No data returned from Graph query")?;
+
+        let mut has_more = false;
+        for field in NIBBLE_ENTITY_FIELDS {
+            let page = object
+                .get(field)
+                .and_then(|v| v.as_array())
+                .cloned()
+                .unwrap_or_default();
+            has_more |= page.len() >= page_size;
+            entities.entry(field.to_string()).or_default().extend(page);
         }
-    });
 
-    let res = client
-        .post(url)
-        .header("Content-Type", "application/json")
-        .json(&query)
-        .send()
-        .await?;
-
-    if res.status().is_success() {
-        let json: Value = res.json().await?;
-
-        if let Some(object) = json["data"]["nibbleDeployed"].as_object() {
-            return Ok(GraphNibbleResponse {
-                // agents: build_agents(object.get("agents").unwrap(), wallet.clone()).await?,
-                agents: vec![],
-                conditions: vec![],
-                listeners: vec![],
-                evaluations: vec![],
-                fhe_gates: vec![],
-                offchain_connectors: vec![],
-                onchain_connectors: vec![],
-                // conditions: build_conditions(object.get("conditions").unwrap(), wallet.clone())
-                //     .await?,
-                // listeners: build_listeners(
-                //     object.get("listeners").unwrap(),
-                //     wallet.clone(),
-                //     provider,
-                // )
-                // .await?,
-                // fhe_gates: build_fhe_gates(object.get("fhe_gates").unwrap(), wallet.clone())
-                //     .await?,
-                // evaluations: build_evaluations(object.get("evaluations").unwrap(), wallet.clone())
-                //     .await?,
-                // onchain_connectors: build_onchain_connectors(
-                //     object.get("onchain_connectors").unwrap(),
-                //     wallet.clone(),
-                // )
-                // .await?,
-                // offchain_connectors: build_offchain_connectors(
-                //     object.get("offchain_connectors").unwrap(),
-                //     wallet.clone(),
-                // )
-                // .await?,
-                contracts: object
-                    .get("contracts")
-                    .cloned()
-                    .ok_or("Missing contracts")?
-                    .as_array()
-                    .ok_or("Contracts should be an array")?
-                    .iter()
-                    .map(|v| from_value(v.clone()))
-                    .collect::<Result<_, _>>()?,
-                count: object
-                    .get("count")
-                    .and_then(|v| v.as_str())
-                    .ok_or("Missing count")?
-                    .parse::<U256>()?,
-            });
-        } else {
-            return Err("No data returned from Graph query".into());
+        if first_page.is_none() {
+            first_page = Some(object);
         }
-    } else {
-        let error_text = res.text().await?;
-        Err(error_text.into())
+        if !has_more {
+            break;
+        }
+        skip += page_size;
     }
+
+    let object = first_page.ok_or("No data returned from Graph query")?;
+    let entity_list = |field: &str| Value::Array(entities.get(field).cloned().unwrap_or_default());
+    let mut warnings = Vec::new();
+
+    Ok(GraphNibbleResponse {
+        agents: build_agents(
+            &entity_list("agents"),
+            wallet.clone(),
+            ipfs_client,
+            &mut warnings,
+        )
+        .await,
+        conditions: build_conditions(
+            &entity_list("conditions"),
+            wallet.clone(),
+            ipfs_client,
+            &mut warnings,
+        )
+        .await,
+        listeners: build_listeners(
+            &entity_list("listeners"),
+            wallet.clone(),
+            provider,
+            ipfs_client,
+            &mut warnings,
+        )
+        .await,
+        evaluations: build_evaluations(
+            &entity_list("evaluations"),
+            wallet.clone(),
+            ipfs_client,
+            &mut warnings,
+        )
+        .await,
+        fhe_gates: build_fhe_gates(
+            &entity_list("fhe_gates"),
+            wallet.clone(),
+            ipfs_client,
+            &mut warnings,
+        )
+        .await,
+        offchain_connectors: build_offchain_connectors(
+            &entity_list("offchain_connectors"),
+            wallet.clone(),
+            ipfs_client,
+            &mut warnings,
+        )
+        .await,
+        onchain_connectors: build_onchain_connectors(
+            &entity_list("onchain_connectors"),
+            wallet,
+            ipfs_client,
+            &mut warnings,
+        )
+        .await,
+        contracts: object
+            .get("contracts")
+            .cloned()
+            .ok_or("Missing contracts")?
+            .as_array()
+            .ok_or("Contracts should be an array")?
+            .iter()
+            .map(|v| from_value(v.clone()))
+            .collect::<Result<_, _>>()?,
+        count: object
+            .get("count")
+            .and_then(|v| v.as_str())
+            .ok_or("Missing count")?
+            .parse::<U256>()?,
+        entities,
+        warnings,
+    })
 }
 
 async fn fetch_metadata_from_ipfs(
+    ipfs_client: &dyn IPFSClient,
     metadata_hash: &str,
 ) -> Result<Value, Box<dyn Error + Send + Sync>> {
-    let ipfs_url = format!("https://thedial.infura-ipfs.io/ipfs/{}", metadata_hash);
-    let client = Client::new();
-    let res = client.get(&ipfs_url).send().await?;
-    let metadata: Value = res.json().await?;
+    let metadata: Value = from_slice(&ipfs_client.fetch(metadata_hash).await?)?;
+    Ok(metadata)
+}
+
+fn decrypt_metadata(
+    mut metadata: Value,
+    encrypted: bool,
+    wallet: &LocalWallet,
+) -> Result<Value, Box<dyn Error + Send + Sync>> {
+    if let Some(fields) = metadata.get("encrypted_fields").and_then(|v| v.as_array()) {
+        let fields: Vec<String> = fields
+            .iter()
+            .filter_map(|field| field.as_str().map(|field| field.to_string()))
+            .collect();
+        decrypt_fields(&mut metadata, &fields, wallet)?;
+        return Ok(metadata);
+    }
+
+    if encrypted {
+        return decrypt_with_private_key(to_vec(&metadata)?, wallet.clone());
+    }
+
     Ok(metadata)
 }
 
+pub async fn send_with_retry<F>(
+    policy: &RetryPolicy,
+    request: F,
+) -> Result<Response, Box<dyn Error + Send + Sync>>
+where
+    F: Fn() -> RequestBuilder,
+{
+    let max_attempts = policy.max_attempts.max(1);
+    let mut backoff = policy.base_delay;
+    let mut attempt = 1;
+
+    loop {
+        let delay = match request().send().await {
+            Ok(res)
+                if res.status() == StatusCode::TOO_MANY_REQUESTS
+                    || res.status().is_server_error() =>
+            {
+                if attempt >= max_attempts {
+                    return Ok(res);
+                }
+                eprintln!(
+                    "Request returned {} (attempt {}/{})",
+                    res.status(),
+                    attempt,
+                    max_attempts
+                );
+                retry_after(&res).unwrap_or(backoff)
+            }
+            Ok(res) => return Ok(res),
+            Err(e) => {
+                if attempt >= max_attempts || !(e.is_timeout() || e.is_connect() || e.is_request())
+                {
+                    return Err(e.into());
+                }
+                eprintln!(
+                    "Request failed: {} (attempt {}/{})",
+                    e, attempt, max_attempts
+                );
+                backoff
+            }
+        };
+
+        sleep(delay.min(policy.max_delay)).await;
+        backoff = (backoff * 2).min(policy.max_delay);
+        attempt += 1;
+    }
+}
+
+fn retry_after(res: &Response) -> Option<Duration> {
+    let value = res.headers().get("retry-after")?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    (date.with_timezone(&Utc) - Utc::now()).to_std().ok()
+}
+
 async fn build_agents(
     data: &Value,
     wallet: LocalWallet,
-) -> Result<Vec<Agent>, Box<dyn Error + Send + Sync>> {
+    ipfs_client: &dyn IPFSClient,
+    warnings: &mut Vec<LoadWarning>,
+) -> Vec<Agent> {
     let mut agents = Vec::new();
     if let Some(agent_array) = data.as_array() {
         for agent_data in agent_array {
-            let metadata_hash = agent_data
-                .get("metadata")
-                .and_then(|v| v.as_str())
-                .ok_or("Missing metadata")?;
-
-            let address = agent_data
-                .get("wallet")
-                .and_then(|v| v.as_str())
-                .ok_or("Missing wallet")?
-                .to_string();
-
-            let encrypted = agent_data
-                .get("encrypted")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false);
-
-            let write_role = agent_data
-                .get("writer")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false);
+            let entity: Result<Agent, Box<dyn Error + Send + Sync>> = async {
+                let metadata_hash = agent_data
+                    .get("metadata")
+                    .and_then(|v| v.as_str())
+                    .ok_or("Missing metadata")?;
 
-            let admin_role = agent_data
-                .get("admin")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false);
+                let address = agent_data
+                    .get("wallet")
+                    .and_then(|v| v.as_str())
+                    .ok_or("Missing wallet")?
+                    .to_string();
 
-            let mut metadata = fetch_metadata_from_ipfs(metadata_hash).await?;
+                let encrypted = agent_data
+                    .get("encrypted")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
 
-            if encrypted {
-                let metadata_bytes = to_vec(&metadata)?;
-                metadata = decrypt_with_private_key(metadata_bytes, wallet.clone())?;
-            }
+                let write_role = agent_data
+                    .get("writer")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
 
-            let role = metadata
-                .get("role")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
-            let personality = metadata
-                .get("personality")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
-            let system = metadata
-                .get("system")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
-            let model = parse_llm_model(&metadata)?;
-            let lens_account = metadata
-                .get("lens_account")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
-            let farcaster_account = metadata
-                .get("farcaster_account")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
+                let admin_role = agent_data
+                    .get("admin")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
 
-            let objectives = metadata
-                .get("objectives")
-                .and_then(|v| v.as_array())
-                .unwrap_or(&vec![])
-                .iter()
-                .filter_map(|v| Objective::try_from(v).ok())
-                .collect();
+                let metadata = decrypt_metadata(
+                    fetch_metadata_from_ipfs(ipfs_client, metadata_hash).await?,
+                    encrypted,
+                    &wallet,
+                )?;
 
-            agents.push(Agent {
-                name: metadata
-                    .get("name")
+                let role = metadata
+                    .get("role")
                     .and_then(|v| v.as_str())
                     .unwrap_or("")
-                    .to_string(),
-                id: metadata
-                    .get("id")
+                    .to_string();
+                let personality = metadata
+                    .get("personality")
                     .and_then(|v| v.as_str())
                     .unwrap_or("")
-                    .to_string(),
-                role,
-                personality,
-                system,
-                model,
-                encrypted,
-                wallet: LocalWallet::from_str(&address)?,
-                write_role,
-                admin_role,
-                farcaster_account: Some(farcaster_account),
-                lens_account: Some(lens_account),
-                objectives,
-            });
+                    .to_string();
+                let system = metadata
+                    .get("system")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string();
+                let model = parse_llm_model(&metadata)?;
+                let lens_account = metadata
+                    .get("lens_account")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string();
+                let farcaster_account = metadata
+                    .get("farcaster_account")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string();
+
+                let objectives = metadata
+                    .get("objectives")
+                    .and_then(|v| v.as_array())
+                    .unwrap_or(&vec![])
+                    .iter()
+                    .filter_map(|v| Objective::try_from(v).ok())
+                    .collect();
+
+                let examples = metadata
+                    .get("examples")
+                    .and_then(|v| v.as_array())
+                    .unwrap_or(&vec![])
+                    .iter()
+                    .filter_map(|example| {
+                        Some((
+                            example.get("input")?.as_str()?.to_string(),
+                            example.get("output")?.as_str()?.to_string(),
+                        ))
+                    })
+                    .collect();
+
+                Ok(Agent {
+                    name: metadata
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("")
+                        .to_string(),
+                    id: parse_id(&metadata, "id")?,
+                    role,
+                    personality,
+                    system,
+                    model,
+                    encrypted,
+                    wallet: LocalWallet::from_str(&address)?,
+                    write_role,
+                    admin_role,
+                    farcaster_account: Some(farcaster_account),
+                    lens_account: Some(lens_account),
+                    objectives,
+                    objective_similarity: DEFAULT_OBJECTIVE_SIMILARITY,
+                    examples,
+                    observer: None,
+                })
+            }
+            .await;
+
+            match entity {
+                Ok(entity) => agents.push(entity),
+                Err(e) => warnings.push(LoadWarning::new("agent", agent_data, e)),
+            }
         }
     }
-    Ok(agents)
+    agents
 }
 
 fn parse_llm_model(metadata: &Value) -> Result<LLMModel, Box<dyn Error + Send + Sync>> {
@@ -439,10 +656,19 @@ fn parse_llm_model(metadata: &Value) -> Result<LLMModel, Box<dyn Error + Send +
             parallel_tool_calls: metadata
                 .get("parallel_tool_calls")
                 .and_then(|v| v.as_bool()),
+            tools: metadata
+                .get("tools")
+                .and_then(|v| v.as_array())
+                .map(|arr| arr.to_vec()),
+            tool_choice: metadata.get("tool_choice").cloned(),
             user: metadata
                 .get("user")
                 .and_then(|v| v.as_str())
                 .map(|s| s.to_string()),
+            base_url: metadata
+                .get("base_url")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
         }),
         "Claude" => Ok(LLMModel::Claude {
             api_key: metadata
@@ -497,7 +723,11 @@ fn parse_llm_model(metadata: &Value) -> Result<LLMModel, Box<dyn Error + Send +
             tools: metadata
                 .get("tools")
                 .and_then(|v| v.as_array())
-                .map(|arr| arr.iter().cloned().collect()),
+                .map(|arr| arr.to_vec()),
+            base_url: metadata
+                .get("base_url")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
         }),
         "Ollama" => Ok(LLMModel::Ollama {
             model: metadata
@@ -599,651 +829,959 @@ fn parse_llm_model(metadata: &Value) -> Result<LLMModel, Box<dyn Error + Send +
 async fn build_conditions(
     data: &Value,
     wallet: LocalWallet,
-) -> Result<Vec<Condition>, Box<dyn Error + Send + Sync>> {
+    ipfs_client: &dyn IPFSClient,
+    warnings: &mut Vec<LoadWarning>,
+) -> Vec<Condition> {
     let mut conditions = Vec::new();
 
     if let Some(condition_array) = data.as_array() {
         for condition_data in condition_array {
-            let metadata_hash = condition_data
-                .get("metadata")
-                .and_then(|v| v.as_str())
-                .ok_or("Missing metadata")?;
-
-            let encrypted = condition_data
-                .get("encrypted")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false);
-
-            let mut metadata = fetch_metadata_from_ipfs(metadata_hash).await?;
+            let entity: Result<Condition, Box<dyn Error + Send + Sync>> = async {
+                let metadata_hash = condition_data
+                    .get("metadata")
+                    .and_then(|v| v.as_str())
+                    .ok_or("Missing metadata")?;
 
-            if encrypted {
-                let metadata_bytes = to_vec(&metadata)?;
-                metadata = decrypt_with_private_key(metadata_bytes, wallet.clone())?;
-            }
+                let encrypted = condition_data
+                    .get("encrypted")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
 
-            let name = metadata
-                .get("name")
-                .and_then(|v| v.as_str())
-                .unwrap_or("Unnamed Condition")
-                .to_string();
+                let metadata = decrypt_metadata(
+                    fetch_metadata_from_ipfs(ipfs_client, metadata_hash).await?,
+                    encrypted,
+                    &wallet,
+                )?;
 
-            let id = metadata
-                .get("id")
-                .and_then(|v| v.as_str())
-                .unwrap_or("No ID for Condition")
-                .to_string();
+                let name = metadata
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("Unnamed Condition")
+                    .to_string();
 
-            let condition_type = match metadata
-                .get("condition_type")
-                .and_then(|v| v.as_str())
-                .ok_or("Missing condition_type")?
-            {
-                "OnChain" => ConditionType::OnChain {
-                    contract_address: metadata
-                        .get("contract_address")
-                        .and_then(|v| v.as_str())
-                        .ok_or("Missing contract_address")?
-                        .parse::<Address>()?,
-                    function_signature: metadata
-                        .get("function_signature")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string(),
-                },
-                "OffChain" => ConditionType::OffChain {
-                    api_url: metadata
-                        .get("api_url")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string(),
-                },
-                "Composite" => ConditionType::Composite {
-                    operator: metadata
-                        .get("operator")
-                        .and_then(|v| v.as_str())
-                        .ok_or("Missing operator")?
-                        .parse::<LogicalOperator>()?,
-                    sub_conditions: metadata
-                        .get("sub_conditions")
-                        .and_then(|v| v.as_array())
-                        .ok_or("Missing or invalid sub_conditions")?
-                        .iter()
-                        .map(Condition::from_json)
-                        .collect::<Result<Vec<Condition>, String>>()?,
-                },
+                let id = parse_id(&metadata, "id")?;
 
-                "ContextBased" => ConditionType::ContextBased {},
-                "TimeBased" => ConditionType::TimeBased {
-                    comparison_time: metadata
-                        .get("comparison_time")
-                        .and_then(|v| v.as_str())
-                        .ok_or("Missing comparison_time")?
-                        .parse::<chrono::NaiveTime>()?,
-                    comparison_type: match metadata
-                        .get("comparison_type")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("After")
-                    {
-                        "Before" => TimeComparisonType::Before,
-                        "After" => TimeComparisonType::After,
-                        _ => return Err("Invalid comparison_type".into()),
+                let condition_type = match metadata
+                    .get("condition_type")
+                    .and_then(|v| v.as_str())
+                    .ok_or("Missing condition_type")?
+                {
+                    "OnChain" => ConditionType::OnChain {
+                        contract_address: metadata
+                            .get("contract_address")
+                            .and_then(|v| v.as_str())
+                            .ok_or("Missing contract_address")?
+                            .parse::<Address>()?,
+                        function_signature: metadata
+                            .get("function_signature")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("")
+                            .to_string(),
+                    },
+                    "OffChain" => ConditionType::OffChain {
+                        api_url: metadata
+                            .get("api_url")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("")
+                            .to_string(),
+                        method: Method::from_bytes(
+                            metadata
+                                .get("method")
+                                .and_then(|v| v.as_str())
+                                .unwrap_or("GET")
+                                .to_uppercase()
+                                .as_bytes(),
+                        )?,
+                        json_pointer: metadata
+                            .get("json_pointer")
+                            .and_then(|v| v.as_str())
+                            .map(|s| s.to_string()),
+                        comparison: metadata
+                            .get("comparison")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("Eq")
+                            .parse::<ComparisonOperator>()?,
+                    },
+                    "Composite" => ConditionType::Composite {
+                        operator: metadata
+                            .get("operator")
+                            .and_then(|v| v.as_str())
+                            .ok_or("Missing operator")?
+                            .parse::<LogicalOperator>()?,
+                        sub_conditions: metadata
+                            .get("sub_conditions")
+                            .and_then(|v| v.as_array())
+                            .ok_or("Missing or invalid sub_conditions")?
+                            .iter()
+                            .map(Condition::from_json)
+                            .collect::<Result<Vec<Condition>, String>>()?,
                     },
-                },
-                _ => return Err("Invalid condition_type".into()),
-            };
 
-            let check = ConditionCheck {
-                condition_fn: |_v| true,
-                expected_value: metadata.get("expected_value").cloned(),
-            };
+                    "ContextBased" => ConditionType::ContextBased {},
+                    "TimeBased" => ConditionType::TimeBased {
+                        comparison_time: metadata
+                            .get("comparison_time")
+                            .and_then(|v| v.as_str())
+                            .ok_or("Missing comparison_time")?
+                            .parse::<chrono::NaiveTime>()?,
+                        comparison_type: match metadata
+                            .get("comparison_type")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("After")
+                        {
+                            "Before" => TimeComparisonType::Before,
+                            "After" => TimeComparisonType::After,
+                            _ => return Err("Invalid comparison_type".into()),
+                        },
+                    },
+                    _ => return Err("Invalid condition_type".into()),
+                };
+
+                let check = ConditionCheck {
+                    condition_fn: |_v| true,
+                    expected_value: metadata.get("expected_value").cloned(),
+                };
+
+                Ok(Condition {
+                    name,
+                    condition_type,
+                    check,
+                    encrypted,
+                    id,
+                })
+            }
+            .await;
 
-            conditions.push(Condition {
-                name,
-                condition_type,
-                check,
-                encrypted,
-                id,
-            });
+            match entity {
+                Ok(entity) => conditions.push(entity),
+                Err(e) => warnings.push(LoadWarning::new("condition", condition_data, e)),
+            }
         }
     }
 
-    Ok(conditions)
+    conditions
 }
 
 async fn build_listeners(
     data: &Value,
     wallet: LocalWallet,
     provider: Provider<Http>,
-) -> Result<Vec<Listener>, Box<dyn Error + Send + Sync>> {
+    ipfs_client: &dyn IPFSClient,
+    warnings: &mut Vec<LoadWarning>,
+) -> Vec<Listener> {
     let mut listeners = Vec::new();
 
     if let Some(listener_array) = data.as_array() {
         for listener_data in listener_array {
-            let provider = provider.clone();
-            let wallet = wallet.clone();
+            let entity: Result<Listener, Box<dyn Error + Send + Sync>> = async {
+                let provider = provider.clone();
+                let wallet = wallet.clone();
 
-            let metadata_hash = listener_data
-                .get("metadata")
-                .and_then(|v| v.as_str())
-                .ok_or("Missing metadata")?;
+                let metadata_hash = listener_data
+                    .get("metadata")
+                    .and_then(|v| v.as_str())
+                    .ok_or("Missing metadata")?;
 
-            let encrypted = listener_data
-                .get("encrypted")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false);
+                let encrypted = listener_data
+                    .get("encrypted")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
 
-            let mut metadata = fetch_metadata_from_ipfs(metadata_hash).await?;
+                let metadata = decrypt_metadata(
+                    fetch_metadata_from_ipfs(ipfs_client, metadata_hash).await?,
+                    encrypted,
+                    &wallet,
+                )?;
 
-            if encrypted {
-                let metadata_bytes = to_vec(&metadata)?;
-                metadata = decrypt_with_private_key(metadata_bytes, wallet.clone())?;
-            }
+                let name = metadata
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("Unnamed Listener")
+                    .to_string();
 
-            let name = metadata
-                .get("name")
-                .and_then(|v| v.as_str())
-                .unwrap_or("Unnamed Listener")
-                .to_string();
+                let id = parse_id(&metadata, "id")?;
 
-            let id = metadata
-                .get("id")
-                .and_then(|v| v.as_str())
-                .unwrap_or("No ID for Listener")
-                .to_string();
+                let listener_type = match metadata
+                    .get("listener_type")
+                    .and_then(|v| v.as_str())
+                    .ok_or("Missing listener_type")?
+                {
+                    "OnChain" => ListenerType::OnChain {
+                        contract_address: metadata
+                            .get("contract_address")
+                            .and_then(|v| v.as_str())
+                            .ok_or("Missing contract_address")?
+                            .parse::<Address>()?,
+                        event_signature: metadata
+                            .get("event_signature")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("")
+                            .to_string(),
+                        abi: metadata
+                            .get("abi")
+                            .and_then(|v| v.as_str())
+                            .ok_or("Missing abi")?
+                            .to_string(),
 
-            let listener_type = match metadata
-                .get("listener_type")
-                .and_then(|v| v.as_str())
-                .ok_or("Missing listener_type")?
-            {
-                "OnChain" => ListenerType::OnChain {
-                    contract_address: metadata
-                        .get("contract_address")
-                        .and_then(|v| v.as_str())
-                        .ok_or("Missing contract_address")?
-                        .parse::<Address>()?,
-                    event_signature: metadata
-                        .get("event_signature")
+                        chain: metadata
+                            .get("chain")
+                            .and_then(|v| v.as_str())
+                            .ok_or("Missing chain")?
+                            .parse::<Chain>()?,
+                        provider,
+                        wallet,
+                    },
+                    "OffChain" => ListenerType::OffChain {
+                        webhook_url: metadata
+                            .get("webhook_url")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("")
+                            .to_string(),
+                        sns_verification: metadata
+                            .get("sns_verification")
+                            .and_then(|v| v.as_bool())
+                            .unwrap_or(false),
+                    },
+                    "WebhookServer" => ListenerType::WebhookServer {
+                        bind_addr: metadata
+                            .get("bind_addr")
+                            .and_then(|v| v.as_str())
+                            .ok_or("Missing bind_addr")?
+                            .parse::<SocketAddr>()?,
+                        path: metadata
+                            .get("path")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("/")
+                            .to_string(),
+                        sns_verification: metadata
+                            .get("sns_verification")
+                            .and_then(|v| v.as_bool())
+                            .unwrap_or(false),
+                    },
+                    "Timer" => ListenerType::Timer {
+                        interval: metadata
+                            .get("interval")
+                            .and_then(|v| v.as_u64())
+                            .map(Duration::from_secs)
+                            .ok_or("Missing interval")?,
+                    },
+                    "WebSocket" => ListenerType::WebSocket {
+                        url: metadata
+                            .get("url")
+                            .and_then(|v| v.as_str())
+                            .ok_or("Missing url")?
+                            .to_string(),
+                        subscribe_message: metadata
+                            .get("subscribe_message")
+                            .and_then(|v| v.as_str())
+                            .map(|v| v.to_string()),
+                        reconnect: metadata
+                            .get("reconnect")
+                            .and_then(|v| v.as_bool())
+                            .unwrap_or(false),
+                    },
+                    "PollContract" => ListenerType::PollContract {
+                        contract_address: metadata
+                            .get("contract_address")
+                            .and_then(|v| v.as_str())
+                            .ok_or("Missing contract_address")?
+                            .parse::<Address>()?,
+                        function_signature: metadata
+                            .get("function_signature")
+                            .and_then(|v| v.as_str())
+                            .ok_or("Missing function_signature")?
+                            .to_string(),
+                        chain: metadata
+                            .get("chain")
+                            .and_then(|v| v.as_str())
+                            .ok_or("Missing chain")?
+                            .parse::<Chain>()?,
+                        interval: metadata
+                            .get("interval")
+                            .and_then(|v| v.as_u64())
+                            .map(Duration::from_secs)
+                            .ok_or("Missing interval")?,
+                        trigger: match metadata
+                            .get("trigger")
+                            .and_then(|v| v.get("type"))
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("Changed")
+                        {
+                            "Above" => TriggerCondition::Above {
+                                value: metadata["trigger"]["value"]
+                                    .as_f64()
+                                    .ok_or("Missing trigger value")?,
+                            },
+                            "Below" => TriggerCondition::Below {
+                                value: metadata["trigger"]["value"]
+                                    .as_f64()
+                                    .ok_or("Missing trigger value")?,
+                            },
+                            "Equals" => TriggerCondition::Equals {
+                                value: metadata["trigger"]["value"].clone(),
+                            },
+                            _ => TriggerCondition::Changed,
+                        },
+                        provider,
+                    },
+                    _ => return Err("Invalid listener_type".into()),
+                };
+
+                Ok(Listener {
+                    name,
+                    id,
+                    listener_type,
+                    encrypted,
+                    debounce: metadata
+                        .get("debounce_ms")
+                        .and_then(|v| v.as_u64())
+                        .map(Duration::from_millis),
+                    dedup_key: metadata
+                        .get("dedup_key")
                         .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string(),
-                    abi: metadata
-                        .get("abi")
-                        .and_then(|v| v.as_str())
-                        .ok_or("Missing abi")?
-                        .to_string(),
-
-                    chain: metadata
-                        .get("chain")
-                        .and_then(|v| v.as_str())
-                        .ok_or("Missing chain")?
-                        .parse::<Chain>()?,
-                    provider,
-                    wallet,
-                },
-                "OffChain" => ListenerType::OffChain {
-                    webhook_url: metadata
-                        .get("webhook_url")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string(),
-                    sns_verification: metadata
-                        .get("sns_verification")
-                        .and_then(|v| v.as_bool())
-                        .unwrap_or(false),
-                },
-                "Timer" => ListenerType::Timer {
-                    interval: metadata
-                        .get("interval")
-                        .and_then(|v| v.as_u64())
-                        .map(Duration::from_secs)
-                        .ok_or("Missing interval")?,
-                },
-                _ => return Err("Invalid listener_type".into()),
-            };
+                        .map(|v| v.to_string()),
+                })
+            }
+            .await;
 
-            listeners.push(Listener {
-                name,
-                id,
-                listener_type,
-                encrypted,
-            });
+            match entity {
+                Ok(entity) => listeners.push(entity),
+                Err(e) => warnings.push(LoadWarning::new("listener", listener_data, e)),
+            }
         }
     }
 
-    Ok(listeners)
+    listeners
 }
 
 async fn build_evaluations(
     data: &Value,
     wallet: LocalWallet,
-) -> Result<Vec<Evaluation>, Box<dyn Error + Send + Sync>> {
+    ipfs_client: &dyn IPFSClient,
+    warnings: &mut Vec<LoadWarning>,
+) -> Vec<Evaluation> {
     let mut evaluations = Vec::new();
 
     if let Some(evaluation_array) = data.as_array() {
         for evaluation_data in evaluation_array {
-            let metadata_hash = evaluation_data
-                .get("metadata")
-                .and_then(|v| v.as_str())
-                .ok_or("Missing metadata")?;
+            let entity: Result<Evaluation, Box<dyn Error + Send + Sync>> = async {
+                let metadata_hash = evaluation_data
+                    .get("metadata")
+                    .and_then(|v| v.as_str())
+                    .ok_or("Missing metadata")?;
 
-            let encrypted: bool = evaluation_data
-                .get("encrypted")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false);
+                let encrypted: bool = evaluation_data
+                    .get("encrypted")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+
+                let metadata = decrypt_metadata(
+                    fetch_metadata_from_ipfs(ipfs_client, metadata_hash).await?,
+                    encrypted,
+                    &wallet,
+                )?;
+
+                let name = metadata
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("Unnamed Evaluation")
+                    .to_string();
 
-            let mut metadata = fetch_metadata_from_ipfs(metadata_hash).await?;
+                let id = parse_id(&metadata, "id")?;
 
-            if encrypted {
-                let metadata_bytes = to_vec(&metadata)?;
-                metadata = decrypt_with_private_key(metadata_bytes, wallet.clone())?;
+                let evaluation_type = parse_evaluation_type(&metadata)?;
+
+                Ok(Evaluation {
+                    name,
+                    encrypted,
+                    id,
+                    evaluation_type,
+                })
             }
+            .await;
 
-            let name = metadata
-                .get("name")
-                .and_then(|v| v.as_str())
-                .unwrap_or("Unnamed Evaluation")
-                .to_string();
+            match entity {
+                Ok(entity) => evaluations.push(entity),
+                Err(e) => warnings.push(LoadWarning::new("evaluation", evaluation_data, e)),
+            }
+        }
+    }
 
-            let id = metadata
-                .get("id")
+    evaluations
+}
+
+fn parse_context_tool(tool_data: &Map<String, Value>) -> ContextParse {
+    if let Some(mapping) = tool_data.get("mapping").and_then(|v| v.as_array()) {
+        return ContextParse::Transform {
+            mapping: mapping
+                .iter()
+                .filter_map(|pair| {
+                    let from = pair.get(0).and_then(|v| v.as_str())?;
+                    let to = pair.get(1).and_then(|v| v.as_str())?;
+                    Some((from.to_string(), to.to_string()))
+                })
+                .collect(),
+        };
+    }
+
+    if let Some(expression) = tool_data.get("expression").and_then(|v| v.as_str()) {
+        return ContextParse::JsonPath {
+            expression: expression.to_string(),
+            output_key: tool_data
+                .get("output_key")
                 .and_then(|v| v.as_str())
-                .unwrap_or("No ID for Evaluation")
-                .to_string();
+                .map(|v| v.to_string()),
+        };
+    }
+
+    let required_fields: Vec<String> = tool_data
+        .get("required_fields")
+        .and_then(|fields| fields.as_array())
+        .map(|fields| {
+            fields
+                .iter()
+                .filter_map(|field| field.as_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    ContextParse::ParseFields {
+        expected_format: tool_data.clone(),
+        required_fields,
+    }
+}
+
+fn parse_history_tool(tool_data: &Map<String, Value>) -> HistoryParse {
+    let field_path: Vec<String> = tool_data
+        .get("field_path")
+        .and_then(|fields| fields.as_array())
+        .map(|fields| {
+            fields
+                .iter()
+                .filter_map(|field| field.as_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if let Some(op) = tool_data.get("aggregate").and_then(|v| v.as_str()) {
+        let op = match op {
+            "Concat" => AggregateOp::Concat,
+            "Sum" => AggregateOp::Sum,
+            "Max" => AggregateOp::Max,
+            _ => AggregateOp::Collect,
+        };
+        return HistoryParse::Aggregate {
+            selector: FieldPath {
+                element_type: tool_data
+                    .get("element_type")
+                    .and_then(|v| v.as_str())
+                    .map(|v| v.to_string()),
+                field_path,
+            },
+            op,
+        };
+    }
 
-            let evaluation_type = match metadata
-                .get("evaluation_type")
+    if let Some(index) = tool_data.get("index").and_then(|v| v.as_u64()) {
+        HistoryParse::ExtractField {
+            index: index as usize,
+            field_path,
+        }
+    } else {
+        eprintln!("Invalid or missing 'index' in history_tool configuration.");
+        HistoryParse::Invalid {
+            reason: "missing 'index' or 'aggregate'".to_string(),
+        }
+    }
+}
+
+fn parse_evaluation_type(metadata: &Value) -> Result<EvaluationType, Box<dyn Error + Send + Sync>> {
+    let evaluation_type = match metadata
+        .get("evaluation_type")
+        .or_else(|| metadata.get("type"))
+        .and_then(|v| v.as_str())
+        .ok_or("Missing evaluation_type")?
+    {
+        "HumanJudge" => EvaluationType::HumanJudge {
+            timeout: metadata
+                .get("timeout")
+                .and_then(|v| v.as_str())
+                .and_then(|s| s.parse::<u64>().ok())
+                .map(Duration::from_secs)
+                .unwrap_or_else(|| Duration::from_secs(0)),
+            default: metadata
+                .get("default")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            endpoint: metadata
+                .get("endpoint")
                 .and_then(|v| v.as_str())
-                .ok_or("Missing evaluation_type")?
-            {
-                "HumanJudge" => EvaluationType::HumanJudge {
-                    timeout: metadata
-                        .get("timeout")
-                        .and_then(|v| v.as_str())
-                        .and_then(|s| s.parse::<u64>().ok())
-                        .map(|secs| Duration::from_secs(secs))
-                        .unwrap_or_else(|| Duration::from_secs(0)),
-                    default: metadata
-                        .get("default")
-                        .and_then(|v| v.as_bool())
-                        .unwrap_or(false),
-                    endpoint: metadata
-                        .get("endpoint")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string(),
-                    auth_key: Some(
-                        metadata
-                            .get("auth_key")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("")
-                            .to_string(),
-                    ),
-                },
-                "LLMJudge" => EvaluationType::LLMJudge {
-                    model_type: parse_llm_model(&metadata)?,
-                    prompt: metadata
-                        .get("prompt")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string(),
-                    response_type: match metadata.get("response_type") {
-                        Some(Value::Bool(expected)) => EvaluationResponseType::Boolean {
-                            expected: *expected,
-                        },
-                        Some(Value::Number(num)) => num
-                            .as_f64()
-                            .map(|threshold| EvaluationResponseType::Score { threshold })
-                            .unwrap_or(EvaluationResponseType::Dynamic),
-                        Some(_) => EvaluationResponseType::Dynamic,
-                        None => EvaluationResponseType::Dynamic,
-                    },
-                },
-                "AgentJudge" => EvaluationType::AgentJudge {
-                    prompt: metadata
-                        .get("prompt")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string(),
-                    agent_id: metadata
-                        .get("agent_id")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string(),
-                    response_type: match metadata.get("response_type") {
-                        Some(Value::Bool(expected)) => EvaluationResponseType::Boolean {
-                            expected: *expected,
-                        },
-                        Some(Value::Number(num)) => num
-                            .as_f64()
-                            .map(|threshold| EvaluationResponseType::Score { threshold })
-                            .unwrap_or(EvaluationResponseType::Dynamic),
-                        Some(_) => EvaluationResponseType::Dynamic,
-                        None => EvaluationResponseType::Dynamic,
-                    },
-                },
-                _ => return Err("Invalid evaluation_type".into()),
-            };
+                .unwrap_or("")
+                .to_string(),
+            auth_key: Some(
+                metadata
+                    .get("auth_key")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string(),
+            ),
+            poll_interval: metadata
+                .get("poll_interval_ms")
+                .and_then(|v| v.as_u64())
+                .map(Duration::from_millis)
+                .unwrap_or(DEFAULT_HUMAN_JUDGE_POLL_INTERVAL),
+        },
+        "LLMJudge" => EvaluationType::LLMJudge {
+            model_type: parse_llm_model(metadata)?,
+            prompt: metadata
+                .get("prompt")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string(),
+            response_type: parse_evaluation_response_type(metadata.get("response_type")),
+        },
+        "AgentJudge" => EvaluationType::AgentJudge {
+            prompt: metadata
+                .get("prompt")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string(),
+            agent_id: metadata
+                .get("agent_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string(),
+            response_type: parse_evaluation_response_type(metadata.get("response_type")),
+        },
+        "Panel" => EvaluationType::Panel {
+            judges: metadata
+                .get("judges")
+                .and_then(|v| v.as_array())
+                .ok_or("Missing judges")?
+                .iter()
+                .map(|entry| {
+                    let judge = flatten_judge(entry.get("judge").ok_or("Missing judge")?);
+                    let weight = entry.get("weight").and_then(|v| v.as_f64()).unwrap_or(1.0);
+                    Ok((parse_evaluation_type(&judge)?, weight))
+                })
+                .collect::<Result<Vec<_>, Box<dyn Error + Send + Sync>>>()?,
+            threshold: metadata
+                .get("threshold")
+                .and_then(|v| v.as_f64())
+                .unwrap_or(0.5),
+        },
+        "EmbeddingSimilarity" => EvaluationType::EmbeddingSimilarity {
+            reference: metadata
+                .get("reference")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string(),
+            threshold: metadata
+                .get("threshold")
+                .and_then(|v| v.as_f64())
+                .unwrap_or(0.8),
+            model: parse_llm_model(&flatten_judge(&json!({
+                "model_type": metadata.get("model").ok_or("Missing model")?,
+            })))?,
+        },
+        _ => return Err("Invalid evaluation_type".into()),
+    };
 
-            evaluations.push(Evaluation {
-                name,
-                encrypted,
-                id,
-                evaluation_type,
-            });
+    Ok(evaluation_type)
+}
+
+fn flatten_judge(judge: &Value) -> Value {
+    let mut flattened = judge.clone();
+    if let (Some(map), Some(Value::Object(model))) =
+        (flattened.as_object_mut(), judge.get("model_type"))
+    {
+        for (key, value) in model {
+            map.insert(key.clone(), value.clone());
         }
+        map.insert(
+            "model_type".to_string(),
+            model.get("type").cloned().unwrap_or(Value::Null),
+        );
     }
+    flattened
+}
 
-    Ok(evaluations)
+fn parse_evaluation_response_type(value: Option<&Value>) -> EvaluationResponseType {
+    match value {
+        Some(Value::Bool(expected)) => EvaluationResponseType::Boolean {
+            expected: *expected,
+        },
+        Some(Value::Number(num)) => num
+            .as_f64()
+            .map(|threshold| EvaluationResponseType::Score { threshold })
+            .unwrap_or(EvaluationResponseType::Dynamic),
+        Some(Value::Object(map))
+            if map.get("with_data").and_then(|v| v.as_bool()) == Some(true) =>
+        {
+            EvaluationResponseType::BooleanWithData {
+                expected: map
+                    .get("expected")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(true),
+            }
+        }
+        Some(_) => EvaluationResponseType::Dynamic,
+        None => EvaluationResponseType::Dynamic,
+    }
 }
 
 async fn build_fhe_gates(
     data: &Value,
     wallet: LocalWallet,
-) -> Result<Vec<FHEGate>, Box<dyn Error + Send + Sync>> {
+    ipfs_client: &dyn IPFSClient,
+    warnings: &mut Vec<LoadWarning>,
+) -> Vec<FHEGate> {
     let mut fhe_gates = Vec::new();
 
     if let Some(fhe_gate_array) = data.as_array() {
         for fhe_gate_data in fhe_gate_array {
-            let metadata_hash = fhe_gate_data
-                .get("metadata")
-                .and_then(|v| v.as_str())
-                .ok_or("Missing metadata")?;
-
-            let encrypted = fhe_gate_data
-                .get("encrypted")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false);
-
-            let mut metadata = fetch_metadata_from_ipfs(metadata_hash).await?;
+            let entity: Result<FHEGate, Box<dyn Error + Send + Sync>> = async {
+                let metadata_hash = fhe_gate_data
+                    .get("metadata")
+                    .and_then(|v| v.as_str())
+                    .ok_or("Missing metadata")?;
 
-            if encrypted {
-                let metadata_bytes = to_vec(&metadata)?;
-                metadata = decrypt_with_private_key(metadata_bytes, wallet.clone())?;
-            }
-            let name = metadata
-                .get("name")
-                .and_then(|v| v.as_str())
-                .unwrap_or("Unnamed FHE Gate")
-                .to_string();
+                let encrypted = fhe_gate_data
+                    .get("encrypted")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+
+                let metadata = decrypt_metadata(
+                    fetch_metadata_from_ipfs(ipfs_client, metadata_hash).await?,
+                    encrypted,
+                    &wallet,
+                )?;
+                let name = metadata
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("Unnamed FHE Gate")
+                    .to_string();
 
-            let id = metadata
-                .get("id")
-                .and_then(|v| v.as_str())
-                .unwrap_or("No ID for FHE Gate")
-                .to_string();
+                let id = parse_id(&metadata, "id")?;
 
-            let key = metadata
-                .get("key")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
-
-            let contract_address = fhe_gate_data
-                .get("contract_address")
-                .and_then(|v| v.as_array())
-                .and_then(|arr| {
-                    if arr.len() == 20 {
-                        Some(H160::from_slice(
-                            &arr.iter()
-                                .filter_map(|v| v.as_u64().map(|x| x as u8))
-                                .collect::<Vec<_>>(),
-                        ))
-                    } else {
-                        None
-                    }
+                let key = metadata
+                    .get("key")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string();
+
+                let contract_address = fhe_gate_data
+                    .get("contract_address")
+                    .and_then(|v| v.as_array())
+                    .and_then(|arr| {
+                        if arr.len() == 20 {
+                            Some(H160::from_slice(
+                                &arr.iter()
+                                    .filter_map(|v| v.as_u64().map(|x| x as u8))
+                                    .collect::<Vec<_>>(),
+                            ))
+                        } else {
+                            None
+                        }
+                    })
+                    .ok_or("Invalid or missing contract address")?;
+
+                let operation = metadata
+                    .get("operation")
+                    .or_else(|| fhe_gate_data.get("operation"))
+                    .ok_or("Missing FHE Gate operation")
+                    .and_then(|v| FheOp::from_json(v).map_err(|_| "Invalid FHE Gate operation"))?;
+
+                let chain = fhe_gate_data
+                    .get("chain")
+                    .and_then(|s| s.as_str())
+                    .and_then(|s| s.parse::<Chain>().ok())
+                    .unwrap_or(Chain::Mainnet);
+
+                Ok(FHEGate {
+                    name,
+                    id,
+                    key,
+                    encrypted,
+                    contract_address,
+                    operation,
+                    chain,
                 })
-                .ok_or("Invalid or missing contract address")?;
-
-            let operation = fhe_gate_data
-                .get("operation")
-                .and_then(|v| v.as_str())
-                .unwrap_or("Unnamed Operation")
-                .to_string();
+            }
+            .await;
 
-            let chain = fhe_gate_data
-                .get("chain")
-                .and_then(|s| s.as_str())
-                .and_then(|s| s.parse::<Chain>().ok())
-                .unwrap_or(Chain::Mainnet);
-
-            fhe_gates.push(FHEGate {
-                name,
-                id,
-                key,
-                encrypted,
-                contract_address,
-                operation,
-                chain,
-            });
+            match entity {
+                Ok(entity) => fhe_gates.push(entity),
+                Err(e) => warnings.push(LoadWarning::new("fhe_gate", fhe_gate_data, e)),
+            }
         }
     }
 
-    Ok(fhe_gates)
+    fhe_gates
 }
 
 async fn build_onchain_connectors(
     data: &Value,
     wallet: LocalWallet,
-) -> Result<Vec<OnChainConnector>, Box<dyn Error + Send + Sync>> {
+    ipfs_client: &dyn IPFSClient,
+    warnings: &mut Vec<LoadWarning>,
+) -> Vec<OnChainConnector> {
     let mut onchain_connectors = Vec::new();
 
     if let Some(connector_array) = data.as_array() {
         for connector_data in connector_array {
-            let metadata_hash = connector_data
-                .get("metadata")
-                .and_then(|v| v.as_str())
-                .ok_or("Missing metadata")?;
+            let entity: Result<Option<OnChainConnector>, Box<dyn Error + Send + Sync>> = async {
+                let metadata_hash = connector_data
+                    .get("metadata")
+                    .and_then(|v| v.as_str())
+                    .ok_or("Missing metadata")?;
 
-            let encrypted = connector_data
-                .get("encrypted")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false);
+                let encrypted = connector_data
+                    .get("encrypted")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
 
-            let is_onchain = connector_data
-                .get("onChain")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false);
-            if !is_onchain {
-                continue;
-            }
+                let is_onchain = connector_data
+                    .get("onChain")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                if !is_onchain {
+                    return Ok(None);
+                }
 
-            let mut metadata = fetch_metadata_from_ipfs(metadata_hash).await?;
+                let metadata = decrypt_metadata(
+                    fetch_metadata_from_ipfs(ipfs_client, metadata_hash).await?,
+                    encrypted,
+                    &wallet,
+                )?;
 
-            if encrypted {
-                let metadata_bytes = to_vec(&metadata)?;
-                metadata = decrypt_with_private_key(metadata_bytes, wallet.clone())?;
-            }
+                let name = metadata
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("Unnamed OnChain Connector")
+                    .to_string();
 
-            let name = metadata
-                .get("name")
-                .and_then(|v| v.as_str())
-                .unwrap_or("Unnamed OnChain Connector")
-                .to_string();
+                let id = parse_id(&metadata, "id")?;
+                let address = metadata
+                    .get("address")
+                    .and_then(|v| v.as_str())
+                    .ok_or("Missing address")?
+                    .parse::<Address>()
+                    .map(Some)?;
 
-            let id = metadata
-                .get("id")
-                .and_then(|v| v.as_str())
-                .unwrap_or("No ID for OnChain Connector")
-                .to_string();
-            let address = metadata
-                .get("address")
-                .and_then(|v| v.as_str())
-                .ok_or("Missing address")?
-                .parse::<Address>()
-                .map(Some)?;
+                let abi = metadata
+                    .get("abi")
+                    .and_then(|v| v.as_str())
+                    .map(from_str::<abi::Abi>)
+                    .transpose()?;
 
-            let abi = metadata
-                .get("abi")
-                .and_then(|v| v.as_str())
-                .map(|abi_str| from_str::<abi::Abi>(abi_str))
-                .transpose()?;
+                let chain = metadata
+                    .get("chain")
+                    .and_then(|v| v.as_str())
+                    .ok_or("Missing chain")?
+                    .parse::<Chain>()?;
 
-            let chain = metadata
-                .get("chain")
-                .and_then(|v| v.as_str())
-                .ok_or("Missing chain")?
-                .parse::<Chain>()?;
+                let gas_options = metadata
+                    .get("gas_options")
+                    .and_then(|v| from_value::<GasOptions>(v.clone()).ok());
 
-            let gas_options = metadata
-                .get("gas_options")
-                .map(|v| from_value::<GasOptions>(v.clone()).ok())
-                .flatten();
+                let bytecode = metadata
+                    .get("bytecode")
+                    .and_then(|v| v.as_str())
+                    .map(|b| Bytes::from(hex::decode(b).unwrap_or_default()));
 
-            let bytecode = metadata
-                .get("bytecode")
-                .and_then(|v| v.as_str())
-                .map(|b| Bytes::from(hex::decode(b).unwrap_or_default()));
-
-            onchain_connectors.push(OnChainConnector {
-                name,
-                id,
-                address,
-                encrypted,
-                abi,
-                chain,
-                gas_options,
-                bytecode,
-            });
+                let transactions = metadata
+                    .get("transactions")
+                    .and_then(|v| from_value::<Vec<OnChainTransaction>>(v.clone()).ok())
+                    .unwrap_or_default();
+
+                let batch_mode = metadata
+                    .get("batch_mode")
+                    .and_then(|v| from_value::<BatchMode>(v.clone()).ok())
+                    .unwrap_or_default();
+
+                let gas_oracle = metadata
+                    .get("gas_oracle")
+                    .and_then(|v| from_value::<GasOracle>(v.clone()).ok())
+                    .unwrap_or_default();
+
+                Ok(Some(OnChainConnector {
+                    name,
+                    id,
+                    address,
+                    encrypted,
+                    abi,
+                    chain,
+                    gas_options,
+                    bytecode,
+                    transactions,
+                    batch_mode,
+                    gas_oracle,
+                }))
+            }
+            .await;
+
+            match entity {
+                Ok(Some(entity)) => onchain_connectors.push(entity),
+                Ok(None) => {}
+                Err(e) => warnings.push(LoadWarning::new("onchain_connector", connector_data, e)),
+            }
         }
     }
 
-    Ok(onchain_connectors)
+    onchain_connectors
 }
 
 pub async fn build_offchain_connectors(
     data: &Value,
     wallet: LocalWallet,
-) -> Result<Vec<OffChainConnector>, Box<dyn Error + Send + Sync>> {
+    ipfs_client: &dyn IPFSClient,
+    warnings: &mut Vec<LoadWarning>,
+) -> Vec<OffChainConnector> {
     let mut offchain_connectors = Vec::new();
 
     if let Some(connector_array) = data.as_array() {
         for connector_data in connector_array {
-            let metadata_hash = connector_data
-                .get("metadata")
-                .and_then(|v| v.as_str())
-                .ok_or("Missing metadata")?;
-
-            let encrypted = connector_data
-                .get("encrypted")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false);
+            let entity: Result<Option<OffChainConnector>, Box<dyn Error + Send + Sync>> = async {
+                let metadata_hash = connector_data
+                    .get("metadata")
+                    .and_then(|v| v.as_str())
+                    .ok_or("Missing metadata")?;
 
-            let is_onchain = connector_data
-                .get("onChain")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false);
-            if is_onchain {
-                continue;
-            }
+                let encrypted = connector_data
+                    .get("encrypted")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
 
-            let mut metadata = fetch_metadata_from_ipfs(metadata_hash).await?;
+                let is_onchain = connector_data
+                    .get("onChain")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                if is_onchain {
+                    return Ok(None);
+                }
 
-            if encrypted {
-                let metadata_bytes = to_vec(&metadata)?;
-                metadata = decrypt_with_private_key(metadata_bytes, wallet.clone())?;
-            }
+                let metadata = decrypt_metadata(
+                    fetch_metadata_from_ipfs(ipfs_client, metadata_hash).await?,
+                    encrypted,
+                    &wallet,
+                )?;
 
-            let name = metadata
-                .get("name")
-                .and_then(|v| v.as_str())
-                .unwrap_or("Unnamed OffChain Connector")
-                .to_string();
+                let name = metadata
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("Unnamed OffChain Connector")
+                    .to_string();
 
-            let id = metadata
-                .get("id")
-                .and_then(|v| v.as_str())
-                .unwrap_or("No ID for OffChain Connector")
-                .to_string();
+                let id = parse_id(&metadata, "id")?;
 
-            let api_url = metadata
-                .get("api_url")
-                .and_then(|v| v.as_str())
-                .ok_or("Missing api_url")?
-                .to_string();
-
-            let http_method = metadata
-                .get("http_method")
-                .and_then(|v| v.as_str())
-                .map(|s| match s {
-                    "GET" => Method::GET,
-                    "POST" => Method::POST,
-                    "PUT" => Method::PUT,
-                    "DELETE" => Method::DELETE,
-                    _ => Method::GET,
-                })
-                .unwrap_or(Method::GET);
+                let api_url = metadata
+                    .get("api_url")
+                    .and_then(|v| v.as_str())
+                    .ok_or("Missing api_url")?
+                    .to_string();
 
-            let headers = metadata
-                .get("headers")
-                .and_then(|v| v.as_object())
-                .map(|map| {
-                    map.iter()
-                        .filter_map(|(k, v)| v.as_str().map(|val| (k.clone(), val.to_string())))
-                        .collect::<HashMap<String, String>>()
-                });
+                let http_method = metadata
+                    .get("http_method")
+                    .and_then(|v| v.as_str())
+                    .map(|s| match s {
+                        "GET" => Method::GET,
+                        "POST" => Method::POST,
+                        "PUT" => Method::PUT,
+                        "DELETE" => Method::DELETE,
+                        _ => Method::GET,
+                    })
+                    .unwrap_or(Method::GET);
+
+                let headers = metadata
+                    .get("headers")
+                    .and_then(|v| v.as_object())
+                    .map(|map| {
+                        map.iter()
+                            .filter_map(|(k, v)| v.as_str().map(|val| (k.clone(), val.to_string())))
+                            .collect::<HashMap<String, String>>()
+                    });
+
+                let connector_type = metadata
+                    .get("connector_type")
+                    .and_then(|v| v.as_str())
+                    .ok_or("Missing connector_type")?;
+
+                let connector_type = match connector_type {
+                    "REST" => {
+                        let base_payload =
+                            metadata.get("base_payload").cloned().unwrap_or(Value::Null);
+                        ConnectorType::REST {
+                            base_payload: if base_payload.is_null() {
+                                None
+                            } else {
+                                Some(base_payload)
+                            },
+                        }
+                    }
+                    "GraphQL" => {
+                        let query = metadata
+                            .get("query")
+                            .and_then(|v| v.as_str())
+                            .ok_or("Missing query for GraphQL connector")?
+                            .to_string();
+
+                        let variables =
+                            metadata
+                                .get("variables")
+                                .and_then(|v| v.as_object())
+                                .map(|map| {
+                                    map.iter()
+                                        .filter_map(|(k, v)| {
+                                            v.as_str().map(|val| (k.clone(), val.to_string()))
+                                        })
+                                        .collect::<HashMap<String, String>>()
+                                });
+
+                        ConnectorType::GraphQL { query, variables }
+                    }
+                    "ContextBody" => ConnectorType::ContextBody {
+                        body_key: metadata
+                            .get("body_key")
+                            .and_then(|v| v.as_str())
+                            .map(|s| s.to_string()),
+                    },
+                    _ => return Err("Invalid connector_type".into()),
+                };
 
-            let connector_type = metadata
-                .get("connector_type")
-                .and_then(|v| v.as_str())
-                .ok_or("Missing connector_type")?;
+                let execution_fn: Option<
+                    Arc<dyn Fn(Value) -> Result<Value, Box<dyn Error + Send + Sync>> + Send + Sync>,
+                > = Some(Arc::new(|_input: Value| Ok(Value::Null)));
 
-            let connector_type = match connector_type {
-                "REST" => {
-                    let base_payload = metadata.get("base_payload").cloned().unwrap_or(Value::Null);
-                    ConnectorType::REST {
-                        base_payload: if base_payload.is_null() {
-                            None
-                        } else {
-                            Some(base_payload)
-                        },
-                    }
-                }
-                "GraphQL" => {
-                    let query = metadata
-                        .get("query")
-                        .and_then(|v| v.as_str())
-                        .ok_or("Missing query for GraphQL connector")?
-                        .to_string();
-
-                    let variables =
-                        metadata
-                            .get("variables")
-                            .and_then(|v| v.as_object())
-                            .map(|map| {
-                                map.iter()
-                                    .filter_map(|(k, v)| {
-                                        v.as_str().map(|val| (k.clone(), val.to_string()))
-                                    })
-                                    .collect::<HashMap<String, String>>()
-                            });
-
-                    ConnectorType::GraphQL { query, variables }
-                }
-                _ => return Err("Invalid connector_type".into()),
-            };
+                Ok(Some(OffChainConnector {
+                    name,
+                    id,
+                    connector_type,
+                    api_url,
+                    encrypted,
+                    http_method,
+                    headers,
+                    params: None,
+                    auth_tokens: None,
+                    result_processing_fn: execution_fn,
+                    auth_subflow: None,
+                    refreshed_auth_tokens: Arc::default(),
+                    pagination: metadata
+                        .get("pagination")
+                        .and_then(PaginationConfig::from_json),
+                }))
+            }
+            .await;
 
-            let execution_fn: Option<
-                Arc<dyn Fn(Value) -> Result<Value, Box<dyn Error + Send + Sync>> + Send + Sync>,
-            > = Some(Arc::new(|_input: Value| Ok(Value::Null)));
-
-            offchain_connectors.push(OffChainConnector {
-                name,
-                id,
-                connector_type,
-                api_url,
-                encrypted,
-                http_method,
-                headers,
-                params: None,
-                auth_tokens: None,
-                result_processing_fn: execution_fn,
-                auth_subflow: None,
-            });
+            match entity {
+                Ok(Some(entity)) => offchain_connectors.push(entity),
+                Ok(None) => {}
+                Err(e) => warnings.push(LoadWarning::new("offchain_connector", connector_data, e)),
+            }
         }
     }
 
-    Ok(offchain_connectors)
+    offchain_connectors
 }
 
 fn build_nodes(
@@ -1261,29 +1799,21 @@ fn build_nodes(
                 "OffChainConnector" => NodeAdapter::OffChainConnector,
                 "OnChainConnector" => NodeAdapter::OnChainConnector,
                 "Agent" => NodeAdapter::Agent,
+                "ToolAgent" => NodeAdapter::ToolAgent,
+                "Delay" => NodeAdapter::Delay,
                 _ => return Err("Invalid adapter_type".into()),
             };
 
-            let id = node_data
-                .get("id")
-                .and_then(|v| v.as_str())
-                .unwrap_or("No ID for Node")
-                .to_string();
+            let id = parse_id(node_data, "id")?;
 
-            let adapter_id: String = node_data
-                .get("adapter_id")
-                .and_then(|v| v.as_str())
-                .unwrap_or("No ID for Adapter")
-                .to_string();
+            let adapter_id = parse_id(node_data, "adapter_id")?;
 
             let repetitions = node_data
                 .get("repetitions")
                 .and_then(|v| v.as_u64())
                 .and_then(|val| u32::try_from(val).ok());
 
-            let context: Option<Value> = node_data
-                .get("context")
-                .and_then(|val| Value::try_from(val.clone()).ok());
+            let context: Option<Value> = node_data.get("context").cloned();
 
             let description = node_data
                 .get("description")
@@ -1292,51 +1822,17 @@ fn build_nodes(
             let context_tool = node_data
                 .get("context_tool")
                 .and_then(|v| v.as_object())
-                .map(|tool_data| {
-                    let required_fields: Vec<String> = tool_data
-                        .get("required_fields")
-                        .and_then(|fields| fields.as_array())
-                        .map(|fields| {
-                            fields
-                                .iter()
-                                .filter_map(|field| field.as_str().map(|s| s.to_string()))
-                                .collect()
-                        })
-                        .unwrap_or_default();
-
-                    ContextParse::ParseFields {
-                        expected_format: tool_data.clone(),
-                        required_fields,
-                    }
-                });
+                .map(parse_context_tool);
 
             let history_tool = node_data
                 .get("history_tool")
                 .and_then(|v| v.as_object())
-                .map(|tool_data| {
-                    if let Some(index) = tool_data.get("index").and_then(|v| v.as_u64()) {
-                        let field_path: Vec<String> = tool_data
-                            .get("field_path")
-                            .and_then(|fields| fields.as_array())
-                            .map(|fields| {
-                                fields
-                                    .iter()
-                                    .filter_map(|field| field.as_str().map(|s| s.to_string()))
-                                    .collect()
-                            })
-                            .unwrap_or_default();
-
-                        HistoryParse::ExtractField {
-                            index: index as usize,
-                            field_path,
-                        }
-                    } else {
-                        eprintln!("Invalid or missing 'index' in history_tool configuration.");
-                        HistoryParse::CustomProcessor {
-                            function: |_| Err("Invalid history_tool configuration".to_string()),
-                        }
-                    }
-                });
+                .map(parse_history_tool);
+
+            let enabled = node_data
+                .get("enabled")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true);
 
             nodes.insert(
                 id.clone(),
@@ -1349,6 +1845,7 @@ fn build_nodes(
                     description,
                     history_tool,
                     context_tool,
+                    enabled,
                 },
             );
         }
@@ -1386,6 +1883,19 @@ fn build_execution_history(
             let description = item
                 .get("description")
                 .and_then(|val| val.as_str().map(|s| s.to_string()));
+            let error = item
+                .get("error")
+                .and_then(|val| val.as_str().map(|s| s.to_string()));
+            let branch = item
+                .get("branch")
+                .and_then(|val| val.as_str().map(|s| s.to_string()));
+            let target_id = item
+                .get("target_id")
+                .and_then(|val| val.as_str().map(|s| s.to_string()));
+            let duration = item
+                .get("duration_ms")
+                .and_then(|val| val.as_u64())
+                .map(Duration::from_millis);
 
             execution_history.push(ExecutionHistory {
                 element_id,
@@ -1393,6 +1903,10 @@ fn build_execution_history(
                 result,
                 timestamp,
                 description,
+                error,
+                branch,
+                target_id,
+                duration,
             });
         }
     }
@@ -1407,17 +1921,9 @@ fn build_links(
 
     if let Some(link_array) = data.as_array() {
         for link_data in link_array {
-            let id = link_data
-                .get("id")
-                .and_then(|v| v.as_str())
-                .unwrap_or("No ID for Link")
-                .to_string();
+            let id = parse_id(link_data, "id")?;
 
-            let adapter_id = link_data
-                .get("adapter_id")
-                .and_then(|v| v.as_str())
-                .unwrap_or("No ID for Adapter")
-                .to_string();
+            let adapter_id = parse_id(link_data, "adapter_id")?;
             let adapter_type = match link_data
                 .get("adapter_type")
                 .and_then(|v| v.as_str())
@@ -1435,9 +1941,7 @@ fn build_links(
                 .and_then(|v| v.as_u64())
                 .and_then(|val| u32::try_from(val).ok());
 
-            let context = link_data
-                .get("context")
-                .and_then(|val| Value::try_from(val.clone()).ok());
+            let context = link_data.get("context").cloned();
 
             let description = link_data
                 .get("description")
@@ -1460,56 +1964,49 @@ fn build_links(
                     generated_target_id: decoded
                         .get("true_target_id")
                         .and_then(|v| v.as_str().map(|s| s.to_string())),
+                    score_targets: decoded
+                        .get("score_targets")
+                        .and_then(|v| v.as_array())
+                        .map(|targets| {
+                            targets
+                                .iter()
+                                .filter_map(|target| {
+                                    let min = target.get("min")?.as_f64()?;
+                                    let max = target.get("max")?.as_f64()?;
+                                    let target_id = target.get("target_id")?.as_str()?;
+                                    Some((min..=max, target_id.to_string()))
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                    loop_limits: decoded
+                        .get("loop_limits")
+                        .and_then(|v| v.as_object())
+                        .map(|limits| {
+                            limits
+                                .iter()
+                                .filter_map(|(target_id, limit)| {
+                                    Some((target_id.clone(), u32::try_from(limit.as_u64()?).ok()?))
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default(),
                 });
 
             let context_tool = link_data
                 .get("context_tool")
                 .and_then(|v| v.as_object())
-                .map(|tool_data| {
-                    let required_fields: Vec<String> = tool_data
-                        .get("required_fields")
-                        .and_then(|fields| fields.as_array())
-                        .map(|fields| {
-                            fields
-                                .iter()
-                                .filter_map(|field| field.as_str().map(|s| s.to_string()))
-                                .collect()
-                        })
-                        .unwrap_or_default();
-
-                    ContextParse::ParseFields {
-                        expected_format: tool_data.clone(),
-                        required_fields,
-                    }
-                });
+                .map(parse_context_tool);
 
             let history_tool = link_data
                 .get("history_tool")
                 .and_then(|v| v.as_object())
-                .map(|tool_data| {
-                    if let Some(index) = tool_data.get("index").and_then(|v| v.as_u64()) {
-                        let field_path: Vec<String> = tool_data
-                            .get("field_path")
-                            .and_then(|fields| fields.as_array())
-                            .map(|fields| {
-                                fields
-                                    .iter()
-                                    .filter_map(|field| field.as_str().map(|s| s.to_string()))
-                                    .collect()
-                            })
-                            .unwrap_or_default();
-
-                        HistoryParse::ExtractField {
-                            index: index as usize,
-                            field_path,
-                        }
-                    } else {
-                        eprintln!("Invalid or missing 'index' in history_tool configuration.");
-                        HistoryParse::CustomProcessor {
-                            function: |_| Err("Invalid history_tool configuration".to_string()),
-                        }
-                    }
-                });
+                .map(parse_history_tool);
+
+            let enabled = link_data
+                .get("enabled")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true);
 
             links.insert(
                 id.clone(),
@@ -1523,6 +2020,7 @@ fn build_links(
                     description,
                     history_tool,
                     context_tool,
+                    enabled,
                 },
             );
         }
@@ -1,10 +1,18 @@
 use crate::{
-    encrypt::encrypt_with_public_key,
+    adapters::{
+        links::conditions::{Condition, ConditionBatcher, ConditionType},
+        nodes::agents::{prompt_from_value, CacheStatus, LlmCache, ToolCall},
+    },
+    constants::{DEFAULT_MAX_WHILE_ITERATIONS, NIBBLE_STORAGE_ABI},
+    encrypt::{decrypt_with_private_key, encrypt_with_public_key},
+    error::NpcError,
     ipfs::IPFSClient,
     nibble::{Adapter, Nibble},
+    telemetry::TelemetrySpan,
     tools::{context::ContextParse, history::HistoryParse},
-    utils::generate_unique_id,
+    utils::{generate_unique_id, id_token, idempotency_key_hash},
 };
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use ethers::{
     abi::{Abi, Token, Tokenize},
@@ -12,21 +20,44 @@ use ethers::{
     prelude::*,
     utils::hex,
 };
-use serde::Serialize;
-use serde_json::{Map, Value};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
 use std::{
-    collections::HashMap, error::Error, fmt::Debug, fs::File, io::Read, marker::Send, path::Path,
-    result::Result, str::FromStr, sync::Arc,
+    collections::{BTreeSet, HashMap, HashSet},
+    error::Error,
+    fmt::{self, Debug},
+    marker::Send,
+    ops::RangeInclusive,
+    path::PathBuf,
+    result::Result,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant},
+};
+use tfhe::ClientKey;
+use tokio::{
+    io::AsyncWriteExt,
+    sync::{mpsc, oneshot, Mutex},
 };
-use tokio::sync::{mpsc, oneshot, Mutex};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ExecutionHistory {
     pub element_id: String,
     pub element_type: String,
     pub result: Option<Value>,
     pub description: Option<String>,
     pub timestamp: DateTime<Utc>,
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub branch: Option<String>,
+    #[serde(default)]
+    pub target_id: Option<String>,
+    #[serde(default)]
+    pub duration: Option<Duration>,
 }
 
 #[derive(Debug, Clone)]
@@ -34,12 +65,103 @@ pub enum NodeAdapter {
     OffChainConnector,
     OnChainConnector,
     Agent,
+    ToolAgent,
     SubFlow {
         subflow: Box<Workflow>,
         blocking: bool,
         repetitions: Option<u32>,
         count_successes: bool,
     },
+    Custom(Arc<dyn NodeExecutor>),
+    Delay,
+}
+
+#[async_trait]
+pub trait NodeExecutor: Debug + Send + Sync {
+    async fn execute(&self, ctx: Value) -> Result<Option<Value>, Box<dyn Error + Send + Sync>>;
+}
+
+#[async_trait]
+pub trait HistorySink: Send + Sync {
+    async fn append(
+        &self,
+        workflow_id: &str,
+        entries: &[ExecutionHistory],
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+}
+
+pub struct FileHistorySink {
+    path: PathBuf,
+}
+
+impl FileHistorySink {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[async_trait]
+impl HistorySink for FileHistorySink {
+    async fn append(
+        &self,
+        workflow_id: &str,
+        entries: &[ExecutionHistory],
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut lines = String::new();
+        for entry in entries {
+            lines.push_str(&serde_json::to_string(&json!({
+                "workflow_id": workflow_id,
+                "entry": entry,
+            }))?);
+            lines.push('\n');
+        }
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(lines.as_bytes()).await?;
+        file.flush().await?;
+        Ok(())
+    }
+}
+
+pub struct IpfsHistorySink {
+    ipfs_client: Arc<dyn IPFSClient + Send + Sync>,
+    cids: RwLock<Vec<String>>,
+}
+
+impl IpfsHistorySink {
+    pub fn new(ipfs_client: Arc<dyn IPFSClient + Send + Sync>) -> Self {
+        Self {
+            ipfs_client,
+            cids: RwLock::new(Vec::new()),
+        }
+    }
+
+    pub fn cids(&self) -> Vec<String> {
+        self.cids.read().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl HistorySink for IpfsHistorySink {
+    async fn append(
+        &self,
+        workflow_id: &str,
+        entries: &[ExecutionHistory],
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let previous = self.cids.read().unwrap().last().cloned();
+        let data = serde_json::to_vec(&json!({
+            "workflow_id": workflow_id,
+            "previous": previous,
+            "entries": entries,
+        }))?;
+        let cid = self.ipfs_client.upload(data).await?;
+        self.cids.write().unwrap().push(cid);
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -50,7 +172,9 @@ pub enum LinkAdapter {
     Evaluation,
 }
 
-#[derive(Debug, Clone)]
+pub type ContextMiddleware = Arc<dyn Fn(Value) -> Value + Send + Sync>;
+
+#[derive(Clone)]
 pub struct Workflow {
     pub id: String,
     pub name: String,
@@ -59,6 +183,227 @@ pub struct Workflow {
     pub nibble_context: Arc<Nibble>,
     pub encrypted: bool,
     pub execution_history: Vec<ExecutionHistory>,
+    pub context_middleware: Vec<ContextMiddleware>,
+    pub repetition_retry: Option<u32>,
+    pub checkpoint_config: Option<CheckpointConfig>,
+    pub checkpoints: Vec<String>,
+    pub runtime_budget: Option<Duration>,
+    pub error_policy: ErrorPolicy,
+    pub join_inputs: HashMap<String, Map<String, Value>>,
+    pub simulation: Option<Simulation>,
+    pub llm_prices: HashMap<String, f64>,
+    pub llm_cache: Option<Arc<dyn LlmCache>>,
+    pub force_llm_cache: bool,
+    pub executing: Arc<AtomicBool>,
+    pub signer: Option<LocalWallet>,
+    pub history_sink: Option<Arc<dyn HistorySink>>,
+    pub condition_batcher: Option<Arc<ConditionBatcher>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeCostEstimate {
+    pub node_id: String,
+    pub agent_id: String,
+    pub model: String,
+    pub max_tokens: u32,
+    pub cost: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CostEstimate {
+    pub nodes: Vec<NodeCostEstimate>,
+    pub total: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AdapterVerification {
+    pub missing: Vec<String>,
+    pub unpersisted: Vec<String>,
+}
+
+impl AdapterVerification {
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.unpersisted.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AdapterOutcomes {
+    pub successes: usize,
+    pub failures: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SlowestElement {
+    pub element_id: String,
+    pub element_type: String,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ExecutionSummary {
+    pub total_elements: usize,
+    pub by_adapter: HashMap<String, AdapterOutcomes>,
+    pub total_duration: Option<Duration>,
+    pub slowest_node: Option<SlowestElement>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ElementDiff {
+    pub signature: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct WorkflowDiff {
+    pub added_nodes: Vec<ElementDiff>,
+    pub removed_nodes: Vec<ElementDiff>,
+    pub modified_nodes: Vec<ElementDiff>,
+    pub added_links: Vec<ElementDiff>,
+    pub removed_links: Vec<ElementDiff>,
+    pub modified_links: Vec<ElementDiff>,
+}
+
+impl WorkflowDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_nodes.is_empty()
+            && self.removed_nodes.is_empty()
+            && self.modified_nodes.is_empty()
+            && self.added_links.is_empty()
+            && self.removed_links.is_empty()
+            && self.modified_links.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Simulation {
+    pub stubs: HashMap<String, Value>,
+}
+
+impl Simulation {
+    fn stub(&self, adapter_id: &str) -> Option<Value> {
+        self.stubs.get(adapter_id).cloned()
+    }
+
+    fn check_condition(&self, condition: &Condition, context: Option<Value>) -> bool {
+        if let Some(stub) = self.stub(&condition.id) {
+            return stub
+                .as_bool()
+                .unwrap_or_else(|| (condition.check.condition_fn)(stub));
+        }
+
+        (condition.check.condition_fn)(context.unwrap_or(Value::Null))
+    }
+
+    fn evaluate(&self, evaluation_id: &str, context: Option<Value>) -> Value {
+        self.stub(evaluation_id).unwrap_or_else(|| {
+            let mut map = Map::new();
+            map.insert("decision".to_string(), Value::Bool(true));
+            map.insert("data".to_string(), context.unwrap_or(Value::Null));
+            Value::Object(map)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum ErrorPolicy {
+    #[default]
+    StopWorkflow,
+    SkipNode,
+    RouteToErrorNode(String),
+}
+
+impl fmt::Display for ErrorPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorPolicy::StopWorkflow => write!(f, "StopWorkflow"),
+            ErrorPolicy::SkipNode => write!(f, "SkipNode"),
+            ErrorPolicy::RouteToErrorNode(node_id) => write!(f, "RouteToErrorNode({})", node_id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CheckpointConfig {
+    pub every_elements: Option<usize>,
+    pub every_seconds: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkflowState {
+    pub workflow_id: String,
+    pub repetition: u32,
+    pub successful_repetitions: u32,
+    pub elements_completed: usize,
+    pub context: Option<Value>,
+    pub execution_history: Vec<ExecutionHistory>,
+}
+
+struct ExecutionGuard(Arc<AtomicBool>);
+
+impl ExecutionGuard {
+    fn acquire(executing: &Arc<AtomicBool>) -> Option<Self> {
+        executing
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .ok()
+            .map(|_| Self(executing.clone()))
+    }
+}
+
+impl Drop for ExecutionGuard {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+struct CheckpointClock {
+    elements: usize,
+    last: Instant,
+}
+
+impl CheckpointClock {
+    fn new() -> Self {
+        Self {
+            elements: 0,
+            last: Instant::now(),
+        }
+    }
+}
+
+impl Debug for Workflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Workflow")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("nodes", &self.nodes)
+            .field("links", &self.links)
+            .field("nibble_context", &self.nibble_context)
+            .field("encrypted", &self.encrypted)
+            .field("execution_history", &self.execution_history)
+            .field(
+                "context_middleware",
+                &format!("{} middleware function(s)", self.context_middleware.len()),
+            )
+            .field("repetition_retry", &self.repetition_retry)
+            .field("checkpoint_config", &self.checkpoint_config)
+            .field("checkpoints", &self.checkpoints)
+            .field("runtime_budget", &self.runtime_budget)
+            .field("error_policy", &self.error_policy)
+            .field("join_inputs", &self.join_inputs)
+            .field("simulation", &self.simulation)
+            .field("llm_prices", &self.llm_prices)
+            .field("llm_cache", &self.llm_cache.is_some())
+            .field("force_llm_cache", &self.force_llm_cache)
+            .field(
+                "signer",
+                &self.signer.as_ref().map(|signer| signer.address()),
+            )
+            .field("history_sink", &self.history_sink.is_some())
+            .field("condition_batcher", &self.condition_batcher)
+            .field("executing", &self.executing.load(Ordering::Acquire))
+            .finish()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -71,20 +416,22 @@ pub struct WorkflowNode {
     pub description: Option<String>,
     pub context_tool: Option<ContextParse>,
     pub history_tool: Option<HistoryParse>,
+    pub enabled: bool,
 }
 
 impl WorkflowNode {
     pub fn to_json(&self) -> Map<String, Value> {
         let mut map = Map::new();
-        map.insert("id".to_string(), Value::String(hex::encode(&self.id)));
+        map.insert("id".to_string(), Value::String(self.id.clone()));
         map.insert(
             "adapter_type".to_string(),
             Value::String(format!("{:?}", self.adapter_type)),
         );
         map.insert(
             "adapter_id".to_string(),
-            Value::String(hex::encode(&self.adapter_id)),
+            Value::String(self.adapter_id.clone()),
         );
+        map.insert("enabled".to_string(), Value::Bool(self.enabled));
         map
     }
 }
@@ -94,6 +441,30 @@ pub struct LinkTarget {
     pub true_target_id: String,
     pub false_target_id: String,
     pub generated_target_id: Option<String>,
+    pub score_targets: Vec<(RangeInclusive<f64>, String)>,
+    pub loop_limits: HashMap<String, u32>,
+}
+
+impl LinkTarget {
+    pub fn target_ids(&self) -> Vec<&String> {
+        let mut ids = vec![&self.true_target_id, &self.false_target_id];
+        if let Some(generated_target_id) = &self.generated_target_id {
+            ids.push(generated_target_id);
+        }
+        ids.extend(self.score_targets.iter().map(|(_, target_id)| target_id));
+        ids
+    }
+
+    pub fn is_loop_target(&self, target_id: &str) -> bool {
+        self.loop_limits.contains_key(target_id)
+    }
+
+    pub fn score_target(&self, score: f64) -> Option<&String> {
+        self.score_targets
+            .iter()
+            .find(|(range, _)| range.contains(&score))
+            .map(|(_, target_id)| target_id)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -107,20 +478,22 @@ pub struct WorkflowLink {
     pub description: Option<String>,
     pub context_tool: Option<ContextParse>,
     pub history_tool: Option<HistoryParse>,
+    pub enabled: bool,
 }
 
 impl WorkflowLink {
     pub fn to_json(&self) -> Map<String, Value> {
         let mut map = Map::new();
-        map.insert("id".to_string(), Value::String(hex::encode(&self.id)));
+        map.insert("id".to_string(), Value::String(self.id.clone()));
         map.insert(
             "adapter_id".to_string(),
-            Value::String(hex::encode(&self.adapter_id)),
+            Value::String(self.adapter_id.clone()),
         );
         map.insert(
             "adapter_type".to_string(),
             Value::String(format!("{:?}", self.adapter_type)),
         );
+        map.insert("enabled".to_string(), Value::Bool(self.enabled));
         map
     }
 }
@@ -134,11 +507,11 @@ pub struct ModifyWorkflow {
 
 impl Tokenize for ModifyWorkflow {
     fn into_tokens(self) -> Vec<Token> {
-        vec![
-            Token::String(self.id),
+        vec![Token::Tuple(vec![
+            id_token(self.id),
             Token::String(self.metadata),
             Token::Bool(self.encrypted),
-        ]
+        ])]
     }
 }
 
@@ -165,6 +538,7 @@ impl Workflow {
                 description,
                 context_tool,
                 history_tool,
+                enabled: true,
             },
         );
         self
@@ -194,130 +568,646 @@ impl Workflow {
                 description,
                 context_tool,
                 history_tool,
+                enabled: true,
             },
         );
         self
     }
 
-    pub async fn remove(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
-        if self.nibble_context.contracts.len() < 1 {
-            return Err("No contracts found. Load or create a Nibble.".into());
-        }
+    pub fn add_agent_node(
+        &mut self,
+        name: &str,
+        repetitions: Option<u32>,
+        context: Option<Value>,
+        description: Option<String>,
+        context_tool: Option<ContextParse>,
+        history_tool: Option<HistoryParse>,
+    ) -> Result<&mut Self, NpcError> {
+        let adapter_id = self.resolve_adapter_id(Adapter::Agent, name)?;
+        Ok(self.add_node(
+            adapter_id,
+            NodeAdapter::Agent,
+            repetitions,
+            context,
+            description,
+            context_tool,
+            history_tool,
+        ))
+    }
 
-        let client = SignerMiddleware::new(
-            self.nibble_context.provider.clone(),
-            self.nibble_context
-                .owner_wallet
-                .clone()
-                .with_chain_id(self.nibble_context.chain),
-        );
-        let client = Arc::new(client);
+    pub fn add_tool_agent_node(
+        &mut self,
+        name: &str,
+        repetitions: Option<u32>,
+        context: Option<Value>,
+        description: Option<String>,
+        context_tool: Option<ContextParse>,
+        history_tool: Option<HistoryParse>,
+    ) -> Result<&mut Self, NpcError> {
+        let adapter_id = self.resolve_adapter_id(Adapter::Agent, name)?;
+        Ok(self.add_node(
+            adapter_id,
+            NodeAdapter::ToolAgent,
+            repetitions,
+            context,
+            description,
+            context_tool,
+            history_tool,
+        ))
+    }
 
-        let storage_contract_address = self
-            .nibble_context
-            .contracts
-            .iter()
-            .find(|c| c.name == "NibbleStorage")
-            .ok_or("NibbleStorage contract not found")?
-            .address;
+    pub fn add_onchain_connector_node(
+        &mut self,
+        name: &str,
+        repetitions: Option<u32>,
+        context: Option<Value>,
+        description: Option<String>,
+        context_tool: Option<ContextParse>,
+        history_tool: Option<HistoryParse>,
+    ) -> Result<&mut Self, NpcError> {
+        let adapter_id = self.resolve_adapter_id(Adapter::OnChainConnector, name)?;
+        Ok(self.add_node(
+            adapter_id,
+            NodeAdapter::OnChainConnector,
+            repetitions,
+            context,
+            description,
+            context_tool,
+            history_tool,
+        ))
+    }
 
-        let mut abi_file = File::open(Path::new("./abis/NibbleStorage.json"))?;
-        let mut abi_content = String::new();
-        abi_file.read_to_string(&mut abi_content)?;
-        let abi = serde_json::from_str::<Abi>(&abi_content)?;
-        let contract_instance = Contract::new(storage_contract_address, abi, client.clone());
+    pub fn add_offchain_connector_node(
+        &mut self,
+        name: &str,
+        repetitions: Option<u32>,
+        context: Option<Value>,
+        description: Option<String>,
+        context_tool: Option<ContextParse>,
+        history_tool: Option<HistoryParse>,
+    ) -> Result<&mut Self, NpcError> {
+        let adapter_id = self.resolve_adapter_id(Adapter::OffChainConnector, name)?;
+        Ok(self.add_node(
+            adapter_id,
+            NodeAdapter::OffChainConnector,
+            repetitions,
+            context,
+            description,
+            context_tool,
+            history_tool,
+        ))
+    }
 
-        let method = contract_instance.method::<_, H256>("removeWorkflow", self.id.clone());
+    pub fn add_delay_node(&mut self, delay: Duration, description: Option<String>) -> &mut Self {
+        self.add_node(
+            "delay".to_string(),
+            NodeAdapter::Delay,
+            None,
+            Some(json!({ "delay_ms": delay.as_millis() as u64 })),
+            description,
+            None,
+            None,
+        )
+    }
 
-        match method {
-            Ok(call) => {
-                let FunctionCall { tx, .. } = call;
+    pub fn add_condition_link(
+        &mut self,
+        name: &str,
+        repetitions: Option<u32>,
+        context: Option<Value>,
+        target: Option<LinkTarget>,
+        description: Option<String>,
+        context_tool: Option<ContextParse>,
+        history_tool: Option<HistoryParse>,
+    ) -> Result<&mut Self, NpcError> {
+        let adapter_id = self.resolve_adapter_id(Adapter::Condition, name)?;
+        Ok(self.add_link(
+            adapter_id,
+            LinkAdapter::Condition,
+            repetitions,
+            context,
+            target,
+            description,
+            context_tool,
+            history_tool,
+        ))
+    }
 
-                if let Some(tx_request) = tx.as_eip1559_ref() {
-                    let gas_price = U256::from(500_000_000_000u64);
-                    let max_priority_fee = U256::from(25_000_000_000u64);
-                    let gas_limit = U256::from(300_000);
+    pub fn add_listener_link(
+        &mut self,
+        name: &str,
+        repetitions: Option<u32>,
+        context: Option<Value>,
+        target: Option<LinkTarget>,
+        description: Option<String>,
+        context_tool: Option<ContextParse>,
+        history_tool: Option<HistoryParse>,
+    ) -> Result<&mut Self, NpcError> {
+        let adapter_id = self.resolve_adapter_id(Adapter::Listener, name)?;
+        Ok(self.add_link(
+            adapter_id,
+            LinkAdapter::Listener,
+            repetitions,
+            context,
+            target,
+            description,
+            context_tool,
+            history_tool,
+        ))
+    }
 
-                    let cliente = contract_instance.client().clone();
-                    let req = Eip1559TransactionRequest {
-                        from: Some(client.address()),
-                        to: Some(NameOrAddress::Address(storage_contract_address)),
-                        gas: Some(gas_limit),
-                        value: tx_request.value,
-                        data: tx_request.data.clone(),
-                        max_priority_fee_per_gas: Some(max_priority_fee),
-                        max_fee_per_gas: Some(gas_price + max_priority_fee),
-                        chain_id: Some(Chain::PolygonAmoy.into()),
-                        ..Default::default()
-                    };
+    pub fn add_fhe_gate_link(
+        &mut self,
+        name: &str,
+        repetitions: Option<u32>,
+        context: Option<Value>,
+        target: Option<LinkTarget>,
+        description: Option<String>,
+        context_tool: Option<ContextParse>,
+        history_tool: Option<HistoryParse>,
+    ) -> Result<&mut Self, NpcError> {
+        let adapter_id = self.resolve_adapter_id(Adapter::FHEGate, name)?;
+        Ok(self.add_link(
+            adapter_id,
+            LinkAdapter::FHEGate,
+            repetitions,
+            context,
+            target,
+            description,
+            context_tool,
+            history_tool,
+        ))
+    }
 
-                    let pending_tx = cliente.send_transaction(req, None).await.map_err(|e| {
-                        eprintln!("Error sending the transaction: {:?}", e);
-                        Box::<dyn Error + Send + Sync>::from(format!(
-                            "Error sending the transaction: {}",
-                            e
-                        ))
-                    })?;
+    pub fn add_evaluation_link(
+        &mut self,
+        name: &str,
+        repetitions: Option<u32>,
+        context: Option<Value>,
+        target: Option<LinkTarget>,
+        description: Option<String>,
+        context_tool: Option<ContextParse>,
+        history_tool: Option<HistoryParse>,
+    ) -> Result<&mut Self, NpcError> {
+        let adapter_id = self.resolve_adapter_id(Adapter::Evaluation, name)?;
+        Ok(self.add_link(
+            adapter_id,
+            LinkAdapter::Evaluation,
+            repetitions,
+            context,
+            target,
+            description,
+            context_tool,
+            history_tool,
+        ))
+    }
 
-                    match pending_tx.await {
-                        Ok(Some(receipt)) => receipt,
-                        Ok(None) => {
-                            return Err("Transaction not recieved".into());
-                        }
-                        Err(e) => {
-                            eprintln!("Error with the transaction: {:?}", e);
-                            return Err(e.into());
-                        }
-                    };
-                } else {
-                    return Err("EIP-1559 reference invalid.".into());
-                }
-            }
-            Err(e) => {
-                eprintln!(
-                    "Error while preparing the method of addOrModifyAdaptersBatch: {}",
-                    e
-                );
-                return Err(e.into());
-            }
+    fn resolve_adapter_id(&self, adapter: Adapter, name: &str) -> Result<String, NpcError> {
+        let nibble = &self.nibble_context;
+        match adapter {
+            Adapter::Agent => nibble.find_agent(name).map(|agent| agent.id.clone()),
+            Adapter::Condition => nibble
+                .find_condition(name)
+                .map(|condition| condition.id.clone()),
+            Adapter::Listener => nibble
+                .find_listener(name)
+                .map(|listener| listener.id.clone()),
+            Adapter::FHEGate => nibble.find_fhe_gate(name).map(|gate| gate.id.clone()),
+            Adapter::Evaluation => nibble
+                .find_evaluation(name)
+                .map(|evaluation| evaluation.id.clone()),
+            Adapter::OnChainConnector => nibble
+                .find_onchain_connector(name)
+                .map(|connector| connector.id.clone()),
+            Adapter::OffChainConnector => nibble
+                .find_offchain_connector(name)
+                .map(|connector| connector.id.clone()),
         }
+        .ok_or_else(|| {
+            NpcError::validation(format!(
+                "No {} adapter named {} found in the nibble",
+                adapter, name
+            ))
+        })
+    }
 
-        self.nodes.clear();
-        self.links.clear();
-        self.id = generate_unique_id(&self.nibble_context.owner_wallet.address());
+    pub fn set_enabled(&mut self, element_id: &str, enabled: bool) -> &mut Self {
+        if let Some(node) = self.nodes.get_mut(element_id) {
+            node.enabled = enabled;
+        } else if let Some(link) = self.links.get_mut(element_id) {
+            link.enabled = enabled;
+        }
+        self
+    }
 
-        Ok(())
+    fn element_enabled(&self, element_id: &str) -> bool {
+        match self.nodes.get(element_id) {
+            Some(node) => node.enabled,
+            None => self.links.get(element_id).is_none_or(|link| link.enabled),
+        }
     }
 
-    pub async fn persist(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
-        let client = SignerMiddleware::new(
-            self.nibble_context.provider.clone(),
-            self.nibble_context
-                .owner_wallet
-                .clone()
-                .with_chain_id(self.nibble_context.chain),
-        );
-        let client = Arc::new(client);
+    pub fn to_dot(&self) -> String {
+        let mut dot = format!("digraph \"{}\" {{\n", self.name.replace('"', "\\\""));
 
-        let storage_contract_address = self
-            .nibble_context
+        let mut nodes: Vec<&WorkflowNode> = self.nodes.values().collect();
+        nodes.sort_by(|a, b| a.id.cmp(&b.id));
+        for node in nodes {
+            let label = match &node.adapter_type {
+                NodeAdapter::SubFlow { subflow, .. } => format!("SubFlow\\n{}", subflow.name),
+                adapter_type => format!("{:?}\\n{}", adapter_type, node.adapter_id),
+            };
+            dot.push_str(&format!(
+                "  \"{}\" [shape=box, label=\"{}\"{}];\n",
+                node.id,
+                label,
+                dot_disabled_style(node.enabled)
+            ));
+        }
+
+        let mut links: Vec<&WorkflowLink> = self.links.values().collect();
+        links.sort_by(|a, b| a.id.cmp(&b.id));
+        for link in links {
+            dot.push_str(&format!(
+                "  \"{}\" [shape=diamond, label=\"{:?}\\n{}\"{}];\n",
+                link.id,
+                link.adapter_type,
+                link.adapter_id,
+                dot_disabled_style(link.enabled)
+            ));
+
+            if let Some(target) = &link.target {
+                let mut edges = vec![
+                    ("true".to_string(), &target.true_target_id),
+                    ("false".to_string(), &target.false_target_id),
+                ];
+                if let Some(generated_target_id) = &target.generated_target_id {
+                    edges.push(("generated".to_string(), generated_target_id));
+                }
+                edges.extend(target.score_targets.iter().map(|(range, target_id)| {
+                    (format!("{}..={}", range.start(), range.end()), target_id)
+                }));
+
+                for (label, target_id) in edges {
+                    dot.push_str(&format!(
+                        "  \"{}\" -> \"{}\" [label=\"{}\"{}];\n",
+                        link.id,
+                        target_id,
+                        label,
+                        if link.enabled { "" } else { ", color=grey" }
+                    ));
+                }
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub fn execution_summary(&self) -> ExecutionSummary {
+        let mut summary = ExecutionSummary {
+            total_elements: self.execution_history.len(),
+            ..Default::default()
+        };
+
+        for entry in &self.execution_history {
+            let outcomes = summary
+                .by_adapter
+                .entry(entry.element_type.clone())
+                .or_default();
+            if entry.result.is_some() && entry.error.is_none() {
+                outcomes.successes += 1;
+            } else {
+                outcomes.failures += 1;
+            }
+        }
+
+        let timestamps = self.execution_history.iter().map(|entry| entry.timestamp);
+        if let (Some(first), Some(last)) = (timestamps.clone().min(), timestamps.max()) {
+            summary.total_duration = (last - first).to_std().ok();
+        }
+
+        summary.slowest_node = self
+            .execution_history
+            .iter()
+            .filter(|entry| self.nodes.contains_key(&entry.element_id))
+            .filter_map(|entry| entry.duration.map(|duration| (entry, duration)))
+            .max_by_key(|(_, duration)| *duration)
+            .map(|(entry, duration)| SlowestElement {
+                element_id: entry.element_id.clone(),
+                element_type: entry.element_type.clone(),
+                duration,
+            });
+
+        summary
+    }
+
+    pub fn diff(&self, other: &Workflow) -> WorkflowDiff {
+        let (added_nodes, removed_nodes, modified_nodes) =
+            diff_elements(self.node_snapshots(), other.node_snapshots());
+        let (added_links, removed_links, modified_links) =
+            diff_elements(self.link_snapshots(), other.link_snapshots());
+
+        WorkflowDiff {
+            added_nodes,
+            removed_nodes,
+            modified_nodes,
+            added_links,
+            removed_links,
+            modified_links,
+        }
+    }
+
+    fn node_signature(&self, node_id: &str) -> String {
+        match self.nodes.get(node_id) {
+            Some(node) => format!("{}:{}", node_kind(&node.adapter_type), node.adapter_id),
+            None => node_id.to_string(),
+        }
+    }
+
+    fn node_snapshots(&self) -> Vec<(String, Value)> {
+        self.nodes
+            .values()
+            .map(|node| {
+                let signature = self.node_signature(&node.id);
+                let snapshot = json!({
+                    "signature": signature,
+                    "context": node.context,
+                    "repetitions": node.repetitions,
+                    "description": node.description,
+                    "enabled": node.enabled,
+                });
+                (signature, snapshot)
+            })
+            .collect()
+    }
+
+    fn link_snapshots(&self) -> Vec<(String, Value)> {
+        self.links
+            .values()
+            .map(|link| {
+                let signature = format!("{:?}:{}", link.adapter_type, link.adapter_id);
+                let target = link.target.as_ref().map(|target| {
+                    json!({
+                        "true": self.node_signature(&target.true_target_id),
+                        "false": self.node_signature(&target.false_target_id),
+                        "generated": target
+                            .generated_target_id
+                            .as_ref()
+                            .map(|target_id| self.node_signature(target_id)),
+                        "scores": target
+                            .score_targets
+                            .iter()
+                            .map(|(range, target_id)| {
+                                json!([range.start(), range.end(), self.node_signature(target_id)])
+                            })
+                            .collect::<Vec<_>>(),
+                        "loops": target
+                            .loop_limits
+                            .iter()
+                            .map(|(target_id, limit)| (self.node_signature(target_id), json!(limit)))
+                            .collect::<Map<String, Value>>(),
+                    })
+                });
+                let snapshot = json!({
+                    "signature": signature,
+                    "context": link.context,
+                    "repetitions": link.repetitions,
+                    "description": link.description,
+                    "enabled": link.enabled,
+                    "target": target,
+                });
+                (signature, snapshot)
+            })
+            .collect()
+    }
+
+    pub fn add_context_middleware(&mut self, middleware: ContextMiddleware) -> &mut Self {
+        self.context_middleware.push(middleware);
+        self
+    }
+
+    pub fn set_repetition_retry(&mut self, retries: Option<u32>) -> &mut Self {
+        self.repetition_retry = retries;
+        self
+    }
+
+    pub fn set_checkpoint_config(&mut self, config: Option<CheckpointConfig>) -> &mut Self {
+        self.checkpoint_config = config;
+        self
+    }
+
+    pub fn set_error_policy(&mut self, policy: ErrorPolicy) -> &mut Self {
+        self.error_policy = policy;
+        self
+    }
+
+    pub fn set_runtime_budget(&mut self, budget: Option<Duration>) -> &mut Self {
+        self.runtime_budget = budget;
+        self
+    }
+
+    pub fn set_history_sink(&mut self, sink: Option<Arc<dyn HistorySink>>) -> &mut Self {
+        self.history_sink = sink;
+        self
+    }
+
+    /// On-chain conditions sharing a topological layer are read through a single
+    /// Multicall3 `aggregate3` call when a batcher is set.
+    pub fn set_condition_batcher(&mut self, batcher: Option<Arc<ConditionBatcher>>) -> &mut Self {
+        self.condition_batcher = batcher;
+        self
+    }
+
+    pub fn set_nibble_context(&mut self, nibble_context: Arc<Nibble>) -> &mut Self {
+        self.nibble_context = nibble_context;
+        self
+    }
+
+    pub fn set_llm_prices(&mut self, prices: HashMap<String, f64>) -> &mut Self {
+        self.llm_prices = prices;
+        self
+    }
+
+    pub fn set_llm_cache(
+        &mut self,
+        cache: Option<Arc<dyn LlmCache>>,
+        force_cache: bool,
+    ) -> &mut Self {
+        self.llm_cache = cache;
+        self.force_llm_cache = force_cache;
+        self
+    }
+
+    pub fn verify_adapters(&self) -> AdapterVerification {
+        let mut verification = AdapterVerification::default();
+        let adapter_ids = self
+            .nodes
+            .values()
+            .filter(|node| {
+                !matches!(
+                    node.adapter_type,
+                    NodeAdapter::SubFlow { .. } | NodeAdapter::Custom(_) | NodeAdapter::Delay
+                )
+            })
+            .map(|node| &node.adapter_id)
+            .chain(self.links.values().map(|link| &link.adapter_id));
+
+        for adapter_id in adapter_ids {
+            if self.nibble_context.adapter_is_saved(adapter_id) {
+                continue;
+            }
+            if self.nibble_context.adapter_exists_locally(adapter_id) {
+                verification.unpersisted.push(adapter_id.clone());
+            } else {
+                verification.missing.push(adapter_id.clone());
+            }
+        }
+
+        for node in self.nodes.values() {
+            if let NodeAdapter::SubFlow { subflow, .. } = &node.adapter_type {
+                let subflow_verification = subflow.verify_adapters();
+                verification.missing.extend(subflow_verification.missing);
+                verification
+                    .unpersisted
+                    .extend(subflow_verification.unpersisted);
+            }
+        }
+
+        verification.missing.sort();
+        verification.missing.dedup();
+        verification.unpersisted.sort();
+        verification.unpersisted.dedup();
+        verification
+    }
+
+    pub fn estimate_llm_cost(&self) -> Result<CostEstimate, Box<dyn Error + Send + Sync>> {
+        let mut estimate = CostEstimate::default();
+        self.collect_llm_costs(&self.llm_prices, &mut estimate)?;
+        estimate.total = estimate.nodes.iter().map(|node| node.cost).sum();
+        Ok(estimate)
+    }
+
+    fn collect_llm_costs(
+        &self,
+        prices: &HashMap<String, f64>,
+        estimate: &mut CostEstimate,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        for node in self.nodes.values() {
+            match &node.adapter_type {
+                NodeAdapter::Agent | NodeAdapter::ToolAgent => {
+                    let agent = self
+                        .nibble_context
+                        .agents
+                        .iter()
+                        .chain(self.nibble_context.saved_agents.iter())
+                        .find(|agent| agent.id == node.adapter_id)
+                        .ok_or_else(|| format!("Agent not found for ID: {:?}", node.adapter_id))?;
+
+                    let model = agent.model.model_name().unwrap_or_default().to_string();
+                    let max_tokens = agent.model.max_tokens().ok_or_else(|| {
+                        format!("No max token limit declared for model {:?}", model)
+                    })?;
+                    let price = agent
+                        .model
+                        .price_per_1k_tokens(prices)
+                        .ok_or_else(|| format!("No price configured for model {:?}", model))?;
+
+                    estimate.nodes.push(NodeCostEstimate {
+                        node_id: node.id.clone(),
+                        agent_id: agent.id.clone(),
+                        model,
+                        max_tokens,
+                        cost: price * max_tokens as f64 / 1000.0,
+                    });
+                }
+                NodeAdapter::SubFlow { subflow, .. } => {
+                    subflow.collect_llm_costs(prices, estimate)?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn enable_simulation(&mut self, simulation: Simulation) -> &mut Self {
+        for node in self.nodes.values_mut() {
+            if let NodeAdapter::SubFlow { subflow, .. } = &mut node.adapter_type {
+                subflow.enable_simulation(simulation.clone());
+            }
+        }
+        self.checkpoint_config = None;
+        self.simulation = Some(simulation);
+        self
+    }
+
+    pub async fn checkpoint(
+        &self,
+        state: &WorkflowState,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let mut data = serde_json::to_vec(state)?;
+
+        if self.encrypted {
+            data = encrypt_with_public_key(data, self.nibble_context.owner_wallet.clone())?;
+        }
+
+        self.nibble_context.ipfs_client.upload(data).await
+    }
+
+    pub async fn restore(
+        &mut self,
+        cid: &str,
+    ) -> Result<WorkflowState, Box<dyn Error + Send + Sync>> {
+        let data = self.nibble_context.ipfs_client.fetch(cid).await?;
+
+        let state: WorkflowState = if self.encrypted {
+            serde_json::from_value(decrypt_with_private_key(
+                data,
+                self.nibble_context.owner_wallet.clone(),
+            )?)?
+        } else {
+            serde_json::from_slice(&data)?
+        };
+
+        if state.workflow_id != self.id {
+            return Err(format!(
+                "Checkpoint {} belongs to workflow {}, not {}",
+                cid, state.workflow_id, self.id
+            )
+            .into());
+        }
+
+        self.execution_history = state.execution_history.clone();
+        Ok(state)
+    }
+
+    pub async fn remove(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self.nibble_context.contracts.is_empty() {
+            return Err("No contracts found. Load or create a Nibble.".into());
+        }
+
+        let client = SignerMiddleware::new(
+            self.nibble_context.provider.clone(),
+            self.nibble_context
+                .owner_wallet
+                .clone()
+                .with_chain_id(self.nibble_context.chain),
+        );
+        let client = Arc::new(client);
+
+        let storage_contract_address = self
+            .nibble_context
             .contracts
             .iter()
             .find(|c| c.name == "NibbleStorage")
             .ok_or("NibbleStorage contract not found")?
             .address;
 
-        let mut abi_file = File::open(Path::new("./abis/NibbleStorage.json"))?;
-        let mut abi_content = String::new();
-        abi_file.read_to_string(&mut abi_content)?;
-        let abi = serde_json::from_str::<Abi>(&abi_content)?;
+        let abi = serde_json::from_str::<Abi>(NIBBLE_STORAGE_ABI)?;
         let contract_instance = Contract::new(storage_contract_address, abi, client.clone());
 
-        let workflow = self
-            .build_workflow(self.nibble_context.ipfs_client.as_ref())
-            .await?;
-
-        let method = contract_instance.method::<_, H256>("addOrModifyWorkflow", workflow);
+        let method = contract_instance.method::<_, H256>("removeWorkflow", self.id.clone());
 
         match method {
             Ok(call) => {
@@ -325,7 +1215,13 @@ impl Workflow {
 
                 if let Some(tx_request) = tx.as_eip1559_ref() {
                     let gas_price = U256::from(500_000_000_000u64);
-                    let max_priority_fee = U256::from(25_000_000_000u64);
+                    let (max_fee_per_gas, max_priority_fee) = self
+                        .nibble_context
+                        .resolve_fees(
+                            gas_price + U256::from(25_000_000_000u64),
+                            U256::from(25_000_000_000u64),
+                        )
+                        .await;
                     let gas_limit = U256::from(300_000);
 
                     let cliente = contract_instance.client().clone();
@@ -336,11 +1232,12 @@ impl Workflow {
                         value: tx_request.value,
                         data: tx_request.data.clone(),
                         max_priority_fee_per_gas: Some(max_priority_fee),
-                        max_fee_per_gas: Some(gas_price + max_priority_fee),
+                        max_fee_per_gas: Some(max_fee_per_gas),
                         chain_id: Some(Chain::PolygonAmoy.into()),
                         ..Default::default()
                     };
 
+                    let req = self.nibble_context.build_transaction(req).await;
                     let pending_tx = cliente.send_transaction(req, None).await.map_err(|e| {
                         eprintln!("Error sending the transaction: {:?}", e);
                         Box::<dyn Error + Send + Sync>::from(format!(
@@ -349,7 +1246,10 @@ impl Workflow {
                         ))
                     })?;
 
-                    match pending_tx.await {
+                    match pending_tx
+                        .confirmations(self.nibble_context.confirmations)
+                        .await
+                    {
                         Ok(Some(receipt)) => receipt,
                         Ok(None) => {
                             return Err("Transaction not recieved".into());
@@ -372,52 +1272,320 @@ impl Workflow {
             }
         }
 
+        self.nodes.clear();
+        self.links.clear();
+        self.id = generate_unique_id(&self.nibble_context.owner_wallet.address());
+
         Ok(())
     }
 
+    pub async fn persist(&self, idempotency_key: Option<&str>) -> Result<(), NpcError> {
+        if let Some(key) = idempotency_key {
+            if self.nibble_context.idempotency_key_landed(key).await? {
+                println!(
+                    "Workflow {} already persisted with idempotency key {}",
+                    self.id, key
+                );
+                return Ok(());
+            }
+        }
+
+        let client = SignerMiddleware::new(
+            self.nibble_context.provider.clone(),
+            self.nibble_context
+                .owner_wallet
+                .clone()
+                .with_chain_id(self.nibble_context.chain),
+        );
+        let client = Arc::new(client);
+
+        let storage_contract_address = self
+            .nibble_context
+            .contracts
+            .iter()
+            .find(|c| c.name == "NibbleStorage")
+            .ok_or_else(|| NpcError::validation("NibbleStorage contract not found"))?
+            .address;
+
+        let abi = serde_json::from_str::<Abi>(NIBBLE_STORAGE_ABI)?;
+        let contract_instance = Contract::new(storage_contract_address, abi, client.clone());
+
+        let workflow = self
+            .build_workflow(self.nibble_context.ipfs_client.as_ref())
+            .await?;
+
+        let method = match idempotency_key {
+            Some(key) => contract_instance.method::<_, H256>(
+                "addOrModifyWorkflowWithKey",
+                [
+                    workflow.into_tokens(),
+                    vec![Token::FixedBytes(idempotency_key_hash(key).to_vec())],
+                ]
+                .concat()
+                .as_slice(),
+            ),
+            None => contract_instance.method::<_, H256>("addOrModifyWorkflow", workflow),
+        };
+
+        match method {
+            Ok(call) => {
+                let FunctionCall { tx, .. } = call;
+
+                if let Some(tx_request) = tx.as_eip1559_ref() {
+                    let gas_price = U256::from(500_000_000_000u64);
+                    let (max_fee_per_gas, max_priority_fee) = self
+                        .nibble_context
+                        .resolve_fees(
+                            gas_price + U256::from(25_000_000_000u64),
+                            U256::from(25_000_000_000u64),
+                        )
+                        .await;
+                    let gas_limit = U256::from(300_000);
+
+                    let cliente = contract_instance.client().clone();
+                    let req = Eip1559TransactionRequest {
+                        from: Some(client.address()),
+                        to: Some(NameOrAddress::Address(storage_contract_address)),
+                        gas: Some(gas_limit),
+                        value: tx_request.value,
+                        data: tx_request.data.clone(),
+                        max_priority_fee_per_gas: Some(max_priority_fee),
+                        max_fee_per_gas: Some(max_fee_per_gas),
+                        chain_id: Some(Chain::PolygonAmoy.into()),
+                        ..Default::default()
+                    };
+
+                    let req = self.nibble_context.build_transaction(req).await;
+                    let pending_tx = cliente.send_transaction(req, None).await.map_err(|e| {
+                        eprintln!("Error sending the transaction: {:?}", e);
+                        NpcError::Rpc(format!("Error sending the transaction: {}", e))
+                    })?;
+
+                    match pending_tx
+                        .confirmations(self.nibble_context.confirmations)
+                        .await
+                    {
+                        Ok(Some(receipt)) if receipt.status == Some(1.into()) => receipt,
+                        Ok(Some(receipt)) => {
+                            eprintln!("Error with the transaction: {:?}", receipt.status);
+                            return Err(NpcError::rpc("addOrModifyWorkflow transaction reverted"));
+                        }
+                        Ok(None) => {
+                            return Err(NpcError::rpc("Transaction not recieved"));
+                        }
+                        Err(e) => {
+                            eprintln!("Error with the transaction: {:?}", e);
+                            return Err(NpcError::rpc(e));
+                        }
+                    };
+                } else {
+                    return Err(NpcError::validation("EIP-1559 reference invalid."));
+                }
+            }
+            Err(e) => {
+                eprintln!(
+                    "Error while preparing the method of addOrModifyAdaptersBatch: {}",
+                    e
+                );
+                return Err(NpcError::validation(e));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn wait_until_indexed(&self, timeout: Duration) -> Result<(), NpcError> {
+        self.nibble_context
+            .wait_for_subgraph("workflows", &self.id, timeout)
+            .await
+    }
+
     pub async fn execute(
         &mut self,
         repetitions: Option<u32>,
         count_successes: bool,
-    ) -> Result<Vec<ExecutionHistory>, Box<dyn Error>> {
+    ) -> Result<Vec<ExecutionHistory>, NpcError> {
+        self.run_repetitions(count_successes, HashMap::new(), |successful, total, _| {
+            repetitions.is_none_or(|r| {
+                if count_successes {
+                    successful < r
+                } else {
+                    total < r
+                }
+            })
+        })
+        .await
+    }
+
+    /// Runs the workflow once, then re-runs it for as long as `predicate` returns true for the
+    /// accumulated execution history. Stops after `max_iterations` runs (defaults to
+    /// `DEFAULT_MAX_WHILE_ITERATIONS`) even if the predicate still holds.
+    pub async fn execute_while(
+        &mut self,
+        predicate: impl Fn(&[ExecutionHistory]) -> bool,
+        max_iterations: Option<u32>,
+    ) -> Result<Vec<ExecutionHistory>, NpcError> {
+        let max_iterations = max_iterations.unwrap_or(DEFAULT_MAX_WHILE_ITERATIONS);
+        let workflow_id = self.id.clone();
+        self.run_repetitions(false, HashMap::new(), |_, total, history| {
+            if total == 0 {
+                return true;
+            }
+            if !predicate(history) {
+                return false;
+            }
+            if total >= max_iterations {
+                println!(
+                    "Workflow {} reached the maximum of {} iteration(s) while the predicate still held",
+                    workflow_id, max_iterations
+                );
+                return false;
+            }
+            true
+        })
+        .await
+    }
+
+    /// Seeds `execution_history` with `history` from a crashed run and executes one repetition
+    /// that skips every element already done. An element counts as done when its most recent
+    /// history entry has a result and no error; error policy entries are ignored. Skipped
+    /// elements are not re-executed, and execution continues from the first incomplete element
+    /// in topological order with the last recorded result of the preceding done element as
+    /// its context.
+    pub async fn resume(
+        &mut self,
+        history: Vec<ExecutionHistory>,
+    ) -> Result<Vec<ExecutionHistory>, NpcError> {
+        self.execution_history = history;
+        let completed = self.completed_elements();
+        println!(
+            "Resuming workflow {} with {} completed element(s)",
+            self.id,
+            completed.len()
+        );
+        self.run_repetitions(false, completed, |_, total, _| total < 1)
+            .await
+    }
+
+    fn completed_elements(&self) -> HashMap<String, Option<Value>> {
+        let mut completed = HashMap::new();
+        for entry in self.execution_history.iter().filter(|entry| {
+            entry.element_type != "ErrorPolicy"
+                && (self.nodes.contains_key(&entry.element_id)
+                    || self.links.contains_key(&entry.element_id))
+        }) {
+            if entry.error.is_none() && entry.result.is_some() {
+                completed.insert(entry.element_id.clone(), entry.result.clone());
+            } else {
+                completed.remove(&entry.element_id);
+            }
+        }
+        completed
+    }
+
+    async fn run_repetitions(
+        &mut self,
+        count_successes: bool,
+        completed: HashMap<String, Option<Value>>,
+        should_continue: impl FnMut(u32, u32, &[ExecutionHistory]) -> bool,
+    ) -> Result<Vec<ExecutionHistory>, NpcError> {
+        let span = TelemetrySpan::workflow(&self.id, &self.name);
+        let started = Instant::now();
+        let result = span
+            .scope(self.run_repetitions_in_span(count_successes, completed, should_continue))
+            .await;
+        span.finish(result.is_ok(), started.elapsed());
+        #[cfg(feature = "metrics")]
+        if result.is_ok() {
+            crate::metrics::record_workflow_executed();
+        }
+        result
+    }
+
+    async fn run_repetitions_in_span(
+        &mut self,
+        count_successes: bool,
+        mut completed: HashMap<String, Option<Value>>,
+        mut should_continue: impl FnMut(u32, u32, &[ExecutionHistory]) -> bool,
+    ) -> Result<Vec<ExecutionHistory>, NpcError> {
+        let _guard = ExecutionGuard::acquire(&self.executing).ok_or_else(|| {
+            NpcError::validation(format!("Workflow {} is already executing", self.id))
+        })?;
+
+        let verification = self.verify_adapters();
+        if !verification.missing.is_empty() {
+            return Err(NpcError::validation(format!(
+                "Workflow {} references adapters that were not found: {:?}",
+                self.id, verification.missing
+            )));
+        }
+        if !verification.unpersisted.is_empty() {
+            eprintln!(
+                "Workflow {} references adapters that have not been persisted: {:?}",
+                self.id, verification.unpersisted
+            );
+        }
+
         let mut successful_repeats = 0;
         let mut total_repeats = 0;
+        let mut checkpoint_clock = CheckpointClock::new();
+        let deadline = self.runtime_budget.map(|budget| Instant::now() + budget);
 
-        while repetitions.map_or(true, |r| {
-            if count_successes {
-                successful_repeats < r
-            } else {
-                total_repeats < r
+        while should_continue(successful_repeats, total_repeats, &self.execution_history) {
+            let history_start = self.execution_history.len();
+            let mut current_success = self
+                .execute_repetition(
+                    total_repeats + 1,
+                    successful_repeats,
+                    &mut checkpoint_clock,
+                    deadline,
+                    &completed,
+                )
+                .await?;
+            let mut retries = 0;
+
+            while !current_success
+                && retries < self.repetition_retry.unwrap_or(0)
+                && !budget_exhausted(deadline)
+            {
+                retries += 1;
+                println!(
+                    "Retrying workflow repetition: {} (attempt {})",
+                    total_repeats + 1,
+                    retries
+                );
+                self.execution_history.truncate(history_start);
+                current_success = self
+                    .execute_repetition(
+                        total_repeats + 1,
+                        successful_repeats,
+                        &mut checkpoint_clock,
+                        deadline,
+                        &completed,
+                    )
+                    .await?;
             }
-        }) {
-            println!("Executing workflow repetition: {}", total_repeats + 1);
-            let mut context_data = None;
-            let mut current_success = true;
-            let subflow_manager = SubflowManager::new();
-
-            for element_id in self.topological_sort()? {
-                if let Some(node) = self.nodes.get(&element_id) {
-                    context_data = self
-                        .process_node(&node.clone(), Some(&subflow_manager), context_data)
-                        .await?;
-
-                    if context_data.is_none() {
-                        println!("Execution stopped for repetition: {}", total_repeats + 1);
-                        current_success = false;
-                        break;
-                    }
-                } else if let Some(link) = self.links.get(&element_id) {
-                    context_data = self
-                        .process_link(&link.clone(), context_data, &mut current_success)
-                        .await?;
-
-                    if context_data.is_none() {
-                        println!("Execution stopped for repetition: {}", total_repeats + 1);
-                        break;
-                    }
-                }
+
+            if budget_exhausted(deadline) {
+                println!(
+                    "Workflow {} runtime budget exhausted after {} repetition(s)",
+                    self.id, total_repeats
+                );
+                self.execution_history.push(ExecutionHistory {
+                    element_id: self.id.clone(),
+                    element_type: "Workflow".to_string(),
+                    result: None,
+                    timestamp: chrono::Utc::now(),
+                    description: Some("Runtime budget exhausted".to_string()),
+                    error: None,
+                    branch: None,
+                    target_id: None,
+                    duration: None,
+                });
+                break;
             }
 
+            completed.clear();
             if current_success && count_successes {
                 successful_repeats += 1;
             }
@@ -432,6 +1600,372 @@ impl Workflow {
         Ok(self.execution_history.clone())
     }
 
+    /// Blocks on `execute` from synchronous code. Must not be called from within an async
+    /// context; returns a validation error if a tokio runtime is already running.
+    #[cfg(feature = "blocking")]
+    pub fn execute_blocking(
+        &mut self,
+        repetitions: Option<u32>,
+        count_successes: bool,
+    ) -> Result<Vec<ExecutionHistory>, NpcError> {
+        if tokio::runtime::Handle::try_current().is_ok() {
+            return Err(NpcError::validation(
+                "execute_blocking cannot be called from within an async runtime, use execute().await instead",
+            ));
+        }
+
+        tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?
+            .block_on(self.execute(repetitions, count_successes))
+    }
+
+    pub async fn execute_with_signer(
+        &mut self,
+        wallet: LocalWallet,
+        repetitions: Option<u32>,
+        count_successes: bool,
+    ) -> Result<Vec<ExecutionHistory>, NpcError> {
+        let previous = self.signer.replace(wallet);
+        let result = self.execute(repetitions, count_successes).await;
+        self.signer = previous;
+        result
+    }
+
+    async fn execute_repetition(
+        &mut self,
+        repetition: u32,
+        successful_repetitions: u32,
+        checkpoint_clock: &mut CheckpointClock,
+        deadline: Option<Instant>,
+        completed: &HashMap<String, Option<Value>>,
+    ) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        println!("Executing workflow repetition: {}", repetition);
+        let mut elements_completed = 0;
+        let mut context_data = None;
+        let mut current_success = true;
+        let subflow_manager = SubflowManager::new();
+        let mut branch_targets: HashSet<String> = HashSet::new();
+        let mut sink_cursor = self.execution_history.len();
+        let mut prefetched_layers: HashSet<usize> = HashSet::new();
+        let layers = match &self.condition_batcher {
+            Some(batcher) if self.simulation.is_none() => {
+                batcher.clear();
+                self.topological_layers()?
+            }
+            _ => HashMap::new(),
+        };
+        self.join_inputs.clear();
+
+        for element_id in self.topological_sort()? {
+            self.flush_history_sink(&mut sink_cursor).await;
+            if budget_exhausted(deadline) {
+                return Ok(false);
+            }
+            if !self.element_enabled(&element_id) {
+                println!("Skipping disabled element: {:?}", element_id);
+                continue;
+            }
+            if branch_targets.contains(&element_id)
+                || self.error_policy == ErrorPolicy::RouteToErrorNode(element_id.clone())
+            {
+                continue;
+            }
+            if let Some(result) = completed.get(&element_id) {
+                println!("Skipping completed element: {:?}", element_id);
+                if let Some(link) = self.links.get(&element_id) {
+                    self.extend_branch_targets(link, &mut branch_targets);
+                }
+                context_data = result.clone();
+                elements_completed += 1;
+                continue;
+            }
+
+            let input = context_data.clone();
+            if let Some(node) = self.nodes.get(&element_id).cloned() {
+                let node_input = if self.inbound_link_count(&element_id) > 1 {
+                    match self.join_inputs.remove(&element_id) {
+                        Some(inputs) => Some(Value::Object(inputs)),
+                        None => {
+                            println!("No inbound link routed to join node: {:?}", element_id);
+                            continue;
+                        }
+                    }
+                } else {
+                    context_data
+                };
+                context_data = self
+                    .process_node(&node, Some(&subflow_manager), node_input)
+                    .await?
+                    .map(|value| self.apply_context_middleware(value));
+                elements_completed += 1;
+                self.checkpoint_if_due(
+                    checkpoint_clock,
+                    repetition,
+                    successful_repetitions,
+                    elements_completed,
+                    &context_data,
+                )
+                .await;
+
+                if context_data.is_none() {
+                    current_success = false;
+                    if self
+                        .apply_error_policy(&element_id, input, &mut context_data, &subflow_manager)
+                        .await?
+                    {
+                        continue;
+                    }
+                    println!("Execution stopped for repetition: {}", repetition);
+                    break;
+                }
+            } else if let Some(link) = self.links.get(&element_id) {
+                let link = link.clone();
+                self.extend_branch_targets(&link, &mut branch_targets);
+                if let Some(&layer) = layers.get(&element_id) {
+                    if matches!(link.adapter_type, LinkAdapter::Condition)
+                        && prefetched_layers.insert(layer)
+                    {
+                        self.prefetch_condition_layer(layer, &layers, &context_data)
+                            .await;
+                    }
+                }
+
+                context_data = self
+                    .process_link(&link, context_data, &mut current_success)
+                    .await?
+                    .map(|value| self.apply_context_middleware(value));
+                elements_completed += 1;
+                self.checkpoint_if_due(
+                    checkpoint_clock,
+                    repetition,
+                    successful_repetitions,
+                    elements_completed,
+                    &context_data,
+                )
+                .await;
+
+                if context_data.is_none() {
+                    if !self.loop_limit_reached(&element_id)
+                        && self
+                            .apply_error_policy(
+                                &element_id,
+                                input,
+                                &mut context_data,
+                                &subflow_manager,
+                            )
+                            .await?
+                    {
+                        continue;
+                    }
+                    println!("Execution stopped for repetition: {}", repetition);
+                    break;
+                }
+            }
+        }
+
+        self.flush_history_sink(&mut sink_cursor).await;
+        Ok(current_success)
+    }
+
+    fn loop_limit_reached(&self, link_id: &str) -> bool {
+        self.execution_history.last().is_some_and(|entry| {
+            entry.element_id == link_id
+                && entry.description.as_deref() == Some("Loop limit reached")
+        })
+    }
+
+    fn extend_branch_targets(&self, link: &WorkflowLink, branch_targets: &mut HashSet<String>) {
+        if let Some(target) = &link.target {
+            branch_targets.extend(
+                target
+                    .target_ids()
+                    .into_iter()
+                    .filter(|target_id| {
+                        !target.is_loop_target(target_id) && self.inbound_link_count(target_id) <= 1
+                    })
+                    .cloned(),
+            );
+        }
+    }
+
+    async fn prefetch_condition_layer(
+        &self,
+        layer: usize,
+        layers: &HashMap<String, usize>,
+        context_data: &Option<Value>,
+    ) {
+        let batcher = match &self.condition_batcher {
+            Some(batcher) => batcher,
+            None => return,
+        };
+        batcher.clear();
+        let conditions: Vec<(&Condition, Option<Value>)> = self
+            .links
+            .values()
+            .filter(|link| {
+                link.enabled
+                    && matches!(link.adapter_type, LinkAdapter::Condition)
+                    && layers.get(&link.id) == Some(&layer)
+            })
+            .filter_map(|link| {
+                self.nibble_context
+                    .conditions
+                    .iter()
+                    .chain(self.nibble_context.saved_conditions.iter())
+                    .find(|condition| {
+                        condition.id == link.adapter_id
+                            && matches!(condition.condition_type, ConditionType::OnChain { .. })
+                    })
+                    .map(|condition| {
+                        let params = link.context.clone().or_else(|| {
+                            process_context_tool(
+                                link.context_tool.as_ref(),
+                                context_data.clone(),
+                                &link.id,
+                            )
+                            .0
+                        });
+                        (condition, params)
+                    })
+            })
+            .collect();
+        if conditions.len() < 2 {
+            return;
+        }
+
+        let conditions: Vec<(&Condition, Option<&Value>)> = conditions
+            .iter()
+            .map(|(condition, params)| (*condition, params.as_ref()))
+            .collect();
+        match batcher.prefetch(&self.nibble_context, &conditions).await {
+            Ok(resolved) => println!(
+                "Prefetched {} on-chain condition(s) for layer {} through Multicall3",
+                resolved, layer
+            ),
+            Err(e) => eprintln!("Failed to batch on-chain conditions: {:?}", e),
+        }
+    }
+
+    async fn flush_history_sink(&self, cursor: &mut usize) {
+        let sink = match &self.history_sink {
+            Some(sink) if self.simulation.is_none() => sink,
+            _ => return,
+        };
+        let start = (*cursor).min(self.execution_history.len());
+        let entries = &self.execution_history[start..];
+        if entries.is_empty() {
+            return;
+        }
+
+        if let Err(e) = sink.append(&self.id, entries).await {
+            eprintln!("Failed to append execution history to sink: {:?}", e);
+        }
+        *cursor = self.execution_history.len();
+    }
+
+    async fn apply_error_policy(
+        &mut self,
+        element_id: &str,
+        input: Option<Value>,
+        context_data: &mut Option<Value>,
+        subflow_manager: &SubflowManager,
+    ) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        let policy = self.error_policy.clone();
+        let error = self
+            .execution_history
+            .iter()
+            .rev()
+            .find(|entry| entry.element_id == element_id)
+            .and_then(|entry| entry.error.clone());
+        println!(
+            "Error policy {} fired for element: {:?}",
+            policy, element_id
+        );
+        self.execution_history.push(ExecutionHistory {
+            element_id: element_id.to_string(),
+            element_type: "ErrorPolicy".to_string(),
+            result: None,
+            timestamp: chrono::Utc::now(),
+            description: Some(policy.to_string()),
+            error: error.clone(),
+            branch: None,
+            target_id: match &policy {
+                ErrorPolicy::RouteToErrorNode(node_id) => Some(node_id.clone()),
+                _ => None,
+            },
+            duration: None,
+        });
+
+        match policy {
+            ErrorPolicy::StopWorkflow => Ok(false),
+            ErrorPolicy::SkipNode => {
+                *context_data = input;
+                Ok(true)
+            }
+            ErrorPolicy::RouteToErrorNode(node_id) => {
+                match self.nodes.get(&node_id).cloned() {
+                    Some(node) => {
+                        let failure = json!({
+                            "element_id": element_id,
+                            "error": error,
+                            "context": input,
+                        });
+                        self.process_node(&node, Some(subflow_manager), Some(failure))
+                            .await?;
+                    }
+                    None => eprintln!("Error node not found for ID: {:?}", node_id),
+                }
+                Ok(false)
+            }
+        }
+    }
+
+    async fn checkpoint_if_due(
+        &mut self,
+        clock: &mut CheckpointClock,
+        repetition: u32,
+        successful_repetitions: u32,
+        elements_completed: usize,
+        context: &Option<Value>,
+    ) {
+        let config = match &self.checkpoint_config {
+            Some(config) => config.clone(),
+            None => return,
+        };
+
+        clock.elements += 1;
+        let due_by_elements = config
+            .every_elements
+            .is_some_and(|every| every > 0 && clock.elements >= every);
+        let due_by_time = config
+            .every_seconds
+            .is_some_and(|every| clock.last.elapsed() >= Duration::from_secs(every));
+        if !due_by_elements && !due_by_time {
+            return;
+        }
+
+        let state = WorkflowState {
+            workflow_id: self.id.clone(),
+            repetition,
+            successful_repetitions,
+            elements_completed,
+            context: context.clone(),
+            execution_history: self.execution_history.clone(),
+        };
+
+        match self.checkpoint(&state).await {
+            Ok(cid) => {
+                println!("Workflow checkpoint persisted: {}", cid);
+                self.checkpoints.push(cid);
+            }
+            Err(e) => eprintln!("Failed to persist workflow checkpoint: {:?}", e),
+        }
+
+        clock.elements = 0;
+        clock.last = Instant::now();
+    }
+
     async fn build_workflow(
         &self,
         ipfs_client: &dyn IPFSClient,
@@ -478,6 +2012,21 @@ impl Workflow {
                             "timestamp".to_string(),
                             Value::String(entry.timestamp.to_rfc3339()),
                         );
+                        if let Some(error) = &entry.error {
+                            map.insert("error".to_string(), Value::String(error.clone()));
+                        }
+                        if let Some(branch) = &entry.branch {
+                            map.insert("branch".to_string(), Value::String(branch.clone()));
+                        }
+                        if let Some(target_id) = &entry.target_id {
+                            map.insert("target_id".to_string(), Value::String(target_id.clone()));
+                        }
+                        if let Some(duration) = entry.duration {
+                            map.insert(
+                                "duration_ms".to_string(),
+                                json!(duration.as_millis() as u64),
+                            );
+                        }
                         Value::Object(map)
                     })
                     .collect(),
@@ -487,10 +2036,7 @@ impl Workflow {
         let mut metadata = serde_json::to_vec(&metadata_map)?;
 
         if self.encrypted {
-            metadata = encrypt_with_public_key(metadata, self.nibble_context.owner_wallet.clone())
-                .map_err(|e| -> Box<dyn Error + Send + Sync> {
-                    Box::<dyn Error + Send + Sync>::from(e)
-                })?;
+            metadata = encrypt_with_public_key(metadata, self.nibble_context.owner_wallet.clone())?;
         }
         let ipfs_hash = ipfs_client.upload(metadata).await?;
 
@@ -502,23 +2048,11 @@ impl Workflow {
     }
 
     fn topological_sort(&self) -> Result<Vec<String>, String> {
-        let mut in_degree: HashMap<String, usize> = HashMap::new();
-        let mut graph: HashMap<String, Vec<String>> = HashMap::new();
+        let (mut in_degree, graph) = self.dependency_graph();
 
-        for node in self.nodes.values() {
-            in_degree.insert(node.id.clone(), 0);
-            graph.insert(node.id.clone(), Vec::new());
-        }
-
-        for link in self.links.values() {
-            graph
-                .entry(link.adapter_id.clone())
-                .or_default()
-                .push(link.id.clone());
-            *in_degree.entry(link.id.clone()).or_default() += 1;
-        }
-
-        let mut stack: Vec<String> = in_degree
+        // Ids lead with their creation timestamp, so taking the smallest ready id
+        // runs independent elements in the order they were added.
+        let mut ready: BTreeSet<String> = in_degree
             .iter()
             .filter(|(_, &deg)| deg == 0)
             .map(|(id, _)| id.clone())
@@ -526,14 +2060,14 @@ impl Workflow {
 
         let mut sorted: Vec<String> = Vec::new();
 
-        while let Some(current) = stack.pop() {
+        while let Some(current) = ready.pop_first() {
             sorted.push(current.clone());
             if let Some(neighbors) = graph.get(&current) {
                 for neighbor in neighbors {
                     if let Some(degree) = in_degree.get_mut(neighbor) {
                         *degree -= 1;
                         if *degree == 0 {
-                            stack.push(neighbor.clone());
+                            ready.insert(neighbor.clone());
                         }
                     }
                 }
@@ -547,38 +2081,190 @@ impl Workflow {
         Ok(sorted)
     }
 
+    fn topological_layers(&self) -> Result<HashMap<String, usize>, String> {
+        let (_, graph) = self.dependency_graph();
+        let mut layers: HashMap<String, usize> = HashMap::new();
+
+        for element_id in self.topological_sort()? {
+            let layer = *layers.entry(element_id.clone()).or_insert(0);
+            if let Some(neighbors) = graph.get(&element_id) {
+                for neighbor in neighbors {
+                    let neighbor_layer = layers.entry(neighbor.clone()).or_insert(0);
+                    *neighbor_layer = (*neighbor_layer).max(layer + 1);
+                }
+            }
+        }
+
+        Ok(layers)
+    }
+
+    fn dependency_graph(&self) -> (HashMap<String, usize>, HashMap<String, Vec<String>>) {
+        let mut in_degree: HashMap<String, usize> = HashMap::new();
+        let mut graph: HashMap<String, Vec<String>> = HashMap::new();
+
+        for node in self.nodes.values() {
+            in_degree.insert(node.id.clone(), 0);
+            graph.insert(node.id.clone(), Vec::new());
+        }
+
+        for link in self.links.values() {
+            in_degree.entry(link.id.clone()).or_insert(0);
+            graph.entry(link.id.clone()).or_default();
+
+            if let Some(target) = &link.target {
+                for target_id in target.target_ids() {
+                    if !self.nodes.contains_key(target_id) {
+                        continue;
+                    }
+
+                    let (from, to) = if target.is_loop_target(target_id) {
+                        (target_id, &link.id)
+                    } else {
+                        (&link.id, target_id)
+                    };
+                    let targets = graph.entry(from.clone()).or_default();
+                    if !targets.contains(to) {
+                        targets.push(to.clone());
+                        *in_degree.entry(to.clone()).or_default() += 1;
+                    }
+                }
+            }
+        }
+
+        (in_degree, graph)
+    }
+
+    fn apply_context_middleware(&self, context: Value) -> Value {
+        self.context_middleware
+            .iter()
+            .fold(context, |value, middleware| middleware(value))
+    }
+
     pub fn get_execution_history(&self) -> &Vec<ExecutionHistory> {
         &self.execution_history
     }
 
+    fn resolve_signer(&self, context: Option<&Value>) -> LocalWallet {
+        let default_wallet = self
+            .signer
+            .clone()
+            .unwrap_or_else(|| self.nibble_context.owner_wallet.clone());
+
+        if let Some(agent_wallet) = context.and_then(|context| context.get("agent_wallet")) {
+            match agent_wallet.as_str() {
+                Some(agent_ref) => self.agent_wallet(agent_ref).unwrap_or_else(|| {
+                    eprintln!(
+                        "Agent with ID {:?} not found, using default signer",
+                        agent_ref
+                    );
+                    default_wallet
+                }),
+                None => {
+                    eprintln!("Invalid agent_wallet context, using default signer");
+                    default_wallet
+                }
+            }
+        } else if let Some(custom_wallet) = context.and_then(|context| context.get("custom_wallet"))
+        {
+            match custom_wallet.as_str().map(Wallet::from_str) {
+                Some(Ok(wallet)) => wallet,
+                Some(Err(e)) => {
+                    eprintln!(
+                        "Failed to parse custom wallet, using default signer: {:?}",
+                        e
+                    );
+                    default_wallet
+                }
+                None => {
+                    eprintln!("Invalid custom_wallet context, using default signer");
+                    default_wallet
+                }
+            }
+        } else {
+            default_wallet
+        }
+    }
+
+    fn agent_wallet(&self, agent_ref: &str) -> Option<LocalWallet> {
+        let agent_id = match self.nodes.get(agent_ref) {
+            Some(node) if matches!(node.adapter_type, NodeAdapter::Agent) => {
+                node.adapter_id.as_str()
+            }
+            _ => agent_ref,
+        };
+
+        self.nibble_context
+            .agents
+            .iter()
+            .chain(self.nibble_context.saved_agents.iter())
+            .find(|agent| agent.id.eq_ignore_ascii_case(agent_id) || agent.name == agent_id)
+            .map(|agent| agent.wallet.clone())
+    }
+
     async fn process_node(
         &mut self,
         node: &WorkflowNode,
         subflow_manager: Option<&SubflowManager>,
         context_data: Option<Value>,
-    ) -> Result<Option<Value>, Box<dyn Error>> {
-        let processed_context = if let Some(context_tool) = &node.context_tool {
-            if let Some(data) = context_data {
-                match context_tool.process(data) {
-                    Ok(parsed_data) => Some(parsed_data),
-                    Err(e) => {
-                        eprintln!(
-                            "Error processing context with ContextTool for node {:?}: {}",
-                            node.id, e
-                        );
-                        None
-                    }
-                }
-            } else {
-                eprintln!(
-                    "No context data provided to process for node {:?}.",
-                    node.id
-                );
-                None
+    ) -> Result<Option<Value>, Box<dyn Error + Send + Sync>> {
+        let history_start = self.execution_history.len();
+        if !node.enabled {
+            println!("Skipping disabled node: {:?}", node.id);
+            return Ok(context_data);
+        }
+
+        let (processed_context, tool_error) =
+            process_context_tool(node.context_tool.as_ref(), context_data, &node.id);
+
+        let span = TelemetrySpan::element("node", &node.id, &node_kind(&node.adapter_type));
+        let started = Instant::now();
+        let result = span
+            .scope(self.process_node_adapter(node, subflow_manager, processed_context))
+            .await;
+        let succeeded =
+            matches!(result, Ok(Some(_))) && self.history_succeeded(history_start, &node.id);
+        span.finish(succeeded, started.elapsed());
+        #[cfg(feature = "metrics")]
+        if !succeeded {
+            crate::metrics::record_node_failure(node_label(&node.adapter_type));
+        }
+        self.record_duration(history_start, &node.id, started.elapsed());
+        self.record_tool_error(history_start, &node.id, tool_error);
+        result
+    }
+
+    fn history_succeeded(&self, history_start: usize, element_id: &str) -> bool {
+        self.execution_history[history_start.min(self.execution_history.len())..]
+            .iter()
+            .filter(|entry| entry.element_id == element_id)
+            .all(|entry| entry.error.is_none())
+    }
+
+    async fn process_node_adapter(
+        &mut self,
+        node: &WorkflowNode,
+        subflow_manager: Option<&SubflowManager>,
+        processed_context: Option<Value>,
+    ) -> Result<Option<Value>, Box<dyn Error + Send + Sync>> {
+        if let Some(simulation) = &self.simulation {
+            if let Some((element_type, result)) =
+                simulate_node(simulation, node, processed_context.clone())
+            {
+                println!("Simulating {}: {:?}", element_type, node.id);
+                self.execution_history.push(ExecutionHistory {
+                    element_id: node.id.clone(),
+                    element_type,
+                    result: Some(result.clone()),
+                    timestamp: chrono::Utc::now(),
+                    description: Some("Simulated".to_string()),
+                    error: None,
+                    branch: None,
+                    target_id: None,
+                    duration: None,
+                });
+                return Ok(Some(result));
             }
-        } else {
-            context_data
-        };
+        }
 
         match node.adapter_type.clone() {
             NodeAdapter::Agent => {
@@ -586,17 +2272,23 @@ impl Workflow {
                     .nibble_context
                     .agents
                     .iter()
+                    .chain(self.nibble_context.saved_agents.iter())
                     .find(|agent| agent.id == *node.adapter_id);
                 if let Some(agent) = agent_found {
                     println!("Executing Agent: {:?}", node.id);
 
-                    let input_context = node
-                        .context
-                        .as_ref()
-                        .map_or("", |v| v.as_str().unwrap_or(""));
+                    let input_context = agent_input(processed_context.as_ref(), node);
 
-                    match agent.execute_agent(input_context).await {
-                        Ok(result) => {
+                    match agent
+                        .execute_agent_with_cache(
+                            &input_context,
+                            self.llm_cache.as_deref(),
+                            self.force_llm_cache,
+                        )
+                        .await
+                    {
+                        Ok(response) => {
+                            let result = response.completion;
                             println!("Agent Result: {}", result);
 
                             self.execution_history.push(ExecutionHistory {
@@ -604,7 +2296,14 @@ impl Workflow {
                                 element_type: Adapter::Agent.to_string(),
                                 result: Some(Value::String(result.clone())),
                                 timestamp: chrono::Utc::now(),
-                                description: None,
+                                description: match response.cache {
+                                    CacheStatus::Bypassed => None,
+                                    status => Some(format!("LLM cache {}", status)),
+                                },
+                                error: None,
+                                branch: None,
+                                target_id: None,
+                                duration: None,
                             });
                             Ok(Some(Value::String(result)))
                         }
@@ -616,6 +2315,10 @@ impl Workflow {
                                 result: None,
                                 timestamp: chrono::Utc::now(),
                                 description: None,
+                                error: Some(e.to_string()),
+                                branch: None,
+                                target_id: None,
+                                duration: None,
                             });
                             Ok(None)
                         }
@@ -628,100 +2331,103 @@ impl Workflow {
                         result: None,
                         timestamp: chrono::Utc::now(),
                         description: None,
+                        error: Some(format!("Agent not found for ID: {}", node.adapter_id)),
+                        branch: None,
+                        target_id: None,
+                        duration: None,
                     });
                     Ok(None)
                 }
             }
-            NodeAdapter::OnChainConnector => {
-                let connector_found = self
-                    .nibble_context
-                    .onchain_connectors
+            NodeAdapter::ToolAgent => {
+                let nibble = self.nibble_context.clone();
+                let agent = nibble
+                    .agents
                     .iter()
-                    .find(|connector| connector.id == *node.adapter_id);
+                    .chain(nibble.saved_agents.iter())
+                    .find(|agent| agent.id == *node.adapter_id)
+                    .ok_or_else(|| format!("Agent not found for ID: {:?}", node.adapter_id))?;
+                println!("Executing ToolAgent: {:?}", node.id);
 
-                if let Some(onchain_connector) = connector_found {
-                    println!("Executing OnChainConnector: {:?}", node.id);
+                let input_context = agent_input(processed_context.as_ref(), node);
 
-                    let (wallet, method_name, params) = if let Some(context) = &node.context {
-                        let wallet = if let Some(wallet_name) = context.get("agent_wallet") {
-                            if let Some(agent_id) = wallet_name.as_str() {
-                                let agent_wallet = self.nodes.values().find_map(|node| {
-                                    if let NodeAdapter::Agent = node.adapter_type {
-                                        if node.adapter_id == agent_id {
-                                            Some(
-                                                self.nibble_context
-                                                    .agents
-                                                    .iter()
-                                                    .find(|agent| agent.id == agent_id)?
-                                                    .wallet
-                                                    .clone(),
-                                            )
-                                        } else {
-                                            self.execution_history.push(ExecutionHistory {
-                                                element_id: node.id.clone(),
-                                                element_type: Adapter::OnChainConnector.to_string(),
-                                                result: None,
-                                                timestamp: chrono::Utc::now(),
-                                                description: None,
-                                            });
-                                            None
-                                        }
-                                    } else {
-                                        self.execution_history.push(ExecutionHistory {
-                                            element_id: node.id.clone(),
-                                            element_type: Adapter::OnChainConnector.to_string(),
-                                            result: None,
-                                            timestamp: chrono::Utc::now(),
-                                            description: None,
-                                        });
-                                        None
-                                    }
-                                });
-
-                                if let Some(wallet) = agent_wallet {
-                                    wallet
-                                } else {
-                                    eprintln!(
-                                        "Agent with ID {:?} not found, using owner_wallet",
-                                        agent_id
-                                    );
-                                    self.nibble_context.owner_wallet.clone()
-                                }
-                            } else {
-                                eprintln!("Invalid agent_wallet context, using owner_wallet");
-                                self.nibble_context.owner_wallet.clone()
-                            }
-                        } else if let Some(custom_wallet) = context.get("custom_wallet") {
-                            if let Some(wallet_str) = custom_wallet.as_str() {
-                                let parsed_wallet = Wallet::from_str(wallet_str).map_err(|e| {
-                                    eprintln!("Failed to parse custom wallet: {:?}", e);
-                                    e
-                                });
-                                match parsed_wallet {
-                                    Ok(wallet) => wallet,
-                                    Err(_) => {
-                                        eprintln!("Invalid custom wallet, using owner_wallet");
-                                        self.nibble_context.owner_wallet.clone()
+                match agent.execute_agent_with_tools(&input_context).await {
+                    Ok(response) => {
+                        let mut tool_results = Vec::new();
+                        for tool_call in &response.tool_calls {
+                            let (result, error) =
+                                match self.dispatch_tool_call(tool_call, subflow_manager).await {
+                                    Ok(result) => (result, None),
+                                    Err(e) => {
+                                        eprintln!("Tool call {} failed: {}", tool_call.name, e);
+                                        (Value::Null, Some(e.to_string()))
                                     }
-                                }
-                            } else {
-                                eprintln!("Invalid custom_wallet context, using owner_wallet");
-                                self.nibble_context.owner_wallet.clone()
-                            }
-                        } else {
-                            self.nibble_context.owner_wallet.clone()
-                        };
+                                };
+                            tool_results.push(json!({
+                                "id": tool_call.id,
+                                "name": tool_call.name,
+                                "arguments": tool_call.arguments,
+                                "result": result,
+                                "error": error,
+                            }));
+                        }
 
-                        let method_name = context.get("method_name").and_then(|v| v.as_str());
+                        let result = json!({
+                            "text": response.text,
+                            "tool_calls": tool_results,
+                        });
+                        self.execution_history.push(ExecutionHistory {
+                            element_id: node.id.clone(),
+                            element_type: Adapter::Agent.to_string(),
+                            result: Some(result.clone()),
+                            timestamp: chrono::Utc::now(),
+                            description: None,
+                            error: None,
+                            branch: None,
+                            target_id: None,
+                            duration: None,
+                        });
+                        Ok(Some(result))
+                    }
+                    Err(e) => {
+                        eprintln!("Agent execution failed: {:?}", e);
+                        self.execution_history.push(ExecutionHistory {
+                            element_id: node.id.clone(),
+                            element_type: Adapter::Agent.to_string(),
+                            result: None,
+                            timestamp: chrono::Utc::now(),
+                            description: None,
+                            error: Some(e.to_string()),
+                            branch: None,
+                            target_id: None,
+                            duration: None,
+                        });
+                        Ok(None)
+                    }
+                }
+            }
+            NodeAdapter::OnChainConnector => {
+                let connector_found = self
+                    .nibble_context
+                    .onchain_connectors
+                    .iter()
+                    .chain(self.nibble_context.saved_onchain_connectors.iter())
+                    .find(|connector| connector.id == *node.adapter_id);
 
-                        let params = context
-                            .get("params")
-                            .and_then(|v| v.as_array().map(|arr| arr.clone()));
+                if let Some(onchain_connector) = connector_found {
+                    println!("Executing OnChainConnector: {:?}", node.id);
 
-                        (wallet, method_name, params)
-                    } else {
-                        (self.nibble_context.owner_wallet.clone(), None, None)
-                    };
+                    let wallet = self.resolve_signer(node.context.as_ref());
+                    let method_name = node
+                        .context
+                        .as_ref()
+                        .and_then(|context| context.get("method_name"))
+                        .and_then(|v| v.as_str());
+                    let params = node
+                        .context
+                        .as_ref()
+                        .and_then(|context| context.get("params"))
+                        .and_then(|v| v.as_array().cloned());
 
                     match onchain_connector
                         .execute_onchain_connector(
@@ -729,6 +2435,9 @@ impl Workflow {
                             wallet,
                             method_name,
                             params,
+                            &self.nibble_context.nonce_manager,
+                            &self.nibble_context.fee_mode,
+                            self.nibble_context.confirmations,
                         )
                         .await
                     {
@@ -745,6 +2454,10 @@ impl Workflow {
                                 result: Some(receipt_value.clone()),
                                 timestamp: chrono::Utc::now(),
                                 description: None,
+                                error: None,
+                                branch: None,
+                                target_id: None,
+                                duration: None,
                             });
                             Ok(Some(receipt_value))
                         }
@@ -756,6 +2469,10 @@ impl Workflow {
                                 result: None,
                                 timestamp: chrono::Utc::now(),
                                 description: None,
+                                error: Some(e.to_string()),
+                                branch: None,
+                                target_id: None,
+                                duration: None,
                             });
                             Ok(None)
                         }
@@ -768,6 +2485,13 @@ impl Workflow {
                         result: None,
                         timestamp: chrono::Utc::now(),
                         description: None,
+                        error: Some(format!(
+                            "OnChainConnector not found for ID: {}",
+                            node.adapter_id
+                        )),
+                        branch: None,
+                        target_id: None,
+                        duration: None,
                     });
                     Ok(None)
                 }
@@ -778,6 +2502,7 @@ impl Workflow {
                     .nibble_context
                     .offchain_connectors
                     .iter()
+                    .chain(self.nibble_context.saved_offchain_connectors.iter())
                     .find(|connector| connector.id == *node.adapter_id);
 
                 if let Some(offchain_connector) = connector_found {
@@ -788,6 +2513,7 @@ impl Workflow {
                             processed_context.clone(),
                             subflow_manager,
                             node.history_tool.clone(),
+                            Some(&self.nibble_context.rate_limiter),
                         )
                         .await
                     {
@@ -799,6 +2525,10 @@ impl Workflow {
                                 result: Some(response.clone()),
                                 timestamp: chrono::Utc::now(),
                                 description: None,
+                                error: None,
+                                branch: None,
+                                target_id: None,
+                                duration: None,
                             });
                             Ok(Some(response))
                         }
@@ -810,6 +2540,10 @@ impl Workflow {
                                 result: None,
                                 timestamp: chrono::Utc::now(),
                                 description: None,
+                                error: Some(e.to_string()),
+                                branch: None,
+                                target_id: None,
+                                duration: None,
                             });
                             Ok(None)
                         }
@@ -822,11 +2556,97 @@ impl Workflow {
                         result: None,
                         timestamp: chrono::Utc::now(),
                         description: None,
+                        error: Some(format!(
+                            "OffChainConnector not found for ID: {}",
+                            node.adapter_id
+                        )),
+                        branch: None,
+                        target_id: None,
+                        duration: None,
                     });
                     Ok(None)
                 }
             }
 
+            NodeAdapter::Delay => {
+                let delay_ms = node
+                    .context
+                    .as_ref()
+                    .and_then(|context| context.get("delay_ms"))
+                    .and_then(|delay| delay.as_u64());
+
+                match delay_ms {
+                    Some(delay_ms) => {
+                        println!("Delaying node {:?} for {}ms", node.id, delay_ms);
+                        tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                        self.execution_history.push(ExecutionHistory {
+                            element_id: node.id.clone(),
+                            element_type: "Delay".to_string(),
+                            result: processed_context.clone(),
+                            timestamp: chrono::Utc::now(),
+                            description: Some(format!("Delayed {}ms", delay_ms)),
+                            error: None,
+                            branch: None,
+                            target_id: None,
+                            duration: None,
+                        });
+                        Ok(processed_context)
+                    }
+                    None => {
+                        eprintln!("Delay node {:?} is missing delay_ms", node.id);
+                        self.execution_history.push(ExecutionHistory {
+                            element_id: node.id.clone(),
+                            element_type: "Delay".to_string(),
+                            result: None,
+                            timestamp: chrono::Utc::now(),
+                            description: None,
+                            error: Some("Delay node requires a delay_ms context value".to_string()),
+                            branch: None,
+                            target_id: None,
+                            duration: None,
+                        });
+                        Ok(None)
+                    }
+                }
+            }
+            NodeAdapter::Custom(executor) => {
+                println!("Executing Custom node: {:?}", node.id);
+
+                match executor
+                    .execute(processed_context.unwrap_or(Value::Null))
+                    .await
+                {
+                    Ok(result) => {
+                        self.execution_history.push(ExecutionHistory {
+                            element_id: node.id.clone(),
+                            element_type: "Custom".to_string(),
+                            result: result.clone(),
+                            timestamp: chrono::Utc::now(),
+                            description: None,
+                            error: None,
+                            branch: None,
+                            target_id: None,
+                            duration: None,
+                        });
+                        Ok(result)
+                    }
+                    Err(e) => {
+                        eprintln!("Custom node execution failed: {:?}", e);
+                        self.execution_history.push(ExecutionHistory {
+                            element_id: node.id.clone(),
+                            element_type: "Custom".to_string(),
+                            result: None,
+                            timestamp: chrono::Utc::now(),
+                            description: None,
+                            error: Some(e.to_string()),
+                            branch: None,
+                            target_id: None,
+                            duration: None,
+                        });
+                        Ok(None)
+                    }
+                }
+            }
             NodeAdapter::SubFlow {
                 subflow,
                 blocking,
@@ -861,6 +2681,10 @@ impl Workflow {
                                 result: Some(Value::String("Blocking SubFlow Success".to_string())),
                                 timestamp: chrono::Utc::now(),
                                 description: None,
+                                error: None,
+                                branch: None,
+                                target_id: None,
+                                duration: None,
                             });
                             self.execution_history.extend(history);
                             Ok(Some(Value::String("Blocking SubFlow Success".to_string())))
@@ -873,6 +2697,10 @@ impl Workflow {
                                 result: None,
                                 timestamp: chrono::Utc::now(),
                                 description: None,
+                                error: Some(e.to_string()),
+                                branch: None,
+                                target_id: None,
+                                duration: None,
                             });
                             Ok(None)
                         }
@@ -883,6 +2711,10 @@ impl Workflow {
                                 result: None,
                                 timestamp: chrono::Utc::now(),
                                 description: None,
+                                error: Some("Blocking SubFlow did not return a result".to_string()),
+                                branch: None,
+                                target_id: None,
+                                duration: None,
                             });
                             Ok(None)
                         }
@@ -904,9 +2736,12 @@ impl Workflow {
 
                             let (tx, rx) = tokio::sync::oneshot::channel();
                             tokio::spawn(async move {
+                                let mut reports = Vec::new();
                                 while let Some(history) = report_receiver.recv().await {
-                                    let _ = tx.send(history);
-                                    break;
+                                    reports.push(history);
+                                }
+                                if !reports.is_empty() {
+                                    let _ = tx.send(reports.concat());
                                 }
                             });
 
@@ -919,6 +2754,10 @@ impl Workflow {
                                     )),
                                     timestamp: chrono::Utc::now(),
                                     description: None,
+                                    error: None,
+                                    branch: None,
+                                    target_id: None,
+                                    duration: None,
                                 });
                                 self.execution_history.extend(history);
                             } else {
@@ -928,6 +2767,13 @@ impl Workflow {
                                     result: None,
                                     timestamp: chrono::Utc::now(),
                                     description: None,
+                                    error: Some(
+                                        "Failed to receive history from non-blocking SubFlow"
+                                            .to_string(),
+                                    ),
+                                    branch: None,
+                                    target_id: None,
+                                    duration: None,
                                 });
                                 eprintln!("Failed to receive history from non-blocking SubFlow.");
                                 return Ok(None);
@@ -940,6 +2786,10 @@ impl Workflow {
                                 result: None,
                                 timestamp: chrono::Utc::now(),
                                 description: None,
+                                error: Some("No SubflowManager available".to_string()),
+                                branch: None,
+                                target_id: None,
+                                duration: None,
                             });
                             eprintln!("No SubflowManager available.");
                             return Ok(None);
@@ -958,30 +2808,148 @@ impl Workflow {
         link: &WorkflowLink,
         context_data: Option<Value>,
         current_success: &mut bool,
-    ) -> Result<Option<Value>, Box<dyn Error>> {
-        let processed_context = if let Some(context_tool) = &link.context_tool {
-            if let Some(data) = context_data {
-                match context_tool.process(data) {
-                    Ok(parsed_data) => Some(parsed_data),
-                    Err(e) => {
-                        eprintln!(
-                            "Error processing context with ContextTool for node {:?}: {}",
-                            link.id, e
-                        );
-                        None
-                    }
-                }
-            } else {
+    ) -> Result<Option<Value>, Box<dyn Error + Send + Sync>> {
+        let mut context_data = context_data;
+        let mut iterations: HashMap<String, u32> = HashMap::new();
+
+        loop {
+            let history_start = self.execution_history.len();
+            let (processed_context, tool_error) =
+                process_context_tool(link.context_tool.as_ref(), context_data, &link.id);
+
+            let span =
+                TelemetrySpan::element("link", &link.id, &format!("{:?}", link.adapter_type));
+            let started = Instant::now();
+            let result = span
+                .scope(self.process_link_adapter(link, processed_context, current_success))
+                .await;
+            span.finish(
+                matches!(result, Ok(Some(_))) && self.history_succeeded(history_start, &link.id),
+                started.elapsed(),
+            );
+            self.record_duration(history_start, &link.id, started.elapsed());
+            self.record_tool_error(history_start, &link.id, tool_error);
+            let result = result?;
+
+            let loop_target = match (&link.target, &result) {
+                (Some(target), Some(_)) => self.execution_history[history_start..]
+                    .iter()
+                    .rev()
+                    .filter(|entry| entry.element_id == link.id)
+                    .find_map(|entry| entry.target_id.clone())
+                    .filter(|target_id| target.is_loop_target(target_id))
+                    .map(|target_id| {
+                        let limit = target.loop_limits[&target_id];
+                        (target_id, limit)
+                    }),
+                _ => None,
+            };
+
+            let (target_id, limit) = match loop_target {
+                Some(loop_target) => loop_target,
+                None => return Ok(result),
+            };
+
+            let count = iterations.entry(target_id.clone()).or_default();
+            *count += 1;
+            if *count >= limit {
                 eprintln!(
-                    "No context data provided to process for node {:?}.",
-                    link.id
+                    "Loop limit of {} reached for link {:?} targeting {:?}",
+                    limit, link.id, target_id
                 );
-                None
+                self.execution_history.push(ExecutionHistory {
+                    element_id: link.id.clone(),
+                    element_type: format!("{:?}", link.adapter_type),
+                    result: None,
+                    timestamp: chrono::Utc::now(),
+                    description: Some("Loop limit reached".to_string()),
+                    error: Some(format!(
+                        "Loop to {} exceeded {} iterations",
+                        target_id, limit
+                    )),
+                    branch: None,
+                    target_id: Some(target_id),
+                    duration: None,
+                });
+                *current_success = false;
+                return Ok(None);
             }
-        } else {
-            context_data
-        };
 
+            println!(
+                "Looping link {:?} back through {:?} (iteration {} of {})",
+                link.id, target_id, count, limit
+            );
+            context_data = result;
+        }
+    }
+
+    fn inbound_link_count(&self, node_id: &str) -> usize {
+        self.links
+            .values()
+            .filter(|link| {
+                link.target.as_ref().is_some_and(|target| {
+                    target
+                        .target_ids()
+                        .into_iter()
+                        .any(|target_id| target_id == node_id && !target.is_loop_target(target_id))
+                })
+            })
+            .count()
+    }
+
+    async fn route_to_target(
+        &mut self,
+        link_id: &str,
+        node: &WorkflowNode,
+        context: Option<Value>,
+        link_output: Option<Value>,
+    ) -> Result<Option<Value>, Box<dyn Error + Send + Sync>> {
+        if self.inbound_link_count(&node.id) <= 1 {
+            return self.process_node(node, None, context).await;
+        }
+
+        println!(
+            "Buffering input from link {:?} for join node {:?}",
+            link_id, node.id
+        );
+        self.join_inputs
+            .entry(node.id.clone())
+            .or_default()
+            .insert(link_id.to_string(), link_output.unwrap_or(Value::Null));
+        Ok(context)
+    }
+
+    fn record_duration(&mut self, history_start: usize, element_id: &str, duration: Duration) {
+        for entry in self.execution_history[history_start..]
+            .iter_mut()
+            .filter(|entry| entry.element_id == element_id && entry.duration.is_none())
+        {
+            entry.duration = Some(duration);
+        }
+    }
+
+    fn record_tool_error(
+        &mut self,
+        history_start: usize,
+        element_id: &str,
+        tool_error: Option<String>,
+    ) {
+        if let Some(tool_error) = tool_error {
+            for entry in self.execution_history[history_start..]
+                .iter_mut()
+                .filter(|entry| entry.element_id == element_id && entry.error.is_none())
+            {
+                entry.error = Some(tool_error.clone());
+            }
+        }
+    }
+
+    async fn process_link_adapter(
+        &mut self,
+        link: &WorkflowLink,
+        processed_context: Option<Value>,
+        current_success: &mut bool,
+    ) -> Result<Option<Value>, Box<dyn Error + Send + Sync>> {
         match link.adapter_type {
             LinkAdapter::Condition => {
                 println!("Processing Condition: {:?}", link.id);
@@ -990,26 +2958,49 @@ impl Workflow {
                     .nibble_context
                     .conditions
                     .iter()
+                    .chain(self.nibble_context.saved_conditions.iter())
                     .find(|condition| condition.id == *link.adapter_id);
 
                 if let Some(condition) = condition_found {
-                    match condition
-                        .check_condition(
-                            &self.nibble_context,
-                            processed_context.clone(),
-                            link.context.clone(),
-                        )
-                        .await
-                    {
+                    let response = match &self.simulation {
+                        Some(simulation) if condition.condition_type.is_external() => {
+                            Ok(simulation.check_condition(condition, processed_context.clone()))
+                        }
+                        _ => {
+                            condition
+                                .check_condition_batched(
+                                    &self.nibble_context,
+                                    processed_context.clone(),
+                                    link.context.clone(),
+                                    self.condition_batcher.as_deref(),
+                                )
+                                .await
+                        }
+                    };
+
+                    match response {
                         Ok(response) => {
                             println!("Condition response: {:?}", response);
 
+                            let branch = link.target.as_ref().map(|_| response.to_string());
+                            let routed_target_id = link.target.as_ref().map(|target| {
+                                if response {
+                                    target.true_target_id.clone()
+                                } else {
+                                    target.false_target_id.clone()
+                                }
+                            });
+
                             self.execution_history.push(ExecutionHistory {
                                 element_id: link.id.clone(),
                                 element_type: Adapter::Condition.to_string(),
                                 result: Some(Value::String("Condition Success".to_string())),
                                 timestamp: chrono::Utc::now(),
                                 description: None,
+                                error: None,
+                                branch: branch.clone(),
+                                target_id: routed_target_id.clone(),
+                                duration: None,
                             });
 
                             if let Some(target) = &link.target {
@@ -1025,9 +3016,10 @@ impl Workflow {
                                         next_node_id
                                     );
                                     let result = self
-                                        .process_node(
+                                        .route_to_target(
+                                            &link.id,
                                             &node.clone(),
-                                            None,
+                                            processed_context.clone(),
                                             processed_context.clone(),
                                         )
                                         .await?;
@@ -1037,6 +3029,10 @@ impl Workflow {
                                         result: result.clone(),
                                         timestamp: chrono::Utc::now(),
                                         description: None,
+                                        error: None,
+                                        branch: branch.clone(),
+                                        target_id: routed_target_id.clone(),
+                                        duration: None,
                                     });
 
                                     Ok(result)
@@ -1051,6 +3047,13 @@ impl Workflow {
                                         result: None,
                                         timestamp: chrono::Utc::now(),
                                         description: None,
+                                        error: Some(format!(
+                                            "Target node not found for condition response: {}",
+                                            next_node_id
+                                        )),
+                                        branch: branch.clone(),
+                                        target_id: routed_target_id.clone(),
+                                        duration: None,
                                     });
                                     Ok(None)
                                 }
@@ -1065,6 +3068,10 @@ impl Workflow {
                                         )),
                                         timestamp: chrono::Utc::now(),
                                         description: None,
+                                        error: None,
+                                        branch: None,
+                                        target_id: None,
+                                        duration: None,
                                     });
                                     Ok(Some(Value::String("Condition Success".to_string())))
                                 } else {
@@ -1074,7 +3081,13 @@ impl Workflow {
                                         element_type: Adapter::Condition.to_string(),
                                         result: None,
                                         timestamp: chrono::Utc::now(),
-                                        description: None,
+                                        description: Some(
+                                            "Condition failed, stopping flow".to_string(),
+                                        ),
+                                        error: None,
+                                        branch: None,
+                                        target_id: None,
+                                        duration: None,
                                     });
                                     Ok(None)
                                 }
@@ -1089,6 +3102,10 @@ impl Workflow {
                                 result: None,
                                 timestamp: chrono::Utc::now(),
                                 description: None,
+                                error: Some(e.to_string()),
+                                branch: None,
+                                target_id: None,
+                                duration: None,
                             });
                             Ok(None)
                         }
@@ -1102,6 +3119,10 @@ impl Workflow {
                         result: None,
                         timestamp: chrono::Utc::now(),
                         description: None,
+                        error: Some(format!("Condition not found for ID: {}", link.adapter_id)),
+                        branch: None,
+                        target_id: None,
+                        duration: None,
                     });
                     Ok(None)
                 }
@@ -1113,8 +3134,31 @@ impl Workflow {
                     .nibble_context
                     .listeners
                     .iter()
+                    .chain(self.nibble_context.saved_listeners.iter())
                     .find(|listener| listener.id == *link.adapter_id);
 
+                if let Some(simulation) = &self.simulation {
+                    if listener_found.is_some() {
+                        let event_data = simulation
+                            .stub(&link.adapter_id)
+                            .or_else(|| processed_context.clone())
+                            .unwrap_or(Value::Null);
+                        println!("Simulating Listener trigger: {:?}", event_data);
+                        self.execution_history.push(ExecutionHistory {
+                            element_id: link.id.clone(),
+                            element_type: Adapter::Listener.to_string(),
+                            result: Some(event_data.clone()),
+                            timestamp: chrono::Utc::now(),
+                            description: Some("Simulated".to_string()),
+                            error: None,
+                            branch: None,
+                            target_id: None,
+                            duration: None,
+                        });
+                        return Ok(Some(event_data));
+                    }
+                }
+
                 if let Some(listener) = listener_found {
                     let (tx, mut rx) = tokio::sync::mpsc::channel(1);
 
@@ -1141,6 +3185,10 @@ impl Workflow {
                                 result: Some(event_data.clone()),
                                 timestamp: chrono::Utc::now(),
                                 description: None,
+                                error: None,
+                                branch: None,
+                                target_id: None,
+                                duration: None,
                             });
                             Some(event_data)
                         }
@@ -1153,6 +3201,10 @@ impl Workflow {
                                 result: None,
                                 timestamp: chrono::Utc::now(),
                                 description: None,
+                                error: Some("Listener did not produce any result".to_string()),
+                                branch: None,
+                                target_id: None,
+                                duration: None,
                             });
                             None
                         }
@@ -1166,6 +3218,10 @@ impl Workflow {
                             result: None,
                             timestamp: chrono::Utc::now(),
                             description: None,
+                            error: Some(e.to_string()),
+                            branch: None,
+                            target_id: None,
+                            duration: None,
                         });
                     }
 
@@ -1179,6 +3235,10 @@ impl Workflow {
                         result: None,
                         timestamp: chrono::Utc::now(),
                         description: None,
+                        error: Some(format!("Listener not found for ID: {}", link.adapter_id)),
+                        branch: None,
+                        target_id: None,
+                        duration: None,
                     });
                     Ok(None)
                 }
@@ -1189,6 +3249,7 @@ impl Workflow {
                     .nibble_context
                     .fhe_gates
                     .iter()
+                    .chain(self.nibble_context.saved_fhe_gates.iter())
                     .find(|fhe_gate| fhe_gate.id == *link.adapter_id);
 
                 if let Some(fhe_gate) = fhe_gate_found {
@@ -1205,17 +3266,61 @@ impl Workflow {
                     match encrypted_value_option {
                         Some(encrypted_value) => {
                             if let Some(context) = &link.context {
+                                let client_key = match context.get("client_key") {
+                                    Some(value) => {
+                                        match serde_json::from_value::<ClientKey>(value.clone()) {
+                                            Ok(client_key) => Some(client_key),
+                                            Err(e) => {
+                                                eprintln!(
+                                                    "Invalid client_key for FHE gate: {:?}",
+                                                    e
+                                                );
+                                                self.execution_history.push(ExecutionHistory {
+                                                    element_id: link.id.clone(),
+                                                    element_type: Adapter::FHEGate.to_string(),
+                                                    result: None,
+                                                    timestamp: chrono::Utc::now(),
+                                                    description: None,
+                                                    error: Some(format!(
+                                                        "Invalid client_key for FHE gate: {}",
+                                                        e
+                                                    )),
+                                                    branch: None,
+                                                    target_id: None,
+                                                    duration: None,
+                                                });
+                                                return Ok(None);
+                                            }
+                                        }
+                                    }
+                                    None if fhe_gate.operation.requires_client_key() => {
+                                        eprintln!(
+                                            "Missing client_key for FHE gate operation: {}",
+                                            fhe_gate.operation
+                                        );
+                                        self.execution_history.push(ExecutionHistory {
+                                            element_id: link.id.clone(),
+                                            element_type: Adapter::FHEGate.to_string(),
+                                            result: None,
+                                            timestamp: chrono::Utc::now(),
+                                            description: None,
+                                            error: Some(format!(
+                                                "FHE gate operation '{}' requires a client_key",
+                                                fhe_gate.operation
+                                            )),
+                                            branch: None,
+                                            target_id: None,
+                                            duration: None,
+                                        });
+                                        return Ok(None);
+                                    }
+                                    None => None,
+                                };
+
                                 match fhe_gate
                                     .check_fhe_gate(
                                         encrypted_value,
-                                        context
-                                            .get("criterion")
-                                            .and_then(|v| v.as_str())
-                                            .map(|s| s.as_bytes().to_vec()),
-                                        context
-                                            .get("client_key")
-                                            .and_then(|v| serde_json::from_value(v.clone()).ok())
-                                            .unwrap(),
+                                        client_key,
                                         self.nibble_context.provider.clone(),
                                         self.nibble_context.owner_wallet.clone(),
                                     )
@@ -1235,9 +3340,10 @@ impl Workflow {
                                                     next_node_id
                                                 );
                                                 let result = self
-                                                    .process_node(
+                                                    .route_to_target(
+                                                        &link.id,
                                                         &node.clone(),
-                                                        None,
+                                                        processed_context.clone(),
                                                         processed_context.clone(),
                                                     )
                                                     .await?;
@@ -1247,6 +3353,10 @@ impl Workflow {
                                                     result: result.clone(),
                                                     timestamp: chrono::Utc::now(),
                                                     description: None,
+                                                    error: None,
+                                                    branch: None,
+                                                    target_id: None,
+                                                    duration: None,
                                                 });
 
                                                 Ok(result)
@@ -1262,6 +3372,10 @@ impl Workflow {
                                                     result: None,
                                                     timestamp: chrono::Utc::now(),
                                                     description: None,
+                                                    error: Some(format!("Target node not found for FHE gate response: {}", next_node_id)),
+                                                    branch: None,
+                                                    target_id: None,
+                                                    duration: None,
                                                 });
                                                 Ok(None)
                                             }
@@ -1277,6 +3391,10 @@ impl Workflow {
                                                     )),
                                                     timestamp: chrono::Utc::now(),
                                                     description: None,
+                                                    error: None,
+                                                    branch: None,
+                                                    target_id: None,
+                                                    duration: None,
                                                 });
                                                 Ok(Some(Value::String(
                                                     "FHE Gate Success".to_string(),
@@ -1289,7 +3407,14 @@ impl Workflow {
                                                     element_type: Adapter::FHEGate.to_string(),
                                                     result: None,
                                                     timestamp: chrono::Utc::now(),
-                                                    description: None,
+                                                    description: Some(
+                                                        "FHE gate failed, stopping flow"
+                                                            .to_string(),
+                                                    ),
+                                                    error: None,
+                                                    branch: None,
+                                                    target_id: None,
+                                                    duration: None,
                                                 });
                                                 Ok(None)
                                             }
@@ -1304,6 +3429,10 @@ impl Workflow {
                                             result: None,
                                             timestamp: chrono::Utc::now(),
                                             description: None,
+                                            error: Some(e.to_string()),
+                                            branch: None,
+                                            target_id: None,
+                                            duration: None,
                                         });
                                         Ok(None)
                                     }
@@ -1315,6 +3444,10 @@ impl Workflow {
                                     result: None,
                                     timestamp: chrono::Utc::now(),
                                     description: None,
+                                    error: Some("FHE gate link has no context".to_string()),
+                                    branch: None,
+                                    target_id: None,
+                                    duration: None,
                                 });
                                 Ok(None)
                             }
@@ -1332,6 +3465,12 @@ impl Workflow {
                                 result: None,
                                 timestamp: chrono::Utc::now(),
                                 description: None,
+                                error: Some(
+                                    "Encrypted value from previous node not found".to_string(),
+                                ),
+                                branch: None,
+                                target_id: None,
+                                duration: None,
                             });
                             Ok(None)
                         }
@@ -1344,6 +3483,10 @@ impl Workflow {
                         result: None,
                         timestamp: chrono::Utc::now(),
                         description: None,
+                        error: Some(format!("FHEGate not found for ID: {}", link.adapter_id)),
+                        branch: None,
+                        target_id: None,
+                        duration: None,
                     });
                     Ok(None)
                 }
@@ -1354,6 +3497,7 @@ impl Workflow {
                     .nibble_context
                     .evaluations
                     .iter()
+                    .chain(self.nibble_context.saved_evaluations.iter())
                     .find(|evaluation| evaluation.id == *link.adapter_id);
 
                 if let Some(evaluation) = evaluation_found {
@@ -1362,7 +3506,7 @@ impl Workflow {
                         .as_ref()
                         .and_then(|v| v.as_array())
                         .map(|arr| arr.iter().filter_map(|v| v.as_str()).collect::<String>())
-                        .unwrap_or_else(String::new);
+                        .unwrap_or_default();
 
                     let flow_previous_context = self
                         .execution_history
@@ -1409,21 +3553,61 @@ impl Workflow {
                         .collect::<Vec<_>>()
                         .join("\n");
 
-                    match evaluation
-                        .check_evaluation(
-                            self.nibble_context.agents.clone(),
-                            processed_context.clone(),
-                            Some(&flow_previous_context),
-                            Some(&flow_next_steps),
-                            interaction_id,
-                        )
-                        .await
-                    {
+                    let response = match &self.simulation {
+                        Some(simulation) => {
+                            Ok(simulation.evaluate(&evaluation.id, processed_context.clone()))
+                        }
+                        None => {
+                            evaluation
+                                .check_evaluation(
+                                    self.nibble_context
+                                        .agents
+                                        .iter()
+                                        .chain(self.nibble_context.saved_agents.iter())
+                                        .cloned()
+                                        .collect(),
+                                    processed_context.clone(),
+                                    Some(&flow_previous_context),
+                                    Some(&flow_next_steps),
+                                    interaction_id,
+                                )
+                                .await
+                        }
+                    };
+
+                    match response {
                         Ok(response) => {
+                            let link_output = response
+                                .get("data")
+                                .cloned()
+                                .or_else(|| processed_context.clone());
+                            let (decision, next_context) =
+                                match response.get("decision").and_then(|v| v.as_bool()) {
+                                    Some(decision) => (
+                                        Some(decision),
+                                        match response.get("data") {
+                                            Some(data) => Some(merge_context(
+                                                processed_context.clone(),
+                                                data.clone(),
+                                            )),
+                                            None => processed_context.clone(),
+                                        },
+                                    ),
+                                    None => (response.as_bool(), processed_context.clone()),
+                                };
+                            let score = response
+                                .get("score")
+                                .and_then(|v| v.as_f64())
+                                .or_else(|| response.as_f64());
+
                             if let Some(target) = &link.target {
                                 let mut next_node_id: &String = &String::new();
+                                let score_target =
+                                    score.and_then(|score| target.score_target(score));
 
-                                if let Some(response_value) = response.as_bool() {
+                                if let Some(score_target) = score_target {
+                                    next_node_id = score_target;
+                                } else if let Some(response_value) = decision {
                                     next_node_id = if response_value {
                                         &target.true_target_id
                                     } else {
@@ -1431,20 +3615,27 @@ impl Workflow {
                                     };
                                 } else {
                                     if let Some(generated_target) = &target.generated_target_id {
-                                        next_node_id = &generated_target;
+                                        next_node_id = generated_target;
                                     }
                                 }
 
+                                let branch = match (score_target, decision) {
+                                    (Some(_), _) => "score".to_string(),
+                                    (None, Some(decision)) => decision.to_string(),
+                                    (None, None) => "generated".to_string(),
+                                };
+
                                 if let Some(node) = self.nodes.get(next_node_id) {
                                     println!(
                                         "Continuing to node based on Evaluation: {:?}",
                                         next_node_id
                                     );
                                     let result = self
-                                        .process_node(
+                                        .route_to_target(
+                                            &link.id,
                                             &node.clone(),
-                                            None,
-                                            processed_context.clone(),
+                                            next_context,
+                                            link_output,
                                         )
                                         .await?;
 
@@ -1454,6 +3645,10 @@ impl Workflow {
                                         result: result.clone(),
                                         timestamp: chrono::Utc::now(),
                                         description: None,
+                                        error: None,
+                                        branch: Some(branch.clone()),
+                                        target_id: Some(next_node_id.clone()),
+                                        duration: None,
                                     });
 
                                     Ok(result)
@@ -1468,6 +3663,13 @@ impl Workflow {
                                         result: None,
                                         timestamp: chrono::Utc::now(),
                                         description: None,
+                                        error: Some(format!(
+                                            "Target node not found for Evaluation response: {}",
+                                            next_node_id
+                                        )),
+                                        branch: Some(branch.clone()),
+                                        target_id: Some(next_node_id.clone()),
+                                        duration: None,
                                     });
                                     Ok(None)
                                 }
@@ -1476,12 +3678,16 @@ impl Workflow {
                                 self.execution_history.push(ExecutionHistory {
                                     element_id: link.id.clone(),
                                     element_type: Adapter::Evaluation.to_string(),
-                                    result: processed_context.clone(),
+                                    result: next_context.clone(),
                                     timestamp: chrono::Utc::now(),
                                     description: None,
+                                    error: None,
+                                    branch: None,
+                                    target_id: None,
+                                    duration: None,
                                 });
 
-                                Ok(processed_context)
+                                Ok(next_context)
                             }
                         }
                         Err(e) => {
@@ -1492,6 +3698,10 @@ impl Workflow {
                                 result: None,
                                 timestamp: chrono::Utc::now(),
                                 description: None,
+                                error: Some(e.to_string()),
+                                branch: None,
+                                target_id: None,
+                                duration: None,
                             });
                             Ok(None)
                         }
@@ -1504,64 +3714,382 @@ impl Workflow {
                         result: None,
                         timestamp: chrono::Utc::now(),
                         description: None,
+                        error: Some(format!("Evaluation not found for ID: {}", link.adapter_id)),
+                        branch: None,
+                        target_id: None,
+                        duration: None,
                     });
                     Ok(None)
                 }
             }
         }
     }
+
+    async fn dispatch_tool_call(
+        &self,
+        tool_call: &ToolCall,
+        subflow_manager: Option<&SubflowManager>,
+    ) -> Result<Value, Box<dyn Error + Send + Sync>> {
+        let nibble = &self.nibble_context;
+
+        if let Some(connector) = nibble
+            .offchain_connectors
+            .iter()
+            .chain(nibble.saved_offchain_connectors.iter())
+            .find(|connector| connector.name == tool_call.name)
+        {
+            return connector
+                .execute_offchain_connector(
+                    Some(tool_call.arguments.clone()),
+                    subflow_manager,
+                    None,
+                    Some(&nibble.rate_limiter),
+                )
+                .await;
+        }
+
+        if let Some(connector) = nibble
+            .onchain_connectors
+            .iter()
+            .chain(nibble.saved_onchain_connectors.iter())
+            .find(|connector| connector.name == tool_call.name)
+        {
+            let params = tool_call
+                .arguments
+                .get("params")
+                .and_then(|v| v.as_array())
+                .cloned();
+            let result = connector
+                .execute_onchain_connector(
+                    nibble.provider.clone(),
+                    nibble.owner_wallet.clone(),
+                    tool_call
+                        .arguments
+                        .get("method_name")
+                        .and_then(|v| v.as_str()),
+                    params,
+                    &nibble.nonce_manager,
+                    &nibble.fee_mode,
+                    nibble.confirmations,
+                )
+                .await?;
+            return Ok(result.unwrap_or(Value::Null));
+        }
+
+        if let Some(agent) = nibble
+            .agents
+            .iter()
+            .chain(nibble.saved_agents.iter())
+            .find(|agent| agent.name == tool_call.name)
+        {
+            let prompt = match &tool_call.arguments {
+                Value::String(prompt) => prompt.clone(),
+                arguments => arguments
+                    .get("prompt")
+                    .and_then(|v| v.as_str())
+                    .map(String::from)
+                    .unwrap_or_else(|| arguments.to_string()),
+            };
+            return Ok(Value::String(agent.execute_agent(&prompt).await?));
+        }
+
+        Err(format!("No adapter named {} to handle tool call", tool_call.name).into())
+    }
+}
+
+fn simulate_node(
+    simulation: &Simulation,
+    node: &WorkflowNode,
+    context: Option<Value>,
+) -> Option<(String, Value)> {
+    let (element_type, default) = match node.adapter_type {
+        NodeAdapter::Agent | NodeAdapter::ToolAgent => (
+            Adapter::Agent.to_string(),
+            node.context.clone().unwrap_or(Value::String(String::new())),
+        ),
+        NodeAdapter::OnChainConnector => (
+            Adapter::OnChainConnector.to_string(),
+            Value::String(format!("Transaction Hash: {:?}", H256::zero())),
+        ),
+        NodeAdapter::OffChainConnector => (
+            Adapter::OffChainConnector.to_string(),
+            context.unwrap_or(Value::Null),
+        ),
+        NodeAdapter::Custom(_) => ("Custom".to_string(), context.unwrap_or(Value::Null)),
+        NodeAdapter::Delay => ("Delay".to_string(), context.unwrap_or(Value::Null)),
+        NodeAdapter::SubFlow { .. } => return None,
+    };
+
+    Some((
+        element_type,
+        simulation.stub(&node.adapter_id).unwrap_or(default),
+    ))
+}
+
+#[cfg(feature = "metrics")]
+fn node_label(adapter_type: &NodeAdapter) -> &'static str {
+    match adapter_type {
+        NodeAdapter::OffChainConnector => "OffChainConnector",
+        NodeAdapter::OnChainConnector => "OnChainConnector",
+        NodeAdapter::Agent => "Agent",
+        NodeAdapter::ToolAgent => "ToolAgent",
+        NodeAdapter::SubFlow { .. } => "SubFlow",
+        NodeAdapter::Custom(_) => "Custom",
+        NodeAdapter::Delay => "Delay",
+    }
+}
+
+fn node_kind(adapter_type: &NodeAdapter) -> String {
+    match adapter_type {
+        NodeAdapter::SubFlow { subflow, .. } => format!("SubFlow({})", subflow.name),
+        adapter_type => format!("{:?}", adapter_type),
+    }
+}
+
+fn diff_elements(
+    before: Vec<(String, Value)>,
+    after: Vec<(String, Value)>,
+) -> (Vec<ElementDiff>, Vec<ElementDiff>, Vec<ElementDiff>) {
+    let group = |elements: Vec<(String, Value)>| {
+        let mut groups: HashMap<String, Vec<Value>> = HashMap::new();
+        for (signature, snapshot) in elements {
+            groups.entry(signature).or_default().push(snapshot);
+        }
+        for snapshots in groups.values_mut() {
+            snapshots.sort_by_key(|snapshot| snapshot.to_string());
+        }
+        groups
+    };
+    let mut before = group(before);
+    let mut after = group(after);
+
+    let mut signatures: Vec<String> = before.keys().chain(after.keys()).cloned().collect();
+    signatures.sort();
+    signatures.dedup();
+
+    let (mut added, mut removed, mut modified) = (Vec::new(), Vec::new(), Vec::new());
+    for signature in signatures {
+        let mut old = before.remove(&signature).unwrap_or_default().into_iter();
+        let mut new = after.remove(&signature).unwrap_or_default().into_iter();
+        loop {
+            let change = ElementDiff {
+                signature: signature.clone(),
+                before: old.next(),
+                after: new.next(),
+            };
+            match (&change.before, &change.after) {
+                (None, None) => break,
+                (None, Some(_)) => added.push(change),
+                (Some(_), None) => removed.push(change),
+                (Some(before), Some(after)) if before != after => modified.push(change),
+                _ => {}
+            }
+        }
+    }
+
+    (added, removed, modified)
+}
+
+fn dot_disabled_style(enabled: bool) -> &'static str {
+    if enabled {
+        ""
+    } else {
+        ", style=\"dashed,filled\", fillcolor=lightgrey, fontcolor=grey"
+    }
+}
+
+fn budget_exhausted(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|deadline| Instant::now() >= deadline)
+}
+
+fn process_context_tool(
+    context_tool: Option<&ContextParse>,
+    context_data: Option<Value>,
+    element_id: &str,
+) -> (Option<Value>, Option<String>) {
+    let context_tool = match context_tool {
+        Some(context_tool) => context_tool,
+        None => return (context_data, None),
+    };
+
+    match context_data {
+        Some(data) => match context_tool.process(data) {
+            Ok(parsed_data) => (Some(parsed_data), None),
+            Err(e) => {
+                eprintln!(
+                    "Error processing context with ContextTool for node {:?}: {}",
+                    element_id, e
+                );
+                (None, Some(format!("ContextTool failed: {}", e)))
+            }
+        },
+        None => {
+            eprintln!(
+                "No context data provided to process for node {:?}.",
+                element_id
+            );
+            (
+                None,
+                Some("ContextTool failed: no context data provided".to_string()),
+            )
+        }
+    }
+}
+
+fn agent_input(processed_context: Option<&Value>, node: &WorkflowNode) -> String {
+    processed_context
+        .filter(|input| !input.is_null())
+        .or(node.context.as_ref())
+        .map_or(String::new(), prompt_from_value)
+}
+
+fn merge_context(context: Option<Value>, data: Value) -> Value {
+    match (context, data) {
+        (Some(Value::Object(mut context)), Value::Object(data)) => {
+            context.extend(data);
+            Value::Object(context)
+        }
+        (_, data) => data,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubflowStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed(String),
+    Cancelled,
+}
+
+impl SubflowStatus {
+    pub fn is_active(&self) -> bool {
+        matches!(self, SubflowStatus::Queued | SubflowStatus::Running)
+    }
 }
 
 #[derive(Debug)]
 pub struct SubflowManager {
     sender: mpsc::Sender<SubflowRequest>,
+    statuses: Arc<RwLock<HashMap<String, SubflowStatus>>>,
+    cancellations: Arc<RwLock<HashMap<String, oneshot::Sender<()>>>>,
 }
 
 #[derive(Debug)]
 pub struct SubflowRequest {
+    id: String,
     subflow: Arc<Mutex<Workflow>>,
     repetitions: Option<u32>,
     count_successes: bool,
     blocking: bool,
     responder: Option<oneshot::Sender<Result<Vec<ExecutionHistory>, String>>>,
     report_sender: Option<mpsc::Sender<Vec<ExecutionHistory>>>,
+    cancel: Option<oneshot::Receiver<()>>,
+    span: TelemetrySpan,
+}
+
+fn set_subflow_status(
+    statuses: &RwLock<HashMap<String, SubflowStatus>>,
+    id: &str,
+    status: SubflowStatus,
+) {
+    if let Ok(mut statuses) = statuses.write() {
+        statuses.insert(id.to_string(), status);
+    }
+}
+
+async fn run_subflow(
+    statuses: &RwLock<HashMap<String, SubflowStatus>>,
+    id: &str,
+    subflow: &Mutex<Workflow>,
+    repetitions: Option<u32>,
+    count_successes: bool,
+) -> Result<Vec<ExecutionHistory>, String> {
+    let mut subflow = subflow.lock().await;
+    set_subflow_status(statuses, id, SubflowStatus::Running);
+    let result = Box::pin(subflow.execute(repetitions, count_successes))
+        .await
+        .map_err(|e| e.to_string());
+    let status = match &result {
+        Ok(_) => SubflowStatus::Completed,
+        Err(e) => SubflowStatus::Failed(e.clone()),
+    };
+    set_subflow_status(statuses, id, status);
+    result
+}
+
+fn prune_cancellations(cancellations: &RwLock<HashMap<String, oneshot::Sender<()>>>) {
+    if let Ok(mut cancellations) = cancellations.write() {
+        cancellations.retain(|_, cancel| !cancel.is_closed());
+    }
+}
+
+impl Default for SubflowManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SubflowManager {
     pub fn new() -> Self {
         let (sender, mut receiver) = mpsc::channel(100);
+        let statuses = Arc::new(RwLock::new(HashMap::new()));
+        let cancellations = Arc::new(RwLock::new(HashMap::new()));
+        let registry = statuses.clone();
+        let pending = cancellations.clone();
 
         tokio::spawn(async move {
             while let Some(request) = receiver.recv().await {
                 let SubflowRequest {
+                    id,
                     subflow,
                     repetitions,
                     count_successes,
                     blocking,
                     responder,
                     report_sender,
+                    cancel,
+                    span,
                 } = request;
 
                 if blocking {
-                    let result = {
-                        let mut subflow = subflow.lock().await;
-                        subflow
-                            .execute(repetitions, count_successes)
-                            .await
-                            .map_err(|e| e.to_string())
-                    };
+                    let result = span
+                        .scope(run_subflow(
+                            &registry,
+                            &id,
+                            &subflow,
+                            repetitions,
+                            count_successes,
+                        ))
+                        .await;
                     if let Some(responder) = responder {
                         let _ = responder.send(result);
                     }
                 } else {
-                    let subflow_clone = subflow.clone();
+                    let registry = registry.clone();
+                    let pending = pending.clone();
                     tokio::spawn(async move {
-                        let result = {
-                            let mut subflow = subflow_clone.lock().await;
-                            subflow
-                                .execute(repetitions, count_successes)
-                                .await
-                                .map_err(|e| e.to_string())
+                        let run = span.scope(run_subflow(
+                            &registry,
+                            &id,
+                            &subflow,
+                            repetitions,
+                            count_successes,
+                        ));
+                        let result = match cancel {
+                            Some(cancel) => tokio::select! {
+                                biased;
+                                Ok(()) = cancel => None,
+                                result = run => Some(result),
+                            },
+                            None => Some(run.await),
+                        };
+                        prune_cancellations(&pending);
+
+                        let Some(result) = result else {
+                            set_subflow_status(&registry, &id, SubflowStatus::Cancelled);
+                            println!("Non-blocking subflow cancelled: {}", id);
+                            return;
                         };
 
                         if let Ok(history) = result {
@@ -1578,7 +4106,50 @@ impl SubflowManager {
             }
         });
 
-        Self { sender }
+        Self {
+            sender,
+            statuses,
+            cancellations,
+        }
+    }
+
+    pub fn cancel(&self, id: &str) -> bool {
+        let cancel = self
+            .cancellations
+            .write()
+            .ok()
+            .and_then(|mut cancellations| cancellations.remove(id));
+
+        match cancel {
+            Some(cancel) => {
+                if cancel.send(()).is_err() {
+                    return false;
+                }
+                set_subflow_status(&self.statuses, id, SubflowStatus::Cancelled);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn status(&self, id: &str) -> Option<SubflowStatus> {
+        self.statuses
+            .read()
+            .ok()
+            .and_then(|statuses| statuses.get(id).cloned())
+    }
+
+    pub fn list_active(&self) -> Vec<String> {
+        self.statuses
+            .read()
+            .map(|statuses| {
+                statuses
+                    .iter()
+                    .filter(|(_, status)| status.is_active())
+                    .map(|(id, _)| id.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
     }
 
     pub async fn execute_subflow(
@@ -1589,33 +4160,64 @@ impl SubflowManager {
         blocking: bool,
         report_sender: Option<mpsc::Sender<Vec<ExecutionHistory>>>,
     ) -> Option<Result<Vec<ExecutionHistory>, String>> {
+        let id = subflow.lock().await.id.clone();
+        set_subflow_status(&self.statuses, &id, SubflowStatus::Queued);
+
         if blocking {
             let (responder, receiver) = oneshot::channel();
-            let _ = self
+            if self
                 .sender
                 .send(SubflowRequest {
+                    id: id.clone(),
                     subflow,
                     repetitions,
                     count_successes,
                     blocking,
                     responder: Some(responder),
                     report_sender,
+                    cancel: None,
+                    span: TelemetrySpan::current(),
                 })
-                .await;
+                .await
+                .is_err()
+            {
+                set_subflow_status(
+                    &self.statuses,
+                    &id,
+                    SubflowStatus::Failed("SubflowManager is not running".to_string()),
+                );
+            }
 
             receiver.await.ok()
         } else {
-            let _ = self
+            let (cancel_sender, cancel) = oneshot::channel();
+            prune_cancellations(&self.cancellations);
+            if let Ok(mut cancellations) = self.cancellations.write() {
+                cancellations.insert(id.clone(), cancel_sender);
+            }
+
+            if self
                 .sender
                 .send(SubflowRequest {
+                    id: id.clone(),
                     subflow,
                     repetitions,
                     count_successes,
                     blocking,
                     responder: None,
                     report_sender,
+                    cancel: Some(cancel),
+                    span: TelemetrySpan::current(),
                 })
-                .await;
+                .await
+                .is_err()
+            {
+                set_subflow_status(
+                    &self.statuses,
+                    &id,
+                    SubflowStatus::Failed("SubflowManager is not running".to_string()),
+                );
+            }
 
             None
         }
@@ -9,7 +9,7 @@ mod tests {
             },
             nodes::{
                 agents::{LLMModel, Objective},
-                connectors::{off_chain::ConnectorType, on_chain::GasOptions},
+                connectors::{off_chain::ConnectorType, on_chain::{BatchMode, GasOptions}},
             },
         },
         ipfs::IPFSProvider,
@@ -23,6 +23,7 @@ mod tests {
     use dotenv::dotenv;
 
     // #[tokio::test]
+    #[allow(dead_code)]
     async fn test_create_nibble() {
         dotenv().ok();
         let owner_private_key = env::var("PRIVATE_KEY").expect("PRIVATE_KEY must be set in .env file");
@@ -187,7 +188,7 @@ mod tests {
                         "Methodical and detail-oriented, analyzing social media trends.",
                         "Analyze social media trends and provide insights to optimize the reach and engagement of the token-related campaigns.",
                         LLMModel::Claude {
-                            api_key: Some(env::var("CLAUDE_KEY").expect("API_KEY must be set in .env file")).unwrap(),
+                            api_key: env::var("CLAUDE_KEY").expect("API_KEY must be set in .env file"),
                             model: "claude-3-5-sonnet-latest".to_string(),
                             temperature: 0.5,
                             max_tokens: 150,
@@ -200,6 +201,7 @@ mod tests {
                             metadata: None,
                             tool_choice: None,
                             tools: None,
+                            base_url: None,
                        
                         },
                         false,
@@ -266,7 +268,7 @@ mod tests {
                     );
 
                     match result {
-                        Ok(mut agent) => {
+                        Ok(_agent) => {
                             println!("Agent {} added successfully.", name);
 
                             // if name == "AnalystAgent" {
@@ -490,16 +492,18 @@ mod tests {
                 
 
                 let interval = Duration::from_secs(1200);
-                let listener_result = nibble.add_listener(
+                let _listener_result = nibble.add_listener(
                     "Timer",
                     ListenerType::Timer {
                         interval,
                     },
                     false,
+                    None,
+                    None,
                 );
 
                 // Subflow connected to Timer Listener for posting on Lens
-                let off_chain_check_notifications = nibble.add_offchain_connector( "LensNotifications",
+                let _off_chain_check_notifications = nibble.add_offchain_connector( "LensNotifications",
                 ConnectorType::GraphQL {
                     query: r#"
                         query notifications($request: NotificationRequest) {
@@ -620,7 +624,7 @@ mod tests {
                 &H160::from_str("0x0000000000000000000000000000000000000001").unwrap(),None
             );
 
-            let agent_notification_judge = nibble.add_evaluation("AgentEvaluationNotifications",  EvaluationType::AgentJudge {
+            let _agent_notification_judge = nibble.add_evaluation("AgentEvaluationNotifications",  EvaluationType::AgentJudge {
                 agent_id: "MemeMaster".into(),
                 prompt: "Based on the notifications, decide which one I should respond to. In your response give me the entire object back of the chosen notification.".to_string(),
                 response_type: EvaluationResponseType::Dynamic,
@@ -628,7 +632,7 @@ mod tests {
 
             // Then I would in the workflow use the meme master agent to right a response/reply "Then craft back the response in your role to increase the lore of the meme. Make your response in JSON format with a field of message and the response, and a field of id where you put the comment/quote ID of the message that the response is for. If I am replying to a follow by someone or creating a new publication/post then dont include anything in the field of id since the message will be a new publication.". 
 
-            let lens_create_post_connector = nibble.add_offchain_connector(
+            let _lens_create_post_connector = nibble.add_offchain_connector(
                 "LensCreatePost",
                 ConnectorType::GraphQL {
                     query: r#"
@@ -692,7 +696,7 @@ mod tests {
             );
             
 
-            let lens_broadcast_post_connector = nibble.add_offchain_connector(
+            let _lens_broadcast_post_connector = nibble.add_offchain_connector(
                 "LensBroadcastPost",
                 ConnectorType::GraphQL {
                     query: r#"
@@ -749,7 +753,7 @@ mod tests {
             );
 
 
-            let lens_comment_onchain_connector = nibble.add_offchain_connector(
+            let _lens_comment_onchain_connector = nibble.add_offchain_connector(
                 "LensCommentOnchain",
                 ConnectorType::GraphQL {
                     query: r#"
@@ -801,7 +805,7 @@ mod tests {
             );
             
 
-            let lens_quote_onchain_connector = nibble.add_offchain_connector(
+            let _lens_quote_onchain_connector = nibble.add_offchain_connector(
                 "LensQuoteOnchain",
                 ConnectorType::GraphQL {
                     query: r#"
@@ -854,26 +858,26 @@ mod tests {
 
             // subflujo de crear el token
             // agent would generate lore / details of the token to send to the on-chain adapter
-            let on_chain_adapter_create_memecoin = nibble.add_onchain_connector("CreateMemecoinConnector", None, false, Some(include_bytes!("../abis/NibbleFactory.json").into()), Some(serde_json::from_str(include_str!("../abis/NibbleFactory.json")).unwrap()), Chain::Polygon,  Some(GasOptions {
+            let _on_chain_adapter_create_memecoin = nibble.add_onchain_connector("CreateMemecoinConnector", None, false, Some(include_bytes!("../abis/NibbleFactory.json").into()), Some(serde_json::from_str(include_str!("../abis/NibbleFactory.json")).unwrap()), Chain::Polygon,  Some(GasOptions {
                 max_fee_per_gas: Some(U256::from(1_000_000_000)), 
                 max_priority_fee_per_gas: Some(U256::from(1_000_000)), 
                 gas_limit: Some(U256::from(3_000_000)), 
                 nonce: None,
-            }));
+            }), vec![], BatchMode::SequentialAbort);
 
-            let on_chain_adapter_uniswap_pool = nibble.add_onchain_connector("CreateUniswapPoolConnector", Some("0x1F98431c8aD98523631AE4a59f267346ea31F984".parse::<Address>().unwrap()), false, None, Some(serde_json::from_str(include_str!("../abis/NibbleFactory.json")).unwrap()), Chain::Polygon,  Some(GasOptions {
+            let _on_chain_adapter_uniswap_pool = nibble.add_onchain_connector("CreateUniswapPoolConnector", Some("0x1F98431c8aD98523631AE4a59f267346ea31F984".parse::<Address>().unwrap()), false, None, Some(serde_json::from_str(include_str!("../abis/NibbleFactory.json")).unwrap()), Chain::Polygon,  Some(GasOptions {
                 max_fee_per_gas: Some(U256::from(1_000_000_000)), 
                 max_priority_fee_per_gas: Some(U256::from(1_000_000)), 
                 gas_limit: Some(U256::from(3_000_000)), 
                 nonce: None,
-            }));
+            }), vec![], BatchMode::SequentialAbort);
 
-            let on_chain_adapter_balancer_pool = nibble.add_onchain_connector("CreateBalancerWeightedPool", Some("0x8e9aa87E45e92BAD84dE4fA65B9988F8235E15F8".parse::<Address>().unwrap()), false, None, Some(serde_json::from_str(include_str!("../abis/NibbleFactory.json")).unwrap()), Chain::Polygon,  Some(GasOptions {
+            let _on_chain_adapter_balancer_pool = nibble.add_onchain_connector("CreateBalancerWeightedPool", Some("0x8e9aa87E45e92BAD84dE4fA65B9988F8235E15F8".parse::<Address>().unwrap()), false, None, Some(serde_json::from_str(include_str!("../abis/NibbleFactory.json")).unwrap()), Chain::Polygon,  Some(GasOptions {
                 max_fee_per_gas: Some(U256::from(1_000_000_000)), 
                 max_priority_fee_per_gas: Some(U256::from(1_000_000)), 
                 gas_limit: Some(U256::from(3_000_000)), 
                 nonce: None,
-            }));
+            }), vec![], BatchMode::SequentialAbort);
             
 
             /* 
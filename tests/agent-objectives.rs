@@ -0,0 +1,171 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{mock_llm_model, spawn_mock_server};
+    use ethers::types::Address;
+    use npc_workbench::adapters::nodes::agents::{configure_new_agent, Agent, Objective};
+    use serde_json::json;
+
+    fn objective(description: &str, priority: u8, generated: bool) -> Objective {
+        Objective {
+            description: description.to_string(),
+            priority,
+            generated,
+        }
+    }
+
+    fn strategist(url: &str, objectives: Vec<Objective>) -> Agent {
+        configure_new_agent(
+            "Strategist",
+            "growth strategist",
+            "curious",
+            "",
+            false,
+            false,
+            false,
+            mock_llm_model(url, "string"),
+            &Address::zero(),
+            None,
+            None,
+            None,
+            objectives,
+        )
+        .unwrap()
+    }
+
+    fn descriptions(agent: &Agent) -> Vec<(&str, u8)> {
+        agent
+            .objectives
+            .iter()
+            .map(|objective| (objective.description.as_str(), objective.priority))
+            .collect()
+    }
+
+    #[test]
+    fn test_dedup_objectives_keeps_higher_priority_duplicate() {
+        let mut agent = strategist(
+            "http://127.0.0.1:1",
+            vec![
+                objective("Build an audience", 4, false),
+                objective("Post daily memes", 6, true),
+                objective("Build initial audience", 9, true),
+            ],
+        );
+
+        agent.dedup_objectives();
+
+        assert_eq!(
+            descriptions(&agent),
+            vec![("Build initial audience", 9), ("Post daily memes", 6)]
+        );
+        assert!(!agent.objectives[0].generated);
+    }
+
+    #[test]
+    fn test_dedup_objectives_respects_threshold() {
+        let mut agent = strategist(
+            "http://127.0.0.1:1",
+            vec![
+                objective("Build an audience", 4, true),
+                objective("Build initial audience", 9, true),
+            ],
+        );
+        agent.objective_similarity = 0.9;
+
+        agent.dedup_objectives();
+
+        assert_eq!(agent.objectives.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_generate_objectives_merges_near_duplicates() {
+        let server = spawn_mock_server(|_| {
+            (
+                200,
+                json!({
+                    "result": "Objective: Build an audience, Priority: 5\nObjective: Build initial audience, Priority: 8\nObjective: Launch a token, Priority: 3"
+                })
+                .to_string(),
+            )
+        })
+        .await;
+        let mut agent = strategist(&server.url, vec![]);
+
+        agent
+            .generate_objectives("a new meme account")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            descriptions(&agent),
+            vec![("Build initial audience", 8), ("Launch a token", 3)]
+        );
+    }
+
+    async fn generated_objectives(response: &str) -> Vec<(String, u8)> {
+        let response = response.to_string();
+        let server =
+            spawn_mock_server(move |_| (200, json!({ "result": response }).to_string())).await;
+        let mut agent = strategist(&server.url, vec![]);
+
+        agent
+            .generate_objectives("a new meme account")
+            .await
+            .unwrap();
+
+        agent
+            .objectives
+            .iter()
+            .map(|objective| (objective.description.clone(), objective.priority))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_generate_objectives_parses_json_array() {
+        let objectives = generated_objectives(
+            "```json\n[{\"description\": \"Grow followers, fast\", \"priority\": 9}, {\"description\": \"Reply to mentions\", \"priority\": \"4\"}]\n```",
+        )
+        .await;
+
+        assert_eq!(
+            objectives,
+            vec![
+                ("Grow followers, fast".to_string(), 9),
+                ("Reply to mentions".to_string(), 4)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_generate_objectives_parses_objective_priority_lines() {
+        let objectives = generated_objectives(
+            "Objective: Grow followers, Priority: 9\nObjective: Reply to mentions, Priority: 4",
+        )
+        .await;
+
+        assert_eq!(
+            objectives,
+            vec![
+                ("Grow followers".to_string(), 9),
+                ("Reply to mentions".to_string(), 4)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_generate_objectives_falls_back_to_freeform_lines() {
+        let objectives = generated_objectives(
+            "Sure! Here is what I'd focus on:\n- Grow followers (7)\n- Reply to mentions 3",
+        )
+        .await;
+
+        assert_eq!(
+            objectives,
+            vec![
+                ("- Grow followers ()".to_string(), 7),
+                ("- Reply to mentions".to_string(), 3)
+            ]
+        );
+    }
+}
@@ -0,0 +1,268 @@
+#![allow(dead_code)]
+
+use ethers::{
+    signers::Signer,
+    types::{Address, Chain},
+};
+use npc_workbench::{
+    adapters::nodes::{agents::LLMModel, connectors::off_chain::ConnectorType},
+    ipfs::IPFSProvider,
+    nibble::Nibble,
+    workflow::Workflow,
+};
+use reqwest::Method;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+pub const TEST_PRIVATE_KEY: &str =
+    "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+pub struct MockServer {
+    pub url: String,
+    pub requests: Arc<Mutex<Vec<MockRequest>>>,
+}
+
+impl MockServer {
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+pub async fn spawn_mock_server<F>(handler: F) -> MockServer
+where
+    F: Fn(&MockRequest) -> (u16, String) + Send + Sync + 'static,
+{
+    spawn_mock_server_with_headers(move |request| {
+        let (status, body) = handler(request);
+        (status, Vec::new(), body)
+    })
+    .await
+}
+
+pub async fn spawn_mock_server_with_headers<F>(handler: F) -> MockServer
+where
+    F: Fn(&MockRequest) -> (u16, Vec<(String, String)>, String) + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let handler = Arc::new(handler);
+
+    let recorded = requests.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(_) => break,
+            };
+            let handler = handler.clone();
+            let recorded = recorded.clone();
+
+            tokio::spawn(async move {
+                let mut buffer = Vec::new();
+                let mut chunk = [0u8; 4096];
+
+                let header_end = loop {
+                    let read = socket.read(&mut chunk).await.unwrap_or(0);
+                    if read == 0 {
+                        return;
+                    }
+                    buffer.extend_from_slice(&chunk[..read]);
+                    if let Some(position) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break position + 4;
+                    }
+                };
+
+                let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+                let mut lines = head.lines();
+                let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+                let method = request_line.next().unwrap_or_default().to_string();
+                let path = request_line.next().unwrap_or_default().to_string();
+                let headers: HashMap<String, String> = lines
+                    .filter_map(|line| line.split_once(':'))
+                    .map(|(k, v)| (k.trim().to_lowercase(), v.trim().to_string()))
+                    .collect();
+
+                let content_length = headers
+                    .get("content-length")
+                    .and_then(|v| v.parse::<usize>().ok())
+                    .unwrap_or(0);
+                while buffer.len() < header_end + content_length {
+                    let read = socket.read(&mut chunk).await.unwrap_or(0);
+                    if read == 0 {
+                        break;
+                    }
+                    buffer.extend_from_slice(&chunk[..read]);
+                }
+
+                let request = MockRequest {
+                    method,
+                    path,
+                    headers,
+                    body: String::from_utf8_lossy(&buffer[header_end..]).to_string(),
+                };
+                let (status, extra_headers, body) = handler(&request);
+                recorded.lock().unwrap().push(request);
+
+                let extra_headers: String = extra_headers
+                    .iter()
+                    .map(|(name, value)| format!("{}: {}\r\n", name, value))
+                    .collect();
+                let response = format!(
+                    "HTTP/1.1 {} OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    extra_headers,
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+                let _ = socket.shutdown().await;
+            });
+        }
+    });
+
+    MockServer { url, requests }
+}
+
+pub async fn spawn_mock_ipfs() -> MockServer {
+    spawn_mock_server(|_| (200, r#"{"Hash":"QmMockHash"}"#.to_string())).await
+}
+
+pub fn offline_nibble(rpc_url: &str, ipfs_url: &str) -> Nibble {
+    Nibble::builder()
+        .owner_private_key(TEST_PRIVATE_KEY)
+        .rpc_url(rpc_url)
+        .ipfs_provider(IPFSProvider::Custom)
+        .ipfs_option("api_url", ipfs_url)
+        .ipfs_option("gateway_url", &format!("{}/ipfs", ipfs_url))
+        .chain(Chain::PolygonAmoy)
+        .build()
+        .unwrap()
+}
+
+pub fn add_rest_connector(nibble: &mut Nibble, name: &str, url: &str) -> String {
+    let owner = nibble.owner_wallet.address();
+    nibble
+        .add_offchain_connector(
+            name,
+            ConnectorType::REST { base_payload: None },
+            url,
+            false,
+            Method::POST,
+            None,
+            None,
+            None,
+            None,
+            &owner,
+            None,
+        )
+        .unwrap()
+        .adapter
+        .id
+        .clone()
+}
+
+pub fn mock_llm_model(url: &str, result_type: &str) -> LLMModel {
+    LLMModel::Other {
+        url: url.to_string(),
+        api_key: None,
+        body: HashMap::new(),
+        result_path: "result".to_string(),
+        result_type: result_type.to_string(),
+    }
+}
+
+pub fn mock_openai_model(base_url: &str, model: &str) -> LLMModel {
+    LLMModel::OpenAI {
+        api_key: "sk-test-key".to_string(),
+        model: model.to_string(),
+        temperature: 0.0,
+        max_completion_tokens: 64,
+        top_p: 1.0,
+        frequency_penalty: 0.0,
+        presence_penalty: 0.0,
+        system_prompt: None,
+        store: None,
+        metadata: None,
+        logit_bias: None,
+        logprobs: None,
+        top_logprobs: None,
+        modalities: None,
+        stop: None,
+        response_format: None,
+        stream: None,
+        parallel_tool_calls: None,
+        tools: None,
+        tool_choice: None,
+        user: None,
+        base_url: Some(base_url.to_string()),
+    }
+}
+
+pub fn node_id_for(workflow: &Workflow, adapter_id: &str) -> String {
+    workflow
+        .nodes
+        .values()
+        .find(|node| node.adapter_id == adapter_id)
+        .map(|node| node.id.clone())
+        .unwrap()
+}
+
+pub fn mock_chain_response(body: &Value, status: &str) -> String {
+    let hash = format!("0x{}", "ab".repeat(32));
+    let result = match body["method"].as_str().unwrap() {
+        "eth_chainId" => json!("0x13882"),
+        "eth_blockNumber" => json!("0x10"),
+        "eth_getTransactionCount" => json!("0x0"),
+        "eth_sendRawTransaction" => json!(hash),
+        "eth_getTransactionByHash" => json!({
+            "hash": hash,
+            "nonce": "0x0",
+            "blockHash": format!("0x{}", "cd".repeat(32)),
+            "blockNumber": "0x10",
+            "transactionIndex": "0x0",
+            "from": format!("{:?}", Address::zero()),
+            "to": format!("{:?}", Address::repeat_byte(0x33)),
+            "value": "0x0",
+            "gas": "0x493e0",
+            "gasPrice": "0x1",
+            "input": "0x",
+            "v": "0x1",
+            "r": "0x1",
+            "s": "0x1"
+        }),
+        "eth_getTransactionReceipt" => json!({
+            "transactionHash": hash,
+            "transactionIndex": "0x0",
+            "blockHash": format!("0x{}", "cd".repeat(32)),
+            "blockNumber": "0x10",
+            "from": format!("{:?}", Address::zero()),
+            "to": format!("{:?}", Address::repeat_byte(0x33)),
+            "cumulativeGasUsed": "0x5208",
+            "gasUsed": "0x5208",
+            "contractAddress": null,
+            "logs": [],
+            "logsBloom": format!("0x{}", "00".repeat(256)),
+            "status": status,
+            "type": "0x2",
+            "effectiveGasPrice": "0x1"
+        }),
+        method => panic!("unexpected RPC method {}", method),
+    };
+    json!({"jsonrpc": "2.0", "id": body["id"], "result": result}).to_string()
+}
@@ -0,0 +1,180 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{offline_nibble, spawn_mock_server, MockServer};
+    use ethers::{
+        abi::{self, Token},
+        types::Address,
+        utils::hex,
+    };
+    use npc_workbench::{
+        adapters::links::conditions::{Condition, ConditionBatcher, ConditionType},
+        nibble::Nibble,
+        workflow::LinkAdapter,
+    };
+    use serde_json::{json, Value};
+    use std::{collections::HashSet, sync::Arc};
+
+    #[tokio::test]
+    async fn test_onchain_conditions_in_one_layer_share_a_multicall() {
+        let batcher = Arc::new(ConditionBatcher::default());
+        let multicall = format!("{:?}", batcher.multicall_address);
+        let rpc = spawn_mock_server(move |request| {
+            let rpc: Value = serde_json::from_str(&request.body).unwrap();
+            assert_eq!(rpc["method"], "eth_call");
+            assert_eq!(rpc["params"][0]["to"], json!(multicall));
+            let balance = abi::encode(&[Token::Uint(7.into())]);
+            let return_data = abi::encode(&[Token::Array(vec![
+                Token::Tuple(vec![Token::Bool(true), Token::Bytes(balance.clone())]),
+                Token::Tuple(vec![Token::Bool(true), Token::Bytes(balance)]),
+            ])]);
+            (
+                200,
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": rpc["id"],
+                    "result": format!("0x{}", hex::encode(return_data))
+                })
+                .to_string(),
+            )
+        })
+        .await;
+        let mut nibble = offline_nibble(&rpc.url, "http://127.0.0.1:5001");
+        let mut condition_ids = Vec::new();
+        for (name, contract) in [("VaultA", 0x11), ("VaultB", 0x22)] {
+            let condition_id = nibble
+                .add_condition(
+                    name,
+                    ConditionType::OnChain {
+                        contract_address: Address::repeat_byte(contract),
+                        function_signature: "totalAssets() view returns (uint256)".to_string(),
+                    },
+                    |_| false,
+                    Some(json!("7")),
+                    false,
+                )
+                .unwrap()
+                .adapter
+                .id
+                .clone();
+            condition_ids.push(condition_id);
+        }
+
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Vaults", false);
+        for condition_id in &condition_ids {
+            workflow.add_link(
+                condition_id.clone(),
+                LinkAdapter::Condition,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            );
+        }
+        workflow.set_condition_batcher(Some(batcher));
+
+        let history = workflow.execute(Some(1), false).await.unwrap();
+
+        assert_eq!(rpc.requests().len(), 1);
+        let succeeded: HashSet<&str> = history
+            .iter()
+            .filter(|entry| entry.result == Some(json!("Condition Success")))
+            .map(|entry| entry.element_id.as_str())
+            .collect();
+        assert_eq!(succeeded.len(), condition_ids.len());
+    }
+
+    async fn balance_rpc(multicall: Address, batched: u64, direct: u64) -> MockServer {
+        spawn_mock_server(move |request| {
+            let rpc: Value = serde_json::from_str(&request.body).unwrap();
+            let balance = |value: u64| abi::encode(&[Token::Uint(value.into())]);
+            let return_data = if rpc["params"][0]["to"] == json!(format!("{:?}", multicall)) {
+                abi::encode(&[Token::Array(vec![Token::Tuple(vec![
+                    Token::Bool(true),
+                    Token::Bytes(balance(batched)),
+                ])])])
+            } else {
+                balance(direct)
+            };
+            (
+                200,
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": rpc["id"],
+                    "result": format!("0x{}", hex::encode(return_data))
+                })
+                .to_string(),
+            )
+        })
+        .await
+    }
+
+    fn balance_condition(nibble: &mut Nibble) -> Condition {
+        nibble
+            .add_condition(
+                "Vault",
+                ConditionType::OnChain {
+                    contract_address: Address::repeat_byte(0x11),
+                    function_signature: "balanceOf(address owner) view returns (uint256)"
+                        .to_string(),
+                },
+                |_| false,
+                Some(json!("7")),
+                false,
+            )
+            .unwrap();
+        nibble.conditions.last().unwrap().clone()
+    }
+
+    #[tokio::test]
+    async fn test_batched_result_is_consumed_by_its_first_check() {
+        let batcher = ConditionBatcher::default();
+        let rpc = balance_rpc(batcher.multicall_address, 7, 8).await;
+        let mut nibble = offline_nibble(&rpc.url, "http://127.0.0.1:5001");
+        let condition = balance_condition(&mut nibble);
+        let owner = json!([format!("{:?}", Address::repeat_byte(0x33))]);
+
+        let resolved = batcher
+            .prefetch(&nibble, &[(&condition, Some(&owner))])
+            .await
+            .unwrap();
+        assert_eq!(resolved, 1);
+
+        let first = condition
+            .check_condition_batched(&nibble, None, Some(owner.clone()), Some(&batcher))
+            .await
+            .unwrap();
+        let retried = condition
+            .check_condition_batched(&nibble, None, Some(owner), Some(&batcher))
+            .await
+            .unwrap();
+
+        assert!(first);
+        assert!(!retried);
+        assert_eq!(rpc.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_batched_result_matches_params_from_previous_result() {
+        let batcher = ConditionBatcher::default();
+        let rpc = balance_rpc(batcher.multicall_address, 7, 8).await;
+        let mut nibble = offline_nibble(&rpc.url, "http://127.0.0.1:5001");
+        let condition = balance_condition(&mut nibble);
+        let previous = json!({ "owner": format!("{:?}", Address::repeat_byte(0x33)) });
+
+        batcher
+            .prefetch(&nibble, &[(&condition, Some(&previous))])
+            .await
+            .unwrap();
+        let response = condition
+            .check_condition_batched(&nibble, Some(previous), None, Some(&batcher))
+            .await
+            .unwrap();
+
+        assert!(response);
+        assert_eq!(rpc.requests().len(), 1);
+    }
+}
@@ -0,0 +1,296 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{offline_nibble, spawn_mock_server, MockRequest, MockServer};
+    use ethers::{
+        abi::Abi,
+        providers::{Http, Provider},
+        types::{
+            transaction::eip2718::TypedTransaction, Address, Chain, Eip1559TransactionRequest, U256,
+        },
+        utils::{hex, rlp::Rlp},
+    };
+    use npc_workbench::{
+        adapters::nodes::connectors::on_chain::{BatchMode, GasOptions, GasOracle},
+        nibble::{FeeMode, Nibble, NonceManager},
+    };
+    use serde_json::{json, Value};
+
+    #[tokio::test]
+    async fn test_fee_history_oracle_computes_eip1559_fees() {
+        let server = spawn_mock_server(|request| {
+            let rpc: Value = serde_json::from_str(&request.body).unwrap();
+            assert_eq!(rpc["method"], "eth_feeHistory");
+            (
+                200,
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": rpc["id"],
+                    "result": {
+                        "oldestBlock": "0x10",
+                        "baseFeePerGas": ["0x3b9aca00", "0x77359400"],
+                        "gasUsedRatio": [0.5],
+                        "reward": [["0x3b9aca00"], ["0x77359400"]]
+                    }
+                })
+                .to_string(),
+            )
+        })
+        .await;
+        let provider: Provider<Http> = Provider::try_from(server.url.as_str()).unwrap();
+
+        let (max_fee_per_gas, max_priority_fee_per_gas) =
+            GasOracle::default().estimate_fees(&provider).await.unwrap();
+
+        assert_eq!(max_priority_fee_per_gas, U256::from(1_500_000_000u64));
+        assert_eq!(max_fee_per_gas, U256::from(5_500_000_000u64));
+    }
+
+    #[tokio::test]
+    async fn test_nibble_dynamic_fees_use_fee_history() {
+        let server = spawn_mock_server(|request| {
+            let rpc: Value = serde_json::from_str(&request.body).unwrap();
+            (
+                200,
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": rpc["id"],
+                    "result": {
+                        "oldestBlock": "0x10",
+                        "baseFeePerGas": ["0x3b9aca00"],
+                        "gasUsedRatio": [0.5],
+                        "reward": [["0x3b9aca00"]]
+                    }
+                })
+                .to_string(),
+            )
+        })
+        .await;
+        let mut nibble = offline_nibble(&server.url, "http://127.0.0.1:5001");
+        let static_fees = (U256::from(44u64), U256::from(25u64));
+
+        assert_eq!(
+            nibble.resolve_fees(static_fees.0, static_fees.1).await,
+            static_fees
+        );
+
+        nibble.set_fee_mode(FeeMode::DynamicFees(GasOracle::default()));
+
+        assert_eq!(
+            nibble.resolve_fees(static_fees.0, static_fees.1).await,
+            (U256::from(3_000_000_000u64), U256::from(1_000_000_000u64))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_nibble_dynamic_fees_fall_back_without_fee_history() {
+        let server = spawn_mock_server(|request| {
+            let rpc: Value = serde_json::from_str(&request.body).unwrap();
+            (
+                200,
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": rpc["id"],
+                    "error": {"code": -32601, "message": "method not found"}
+                })
+                .to_string(),
+            )
+        })
+        .await;
+        let mut nibble = offline_nibble(&server.url, "http://127.0.0.1:5001");
+        nibble.set_fee_mode(FeeMode::DynamicFees(GasOracle::default()));
+
+        assert_eq!(
+            nibble
+                .resolve_fees(U256::from(44u64), U256::from(25u64))
+                .await,
+            (U256::from(44u64), U256::from(25u64))
+        );
+    }
+
+    fn block_response(request: &MockRequest, base_fee: Option<&str>) -> String {
+        let rpc: Value = serde_json::from_str(&request.body).unwrap();
+        let zero = format!("0x{}", "0".repeat(64));
+        let mut block = json!({
+            "hash": zero,
+            "parentHash": zero,
+            "sha3Uncles": zero,
+            "miner": "0x0000000000000000000000000000000000000000",
+            "stateRoot": zero,
+            "transactionsRoot": zero,
+            "receiptsRoot": zero,
+            "number": "0x10",
+            "gasUsed": "0x0",
+            "gasLimit": "0x1c9c380",
+            "extraData": "0x",
+            "logsBloom": format!("0x{}", "0".repeat(512)),
+            "timestamp": "0x0",
+            "difficulty": "0x0",
+            "uncles": [],
+            "transactions": []
+        });
+        if let Some(base_fee) = base_fee {
+            block["baseFeePerGas"] = json!(base_fee);
+        }
+        json!({"jsonrpc": "2.0", "id": rpc["id"], "result": block}).to_string()
+    }
+
+    #[tokio::test]
+    async fn test_detect_fee_mode_switches_to_legacy_without_base_fee() {
+        let legacy = spawn_mock_server(|request| (200, block_response(request, None))).await;
+        let mut nibble = offline_nibble(&legacy.url, "http://127.0.0.1:5001");
+
+        assert_eq!(
+            nibble.detect_fee_mode().await.unwrap(),
+            &FeeMode::Legacy(None)
+        );
+
+        let london =
+            spawn_mock_server(|request| (200, block_response(request, Some("0x3b9aca00")))).await;
+        let mut nibble = offline_nibble(&london.url, "http://127.0.0.1:5001");
+
+        assert_eq!(nibble.detect_fee_mode().await.unwrap(), &FeeMode::Static);
+    }
+
+    #[tokio::test]
+    async fn test_legacy_fee_mode_builds_flat_gas_price_transactions() {
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        let request = Eip1559TransactionRequest {
+            gas: Some(U256::from(300_000)),
+            max_fee_per_gas: Some(U256::from(44u64)),
+            max_priority_fee_per_gas: Some(U256::from(25u64)),
+            nonce: Some(U256::from(7)),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            nibble.build_transaction(request.clone()).await,
+            TypedTransaction::Eip1559(_)
+        ));
+
+        nibble.set_fee_mode(FeeMode::Legacy(Some(U256::from(30u64))));
+
+        match nibble.build_transaction(request).await {
+            TypedTransaction::Legacy(legacy) => {
+                assert_eq!(legacy.gas_price, Some(U256::from(30u64)));
+                assert_eq!(legacy.gas, Some(U256::from(300_000)));
+                assert_eq!(legacy.nonce, Some(U256::from(7)));
+            }
+            other => panic!("expected a legacy transaction, got {:?}", other),
+        }
+    }
+
+    async fn send_rpc() -> MockServer {
+        spawn_mock_server(|request| {
+            let rpc: Value = serde_json::from_str(&request.body).unwrap();
+            let mut reply = match rpc["method"].as_str() {
+                Some("eth_getTransactionCount") => json!({"result": "0x0"}),
+                _ => json!({"error": {"code": -32000, "message": "rejected"}}),
+            };
+            reply["jsonrpc"] = json!("2.0");
+            reply["id"] = rpc["id"].clone();
+            (200, reply.to_string())
+        })
+        .await
+    }
+
+    async fn send_ping(nibble: &mut Nibble, rpc: &MockServer) -> TypedTransaction {
+        let abi: Abi = serde_json::from_value(json!([{
+            "type": "function",
+            "name": "ping",
+            "inputs": [],
+            "outputs": [],
+            "stateMutability": "nonpayable"
+        }]))
+        .unwrap();
+        let connector = nibble
+            .add_onchain_connector(
+                "Ping",
+                Some(Address::repeat_byte(0x11)),
+                false,
+                None,
+                Some(abi),
+                Chain::PolygonAmoy,
+                Some(GasOptions {
+                    gas_limit: Some(U256::from(100_000)),
+                    ..GasOptions::default()
+                }),
+                vec![],
+                BatchMode::SequentialAbort,
+            )
+            .unwrap()
+            .adapter
+            .clone();
+
+        let error = connector
+            .execute_onchain_connector(
+                nibble.provider.clone(),
+                nibble.owner_wallet.clone(),
+                Some("ping"),
+                None,
+                &NonceManager::default(),
+                &nibble.fee_mode,
+                1,
+            )
+            .await
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("rejected"));
+
+        let raw = rpc
+            .requests()
+            .iter()
+            .map(|request| serde_json::from_str::<Value>(&request.body).unwrap())
+            .find(|body| body["method"] == "eth_sendRawTransaction")
+            .map(|body| body["params"][0].as_str().unwrap().to_string())
+            .unwrap();
+        TypedTransaction::decode_signed(&Rlp::new(&hex::decode(&raw[2..]).unwrap()))
+            .unwrap()
+            .0
+    }
+
+    #[tokio::test]
+    async fn test_onchain_connector_follows_nibble_legacy_fee_mode() {
+        let rpc = send_rpc().await;
+        let mut nibble = offline_nibble(&rpc.url, "http://127.0.0.1:5001");
+        nibble.set_fee_mode(FeeMode::Legacy(Some(U256::from(30u64))));
+
+        match send_ping(&mut nibble, &rpc).await {
+            TypedTransaction::Legacy(legacy) => {
+                assert_eq!(legacy.gas_price, Some(U256::from(30u64)));
+                assert_eq!(legacy.gas, Some(U256::from(100_000)));
+            }
+            other => panic!("expected a legacy transaction, got {:?}", other),
+        }
+        assert!(rpc
+            .requests()
+            .iter()
+            .all(|request| !request.body.contains("eth_feeHistory")));
+    }
+
+    #[tokio::test]
+    async fn test_onchain_connector_uses_nibble_dynamic_fee_oracle() {
+        let oracle = spawn_mock_server(|_| {
+            (
+                200,
+                json!({"max_fee_per_gas": "77", "max_priority_fee_per_gas": "7"}).to_string(),
+            )
+        })
+        .await;
+        let rpc = send_rpc().await;
+        let mut nibble = offline_nibble(&rpc.url, "http://127.0.0.1:5001");
+        nibble.set_fee_mode(FeeMode::DynamicFees(GasOracle::External {
+            url: oracle.url.clone(),
+        }));
+
+        match send_ping(&mut nibble, &rpc).await {
+            TypedTransaction::Eip1559(request) => {
+                assert_eq!(request.max_fee_per_gas, Some(U256::from(77u64)));
+                assert_eq!(request.max_priority_fee_per_gas, Some(U256::from(7u64)));
+            }
+            other => panic!("expected an EIP-1559 transaction, got {:?}", other),
+        }
+        assert_eq!(oracle.requests().len(), 1);
+    }
+}
@@ -0,0 +1,53 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::TEST_PRIVATE_KEY;
+    use ethers::types::Chain;
+    use npc_workbench::{ipfs::IPFSProvider, nibble::Nibble};
+    use std::collections::HashMap;
+
+    fn infura_nibble(
+        ipfs_config: HashMap<String, String>,
+    ) -> Result<Nibble, Box<dyn std::error::Error + Send + Sync>> {
+        Nibble::new(
+            TEST_PRIVATE_KEY,
+            "http://127.0.0.1:8545",
+            IPFSProvider::Infura,
+            ipfs_config,
+            Chain::PolygonAmoy,
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_infura_config_with_required_keys_is_accepted() {
+        let mut ipfs_config = HashMap::new();
+        ipfs_config.insert("project_id".to_string(), "project".to_string());
+        ipfs_config.insert("project_secret".to_string(), "secret".to_string());
+
+        assert!(infura_nibble(ipfs_config).is_ok());
+    }
+
+    #[test]
+    fn test_infura_config_missing_keys_lists_them() {
+        let error = infura_nibble(HashMap::new()).err().unwrap().to_string();
+
+        assert!(error.contains("Infura"));
+        assert!(error.contains("project_id"));
+        assert!(error.contains("project_secret"));
+    }
+
+    #[test]
+    fn test_infura_config_with_empty_secret_is_rejected() {
+        let mut ipfs_config = HashMap::new();
+        ipfs_config.insert("project_id".to_string(), "project".to_string());
+        ipfs_config.insert("project_secret".to_string(), " ".to_string());
+
+        let error = infura_nibble(ipfs_config).err().unwrap().to_string();
+
+        assert!(error.contains("project_secret"));
+        assert!(!error.contains("project_id"));
+    }
+}
@@ -0,0 +1,44 @@
+mod common;
+
+#[cfg(all(test, feature = "mock-ipfs"))]
+mod tests {
+    use crate::common::{add_rest_connector, offline_nibble};
+    use npc_workbench::{ipfs::MockIPFSClient, workflow::WorkflowState};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_build_modify_adapters_uploads_to_mock() {
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        add_rest_connector(&mut nibble, "Ping", "http://127.0.0.1:9");
+        let ipfs = MockIPFSClient::new();
+
+        let modify = nibble.build_modify_adapters(&ipfs).await.unwrap();
+
+        assert_eq!(ipfs.uploads(), vec![modify.connectors[0].metadata.clone()]);
+        let metadata = ipfs.get_json(&modify.connectors[0].metadata).unwrap();
+        assert_eq!(metadata["name"], "Ping");
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_round_trips_through_mock() {
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        let ipfs = Arc::new(MockIPFSClient::new());
+        nibble.ipfs_client = ipfs.clone();
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Checkpointed", false);
+        let state = WorkflowState {
+            workflow_id: workflow.id.clone(),
+            repetition: 1,
+            successful_repetitions: 0,
+            elements_completed: 0,
+            context: None,
+            execution_history: vec![],
+        };
+
+        let cid = workflow.checkpoint(&state).await.unwrap();
+        let restored = workflow.restore(&cid).await.unwrap();
+
+        assert_eq!(restored, state);
+        assert_eq!(ipfs.uploads(), vec![cid]);
+        assert!(workflow.restore("missing").await.is_err());
+    }
+}
@@ -0,0 +1,619 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{
+        mock_llm_model, mock_openai_model, offline_nibble, spawn_mock_server, MockServer,
+    };
+    use ethers::types::Address;
+    use npc_workbench::adapters::{
+        links::evaluations::{configure_new_evaluation, EvaluationType},
+        nodes::agents::{
+            call_embeddings_api, call_llm_api, call_llm_api_with_examples, call_llm_api_with_tools,
+            configure_new_agent, Agent, AgentError, LLMModel, LlmObserver, ToolCall,
+        },
+    };
+    use serde_json::{json, Value};
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    #[test]
+    fn test_redacted_model_serialization_omits_api_key() {
+        let models = vec![
+            LLMModel::Claude {
+                api_key: "sk-ant-secret-key".to_string(),
+                model: "claude-3-5-sonnet-20241022".to_string(),
+                temperature: 0.7,
+                max_tokens: 1024,
+                top_k: None,
+                top_p: 1.0,
+                system_prompt: None,
+                version: "2023-06-01".to_string(),
+                stop_sequences: None,
+                stream: false,
+                metadata: None,
+                tool_choice: None,
+                tools: None,
+                base_url: None,
+            },
+            LLMModel::Other {
+                url: "http://localhost:8080".to_string(),
+                api_key: Some("sk-ant-secret-key".to_string()),
+                body: HashMap::new(),
+                result_path: "result".to_string(),
+                result_type: "string".to_string(),
+            },
+        ];
+
+        for model in models {
+            let redacted = model.to_json_with(true);
+            assert_eq!(redacted["api_key"], "***");
+            assert!(!redacted.to_string().contains("sk-ant-secret-key"));
+            assert!(!format!("{:?}", model).contains("sk-ant-secret-key"));
+            assert_eq!(model.to_json()["api_key"], "sk-ant-secret-key");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_length_truncated_response_returns_typed_error() {
+        let server = spawn_mock_server(|_| {
+            (
+                200,
+                json!({"result": "{\"caption\": \"gm fr", "finish_reason": "length"}).to_string(),
+            )
+        })
+        .await;
+
+        let error = call_llm_api(&mock_llm_model(&server.url, "string"), "Write a caption")
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            error.downcast_ref::<AgentError>(),
+            Some(&AgentError::Truncated {
+                finish_reason: "length".to_string(),
+                partial: "{\"caption\": \"gm fr".to_string(),
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_openai_requests_use_configured_base_url() {
+        let server = spawn_mock_server(|_| {
+            (
+                200,
+                json!({
+                    "choices": [{
+                        "message": {"role": "assistant", "content": "gm"},
+                        "finish_reason": "stop"
+                    }]
+                })
+                .to_string(),
+            )
+        })
+        .await;
+        let model = LLMModel::OpenAI {
+            api_key: "sk-proxy-key".to_string(),
+            model: "gpt-4o".to_string(),
+            temperature: 0.0,
+            max_completion_tokens: 64,
+            top_p: 1.0,
+            frequency_penalty: 0.0,
+            presence_penalty: 0.0,
+            system_prompt: None,
+            store: None,
+            metadata: None,
+            logit_bias: None,
+            logprobs: None,
+            top_logprobs: None,
+            modalities: None,
+            stop: None,
+            response_format: None,
+            stream: None,
+            parallel_tool_calls: None,
+            tools: None,
+            tool_choice: None,
+            user: None,
+            base_url: Some(format!("{}/openai/v1/", server.url)),
+        };
+
+        let completion = call_llm_api(&model, "Say gm").await.unwrap();
+
+        assert_eq!(completion, "gm");
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/openai/v1/chat/completions");
+        assert_eq!(
+            requests[0].headers.get("authorization").map(String::as_str),
+            Some("Bearer sk-proxy-key")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_few_shot_examples_precede_input_in_message_order() {
+        let server = spawn_mock_server(|_| {
+            (
+                200,
+                json!({
+                    "choices": [{
+                        "message": {"role": "assistant", "content": "{\"mood\": \"hype\"}"},
+                        "finish_reason": "stop"
+                    }]
+                })
+                .to_string(),
+            )
+        })
+        .await;
+        let model = LLMModel::OpenAI {
+            api_key: "sk-test".to_string(),
+            model: "gpt-4o".to_string(),
+            temperature: 0.0,
+            max_completion_tokens: 64,
+            top_p: 1.0,
+            frequency_penalty: 0.0,
+            presence_penalty: 0.0,
+            system_prompt: Some("Reply with JSON only.".to_string()),
+            store: None,
+            metadata: None,
+            logit_bias: None,
+            logprobs: None,
+            top_logprobs: None,
+            modalities: None,
+            stop: None,
+            response_format: None,
+            stream: None,
+            parallel_tool_calls: None,
+            tools: None,
+            tool_choice: None,
+            user: None,
+            base_url: Some(server.url.clone()),
+        };
+        let examples = vec![
+            ("gm frens".to_string(), "{\"mood\": \"cozy\"}".to_string()),
+            (
+                "number go up".to_string(),
+                "{\"mood\": \"greedy\"}".to_string(),
+            ),
+        ];
+
+        call_llm_api_with_examples(&model, "wagmi", &examples)
+            .await
+            .unwrap();
+
+        let body: Value = serde_json::from_str(&server.requests()[0].body).unwrap();
+        let messages: Vec<(&str, &str)> = body["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|message| {
+                (
+                    message["role"].as_str().unwrap(),
+                    message["content"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                ("system", "Reply with JSON only."),
+                ("user", "gm frens"),
+                ("assistant", "{\"mood\": \"cozy\"}"),
+                ("user", "number go up"),
+                ("assistant", "{\"mood\": \"greedy\"}"),
+                ("user", "wagmi"),
+            ]
+        );
+    }
+
+    async fn spawn_embeddings_server() -> MockServer {
+        spawn_mock_server(|request| {
+            let body: Value = serde_json::from_str(&request.body).unwrap();
+            let embedding = match body["input"].as_str().unwrap() {
+                "memes about cats" => json!([1.0, 0.0]),
+                "cat memes" => json!([0.9, 0.1]),
+                _ => json!([0.0, 1.0]),
+            };
+            (
+                200,
+                json!({ "data": [{ "embedding": embedding }] }).to_string(),
+            )
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_openai_embeddings_return_vector() {
+        let server = spawn_embeddings_server().await;
+
+        let embedding = call_embeddings_api(
+            &mock_openai_model(&server.url, "text-embedding-3-small"),
+            "cat memes",
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(embedding, vec![0.9, 0.1]);
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/embeddings");
+        let body: Value = serde_json::from_str(&requests[0].body).unwrap();
+        assert_eq!(body["model"], "text-embedding-3-small");
+    }
+
+    #[tokio::test]
+    async fn test_embedding_similarity_evaluation_gates_on_threshold() {
+        let server = spawn_embeddings_server().await;
+        let evaluation = configure_new_evaluation(
+            "OnTopic",
+            EvaluationType::EmbeddingSimilarity {
+                reference: "memes about cats".to_string(),
+                threshold: 0.8,
+                model: mock_openai_model(&server.url, "text-embedding-3-small"),
+            },
+            false,
+            &Address::zero(),
+        )
+        .unwrap();
+
+        let on_topic = evaluation
+            .check_evaluation(
+                vec![],
+                Some(json!("cat memes")),
+                None,
+                None,
+                "interaction".to_string(),
+            )
+            .await
+            .unwrap();
+        let off_topic = evaluation
+            .check_evaluation(
+                vec![],
+                Some(json!("gas prices")),
+                None,
+                None,
+                "interaction".to_string(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(on_topic["decision"], json!(true));
+        assert!(on_topic["score"].as_f64().unwrap() > 0.99);
+        assert_eq!(on_topic["data"], json!("cat memes"));
+        assert_eq!(off_topic["decision"], json!(false));
+        assert_eq!(off_topic["score"].as_f64().unwrap(), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_openai_tool_calls_are_returned_with_text() {
+        let server = spawn_mock_server(|_| {
+            (
+                200,
+                json!({
+                    "choices": [{
+                        "message": {
+                            "role": "assistant",
+                            "content": null,
+                            "tool_calls": [{
+                                "id": "call_1",
+                                "type": "function",
+                                "function": {
+                                    "name": "PostMeme",
+                                    "arguments": "{\"caption\": \"gm\"}"
+                                }
+                            }]
+                        },
+                        "finish_reason": "tool_calls"
+                    }]
+                })
+                .to_string(),
+            )
+        })
+        .await;
+        let mut model = mock_openai_model(&server.url, "gpt-4o");
+        if let LLMModel::OpenAI { tools, .. } = &mut model {
+            *tools = Some(vec![json!({
+                "type": "function",
+                "function": {"name": "PostMeme", "parameters": {"type": "object"}}
+            })]);
+        }
+
+        let response = call_llm_api_with_tools(&model, "Post a meme", &[])
+            .await
+            .unwrap();
+
+        assert_eq!(response.text, "");
+        assert_eq!(
+            response.tool_calls,
+            vec![ToolCall {
+                id: Some("call_1".to_string()),
+                name: "PostMeme".to_string(),
+                arguments: json!({"caption": "gm"}),
+            }]
+        );
+        let body: Value = serde_json::from_str(&server.requests()[0].body).unwrap();
+        assert_eq!(body["tools"][0]["function"]["name"], "PostMeme");
+    }
+
+    #[tokio::test]
+    async fn test_claude_tool_use_blocks_are_returned_as_tool_calls() {
+        let server = spawn_mock_server(|_| {
+            (
+                200,
+                json!({
+                    "content": [
+                        {"type": "text", "text": "Posting now"},
+                        {
+                            "type": "tool_use",
+                            "id": "toolu_1",
+                            "name": "PostMeme",
+                            "input": {"caption": "gm"}
+                        }
+                    ],
+                    "stop_reason": "tool_use"
+                })
+                .to_string(),
+            )
+        })
+        .await;
+        let model = LLMModel::Claude {
+            api_key: "sk-ant-key".to_string(),
+            model: "claude-3-5-sonnet-20241022".to_string(),
+            temperature: 0.0,
+            max_tokens: 64,
+            top_k: None,
+            top_p: 1.0,
+            system_prompt: None,
+            version: "2023-06-01".to_string(),
+            stop_sequences: None,
+            stream: false,
+            metadata: None,
+            tool_choice: None,
+            tools: None,
+            base_url: Some(server.url.clone()),
+        };
+
+        let response = call_llm_api_with_tools(&model, "Post a meme", &[])
+            .await
+            .unwrap();
+
+        assert_eq!(response.text, "Posting now");
+        assert_eq!(response.tool_calls.len(), 1);
+        assert_eq!(response.tool_calls[0].name, "PostMeme");
+        assert_eq!(response.tool_calls[0].arguments, json!({"caption": "gm"}));
+    }
+
+    #[tokio::test]
+    async fn test_other_model_defaults_to_openai_style_response() {
+        let server = spawn_mock_server(|_| {
+            (
+                200,
+                json!({"choices": [{"message": {"role": "assistant", "content": "gm"}}]})
+                    .to_string(),
+            )
+        })
+        .await;
+        let model = LLMModel::Other {
+            url: server.url.clone(),
+            api_key: Some("azure-key".to_string()),
+            body: HashMap::from([
+                ("model".to_string(), "gpt-4o-mini".to_string()),
+                ("temperature".to_string(), "1.0".to_string()),
+                (
+                    "messages".to_string(),
+                    json!([{"role": "system", "content": "You are a helpful assistant."}])
+                        .to_string(),
+                ),
+            ]),
+            result_path: "".to_string(),
+            result_type: "".to_string(),
+        };
+
+        let completion = call_llm_api(&model, "Say gm").await.unwrap();
+
+        assert_eq!(completion, "gm");
+        let body: Value = serde_json::from_str(&server.requests()[0].body).unwrap();
+        assert_eq!(body["model"], "gpt-4o-mini");
+        assert_eq!(body["temperature"], json!(1.0));
+        assert_eq!(
+            body["messages"],
+            json!([
+                {"role": "system", "content": "You are a helpful assistant."},
+                {"role": "user", "content": "Say gm"}
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_other_model_joins_array_at_json_pointer() {
+        let server = spawn_mock_server(|_| {
+            (
+                200,
+                json!({"output": {"lines": ["gm", "wagmi"]}}).to_string(),
+            )
+        })
+        .await;
+        let model = LLMModel::Other {
+            url: server.url.clone(),
+            api_key: None,
+            body: HashMap::new(),
+            result_path: "/output/lines".to_string(),
+            result_type: "array-join".to_string(),
+        };
+
+        let completion = call_llm_api(&model, "Say gm").await.unwrap();
+
+        assert_eq!(completion, "gm\nwagmi");
+        let body: Value = serde_json::from_str(&server.requests()[0].body).unwrap();
+        assert_eq!(body, json!({"prompt": "Say gm"}));
+    }
+
+    #[derive(Debug, Default)]
+    struct RecordingObserver {
+        requests: Mutex<Vec<(String, Value)>>,
+        responses: Mutex<Vec<String>>,
+    }
+
+    impl LlmObserver for RecordingObserver {
+        fn on_request(&self, model_name: &str, prompt: &Value) {
+            self.requests
+                .lock()
+                .unwrap()
+                .push((model_name.to_string(), prompt.clone()));
+        }
+
+        fn on_response(&self, raw: &str) {
+            self.responses.lock().unwrap().push(raw.to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_nibble_observer_sees_redacted_requests_and_raw_responses() {
+        let server = spawn_mock_server(|_| (200, json!({"result": "gm"}).to_string())).await;
+        let observer = Arc::new(RecordingObserver::default());
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        nibble.set_llm_observer(observer.clone());
+        nibble
+            .add_agent(
+                "Observed",
+                "meme poster",
+                "playful",
+                "",
+                false,
+                false,
+                LLMModel::Other {
+                    url: server.url.clone(),
+                    api_key: Some("bearer-secret".to_string()),
+                    body: HashMap::from([
+                        ("model".to_string(), "custom-llm".to_string()),
+                        ("api_key".to_string(), "body-secret".to_string()),
+                        (
+                            "auth".to_string(),
+                            json!({"client_secret": "nested-secret", "region": "eu"}).to_string(),
+                        ),
+                    ]),
+                    result_path: "result".to_string(),
+                    result_type: "string".to_string(),
+                },
+                false,
+                None,
+                None,
+                None,
+                vec![],
+            )
+            .unwrap();
+
+        let completion = nibble.agents[0].execute_agent("Say gm").await.unwrap();
+
+        assert_eq!(completion, "gm");
+        let requests = observer.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        let (model_name, prompt) = &requests[0];
+        assert_eq!(model_name, "custom-llm");
+        assert_eq!(prompt["prompt"], "Say gm");
+        assert_eq!(prompt["api_key"], "***");
+        assert_eq!(prompt["auth"]["client_secret"], "***");
+        assert_eq!(prompt["auth"]["region"], "eu");
+        assert_eq!(
+            *observer.responses.lock().unwrap(),
+            vec![json!({"result": "gm"}).to_string()]
+        );
+
+        let body: Value = serde_json::from_str(&server.requests()[0].body).unwrap();
+        assert_eq!(body["api_key"], "body-secret");
+    }
+
+    fn pirate_agent(model: LLMModel) -> Agent {
+        configure_new_agent(
+            "Pirate",
+            "meme poster",
+            "salty",
+            "Speak like a pirate.",
+            false,
+            false,
+            false,
+            model,
+            &Address::zero(),
+            None,
+            None,
+            None,
+            vec![],
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_openai_agent_system_is_appended_to_model_system_prompt() {
+        let server = spawn_mock_server(|_| {
+            (
+                200,
+                json!({"choices": [{"message": {"role": "assistant", "content": "arr"}}]})
+                    .to_string(),
+            )
+        })
+        .await;
+        let mut model = mock_openai_model(&server.url, "gpt-4o");
+        if let LLMModel::OpenAI { system_prompt, .. } = &mut model {
+            *system_prompt = Some("Reply with JSON only.".to_string());
+        }
+
+        pirate_agent(model).execute_agent("gm").await.unwrap();
+
+        let body: Value = serde_json::from_str(&server.requests()[0].body).unwrap();
+        assert_eq!(
+            body["messages"][0],
+            json!({
+                "role": "system",
+                "content": "Reply with JSON only.\n\nSpeak like a pirate."
+            })
+        );
+        assert_eq!(body["messages"].as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_claude_agent_system_is_appended_to_model_system_prompt() {
+        let server = spawn_mock_server(|_| {
+            (
+                200,
+                json!({"content": [{"type": "text", "text": "arr"}], "stop_reason": "end_turn"})
+                    .to_string(),
+            )
+        })
+        .await;
+        let model = |system_prompt: Option<&str>| LLMModel::Claude {
+            api_key: "sk-ant-key".to_string(),
+            model: "claude-3-5-sonnet-20241022".to_string(),
+            temperature: 0.0,
+            max_tokens: 64,
+            top_k: None,
+            top_p: 1.0,
+            system_prompt: system_prompt.map(str::to_string),
+            version: "2023-06-01".to_string(),
+            stop_sequences: None,
+            stream: false,
+            metadata: None,
+            tool_choice: None,
+            tools: None,
+            base_url: Some(server.url.clone()),
+        };
+
+        pirate_agent(model(Some("You analyse memes.")))
+            .execute_agent("gm")
+            .await
+            .unwrap();
+        pirate_agent(model(None)).execute_agent("gm").await.unwrap();
+
+        let systems: Vec<Value> = server
+            .requests()
+            .iter()
+            .map(|request| serde_json::from_str::<Value>(&request.body).unwrap()["system"].clone())
+            .collect();
+        assert_eq!(
+            systems,
+            vec![
+                json!("You analyse memes.\n\nSpeak like a pirate."),
+                json!("Speak like a pirate.")
+            ]
+        );
+    }
+}
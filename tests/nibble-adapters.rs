@@ -0,0 +1,590 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{
+        add_rest_connector, mock_chain_response, mock_llm_model, offline_nibble, spawn_mock_ipfs,
+        spawn_mock_server, MockServer,
+    };
+    use ethers::{
+        abi::{Abi, Token},
+        signers::{LocalWallet, Signer},
+        types::{transaction::eip2718::TypedTransaction, Address},
+        utils::{hex, rlp::Rlp},
+    };
+    use npc_workbench::{
+        adapters::nodes::{agents::LLMModel, connectors::off_chain::ConnectorType},
+        nibble::{Adapter, ContractInfo, Nibble, RemoveAdapters},
+        workflow::NodeAdapter,
+    };
+    use reqwest::Method;
+    use serde_json::{json, Value};
+    use std::{collections::HashMap, sync::Arc, time::Duration};
+
+    fn test_model() -> LLMModel {
+        LLMModel::Other {
+            url: "http://127.0.0.1:0".to_string(),
+            api_key: None,
+            body: HashMap::new(),
+            result_path: "result".to_string(),
+            result_type: "string".to_string(),
+        }
+    }
+
+    async fn writer_agent_nibble(rpc_url: &str) -> (Nibble, String) {
+        let ipfs = spawn_mock_ipfs().await;
+        let mut nibble = offline_nibble(rpc_url, &ipfs.url);
+        nibble.provider = nibble.provider.clone().interval(Duration::from_millis(10));
+        nibble.contracts.push(ContractInfo {
+            name: "NibbleAccessControl".to_string(),
+            address: Address::repeat_byte(0x33),
+        });
+        let agent_id = nibble
+            .add_agent(
+                "Writer",
+                "role",
+                "personality",
+                "system",
+                false,
+                false,
+                test_model(),
+                false,
+                None,
+                None,
+                None,
+                vec![],
+            )
+            .unwrap()
+            .adapter
+            .id
+            .clone();
+        (nibble, agent_id)
+    }
+
+    async fn access_controls_rpc(status: &'static str, version: Option<u64>) -> MockServer {
+        spawn_mock_server(move |request| {
+            let body: Value = serde_json::from_str(&request.body).unwrap();
+            if body["method"] != "eth_call" {
+                return (200, mock_chain_response(&body, status));
+            }
+            let response = match version {
+                Some(version) => json!({
+                    "jsonrpc": "2.0",
+                    "id": body["id"],
+                    "result": format!("0x{:064x}", version)
+                }),
+                None => json!({
+                    "jsonrpc": "2.0",
+                    "id": body["id"],
+                    "error": {"code": 3, "message": "execution reverted"}
+                }),
+            };
+            (200, response.to_string())
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_set_agent_writer_updates_flag_after_confirmed_tx() {
+        let rpc = access_controls_rpc("0x1", Some(2)).await;
+        let (mut nibble, agent_id) = writer_agent_nibble(&rpc.url).await;
+        let agent_address = nibble.agents[0].wallet.address();
+
+        nibble.set_agent_writer(&agent_id, true).await.unwrap();
+
+        assert!(nibble.agents[0].write_role);
+        assert!(!nibble.agents[0].admin_role);
+        let raw = rpc
+            .requests()
+            .iter()
+            .map(|request| serde_json::from_str::<Value>(&request.body).unwrap())
+            .find(|body| body["method"] == "eth_sendRawTransaction")
+            .unwrap()["params"][0]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let (tx, _) =
+            TypedTransaction::decode_signed(&Rlp::new(&hex::decode(&raw[2..]).unwrap())).unwrap();
+        let abi: Abi =
+            serde_json::from_str(include_str!("../abis/NibbleAccessControls.json")).unwrap();
+        let function = abi.function("setAgentWriter").unwrap();
+        let data = tx.data().unwrap();
+
+        assert_eq!(tx.to_addr(), Some(&Address::repeat_byte(0x33)));
+        assert_eq!(&data[..4], &function.short_signature()[..]);
+        assert_eq!(
+            function.decode_input(&data[4..]).unwrap(),
+            vec![Token::Address(agent_address), Token::Bool(true)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_agent_writer_keeps_flag_when_tx_reverts() {
+        let rpc = access_controls_rpc("0x0", Some(2)).await;
+        let (mut nibble, agent_id) = writer_agent_nibble(&rpc.url).await;
+
+        let error = nibble.set_agent_writer(&agent_id, true).await.unwrap_err();
+
+        assert!(error.to_string().contains("reverted"));
+        assert!(!nibble.agents[0].write_role);
+    }
+
+    #[tokio::test]
+    async fn test_set_agent_writer_rejects_access_controls_without_setter() {
+        let rpc = access_controls_rpc("0x1", None).await;
+        let (mut nibble, agent_id) = writer_agent_nibble(&rpc.url).await;
+
+        let error = nibble.set_agent_writer(&agent_id, true).await.unwrap_err();
+
+        assert!(error.to_string().contains("predates setAgentWriter"));
+        assert!(!nibble.agents[0].write_role);
+        assert!(!rpc.requests().iter().any(|request| {
+            serde_json::from_str::<Value>(&request.body).unwrap()["method"]
+                == "eth_sendRawTransaction"
+        }));
+    }
+
+    #[tokio::test]
+    async fn test_set_agent_writer_reflected_in_modify_adapters() {
+        let ipfs = spawn_mock_ipfs().await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &ipfs.url);
+
+        let agent_id = nibble
+            .add_agent(
+                "Writer",
+                "role",
+                "personality",
+                "system",
+                false,
+                false,
+                test_model(),
+                false,
+                None,
+                None,
+                None,
+                vec![],
+            )
+            .unwrap()
+            .adapter
+            .id
+            .clone();
+
+        let modify = nibble
+            .build_modify_adapters(nibble.ipfs_client.as_ref())
+            .await
+            .unwrap();
+        assert!(!modify.agents[0].writer);
+
+        nibble.set_agent_writer(&agent_id, true).await.unwrap();
+        let modify = nibble
+            .build_modify_adapters(nibble.ipfs_client.as_ref())
+            .await
+            .unwrap();
+        assert!(modify.agents[0].writer);
+
+        nibble.set_agent_writer(&agent_id, false).await.unwrap();
+        let modify = nibble
+            .build_modify_adapters(nibble.ipfs_client.as_ref())
+            .await
+            .unwrap();
+        assert!(!modify.agents[0].writer);
+    }
+
+    #[tokio::test]
+    async fn test_workflow_resolves_saved_agent() {
+        let llm = spawn_mock_server(|_| (200, json!({"result": "gm"}).to_string())).await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &llm.url);
+
+        nibble
+            .add_agent(
+                "Saved",
+                "role",
+                "personality",
+                "system",
+                false,
+                false,
+                mock_llm_model(&llm.url, "string"),
+                false,
+                None,
+                None,
+                None,
+                vec![],
+            )
+            .unwrap();
+        let saved_agent = nibble.agents.pop().unwrap();
+        let agent_id = saved_agent.id.clone();
+        nibble.saved_agents.push(saved_agent);
+
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Loaded", false);
+        workflow.add_node(
+            agent_id,
+            NodeAdapter::Agent,
+            None,
+            Some(json!("Say gm")),
+            None,
+            None,
+            None,
+        );
+
+        let history = workflow.execute(Some(1), false).await.unwrap();
+
+        assert_eq!(llm.requests().len(), 1);
+        assert_eq!(history[0].result, Some(json!("gm")));
+    }
+
+    #[tokio::test]
+    async fn test_verify_adapters_flags_unpersisted_and_missing_ids() {
+        let ipfs = spawn_mock_ipfs().await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &ipfs.url);
+
+        let local_agent = nibble
+            .add_agent(
+                "Local",
+                "role",
+                "personality",
+                "system",
+                false,
+                false,
+                test_model(),
+                false,
+                None,
+                None,
+                None,
+                vec![],
+            )
+            .unwrap()
+            .adapter
+            .id
+            .clone();
+        nibble
+            .add_agent(
+                "Saved",
+                "role",
+                "personality",
+                "system",
+                false,
+                false,
+                test_model(),
+                false,
+                None,
+                None,
+                None,
+                vec![],
+            )
+            .unwrap();
+        let saved_agent = nibble.agents.pop().unwrap();
+        let saved_agent_id = saved_agent.id.clone();
+        nibble.saved_agents.push(saved_agent);
+
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Unpersisted", false);
+        for adapter_id in [local_agent.clone(), saved_agent_id, "deleted".to_string()] {
+            workflow.add_node(adapter_id, NodeAdapter::Agent, None, None, None, None, None);
+        }
+
+        let verification = workflow.verify_adapters();
+
+        assert_eq!(verification.unpersisted, vec![local_agent]);
+        assert_eq!(verification.missing, vec!["deleted".to_string()]);
+        assert!(!verification.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_reencrypt_all_without_encrypted_adapters_only_rotates_wallet() {
+        let subgraph = spawn_mock_server(|_| {
+            (
+                200,
+                json!({
+                    "data": {
+                        "nibbleDeployed": {
+                            "contracts": [{
+                                "name": "NibbleStorage",
+                                "address": "0x0000000000000000000000000000000000000001"
+                            }],
+                            "count": "0"
+                        }
+                    }
+                })
+                .to_string(),
+            )
+        })
+        .await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        nibble.subgraph.endpoint = subgraph.url.clone();
+        nibble.id = Some("0xnibble".to_string());
+        nibble.contracts.push(ContractInfo {
+            name: "NibbleStorage".to_string(),
+            address: Address::zero(),
+        });
+
+        let new_wallet = LocalWallet::new(&mut rand::thread_rng());
+        let new_address = new_wallet.address();
+        nibble.reencrypt_all(new_wallet).await.unwrap();
+
+        assert_eq!(nibble.owner_wallet.address(), new_address);
+        assert!(nibble.agents.is_empty());
+        assert_eq!(subgraph.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reencrypt_all_without_contracts_keeps_wallet() {
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        let old_address = nibble.owner_wallet.address();
+
+        let result = nibble
+            .reencrypt_all(LocalWallet::new(&mut rand::thread_rng()))
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(nibble.owner_wallet.address(), old_address);
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_agent_only_encrypts_sensitive_fields() {
+        let ipfs = spawn_mock_ipfs().await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &ipfs.url);
+        nibble
+            .add_agent(
+                "Secretive",
+                "role",
+                "personality",
+                "system",
+                false,
+                false,
+                LLMModel::Other {
+                    url: "http://127.0.0.1:0".to_string(),
+                    api_key: Some("sk-very-secret".to_string()),
+                    body: HashMap::new(),
+                    result_path: "result".to_string(),
+                    result_type: "string".to_string(),
+                },
+                true,
+                None,
+                None,
+                None,
+                vec![],
+            )
+            .unwrap();
+
+        nibble
+            .build_modify_adapters(nibble.ipfs_client.as_ref())
+            .await
+            .unwrap();
+
+        let uploaded: Value = serde_json::from_str(&ipfs.requests()[0].body).unwrap();
+        assert_eq!(uploaded["name"], json!("Secretive"));
+        assert_eq!(uploaded["model"]["type"], json!("Other"));
+        let api_key = uploaded["model"]["api_key"].as_str().unwrap();
+        assert_ne!(api_key, "sk-very-secret");
+        assert!(!ipfs.requests()[0].body.contains("sk-very-secret"));
+        assert!(uploaded["encrypted_fields"]
+            .as_array()
+            .unwrap()
+            .contains(&json!("api_key")));
+    }
+
+    #[test]
+    fn test_find_adapters_by_name_and_id() {
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        let local_id = nibble
+            .add_agent(
+                "Local",
+                "role",
+                "personality",
+                "system",
+                false,
+                false,
+                test_model(),
+                false,
+                None,
+                None,
+                None,
+                vec![],
+            )
+            .unwrap()
+            .adapter
+            .id
+            .clone();
+        let mut saved = nibble.agents[0].clone();
+        saved.name = "Saved".to_string();
+        saved.id = "0xa2".to_string();
+        nibble.saved_agents.push(saved);
+        let connector_id = add_rest_connector(&mut nibble, "Poster", "http://127.0.0.1:1");
+
+        assert_eq!(nibble.find_agent("Local").unwrap().id, local_id);
+        assert_eq!(nibble.find_agent("Saved").unwrap().id, "0xa2");
+        assert_eq!(nibble.find_agent_by_id("0xa2").unwrap().name, "Saved");
+        assert!(nibble.find_agent("Missing").is_none());
+        assert_eq!(
+            nibble.find_offchain_connector("Poster").unwrap().id,
+            connector_id
+        );
+        assert_eq!(
+            nibble
+                .find_offchain_connector_by_id(&connector_id)
+                .unwrap()
+                .name,
+            "Poster"
+        );
+        assert!(nibble.find_condition("Poster").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_persist_adapter_encodes_single_adapter_batch() {
+        let ipfs = spawn_mock_ipfs().await;
+        let rpc = spawn_mock_server(|request| {
+            let body: Value = serde_json::from_str(&request.body).unwrap();
+            let mut reply = match body["method"].as_str().unwrap() {
+                "eth_getTransactionCount" => json!({"result": "0x0"}),
+                _ => json!({"error": {"code": -32000, "message": "rejected"}}),
+            };
+            reply["jsonrpc"] = json!("2.0");
+            reply["id"] = body["id"].clone();
+            (200, reply.to_string())
+        })
+        .await;
+        let mut nibble = offline_nibble(&rpc.url, &ipfs.url);
+        nibble.id = Some("0xnibble".to_string());
+        nibble.contracts.push(ContractInfo {
+            name: "NibbleStorage".to_string(),
+            address: Address::repeat_byte(0x22),
+        });
+        add_rest_connector(&mut nibble, "Pending", "http://127.0.0.1:1");
+        let owner = nibble.owner_wallet.address();
+        nibble
+            .nonce_manager
+            .next_nonce(&nibble.provider, owner)
+            .await
+            .unwrap();
+        let handle = nibble
+            .add_offchain_connector(
+                "Poster",
+                ConnectorType::REST { base_payload: None },
+                "http://127.0.0.1:1",
+                false,
+                Method::POST,
+                None,
+                None,
+                None,
+                None,
+                &owner,
+                None,
+            )
+            .unwrap();
+        let connector_id = handle.adapter.id.clone();
+
+        let error = handle.persist_adapter().await.err().unwrap();
+
+        assert!(error.to_string().contains("rejected"));
+        let raw = rpc
+            .requests()
+            .iter()
+            .map(|request| serde_json::from_str::<Value>(&request.body).unwrap())
+            .find(|body| body["method"] == "eth_sendRawTransaction")
+            .unwrap()["params"][0]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let (tx, _) =
+            TypedTransaction::decode_signed(&Rlp::new(&hex::decode(&raw[2..]).unwrap())).unwrap();
+        let abi: Abi = serde_json::from_str(include_str!("../abis/NibbleStorage.json")).unwrap();
+        let function = abi.function("addOrModifyAdaptersBatch").unwrap();
+        let data = tx.data().unwrap();
+
+        assert_eq!(&data[..4], &function.short_signature()[..]);
+        let tokens = function.decode_input(&data[4..]).unwrap();
+        let batch = tokens[0].clone().into_tuple().unwrap();
+        let connectors = batch[2].clone().into_array().unwrap();
+        assert_eq!(connectors.len(), 1);
+        assert_eq!(
+            connectors[0].clone().into_tuple().unwrap()[0],
+            Token::Bytes(hex::decode(&connector_id[2..]).unwrap())
+        );
+        assert_eq!(tx.to_addr(), Some(&Address::repeat_byte(0x22)));
+        assert_eq!(tx.nonce(), Some(&1.into()));
+    }
+
+    #[tokio::test]
+    async fn test_persist_adapter_without_nibble_id_sends_nothing() {
+        let ipfs = spawn_mock_ipfs().await;
+        let rpc = spawn_mock_server(|_| (500, "unexpected".to_string())).await;
+        let mut nibble = offline_nibble(&rpc.url, &ipfs.url);
+        nibble.contracts.push(ContractInfo {
+            name: "NibbleStorage".to_string(),
+            address: Address::repeat_byte(0x22),
+        });
+        let owner = nibble.owner_wallet.address();
+        let handle = nibble
+            .add_offchain_connector(
+                "Poster",
+                ConnectorType::REST { base_payload: None },
+                "http://127.0.0.1:1",
+                false,
+                Method::POST,
+                None,
+                None,
+                None,
+                None,
+                &owner,
+                None,
+            )
+            .unwrap();
+
+        let error = handle.persist_adapter().await.err().unwrap();
+
+        assert!(error.to_string().contains("Nibble id is missing"));
+        assert!(rpc.requests().is_empty());
+        assert!(ipfs.requests().is_empty());
+        assert_eq!(nibble.offchain_connectors.len(), 1);
+    }
+
+    #[test]
+    fn test_remove_adapters_maps_each_adapter_to_its_ids() {
+        let ids = |adapter: Adapter| RemoveAdapters::for_adapter(&adapter, "0x01");
+        let expected = |conditions: bool,
+                        listeners: bool,
+                        connectors: bool,
+                        agents: bool,
+                        evaluations: bool| {
+            let field = |set: bool| {
+                if set {
+                    vec!["0x01".to_string()]
+                } else {
+                    vec![]
+                }
+            };
+            Some(RemoveAdapters {
+                conditions: field(conditions),
+                listeners: field(listeners),
+                connectors: field(connectors),
+                agents: field(agents),
+                evaluations: field(evaluations),
+            })
+        };
+
+        assert_eq!(
+            ids(Adapter::Condition),
+            expected(true, false, false, false, false)
+        );
+        assert_eq!(
+            ids(Adapter::Listener),
+            expected(false, true, false, false, false)
+        );
+        assert_eq!(
+            ids(Adapter::OnChainConnector),
+            expected(false, false, true, false, false)
+        );
+        assert_eq!(
+            ids(Adapter::OffChainConnector),
+            expected(false, false, true, false, false)
+        );
+        assert_eq!(
+            ids(Adapter::Agent),
+            expected(false, false, false, true, false)
+        );
+        assert_eq!(
+            ids(Adapter::Evaluation),
+            expected(false, false, false, false, true)
+        );
+        assert_eq!(ids(Adapter::FHEGate), None);
+    }
+}
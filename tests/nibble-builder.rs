@@ -0,0 +1,89 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::TEST_PRIVATE_KEY;
+    use ethers::types::Chain;
+    use npc_workbench::{
+        error::NpcError,
+        ipfs::IPFSProvider,
+        nibble::{Nibble, NibbleBuilder},
+    };
+
+    fn custom_ipfs_builder() -> NibbleBuilder {
+        Nibble::builder()
+            .ipfs_provider(IPFSProvider::Custom)
+            .ipfs_option("api_url", "http://127.0.0.1:5001")
+            .ipfs_option("gateway_url", "http://127.0.0.1:5001/ipfs")
+    }
+
+    #[test]
+    fn test_builder_applies_settings_and_defaults() {
+        let nibble = custom_ipfs_builder()
+            .owner_private_key(TEST_PRIVATE_KEY)
+            .rpc_url("http://127.0.0.1:8545")
+            .graph_api_key("graph-key")
+            .encrypted_fields(vec!["api_key".to_string()])
+            .debug(true)
+            .build()
+            .unwrap();
+
+        assert_eq!(nibble.chain, Chain::PolygonAmoy);
+        assert_eq!(nibble.graph_api_key.as_deref(), Some("graph-key"));
+        assert!(nibble.subgraph.endpoint.contains("graph-key"));
+        assert_eq!(nibble.encrypted_fields, vec!["api_key".to_string()]);
+        assert!(nibble.debug);
+        assert_eq!(nibble.confirmations, 1);
+    }
+
+    #[test]
+    fn test_builder_sets_confirmations() {
+        let nibble = custom_ipfs_builder()
+            .owner_private_key(TEST_PRIVATE_KEY)
+            .rpc_url("http://127.0.0.1:8545")
+            .confirmations(3)
+            .build()
+            .unwrap();
+
+        assert_eq!(nibble.confirmations, 3);
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_private_key() {
+        let error = custom_ipfs_builder()
+            .owner_private_key("not-a-key")
+            .rpc_url("http://127.0.0.1:8545")
+            .build()
+            .err()
+            .unwrap();
+
+        assert!(matches!(error, NpcError::Validation(_)));
+        assert!(error.to_string().contains("private key"));
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_rpc_url() {
+        for rpc_url in ["not a url", "ftp://127.0.0.1:8545"] {
+            let error = custom_ipfs_builder()
+                .owner_private_key(TEST_PRIVATE_KEY)
+                .rpc_url(rpc_url)
+                .build()
+                .err()
+                .unwrap();
+
+            assert!(matches!(error, NpcError::Validation(_)));
+            assert!(error.to_string().contains("Invalid RPC url"));
+        }
+    }
+
+    #[test]
+    fn test_builder_requires_rpc_url() {
+        let error = custom_ipfs_builder()
+            .owner_private_key(TEST_PRIVATE_KEY)
+            .build()
+            .err()
+            .unwrap();
+
+        assert!(error.to_string().contains("RPC url"));
+    }
+}
@@ -0,0 +1,100 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{offline_nibble, spawn_mock_server, MockRequest};
+    use npc_workbench::nibble::{PreflightStatus, RetryPolicy, SubgraphConfig};
+    use serde_json::{json, Value};
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    fn rpc_response(request: &MockRequest, balance: &str) -> String {
+        let body: Value = serde_json::from_str(&request.body).unwrap();
+        let result = match body["method"].as_str().unwrap() {
+            "eth_blockNumber" => json!("0x10"),
+            "eth_getBalance" => json!(balance),
+            method => panic!("unexpected RPC method {}", method),
+        };
+        json!({"jsonrpc": "2.0", "id": body["id"], "result": result}).to_string()
+    }
+
+    fn single_attempt(endpoint: String) -> SubgraphConfig {
+        SubgraphConfig {
+            endpoint,
+            ..SubgraphConfig::new(None).with_retry(RetryPolicy {
+                max_attempts: 1,
+                base_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(1),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_preflight_reports_every_check_ok() {
+        let uploaded = Arc::new(Mutex::new(String::new()));
+        let stored = uploaded.clone();
+        let server = spawn_mock_server(move |request| {
+            if request.path.starts_with("/ipfs/") {
+                (200, stored.lock().unwrap().clone())
+            } else if request.path == "/subgraph" {
+                (
+                    200,
+                    json!({"data": {"_meta": {"block": {"number": 42}}}}).to_string(),
+                )
+            } else if request.body.contains("jsonrpc") {
+                (200, rpc_response(request, "0xde0b6b3a7640000"))
+            } else {
+                *stored.lock().unwrap() = request.body.clone();
+                (200, json!({"Hash": "QmPreflight"}).to_string())
+            }
+        })
+        .await;
+        let mut nibble = offline_nibble(&server.url, &server.url);
+        nibble.subgraph = single_attempt(format!("{}/subgraph", server.url));
+
+        let report = nibble.preflight().await;
+
+        assert!(report.is_ok(), "{:?}", report);
+        assert_eq!(report.rpc, PreflightStatus::Ok("Block 16".to_string()));
+        assert_eq!(report.ipfs, PreflightStatus::Ok("QmPreflight".to_string()));
+        assert_eq!(
+            report.subgraph,
+            PreflightStatus::Ok("Indexed block 42".to_string())
+        );
+        assert_eq!(
+            report.balance,
+            PreflightStatus::Ok("1000000000000000000 wei".to_string())
+        );
+        assert!(!uploaded.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_preflight_reports_failures_per_check() {
+        let server = spawn_mock_server(|request| {
+            if request.body.contains("jsonrpc") {
+                (200, rpc_response(request, "0x0"))
+            } else {
+                (500, "boom".to_string())
+            }
+        })
+        .await;
+        let mut nibble = offline_nibble(&server.url, &server.url);
+        nibble.subgraph = single_attempt(server.url.clone());
+
+        let report = nibble.preflight().await;
+
+        assert!(!report.is_ok());
+        assert!(report.rpc.is_ok());
+        assert!(!report.ipfs.is_ok());
+        assert!(matches!(
+            &report.subgraph,
+            PreflightStatus::Failed(error) if error.contains("boom")
+        ));
+        assert!(matches!(
+            &report.balance,
+            PreflightStatus::Failed(error) if error.contains("no funds")
+        ));
+    }
+}
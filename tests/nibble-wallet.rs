@@ -0,0 +1,107 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::TEST_PRIVATE_KEY;
+    use ethers::{
+        signers::{LocalWallet, Signer},
+        types::{Address, Chain},
+    };
+    use npc_workbench::{
+        ipfs::IPFSProvider,
+        nibble::{Nibble, NibbleBuilder},
+    };
+    use std::{collections::HashMap, env, fs};
+
+    fn owner_address() -> Address {
+        TEST_PRIVATE_KEY.parse::<LocalWallet>().unwrap().address()
+    }
+
+    fn ipfs_config() -> HashMap<String, String> {
+        HashMap::from([("api_url".to_string(), "http://127.0.0.1:5001".to_string())])
+    }
+
+    #[test]
+    fn test_from_keystore_decrypts_owner_wallet() {
+        let dir = env::temp_dir().join(format!("npc-keystore-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let key = TEST_PRIVATE_KEY.parse::<LocalWallet>().unwrap();
+        LocalWallet::encrypt_keystore(
+            &dir,
+            &mut rand::thread_rng(),
+            key.signer().to_bytes(),
+            "hunter2",
+            Some("owner.json"),
+        )
+        .unwrap();
+        let path = dir.join("owner.json");
+
+        let nibble = Nibble::from_keystore(
+            &path,
+            "hunter2",
+            "http://127.0.0.1:8545",
+            IPFSProvider::Custom,
+            ipfs_config(),
+            Chain::PolygonAmoy,
+            None,
+            None,
+        )
+        .unwrap();
+        let error = Nibble::from_keystore(
+            &path,
+            "wrong",
+            "http://127.0.0.1:8545",
+            IPFSProvider::Custom,
+            ipfs_config(),
+            Chain::PolygonAmoy,
+            None,
+            None,
+        )
+        .err()
+        .unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(nibble.owner_wallet.address(), owner_address());
+        assert!(error.to_string().contains("Invalid owner keystore"));
+    }
+
+    #[test]
+    fn test_from_env_reads_owner_private_key() {
+        env::set_var("NPC_TEST_OWNER_KEY", TEST_PRIVATE_KEY);
+
+        let nibble = Nibble::from_env(
+            "NPC_TEST_OWNER_KEY",
+            "http://127.0.0.1:8545",
+            IPFSProvider::Custom,
+            ipfs_config(),
+            Chain::PolygonAmoy,
+            None,
+            None,
+        )
+        .unwrap();
+        let error = Nibble::from_env(
+            "NPC_TEST_MISSING_OWNER_KEY",
+            "http://127.0.0.1:8545",
+            IPFSProvider::Custom,
+            ipfs_config(),
+            Chain::PolygonAmoy,
+            None,
+            None,
+        )
+        .err()
+        .unwrap();
+
+        assert_eq!(nibble.owner_wallet.address(), owner_address());
+        assert!(error.to_string().contains("NPC_TEST_MISSING_OWNER_KEY"));
+    }
+
+    #[test]
+    fn test_builder_debug_redacts_private_key() {
+        let builder: NibbleBuilder = Nibble::builder().owner_private_key(TEST_PRIVATE_KEY);
+
+        let debug = format!("{:?}", builder);
+
+        assert!(!debug.contains(TEST_PRIVATE_KEY.trim_start_matches("0x")));
+        assert!(debug.contains("***"));
+    }
+}
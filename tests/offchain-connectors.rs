@@ -0,0 +1,414 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{add_rest_connector, offline_nibble, spawn_mock_server};
+    use ethers::{signers::Signer, types::Address};
+    use npc_workbench::{
+        adapters::nodes::connectors::off_chain::{
+            configure_new_offchain_connector, ConnectorType, OffChainConnector, PaginationConfig,
+        },
+        nibble::RateLimit,
+        tools::history::HistoryParse,
+        workflow::{ExecutionHistory, NodeAdapter, SubflowManager},
+    };
+    use reqwest::Method;
+    use serde_json::{json, Value};
+    use std::{
+        collections::HashMap,
+        sync::Arc,
+        time::{Duration, Instant},
+    };
+
+    #[tokio::test]
+    async fn test_context_body_connector_posts_full_context() {
+        let server = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &server.url);
+        let owner = nibble.owner_wallet.address();
+        let connector = nibble
+            .add_offchain_connector(
+                "PostPublication",
+                ConnectorType::ContextBody {
+                    body_key: Some("publication".to_string()),
+                },
+                &server.url,
+                false,
+                Method::POST,
+                None,
+                None,
+                None,
+                None,
+                &owner,
+                None,
+            )
+            .unwrap()
+            .adapter
+            .clone();
+        let context = json!({
+            "content": "gm",
+            "tags": ["meme", "lens"],
+            "metadata": {"locale": "en", "nsfw": false}
+        });
+
+        let response = connector
+            .execute_offchain_connector(Some(context.clone()), None, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(response, json!({"ok": true}));
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "POST");
+        let body: Value = serde_json::from_str(&requests[0].body).unwrap();
+        assert_eq!(body, json!({ "publication": context }));
+    }
+
+    async fn spawn_query_echo() -> crate::common::MockServer {
+        spawn_mock_server(|request| {
+            let query = request.path.split_once('?').map(|(_, q)| q).unwrap_or("");
+            (200, json!({ "query": query }).to_string())
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_get_connector_sends_templated_params_as_query() {
+        let server = spawn_query_echo().await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &server.url);
+        let owner = nibble.owner_wallet.address();
+        let mut params = HashMap::new();
+        params.insert("q".to_string(), "{{topic}} memes".to_string());
+        params.insert("limit".to_string(), "5".to_string());
+        let connector = nibble
+            .add_offchain_connector(
+                "Search",
+                ConnectorType::REST { base_payload: None },
+                &format!("{}/search", server.url),
+                false,
+                Method::GET,
+                None,
+                Some(params),
+                None,
+                None,
+                &owner,
+                None,
+            )
+            .unwrap()
+            .adapter
+            .clone();
+
+        let response = connector
+            .execute_offchain_connector(Some(json!({"topic": "cats & dogs"})), None, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(response, json!({"query": "limit=5&q=cats+%26+dogs+memes"}));
+        let requests = server.requests();
+        assert_eq!(requests[0].method, "GET");
+        assert!(requests[0].body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_connector_without_params_sends_context_as_query() {
+        let server = spawn_query_echo().await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &server.url);
+        let owner = nibble.owner_wallet.address();
+        let connector = nibble
+            .add_offchain_connector(
+                "Lookup",
+                ConnectorType::ContextBody { body_key: None },
+                &server.url,
+                false,
+                Method::GET,
+                None,
+                None,
+                None,
+                None,
+                &owner,
+                None,
+            )
+            .unwrap()
+            .adapter
+            .clone();
+
+        let response = connector
+            .execute_offchain_connector(Some(json!({"id": 7, "name": "gm"})), None, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(response, json!({"query": "id=7&name=gm"}));
+        assert!(server.requests()[0].body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_placeholders_resolve_from_context_over_auth_tokens() {
+        let server = spawn_mock_server(|_| (200, json!({"data": {}}).to_string())).await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &server.url);
+        let owner = nibble.owner_wallet.address();
+        let mut headers = HashMap::new();
+        headers.insert(
+            "Authorization".to_string(),
+            "Bearer {{authToken}}".to_string(),
+        );
+        let mut variables = HashMap::new();
+        variables.insert("request.for".to_string(), "{{profileId}}".to_string());
+        let connector = nibble
+            .add_offchain_connector(
+                "Profile",
+                ConnectorType::GraphQL {
+                    query: "query Profile { profile(id: \"{{profileId}}\") { id } }".to_string(),
+                    variables: Some(variables),
+                },
+                &format!("{}/profiles/{{{{profileId}}}}", server.url),
+                false,
+                Method::POST,
+                Some(headers),
+                None,
+                Some(json!({"authToken": "stored", "profileId": "0x01"})),
+                None,
+                &owner,
+                None,
+            )
+            .unwrap()
+            .adapter
+            .clone();
+
+        connector
+            .execute_offchain_connector(Some(json!({"authToken": "fresh"})), None, None, None)
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/profiles/0x01");
+        assert_eq!(requests[0].headers["authorization"], "Bearer fresh");
+        let body: Value = serde_json::from_str(&requests[0].body).unwrap();
+        assert_eq!(
+            body["query"],
+            json!("query Profile { profile(id: \"0x01\") { id } }")
+        );
+        assert_eq!(body["variables"]["request.for"], json!("0x01"));
+    }
+
+    #[tokio::test]
+    async fn test_unresolved_placeholder_errors_before_sending() {
+        let server = spawn_mock_server(|_| (200, json!({}).to_string())).await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &server.url);
+        let owner = nibble.owner_wallet.address();
+        let connector = nibble
+            .add_offchain_connector(
+                "Missing",
+                ConnectorType::REST { base_payload: None },
+                &format!("{}/posts/{{{{postId}}}}", server.url),
+                false,
+                Method::POST,
+                None,
+                None,
+                None,
+                None,
+                &owner,
+                None,
+            )
+            .unwrap()
+            .adapter
+            .clone();
+
+        let error = connector
+            .execute_offchain_connector(Some(json!({"other": 1})), None, None, None)
+            .await
+            .err()
+            .unwrap();
+
+        assert!(error.to_string().contains("{{postId}}"));
+        assert!(server.requests().is_empty());
+    }
+
+    fn tokens_from_history(history: Vec<ExecutionHistory>) -> Result<Value, String> {
+        history
+            .into_iter()
+            .find_map(|entry| entry.result)
+            .ok_or_else(|| "Auth subflow produced no result".to_string())
+    }
+
+    #[tokio::test]
+    async fn test_unauthorized_response_refreshes_tokens_and_retries_once() {
+        let auth = spawn_mock_server(|_| (200, json!({"access_token": "fresh"}).to_string())).await;
+        let api = spawn_mock_server(|request| {
+            match request.headers.get("authorization").map(String::as_str) {
+                Some("Bearer fresh") => (200, json!({"ok": true}).to_string()),
+                _ => (401, json!({"error": "expired"}).to_string()),
+            }
+        })
+        .await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &auth.url);
+        let owner = nibble.owner_wallet.address();
+        let auth_connector = add_rest_connector(&mut nibble, "Refresh", &auth.url);
+        let mut auth_subflow = Arc::new(nibble).create_shared_workflow("LensRefresh", false);
+        auth_subflow.add_node(
+            auth_connector,
+            NodeAdapter::OffChainConnector,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        let connector = configure_new_offchain_connector(
+            "Profile",
+            ConnectorType::REST { base_payload: None },
+            &api.url,
+            false,
+            Method::POST,
+            None,
+            None,
+            Some(json!({"access_token": "stale"})),
+            None,
+            &owner,
+            Some(auth_subflow),
+        )
+        .unwrap();
+        let manager = SubflowManager::new();
+        let history_tool = HistoryParse::CustomProcessor {
+            function: tokens_from_history,
+        };
+
+        let response = connector
+            .execute_offchain_connector(Some(json!({})), Some(&manager), Some(history_tool), None)
+            .await
+            .unwrap();
+
+        assert_eq!(response, json!({"ok": true}));
+        let requests = api.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].headers["authorization"], "Bearer stale");
+        assert_eq!(requests[1].headers["authorization"], "Bearer fresh");
+        assert_eq!(auth.requests().len(), 1);
+    }
+
+    fn notifications_connector(url: &str, max_pages: u32) -> OffChainConnector {
+        configure_new_offchain_connector(
+            "Notifications",
+            ConnectorType::GraphQL {
+                query: "query Notifications($cursor: Cursor) { notifications { items } }"
+                    .to_string(),
+                variables: None,
+            },
+            url,
+            false,
+            Method::POST,
+            None,
+            None,
+            None,
+            None,
+            &Address::zero(),
+            None,
+        )
+        .unwrap()
+        .with_pagination(PaginationConfig {
+            items_path: vec!["data".into(), "notifications".into(), "items".into()],
+            cursor_path: vec![
+                "data".into(),
+                "notifications".into(),
+                "pageInfo".into(),
+                "next".into(),
+            ],
+            cursor_variable: "cursor".to_string(),
+            max_pages,
+        })
+    }
+
+    fn notifications_page(items: Value, next: Value) -> String {
+        json!({
+            "data": {
+                "notifications": {
+                    "items": items,
+                    "pageInfo": {"next": next}
+                }
+            }
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_paginated_connector_follows_cursor_until_exhausted() {
+        let server = spawn_mock_server(|request| {
+            let body: Value = serde_json::from_str(&request.body).unwrap();
+            match body["variables"]["cursor"].as_str() {
+                None => (200, notifications_page(json!([1, 2]), json!("page-2"))),
+                Some("page-2") => (200, notifications_page(json!([3]), Value::Null)),
+                Some(other) => (400, json!({ "error": other }).to_string()),
+            }
+        })
+        .await;
+
+        let response = notifications_connector(&server.url, 10)
+            .execute_offchain_connector(None, None, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(response["data"]["notifications"]["items"], json!([1, 2, 3]));
+        assert_eq!(
+            response["data"]["notifications"]["pageInfo"]["next"],
+            Value::Null
+        );
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_paginated_connector_stops_at_max_pages() {
+        let server = spawn_mock_server(|request| {
+            let body: Value = serde_json::from_str(&request.body).unwrap();
+            let page = body["variables"]["cursor"]
+                .as_str()
+                .map(|cursor| cursor.trim_start_matches("page-").parse::<u64>().unwrap())
+                .unwrap_or(0);
+            (
+                200,
+                notifications_page(json!([page]), json!(format!("page-{}", page + 1))),
+            )
+        })
+        .await;
+
+        let response = notifications_connector(&server.url, 3)
+            .execute_offchain_connector(None, None, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(response["data"]["notifications"]["items"], json!([0, 1, 2]));
+        assert_eq!(
+            response["data"]["notifications"]["pageInfo"]["next"],
+            json!("page-3")
+        );
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_delays_requests_to_shared_host() {
+        let server = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &server.url);
+        nibble.rate_limiter.set_host_limit(
+            "127.0.0.1",
+            RateLimit {
+                requests_per_second: 10.0,
+                burst: 1,
+            },
+        );
+        let first = add_rest_connector(&mut nibble, "First", &server.url);
+        let second = add_rest_connector(&mut nibble, "Second", &server.url);
+
+        let started = Instant::now();
+        for id in [&first, &second, &first] {
+            let connector = nibble
+                .offchain_connectors
+                .iter()
+                .find(|connector| connector.id == *id)
+                .unwrap();
+            connector
+                .execute_offchain_connector(None, None, None, Some(&nibble.rate_limiter))
+                .await
+                .unwrap();
+        }
+
+        assert!(started.elapsed() >= Duration::from_millis(180));
+        assert_eq!(server.requests().len(), 3);
+    }
+}
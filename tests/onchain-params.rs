@@ -0,0 +1,225 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{mock_chain_response, offline_nibble, spawn_mock_server};
+    use ethers::{
+        abi::{Abi, ParamType, Token},
+        types::{Address, Chain, I256, U256},
+    };
+    use npc_workbench::{
+        adapters::nodes::connectors::on_chain::{convert_value_to_token, BatchMode, GasOptions},
+        nibble::NonceManager,
+    };
+    use serde_json::{json, Value};
+    use std::time::Duration;
+
+    const ADDRESS: &str = "0x026FFeCD16227436764A8e3261245f6C21E9D1E4";
+
+    #[test]
+    fn test_convert_value_to_token_infers_common_types() {
+        assert_eq!(
+            convert_value_to_token(&json!(true), None).unwrap(),
+            Token::Bool(true)
+        );
+        assert_eq!(
+            convert_value_to_token(&json!(42), None).unwrap(),
+            Token::Uint(U256::from(42))
+        );
+        assert_eq!(
+            convert_value_to_token(&json!(ADDRESS), None).unwrap(),
+            Token::Address(ADDRESS.parse::<Address>().unwrap())
+        );
+        assert_eq!(
+            convert_value_to_token(&json!("115792089237316195423570985008687907853269984665640564039457584007913129639935"), None).unwrap(),
+            Token::Uint(U256::MAX)
+        );
+        assert_eq!(
+            convert_value_to_token(&json!(["gm", 1]), None).unwrap(),
+            Token::Array(vec![
+                Token::String("gm".to_string()),
+                Token::Uint(U256::one())
+            ])
+        );
+    }
+
+    #[test]
+    fn test_convert_value_to_token_follows_type_hint() {
+        assert_eq!(
+            convert_value_to_token(&json!(ADDRESS), Some(&ParamType::String)).unwrap(),
+            Token::String(ADDRESS.to_string())
+        );
+        assert_eq!(
+            convert_value_to_token(&json!("0xff"), Some(&ParamType::Uint(256))).unwrap(),
+            Token::Uint(U256::from(255))
+        );
+        assert_eq!(
+            convert_value_to_token(
+                &json!([ADDRESS]),
+                Some(&ParamType::Array(Box::new(ParamType::Address)))
+            )
+            .unwrap(),
+            Token::Array(vec![Token::Address(ADDRESS.parse().unwrap())])
+        );
+        assert!(convert_value_to_token(
+            &json!([1, 2]),
+            Some(&ParamType::FixedArray(Box::new(ParamType::Uint(8)), 3))
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_convert_value_to_token_encodes_ints_and_bytes() {
+        assert_eq!(
+            convert_value_to_token(&json!(-5), None).unwrap(),
+            Token::Int(I256::from(-5).into_raw())
+        );
+        assert_eq!(
+            convert_value_to_token(&json!("-7"), Some(&ParamType::Int(256))).unwrap(),
+            Token::Int(I256::from(-7).into_raw())
+        );
+        assert_eq!(
+            convert_value_to_token(&json!("0x0102"), Some(&ParamType::Bytes)).unwrap(),
+            Token::Bytes(vec![1, 2])
+        );
+        assert_eq!(
+            convert_value_to_token(
+                &json!(format!("0x{}", "ab".repeat(32))),
+                Some(&ParamType::FixedBytes(32))
+            )
+            .unwrap(),
+            Token::FixedBytes(vec![0xab; 32])
+        );
+        assert!(
+            convert_value_to_token(&json!("0x0102"), Some(&ParamType::FixedBytes(32))).is_err()
+        );
+        assert!(convert_value_to_token(&json!("0102"), Some(&ParamType::Bytes)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_view_call_returns_decoded_outputs() {
+        let rpc = spawn_mock_server(|request| {
+            let rpc: Value = serde_json::from_str(&request.body).unwrap();
+            assert_eq!(rpc["method"], "eth_call");
+            (
+                200,
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": rpc["id"],
+                    "result": format!("0x{:064x}", 42)
+                })
+                .to_string(),
+            )
+        })
+        .await;
+        let mut nibble = offline_nibble(&rpc.url, "http://127.0.0.1:5001");
+        let abi: Abi = serde_json::from_value(json!([{
+            "type": "function",
+            "name": "count",
+            "inputs": [],
+            "outputs": [{"name": "", "type": "uint256"}],
+            "stateMutability": "view"
+        }]))
+        .unwrap();
+        let connector = nibble
+            .add_onchain_connector(
+                "Counter",
+                Some(Address::repeat_byte(0x11)),
+                false,
+                None,
+                Some(abi),
+                Chain::PolygonAmoy,
+                None,
+                vec![],
+                BatchMode::SequentialAbort,
+            )
+            .unwrap()
+            .adapter;
+
+        let result = connector
+            .execute_onchain_connector(
+                nibble.provider.clone(),
+                nibble.owner_wallet.clone(),
+                Some("count"),
+                None,
+                &NonceManager::default(),
+                &nibble.fee_mode,
+                1,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result, Some(json!({"return_values": ["42"]})));
+        assert_eq!(rpc.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_state_changing_call_reports_simulated_return_values() {
+        let rpc = spawn_mock_server(|request| {
+            let rpc: Value = serde_json::from_str(&request.body).unwrap();
+            if rpc["method"] == "eth_call" {
+                return (
+                    200,
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": rpc["id"],
+                        "result": format!("0x{:064x}", 42)
+                    })
+                    .to_string(),
+                );
+            }
+            (200, mock_chain_response(&rpc, "0x1"))
+        })
+        .await;
+        let mut nibble = offline_nibble(&rpc.url, "http://127.0.0.1:5001");
+        nibble.provider = nibble.provider.clone().interval(Duration::from_millis(10));
+        let abi: Abi = serde_json::from_value(json!([{
+            "type": "function",
+            "name": "increment",
+            "inputs": [],
+            "outputs": [{"name": "", "type": "uint256"}],
+            "stateMutability": "nonpayable"
+        }]))
+        .unwrap();
+        let connector = nibble
+            .add_onchain_connector(
+                "Counter",
+                Some(Address::repeat_byte(0x11)),
+                false,
+                None,
+                Some(abi),
+                Chain::PolygonAmoy,
+                Some(GasOptions {
+                    max_fee_per_gas: Some(U256::from(44u64)),
+                    max_priority_fee_per_gas: Some(U256::from(25u64)),
+                    gas_limit: Some(U256::from(100_000)),
+                    nonce: None,
+                }),
+                vec![],
+                BatchMode::SequentialAbort,
+            )
+            .unwrap()
+            .adapter;
+
+        let result = connector
+            .execute_onchain_connector(
+                nibble.provider.clone(),
+                nibble.owner_wallet.clone(),
+                Some("increment"),
+                None,
+                &NonceManager::default(),
+                &nibble.fee_mode,
+                1,
+            )
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(result["simulated_return_values"], json!(["42"]));
+        assert!(result.get("return_values").is_none());
+        assert!(result["transaction"]
+            .as_str()
+            .unwrap()
+            .starts_with("Transaction Hash: 0xabab"));
+    }
+}
@@ -0,0 +1,198 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{
+        add_rest_connector, offline_nibble, spawn_mock_ipfs, spawn_mock_server, MockServer,
+    };
+    use ethers::{
+        abi::{self, Abi, Token},
+        types::{transaction::eip2718::TypedTransaction, Address},
+        utils::{hex, keccak256, rlp::Rlp},
+    };
+    use npc_workbench::nibble::{ContractInfo, Nibble};
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+
+    fn storage_abi() -> Abi {
+        serde_json::from_str(include_str!("../abis/NibbleStorage.json")).unwrap()
+    }
+
+    async fn storage_rpc(key_recorded: bool) -> MockServer {
+        let recorded = format!(
+            "0x{}",
+            hex::encode(abi::encode(&[Token::Bool(key_recorded)]))
+        );
+        spawn_mock_server(move |request| {
+            let body: Value = serde_json::from_str(&request.body).unwrap();
+            let mut reply = match body["method"].as_str() {
+                Some("eth_call") => json!({"result": recorded}),
+                Some("eth_getTransactionCount") => json!({"result": "0x0"}),
+                Some(_) => json!({"error": {"code": -32000, "message": "rejected"}}),
+                None => {
+                    return (
+                        200,
+                        json!({
+                            "data": {
+                                "nibbleDeployed": {
+                                    "agents": [],
+                                    "contracts": [{
+                                        "name": "NibbleStorage",
+                                        "address": format!("{:?}", Address::repeat_byte(0x22))
+                                    }],
+                                    "count": "1"
+                                }
+                            }
+                        })
+                        .to_string(),
+                    )
+                }
+            };
+            reply["jsonrpc"] = json!("2.0");
+            reply["id"] = body["id"].clone();
+            (200, reply.to_string())
+        })
+        .await
+    }
+
+    fn storage_nibble(rpc: &MockServer, ipfs: &MockServer) -> Nibble {
+        let mut nibble = offline_nibble(&rpc.url, &ipfs.url);
+        nibble.subgraph.endpoint = rpc.url.clone();
+        nibble.id = Some("0xnibble".to_string());
+        nibble.contracts.push(ContractInfo {
+            name: "NibbleStorage".to_string(),
+            address: Address::repeat_byte(0x22),
+        });
+        nibble
+    }
+
+    fn rpc_calls(rpc: &MockServer, method: &str) -> Vec<Value> {
+        rpc.requests()
+            .iter()
+            .filter_map(|request| serde_json::from_str::<Value>(&request.body).ok())
+            .filter(|body| body["method"] == method)
+            .collect()
+    }
+
+    fn assert_key_checked(rpc: &MockServer, key: &str) {
+        let calls = rpc_calls(rpc, "eth_call");
+        assert_eq!(calls.len(), 1);
+        let function = storage_abi()
+            .function("getIdempotencyKeyRecorded")
+            .unwrap()
+            .clone();
+        let data = calls[0]["params"][0]["data"]
+            .as_str()
+            .or(calls[0]["params"][0]["input"].as_str())
+            .unwrap();
+        assert_eq!(
+            data,
+            format!(
+                "0x{}",
+                hex::encode(
+                    function
+                        .encode_input(&[Token::FixedBytes(keccak256(key).to_vec())])
+                        .unwrap()
+                )
+            )
+        );
+        assert_eq!(
+            calls[0]["params"][0]["to"],
+            json!(format!("{:?}", Address::repeat_byte(0x22)))
+        );
+    }
+
+    fn sent_input(rpc: &MockServer, method: &str) -> Vec<Token> {
+        let raw = rpc_calls(rpc, "eth_sendRawTransaction")[0]["params"][0]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let (tx, _) =
+            TypedTransaction::decode_signed(&Rlp::new(&hex::decode(&raw[2..]).unwrap())).unwrap();
+        let abi = storage_abi();
+        let function = abi.function(method).unwrap();
+        let data = tx.data().unwrap();
+
+        assert_eq!(&data[..4], &function.short_signature()[..]);
+        function.decode_input(&data[4..]).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_workflow_persist_is_noop_when_key_already_recorded() {
+        let rpc = storage_rpc(true).await;
+        let ipfs = spawn_mock_ipfs().await;
+        let workflow = Arc::new(storage_nibble(&rpc, &ipfs)).create_shared_workflow("Retry", false);
+
+        workflow.persist(Some("persist-1")).await.unwrap();
+
+        assert_key_checked(&rpc, "persist-1");
+        assert!(rpc_calls(&rpc, "eth_sendRawTransaction").is_empty());
+        assert!(ipfs.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_workflow_persist_records_new_key_with_the_write() {
+        let rpc = storage_rpc(false).await;
+        let ipfs = spawn_mock_ipfs().await;
+        let workflow = Arc::new(storage_nibble(&rpc, &ipfs)).create_shared_workflow("Retry", false);
+
+        let error = workflow.persist(Some("persist-2")).await.err().unwrap();
+
+        assert!(error.to_string().contains("rejected"));
+        assert_key_checked(&rpc, "persist-2");
+        let tokens = sent_input(&rpc, "addOrModifyWorkflowWithKey");
+        assert_eq!(
+            tokens[0].clone().into_tuple().unwrap()[0],
+            Token::Bytes(hex::decode(&workflow.id[2..]).unwrap())
+        );
+        assert_eq!(
+            tokens[1],
+            Token::FixedBytes(keccak256("persist-2").to_vec())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_persist_adapters_is_noop_when_key_already_recorded() {
+        let rpc = storage_rpc(true).await;
+        let ipfs = spawn_mock_ipfs().await;
+        let mut nibble = storage_nibble(&rpc, &ipfs);
+        add_rest_connector(&mut nibble, "Pending", "http://127.0.0.1:1");
+
+        nibble.persist_adapters(Some("persist-3")).await.unwrap();
+
+        assert_key_checked(&rpc, "persist-3");
+        assert!(rpc_calls(&rpc, "eth_sendRawTransaction").is_empty());
+        assert!(nibble.offchain_connectors.is_empty());
+        assert_eq!(nibble.contracts[0].name, "NibbleStorage");
+    }
+
+    #[tokio::test]
+    async fn test_persist_adapters_records_new_key_with_the_write() {
+        let rpc = storage_rpc(false).await;
+        let ipfs = spawn_mock_ipfs().await;
+        let mut nibble = storage_nibble(&rpc, &ipfs);
+        let connector_id = add_rest_connector(&mut nibble, "Pending", "http://127.0.0.1:1");
+
+        let error = nibble
+            .persist_adapters(Some("persist-4"))
+            .await
+            .err()
+            .unwrap();
+
+        assert!(error.to_string().contains("rejected"));
+        assert_key_checked(&rpc, "persist-4");
+        let tokens = sent_input(&rpc, "addOrModifyAdaptersBatchWithKey");
+        let connectors = tokens[0].clone().into_tuple().unwrap()[2]
+            .clone()
+            .into_array()
+            .unwrap();
+        assert_eq!(
+            connectors[0].clone().into_tuple().unwrap()[0],
+            Token::Bytes(hex::decode(&connector_id[2..]).unwrap())
+        );
+        assert_eq!(
+            tokens[1],
+            Token::FixedBytes(keccak256("persist-4").to_vec())
+        );
+    }
+}
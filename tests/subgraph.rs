@@ -0,0 +1,522 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{
+        add_rest_connector, offline_nibble, spawn_mock_server, spawn_mock_server_with_headers,
+    };
+    use async_trait::async_trait;
+    use ethers::types::Address;
+    use npc_workbench::{
+        error::NpcError,
+        nibble::{ContractInfo, Nibble, RetryPolicy, SubgraphClient, SubgraphConfig},
+        workflow::{LinkAdapter, NodeAdapter},
+    };
+    use serde_json::{json, Value};
+    use std::{
+        error::Error,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+    };
+    use tokio::time::{timeout, Duration};
+
+    fn nibble_page() -> String {
+        json!({
+            "data": {
+                "nibbleDeployed": {
+                    "agents": [],
+                    "contracts": [{
+                        "name": "NibbleAgents",
+                        "address": "0x0000000000000000000000000000000000000001"
+                    }],
+                    "count": "1"
+                }
+            }
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_load_nibble_pages_until_short_page() {
+        let subgraph = spawn_mock_server(|request| {
+            let body: Value = serde_json::from_str(&request.body).unwrap();
+            let agents = match body["variables"]["skip"].as_u64().unwrap() {
+                0 => json!(["0x01", "0x02"]),
+                2 => json!(["0x03"]),
+                _ => json!([]),
+            };
+            (
+                200,
+                json!({
+                    "data": {
+                        "nibbleDeployed": {
+                            "agents": agents,
+                            "conditions": [],
+                            "listeners": [],
+                            "fhe_gates": [],
+                            "evaluations": [],
+                            "onchain_connectors": [],
+                            "offchain_connectors": [],
+                            "workflows": [],
+                            "contracts": [{
+                                "name": "NibbleAgents",
+                                "address": "0x0000000000000000000000000000000000000001"
+                            }],
+                            "count": "3"
+                        }
+                    }
+                })
+                .to_string(),
+            )
+        })
+        .await;
+
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        nibble.subgraph = SubgraphConfig {
+            endpoint: subgraph.url.clone(),
+            ..SubgraphConfig::new(None).with_page_size(2)
+        };
+        nibble.load_nibble("0xnibble").await.unwrap();
+
+        assert_eq!(nibble.contracts.len(), 1);
+        assert_eq!(nibble.count.as_u64(), 3);
+        assert_eq!(
+            nibble
+                .load_warnings
+                .iter()
+                .map(|warning| warning.entity_id.clone().unwrap())
+                .collect::<Vec<_>>(),
+            vec!["0x01", "0x02", "0x03"]
+        );
+
+        let skips: Vec<u64> = subgraph
+            .requests()
+            .iter()
+            .map(|request| {
+                let body: Value = serde_json::from_str(&request.body).unwrap();
+                assert_eq!(body["variables"]["first"], json!(2));
+                body["variables"]["skip"].as_u64().unwrap()
+            })
+            .collect();
+        assert_eq!(skips, vec![0, 2]);
+    }
+
+    fn workflow_payload(node_id: &str) -> String {
+        json!({
+            "data": {
+                "workflow": {
+                    "id": "0xf1",
+                    "name": "Meme Pipeline",
+                    "encrypted": false,
+                    "nodes": [{
+                        "id": node_id,
+                        "adapter_type": "Agent",
+                        "adapter_id": "0xa1"
+                    }],
+                    "links": [{
+                        "id": "0xb1",
+                        "adapter_type": "Condition",
+                        "adapter_id": "0xc1",
+                        "enabled": false
+                    }],
+                    "execution_history": [{
+                        "element_id": node_id,
+                        "element_type": "Agent",
+                        "result": "gm",
+                        "description": "Agent executed",
+                        "timestamp": "2024-01-01T00:00:00Z"
+                    }]
+                }
+            }
+        })
+        .to_string()
+    }
+
+    fn workflow_nibble(subgraph_url: &str) -> Nibble {
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        nibble.subgraph.endpoint = subgraph_url.to_string();
+        nibble.id = Some("0xnibble".to_string());
+        nibble.contracts.push(ContractInfo {
+            name: "NibbleAgents".to_string(),
+            address: Address::zero(),
+        });
+        nibble
+    }
+
+    #[tokio::test]
+    async fn test_load_nibble_fetches_metadata_from_configured_gateway() {
+        let server = spawn_mock_server(|request| {
+            if request.method == "GET" {
+                return (
+                    200,
+                    json!({
+                        "name": "Gateway",
+                        "id": "0xc0ffee",
+                        "condition_type": "ContextBased"
+                    })
+                    .to_string(),
+                );
+            }
+            (
+                200,
+                json!({
+                    "data": {
+                        "nibbleDeployed": {
+                            "conditions": [{ "metadata": "QmCondition", "encrypted": false }],
+                            "contracts": [],
+                            "count": "1"
+                        }
+                    }
+                })
+                .to_string(),
+            )
+        })
+        .await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &server.url);
+        nibble.subgraph.endpoint = server.url.clone();
+
+        nibble.load_nibble("0xnibble").await.unwrap();
+
+        assert_eq!(nibble.saved_conditions.len(), 1);
+        assert_eq!(nibble.saved_conditions[0].name, "Gateway");
+        assert!(server
+            .requests()
+            .iter()
+            .any(|request| request.method == "GET" && request.path == "/ipfs/QmCondition"));
+    }
+
+    #[tokio::test]
+    async fn test_load_workflow_reads_workflow_payload() {
+        let subgraph = spawn_mock_server(|_| (200, workflow_payload("0xd1"))).await;
+
+        let workflow = Arc::new(workflow_nibble(&subgraph.url))
+            .load_shared_workflow("0xf1")
+            .await
+            .unwrap();
+
+        assert_eq!(workflow.id, "0xf1");
+        assert_eq!(workflow.name, "Meme Pipeline");
+        assert!(!workflow.encrypted);
+        assert_eq!(workflow.nodes["0xd1"].adapter_id, "0xa1");
+        assert_eq!(workflow.links["0xb1"].adapter_id, "0xc1");
+        assert!(workflow.nodes["0xd1"].enabled);
+        assert!(!workflow.links["0xb1"].enabled);
+        assert_eq!(workflow.execution_history.len(), 1);
+        assert_eq!(workflow.execution_history[0].result, Some(json!("gm")));
+
+        let body: Value = serde_json::from_str(&subgraph.requests()[0].body).unwrap();
+        let query = body["query"].as_str().unwrap();
+        assert!(query.contains("workflow(id: $id"));
+        assert!(query.contains("encrypted"));
+        assert!(query.contains("execution_history"));
+        assert_eq!(body["variables"]["nibble_id"], json!("0xnibble"));
+    }
+
+    #[tokio::test]
+    async fn test_load_workflow_normalizes_hex_ids() {
+        let subgraph = spawn_mock_server(|_| (200, workflow_payload("0xD1E2"))).await;
+
+        let workflow = Arc::new(workflow_nibble(&subgraph.url))
+            .load_shared_workflow("0xf1")
+            .await
+            .unwrap();
+
+        assert!(workflow.nodes.contains_key("0xd1e2"));
+    }
+
+    #[tokio::test]
+    async fn test_saved_workflow_and_connector_reload_with_the_same_ids() {
+        let mut source = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        let connector_id = add_rest_connector(&mut source, "Feed", "http://127.0.0.1:1/feed");
+        let connector_metadata = Value::Object(source.offchain_connectors[0].to_json());
+
+        let mut workflow = Arc::new(source).create_shared_workflow("Saved", false);
+        workflow.add_node(
+            connector_id.clone(),
+            NodeAdapter::OffChainConnector,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        workflow.add_link(
+            "0xc1".to_string(),
+            LinkAdapter::Condition,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        let node_id = workflow.nodes.keys().next().unwrap().clone();
+        let link_id = workflow.links.keys().next().unwrap().clone();
+        let workflow_payload = json!({
+            "data": {
+                "workflow": {
+                    "id": "0xf1",
+                    "name": "Saved",
+                    "encrypted": false,
+                    "nodes": workflow
+                        .nodes
+                        .values()
+                        .map(|node| Value::Object(node.to_json()))
+                        .collect::<Vec<_>>(),
+                    "links": workflow
+                        .links
+                        .values()
+                        .map(|link| Value::Object(link.to_json()))
+                        .collect::<Vec<_>>(),
+                    "execution_history": []
+                }
+            }
+        })
+        .to_string();
+
+        let server = spawn_mock_server(move |request| {
+            if request.method == "GET" {
+                return (200, connector_metadata.to_string());
+            }
+            if request.body.contains("workflow(id") {
+                return (200, workflow_payload.clone());
+            }
+            (
+                200,
+                json!({
+                    "data": {
+                        "nibbleDeployed": {
+                            "offchain_connectors": [{ "metadata": "QmConnector", "encrypted": false }],
+                            "contracts": [],
+                            "count": "1"
+                        }
+                    }
+                })
+                .to_string(),
+            )
+        })
+        .await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &server.url);
+        nibble.subgraph.endpoint = server.url.clone();
+
+        nibble.load_nibble("0xnibble").await.unwrap();
+        assert_eq!(nibble.saved_offchain_connectors.len(), 1);
+        assert_eq!(nibble.saved_offchain_connectors[0].id, connector_id);
+
+        nibble.id = Some("0xnibble".to_string());
+        nibble.contracts.push(ContractInfo {
+            name: "NibbleAgents".to_string(),
+            address: Address::zero(),
+        });
+        let loaded = Arc::new(nibble).load_shared_workflow("0xf1").await.unwrap();
+
+        assert_eq!(loaded.nodes[&node_id].id, node_id);
+        assert_eq!(loaded.nodes[&node_id].adapter_id, connector_id);
+        assert_eq!(loaded.links[&link_id].id, link_id);
+        assert_eq!(loaded.links[&link_id].adapter_id, "0xc1");
+    }
+
+    #[tokio::test]
+    async fn test_load_workflow_rejects_non_hex_ids() {
+        let subgraph = spawn_mock_server(|_| (200, workflow_payload("node-1"))).await;
+
+        let error = Arc::new(workflow_nibble(&subgraph.url))
+            .load_shared_workflow("0xf1")
+            .await
+            .err()
+            .unwrap();
+
+        assert!(error.to_string().contains("expected 0x-prefixed hex"));
+    }
+
+    #[tokio::test]
+    async fn test_subgraph_retries_rate_limit_using_retry_after() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let subgraph = spawn_mock_server_with_headers(move |_| {
+            if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                (
+                    429,
+                    vec![("Retry-After".to_string(), "0".to_string())],
+                    "rate limited".to_string(),
+                )
+            } else {
+                (200, Vec::new(), nibble_page())
+            }
+        })
+        .await;
+
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        nibble.subgraph = SubgraphConfig {
+            endpoint: subgraph.url.clone(),
+            ..SubgraphConfig::new(None).with_retry(RetryPolicy {
+                max_attempts: 3,
+                base_delay: Duration::from_secs(30),
+                max_delay: Duration::from_secs(60),
+            })
+        };
+
+        timeout(Duration::from_secs(5), nibble.load_nibble("0xnibble"))
+            .await
+            .expect("Retry-After should override the base delay")
+            .unwrap();
+
+        assert_eq!(nibble.contracts.len(), 1);
+        assert_eq!(subgraph.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_subgraph_gives_up_after_max_attempts() {
+        let subgraph = spawn_mock_server(|_| (503, "unavailable".to_string())).await;
+
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        nibble.subgraph = SubgraphConfig {
+            endpoint: subgraph.url.clone(),
+            ..SubgraphConfig::new(None).with_retry(RetryPolicy {
+                max_attempts: 2,
+                base_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(5),
+            })
+        };
+
+        let error = nibble.load_nibble("0xnibble").await.err().unwrap();
+
+        assert!(matches!(error, NpcError::Subgraph(_)));
+        assert!(error.is_retryable());
+        assert!(error.to_string().contains("unavailable"));
+        assert_eq!(subgraph.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_entities_become_load_warnings() {
+        let subgraph = spawn_mock_server(|_| {
+            (
+                200,
+                json!({
+                    "data": {
+                        "nibbleDeployed": {
+                            "agents": [{"id": "0xa1", "wallet": "0x01"}],
+                            "offchain_connectors": [{"id": "0xc1"}],
+                            "contracts": [{
+                                "name": "NibbleAgents",
+                                "address": "0x0000000000000000000000000000000000000001"
+                            }],
+                            "count": "2"
+                        }
+                    }
+                })
+                .to_string(),
+            )
+        })
+        .await;
+
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        nibble.subgraph.endpoint = subgraph.url.clone();
+        nibble.load_nibble("0xnibble").await.unwrap();
+
+        assert_eq!(nibble.contracts.len(), 1);
+        assert!(nibble.saved_agents.is_empty());
+        assert_eq!(nibble.load_warnings.len(), 2);
+        assert_eq!(nibble.load_warnings[0].entity_type, "agent");
+        assert_eq!(nibble.load_warnings[0].entity_id.as_deref(), Some("0xa1"));
+        assert!(nibble.load_warnings[0].error.contains("Missing metadata"));
+        assert_eq!(nibble.load_warnings[1].entity_type, "offchain_connector");
+        assert_eq!(nibble.load_warnings[1].entity_id.as_deref(), Some("0xc1"));
+    }
+
+    fn deployed_page(field: &str, records: Value) -> String {
+        json!({ "data": { "nibbleDeployed": { field: records } } }).to_string()
+    }
+
+    #[tokio::test]
+    async fn test_wait_until_indexed_polls_until_workflow_appears() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let subgraph = spawn_mock_server(move |_| {
+            let records = if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                json!([])
+            } else {
+                json!([{ "id": "0xf1" }])
+            };
+            (200, deployed_page("workflows", records))
+        })
+        .await;
+        let workflow =
+            Arc::new(workflow_nibble(&subgraph.url)).create_shared_workflow("Indexed", false);
+
+        workflow
+            .wait_until_indexed(Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        let body: Value = serde_json::from_str(&subgraph.requests()[0].body).unwrap();
+        assert!(body["query"]
+            .as_str()
+            .unwrap()
+            .contains("workflows(where: { id: $entity }"));
+        assert_eq!(body["variables"]["entity"], json!(workflow.id));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_subgraph_times_out() {
+        let subgraph = spawn_mock_server(|_| (200, deployed_page("agents", json!([])))).await;
+        let nibble = workflow_nibble(&subgraph.url);
+
+        let error = nibble
+            .wait_for_subgraph("agents", "0xa1", Duration::from_millis(10))
+            .await
+            .err()
+            .unwrap();
+
+        assert!(matches!(error, NpcError::Timeout(_)));
+        assert!(error.to_string().contains("0xa1"));
+    }
+
+    #[derive(Debug, Default)]
+    struct CannedSubgraph {
+        responses: Mutex<Vec<Value>>,
+        queries: Mutex<Vec<Value>>,
+    }
+
+    #[async_trait]
+    impl SubgraphClient for CannedSubgraph {
+        async fn query(
+            &self,
+            _endpoint: &str,
+            _retry: &RetryPolicy,
+            query: &Value,
+        ) -> Result<Value, Box<dyn Error + Send + Sync>> {
+            self.queries.lock().unwrap().push(query.clone());
+            Ok(self.responses.lock().unwrap().remove(0))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subgraph_client_serves_canned_responses() {
+        let canned = Arc::new(CannedSubgraph {
+            responses: Mutex::new(vec![
+                serde_json::from_str(&nibble_page()).unwrap(),
+                serde_json::from_str(&workflow_payload("0xd1")).unwrap(),
+            ]),
+            ..CannedSubgraph::default()
+        });
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        nibble.subgraph = SubgraphConfig::new(None).with_client(canned.clone());
+        nibble.id = Some("0xnibble".to_string());
+
+        nibble.load_nibble("0xnibble").await.unwrap();
+        let workflow = Arc::new(nibble.clone())
+            .load_shared_workflow("0xf1")
+            .await
+            .unwrap();
+
+        assert_eq!(nibble.contracts.len(), 1);
+        assert_eq!(workflow.name, "Meme Pipeline");
+        let queries = canned.queries.lock().unwrap();
+        assert_eq!(queries.len(), 2);
+        assert_eq!(queries[1]["variables"]["id"], json!("0xf1"));
+    }
+}
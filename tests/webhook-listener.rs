@@ -0,0 +1,102 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::spawn_mock_server;
+    use ethers::types::Address;
+    use npc_workbench::adapters::links::listeners::{configure_new_listener, ListenerType};
+    use serde_json::{json, Value};
+    use std::net::SocketAddr;
+    use tokio::{net::TcpListener, sync::mpsc, time::Duration};
+
+    async fn free_addr() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap()
+    }
+
+    async fn post_when_ready(url: &str, body: &Value) -> reqwest::StatusCode {
+        let client = reqwest::Client::new();
+        for _ in 0..50 {
+            match client.post(url).json(body).send().await {
+                Ok(response) => return response.status(),
+                Err(_) => tokio::time::sleep(Duration::from_millis(20)).await,
+            }
+        }
+        panic!("Webhook server never became ready at {}", url);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_server_forwards_posts_and_shuts_down() {
+        let bind_addr = free_addr().await;
+        let listener = configure_new_listener(
+            "Inbound",
+            ListenerType::WebhookServer {
+                bind_addr,
+                path: "/hooks/meme".to_string(),
+                sns_verification: false,
+            },
+            false,
+            None,
+            None,
+            &Address::zero(),
+        )
+        .unwrap();
+
+        let (sender, mut receiver) = mpsc::channel(8);
+        let handle =
+            tokio::spawn(async move { listener.listen_and_trigger(sender, Some(2)).await });
+
+        let url = format!("http://{}/hooks/meme", bind_addr);
+        assert!(post_when_ready(&url, &json!({"id": 1})).await.is_success());
+        assert!(post_when_ready(&url, &json!({"id": 2})).await.is_success());
+
+        assert_eq!(receiver.recv().await.unwrap(), json!({"id": 1}));
+        assert_eq!(receiver.recv().await.unwrap(), json!({"id": 2}));
+        handle.await.unwrap().unwrap();
+        assert!(reqwest::Client::new()
+            .post(&url)
+            .json(&json!({"id": 3}))
+            .send()
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_webhook_server_confirms_sns_subscription() {
+        let sns = spawn_mock_server(|_| (200, "{}".to_string())).await;
+        let bind_addr = free_addr().await;
+        let listener = configure_new_listener(
+            "Sns",
+            ListenerType::WebhookServer {
+                bind_addr,
+                path: "/sns".to_string(),
+                sns_verification: true,
+            },
+            false,
+            None,
+            None,
+            &Address::zero(),
+        )
+        .unwrap();
+
+        let (sender, mut receiver) = mpsc::channel(8);
+        let handle =
+            tokio::spawn(async move { listener.listen_and_trigger(sender, Some(1)).await });
+
+        let url = format!("http://{}/sns", bind_addr);
+        let confirmation = json!({
+            "Type": "SubscriptionConfirmation",
+            "SubscribeURL": format!("{}/confirm?token=abc", sns.url),
+        });
+        assert!(post_when_ready(&url, &confirmation).await.is_success());
+        let notification = json!({"Type": "Notification", "Message": "gm"});
+        assert!(post_when_ready(&url, &notification).await.is_success());
+
+        assert_eq!(receiver.recv().await.unwrap(), notification);
+        handle.await.unwrap().unwrap();
+        let requests = sns.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "GET");
+        assert_eq!(requests[0].path, "/confirm?token=abc");
+    }
+}
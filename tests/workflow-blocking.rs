@@ -0,0 +1,29 @@
+mod common;
+
+#[cfg(all(test, feature = "blocking"))]
+mod tests {
+    use crate::common::offline_nibble;
+    use npc_workbench::error::NpcError;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_execute_blocking_runs_without_async_caller() {
+        let nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Blocking", false);
+
+        let history = workflow.execute_blocking(Some(1), false).unwrap();
+
+        assert!(history.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_execute_blocking_rejects_running_runtime() {
+        let nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Blocking", false);
+
+        let error = workflow.execute_blocking(Some(1), false).unwrap_err();
+
+        assert!(matches!(error, NpcError::Validation(_)));
+        assert!(error.to_string().contains("execute().await"));
+    }
+}
@@ -0,0 +1,81 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::offline_nibble;
+    use async_trait::async_trait;
+    use npc_workbench::workflow::{NodeAdapter, NodeExecutor};
+    use serde_json::{json, Value};
+    use std::{
+        error::Error,
+        sync::{Arc, Mutex},
+    };
+
+    #[derive(Debug, Default)]
+    struct EchoNode {
+        contexts: Mutex<Vec<Value>>,
+    }
+
+    #[async_trait]
+    impl NodeExecutor for EchoNode {
+        async fn execute(&self, ctx: Value) -> Result<Option<Value>, Box<dyn Error + Send + Sync>> {
+            self.contexts.lock().unwrap().push(ctx.clone());
+            Ok(Some(json!({ "echo": ctx })))
+        }
+    }
+
+    #[derive(Debug)]
+    struct FailingNode;
+
+    #[async_trait]
+    impl NodeExecutor for FailingNode {
+        async fn execute(&self, _: Value) -> Result<Option<Value>, Box<dyn Error + Send + Sync>> {
+            Err("script crashed".into())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_node_executes_and_records_history() {
+        let nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        let echo = Arc::new(EchoNode::default());
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Custom", false);
+        workflow.add_node(
+            "echo".to_string(),
+            NodeAdapter::Custom(echo.clone()),
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+
+        let history = workflow.execute(Some(1), false).await.unwrap();
+
+        assert_eq!(echo.contexts.lock().unwrap().len(), 1);
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].element_type, "Custom");
+        assert_eq!(history[0].result, Some(json!({ "echo": null })));
+        assert!(history[0].error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_custom_node_errors_are_recorded() {
+        let nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Custom", false);
+        workflow.add_node(
+            "fail".to_string(),
+            NodeAdapter::Custom(Arc::new(FailingNode)),
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+
+        let history = workflow.execute(Some(1), false).await.unwrap();
+
+        assert_eq!(history[0].element_type, "Custom");
+        assert_eq!(history[0].error.as_deref(), Some("script crashed"));
+        assert!(workflow.verify_adapters().missing.is_empty());
+    }
+}
@@ -0,0 +1,55 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{node_id_for, offline_nibble};
+    use npc_workbench::workflow::NodeAdapter;
+    use serde_json::json;
+    use std::{
+        sync::Arc,
+        time::{Duration, Instant},
+    };
+
+    #[tokio::test]
+    async fn test_delay_node_sleeps_and_records_history() {
+        let nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Paced", false);
+        workflow.add_delay_node(Duration::from_millis(20), None);
+        let delay_node = node_id_for(&workflow, "delay");
+
+        let started = Instant::now();
+        let history = workflow.execute(Some(1), false).await.unwrap();
+
+        assert!(started.elapsed() >= Duration::from_millis(20));
+        let entry = history
+            .iter()
+            .find(|entry| entry.element_id == delay_node)
+            .unwrap();
+        assert_eq!(entry.element_type, "Delay");
+        assert_eq!(entry.description.as_deref(), Some("Delayed 20ms"));
+        assert!(entry.error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_delay_node_without_duration_records_error() {
+        let nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Paced", false);
+        workflow.add_node(
+            "delay".to_string(),
+            NodeAdapter::Delay,
+            None,
+            Some(json!({})),
+            None,
+            None,
+            None,
+        );
+
+        let history = workflow.execute(Some(1), false).await.unwrap();
+
+        assert!(history[0]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("requires a delay_ms"));
+    }
+}
@@ -0,0 +1,101 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{node_id_for, offline_nibble};
+    use npc_workbench::workflow::{LinkAdapter, LinkTarget, NodeAdapter, Workflow};
+    use serde_json::{json, Value};
+    use std::{collections::HashMap, sync::Arc};
+
+    fn meme_workflow(caption: &str) -> Workflow {
+        let nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Meme", false);
+        workflow
+            .add_node(
+                "0xa1".to_string(),
+                NodeAdapter::Agent,
+                None,
+                Some(json!(caption)),
+                None,
+                None,
+                None,
+            )
+            .add_node(
+                "0xc1".to_string(),
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            );
+        let agent_node = node_id_for(&workflow, "0xa1");
+        let post_node = node_id_for(&workflow, "0xc1");
+        workflow.add_link(
+            "0xe1".to_string(),
+            LinkAdapter::Evaluation,
+            None,
+            None,
+            Some(LinkTarget {
+                true_target_id: post_node,
+                false_target_id: agent_node,
+                generated_target_id: None,
+                score_targets: vec![],
+                loop_limits: HashMap::new(),
+            }),
+            None,
+            None,
+            None,
+        );
+        workflow
+    }
+
+    #[test]
+    fn test_diff_ignores_generated_ids() {
+        let diff = meme_workflow("gm").diff(&meme_workflow("gm"));
+
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn test_diff_reports_added_removed_and_modified_elements() {
+        let before = meme_workflow("gm");
+        let mut after = meme_workflow("gn");
+        after.add_node(
+            "0xc2".to_string(),
+            NodeAdapter::OffChainConnector,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        after.links.clear();
+
+        let diff = before.diff(&after);
+
+        assert_eq!(diff.added_nodes.len(), 1);
+        assert_eq!(diff.added_nodes[0].signature, "OffChainConnector:0xc2");
+        assert!(diff.removed_nodes.is_empty());
+        assert_eq!(diff.modified_nodes.len(), 1);
+        assert_eq!(
+            diff.modified_nodes[0].before.as_ref().unwrap()["context"],
+            json!("gm")
+        );
+        assert_eq!(
+            diff.modified_nodes[0].after.as_ref().unwrap()["context"],
+            json!("gn")
+        );
+        assert_eq!(diff.removed_links.len(), 1);
+        assert_eq!(
+            diff.removed_links[0].before.as_ref().unwrap()["target"]["true"],
+            json!("OffChainConnector:0xc1")
+        );
+
+        let serialized: Value = serde_json::to_value(&diff).unwrap();
+        assert_eq!(
+            serialized["removed_links"][0]["signature"],
+            "Evaluation:0xe1"
+        );
+    }
+}
@@ -0,0 +1,1653 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{
+        add_rest_connector, mock_llm_model, mock_openai_model, node_id_for, offline_nibble,
+        spawn_mock_server,
+    };
+    use npc_workbench::{
+        adapters::links::{
+            conditions::{ConditionType, TimeComparisonType},
+            evaluations::{EvaluationResponseType, EvaluationType},
+        },
+        error::NpcError,
+        tools::context::ContextParse,
+        workflow::{
+            AdapterOutcomes, CheckpointConfig, ErrorPolicy, ExecutionHistory, LinkAdapter,
+            LinkTarget, NodeAdapter,
+        },
+    };
+    use serde_json::{json, Value};
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+    };
+    use tokio::time::{timeout, Duration};
+
+    #[tokio::test]
+    async fn test_context_middleware_redacts_between_elements() {
+        let server = spawn_mock_server(|_| {
+            (
+                200,
+                json!({"secret": "hunter2", "public": "ok"}).to_string(),
+            )
+        })
+        .await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &server.url);
+        let connector_ids = vec![
+            add_rest_connector(&mut nibble, "First", &server.url),
+            add_rest_connector(&mut nibble, "Second", &server.url),
+        ];
+
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Redaction", false);
+        for id in connector_ids {
+            workflow.add_node(
+                id,
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            );
+        }
+        workflow.add_context_middleware(Arc::new(|mut value: Value| {
+            if let Some(map) = value.as_object_mut() {
+                map.remove("secret");
+            }
+            value
+        }));
+
+        workflow.execute(Some(1), false).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests
+            .iter()
+            .all(|request| !request.body.contains("hunter2")));
+        assert!(requests
+            .iter()
+            .any(|request| request.body.contains("\"public\":\"ok\"")));
+    }
+
+    #[tokio::test]
+    async fn test_evaluation_routes_on_decision_and_forwards_data() {
+        let llm = spawn_mock_server(|_| {
+            (
+                200,
+                json!({
+                    "result": {
+                        "decision": true,
+                        "data": {"chosen": {"id": 7, "text": "gm"}}
+                    }
+                })
+                .to_string(),
+            )
+        })
+        .await;
+        let chosen_target = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+        let other_target = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &llm.url);
+        let chosen_connector = add_rest_connector(&mut nibble, "Chosen", &chosen_target.url);
+        let other_connector = add_rest_connector(&mut nibble, "Other", &other_target.url);
+        let evaluation_id = nibble
+            .add_evaluation(
+                "PickNotification",
+                EvaluationType::LLMJudge {
+                    model_type: mock_llm_model(&llm.url, "object"),
+                    prompt: "Pick the best notification".to_string(),
+                    response_type: EvaluationResponseType::BooleanWithData { expected: true },
+                },
+                false,
+            )
+            .unwrap()
+            .adapter
+            .id
+            .clone();
+
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Evaluation", false);
+        workflow
+            .add_node(
+                chosen_connector.clone(),
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .add_node(
+                other_connector.clone(),
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            );
+        let true_target_id = node_id_for(&workflow, &chosen_connector);
+        let false_target_id = node_id_for(&workflow, &other_connector);
+        workflow.add_link(
+            evaluation_id,
+            LinkAdapter::Evaluation,
+            None,
+            None,
+            Some(LinkTarget {
+                true_target_id,
+                false_target_id,
+                generated_target_id: None,
+                score_targets: vec![],
+                loop_limits: HashMap::new(),
+            }),
+            None,
+            None,
+            None,
+        );
+
+        workflow.execute(Some(1), false).await.unwrap();
+
+        let chosen_requests = chosen_target.requests();
+        assert_eq!(chosen_requests.len(), 1);
+        let body: Value = serde_json::from_str(&chosen_requests[0].body).unwrap();
+        assert_eq!(body["chosen"], json!({"id": 7, "text": "gm"}));
+        assert!(other_target.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_repetition_retry_counts_recovered_pass_once() {
+        let calls = AtomicUsize::new(0);
+        let server = spawn_mock_server(move |_| {
+            if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                (500, "upstream unavailable".to_string())
+            } else {
+                (200, json!({"ok": true}).to_string())
+            }
+        })
+        .await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &server.url);
+        let connector_id = add_rest_connector(&mut nibble, "Flaky", &server.url);
+
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Retry", false);
+        workflow
+            .add_node(
+                connector_id,
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .set_repetition_retry(Some(2));
+
+        let history = workflow.execute(Some(1), true).await.unwrap();
+
+        assert_eq!(server.requests().len(), 2);
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].result, Some(json!({"ok": true})));
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_restores_equivalent_state() {
+        let stored: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+        let pinned = stored.clone();
+        let ipfs = spawn_mock_server(move |request| {
+            let mut pinned = pinned.lock().unwrap();
+            if request.method == "POST" {
+                pinned.push(request.body.clone());
+                (
+                    200,
+                    json!({"Hash": format!("QmCheckpoint{}", pinned.len() - 1)}).to_string(),
+                )
+            } else {
+                let index: usize = request
+                    .path
+                    .trim_start_matches("/ipfs/QmCheckpoint")
+                    .parse()
+                    .unwrap();
+                (200, pinned[index].clone())
+            }
+        })
+        .await;
+        let api = spawn_mock_server(|_| (200, json!({"step": "done"}).to_string())).await;
+
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &ipfs.url);
+        let connector_ids = vec![
+            add_rest_connector(&mut nibble, "First", &api.url),
+            add_rest_connector(&mut nibble, "Second", &api.url),
+        ];
+
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Checkpointed", false);
+        for id in connector_ids {
+            workflow.add_node(
+                id,
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            );
+        }
+        workflow.set_checkpoint_config(Some(CheckpointConfig {
+            every_elements: Some(1),
+            every_seconds: None,
+        }));
+
+        let history = workflow.execute(Some(1), false).await.unwrap();
+
+        assert_eq!(workflow.checkpoints, vec!["QmCheckpoint0", "QmCheckpoint1"]);
+        assert_eq!(stored.lock().unwrap().len(), 2);
+
+        workflow.execution_history.clear();
+        let state = workflow.restore("QmCheckpoint1").await.unwrap();
+
+        assert_eq!(state.workflow_id, workflow.id);
+        assert_eq!(state.repetition, 1);
+        assert_eq!(state.elements_completed, 2);
+        assert_eq!(state.context, Some(json!({"step": "done"})));
+        assert_eq!(state.execution_history, history);
+        assert_eq!(workflow.execution_history, history);
+    }
+
+    #[tokio::test]
+    async fn test_simulate_workflow_follows_stubbed_branch_without_side_effects() {
+        let llm = spawn_mock_server(|_| (200, json!({"result": true}).to_string())).await;
+        let api = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &llm.url);
+        let post_connector = add_rest_connector(&mut nibble, "PostToLens", &api.url);
+        let agent_id = nibble
+            .add_agent(
+                "MemeMaster",
+                "role",
+                "personality",
+                "system",
+                false,
+                false,
+                mock_llm_model(&llm.url, "string"),
+                false,
+                None,
+                None,
+                None,
+                vec![],
+            )
+            .unwrap()
+            .adapter
+            .id
+            .clone();
+        let evaluation_id = nibble
+            .add_evaluation(
+                "ShouldPost",
+                EvaluationType::LLMJudge {
+                    model_type: mock_llm_model(&llm.url, "boolean"),
+                    prompt: "Should the meme be posted?".to_string(),
+                    response_type: EvaluationResponseType::BooleanWithData { expected: true },
+                },
+                false,
+            )
+            .unwrap()
+            .adapter
+            .id
+            .clone();
+
+        let mut workflow = Arc::new(nibble.clone()).create_shared_workflow("Meme", false);
+        workflow
+            .add_node(
+                post_connector.clone(),
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .add_node(
+                agent_id.clone(),
+                NodeAdapter::Agent,
+                None,
+                Some(json!("Draft a reply instead")),
+                None,
+                None,
+                None,
+            );
+        let post_node = node_id_for(&workflow, &post_connector);
+        let agent_node = node_id_for(&workflow, &agent_id);
+        workflow.add_link(
+            evaluation_id.clone(),
+            LinkAdapter::Evaluation,
+            None,
+            None,
+            Some(LinkTarget {
+                true_target_id: post_node.clone(),
+                false_target_id: agent_node.clone(),
+                generated_target_id: None,
+                score_targets: vec![],
+                loop_limits: HashMap::new(),
+            }),
+            None,
+            None,
+            None,
+        );
+
+        let mut stubs = HashMap::new();
+        stubs.insert(
+            evaluation_id,
+            json!({"decision": false, "data": {"reason": "too spicy"}}),
+        );
+        let history = nibble
+            .simulate_workflow(&workflow, stubs, Some(1))
+            .await
+            .unwrap();
+
+        assert!(llm.requests().is_empty());
+        assert!(api.requests().is_empty());
+        assert!(history.iter().all(|entry| entry.element_id != post_node));
+        let agent_entry = history
+            .iter()
+            .find(|entry| entry.element_id == agent_node)
+            .unwrap();
+        assert_eq!(agent_entry.result, Some(json!("Draft a reply instead")));
+        assert_eq!(agent_entry.description.as_deref(), Some("Simulated"));
+        assert!(workflow.execution_history.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failing_context_tool_is_recorded_in_history() {
+        let llm = spawn_mock_server(|_| {
+            (
+                200,
+                json!({"result": {"decision": true, "data": {"text": "gm"}}}).to_string(),
+            )
+        })
+        .await;
+        let api = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &llm.url);
+        let connector_id = add_rest_connector(&mut nibble, "Publish", &api.url);
+        let evaluation_id = nibble
+            .add_evaluation(
+                "ShouldPublish",
+                EvaluationType::LLMJudge {
+                    model_type: mock_llm_model(&llm.url, "object"),
+                    prompt: "Should this be published?".to_string(),
+                    response_type: EvaluationResponseType::BooleanWithData { expected: true },
+                },
+                false,
+            )
+            .unwrap()
+            .adapter
+            .id
+            .clone();
+
+        let mut workflow = Arc::new(nibble).create_shared_workflow("ContextToolFailure", false);
+        workflow.add_node(
+            connector_id.clone(),
+            NodeAdapter::OffChainConnector,
+            None,
+            None,
+            None,
+            Some(ContextParse::ParseFields {
+                expected_format: serde_json::Map::new(),
+                required_fields: vec!["publication_id".to_string()],
+            }),
+            None,
+        );
+        let publish_node = node_id_for(&workflow, &connector_id);
+        workflow.add_link(
+            evaluation_id,
+            LinkAdapter::Evaluation,
+            None,
+            None,
+            Some(LinkTarget {
+                true_target_id: publish_node.clone(),
+                false_target_id: publish_node.clone(),
+                generated_target_id: None,
+                score_targets: vec![],
+                loop_limits: HashMap::new(),
+            }),
+            None,
+            None,
+            None,
+        );
+
+        let history = workflow.execute(Some(1), false).await.unwrap();
+
+        let publish_entry = history
+            .iter()
+            .find(|entry| entry.element_id == publish_node)
+            .unwrap();
+        let error = publish_entry.error.as_deref().unwrap();
+        assert!(error.contains("Required Field Not Found"));
+        assert!(error.contains("publication_id"));
+        assert!(history
+            .iter()
+            .filter(|entry| entry.element_id != publish_node)
+            .all(|entry| entry.error.is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_execute_on_shared_workflow_is_rejected() {
+        let server = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &server.url);
+        let connector_id = add_rest_connector(&mut nibble, "Slow", &server.url);
+
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Guarded", false);
+        workflow.add_node(
+            connector_id,
+            NodeAdapter::OffChainConnector,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        let mut clone = workflow.clone();
+
+        let (first, second) = tokio::join!(
+            workflow.execute(Some(1), false),
+            clone.execute(Some(1), false)
+        );
+
+        assert_eq!(first.unwrap().len(), 1);
+        let error = second.unwrap_err();
+        assert!(matches!(error, NpcError::Validation(_)));
+        assert!(error.to_string().contains("already executing"));
+        assert_eq!(server.requests().len(), 1);
+
+        let history = clone.execute(Some(1), false).await.unwrap();
+        assert_eq!(history.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_false_condition_records_branch_and_target() {
+        let accepted = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+        let rejected = spawn_mock_server(|_| (200, json!({"ok": false}).to_string())).await;
+
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &accepted.url);
+        let accept_connector = add_rest_connector(&mut nibble, "Accept", &accepted.url);
+        let reject_connector = add_rest_connector(&mut nibble, "Reject", &rejected.url);
+        let condition_id = nibble
+            .add_condition(
+                "BeforeMidnight",
+                ConditionType::TimeBased {
+                    comparison_time: chrono::NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
+                    comparison_type: TimeComparisonType::Before,
+                },
+                |_| true,
+                None,
+                false,
+            )
+            .unwrap()
+            .adapter
+            .id
+            .clone();
+
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Branching", false);
+        workflow
+            .add_node(
+                accept_connector.clone(),
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .add_node(
+                reject_connector.clone(),
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            );
+        let accept_node = node_id_for(&workflow, &accept_connector);
+        let reject_node = node_id_for(&workflow, &reject_connector);
+        workflow.add_link(
+            condition_id,
+            LinkAdapter::Condition,
+            None,
+            None,
+            Some(LinkTarget {
+                true_target_id: accept_node.clone(),
+                false_target_id: reject_node.clone(),
+                generated_target_id: None,
+                score_targets: vec![],
+                loop_limits: HashMap::new(),
+            }),
+            None,
+            None,
+            None,
+        );
+
+        let history = workflow.execute(Some(1), false).await.unwrap();
+
+        let routed: Vec<_> = history
+            .iter()
+            .filter(|entry| entry.branch.is_some())
+            .collect();
+        assert!(!routed.is_empty());
+        assert!(routed.iter().all(|entry| {
+            entry.branch.as_deref() == Some("false")
+                && entry.target_id.as_deref() == Some(reject_node.as_str())
+        }));
+        assert!(accepted.requests().is_empty());
+        assert_eq!(rejected.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_score_evaluation_routes_to_matching_range() {
+        let llm =
+            spawn_mock_server(|_| (200, json!({"result": {"score": 0.55}}).to_string())).await;
+        let low = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+        let medium = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+        let high = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &llm.url);
+        let low_connector = add_rest_connector(&mut nibble, "Low", &low.url);
+        let medium_connector = add_rest_connector(&mut nibble, "Medium", &medium.url);
+        let high_connector = add_rest_connector(&mut nibble, "High", &high.url);
+        let evaluation_id = nibble
+            .add_evaluation(
+                "Engagement",
+                EvaluationType::LLMJudge {
+                    model_type: mock_llm_model(&llm.url, "object"),
+                    prompt: "Score the expected engagement.".to_string(),
+                    response_type: EvaluationResponseType::Score { threshold: 0.5 },
+                },
+                false,
+            )
+            .unwrap()
+            .adapter
+            .id
+            .clone();
+
+        let mut workflow = Arc::new(nibble).create_shared_workflow("ScoreRouting", false);
+        for connector in [&low_connector, &medium_connector, &high_connector] {
+            workflow.add_node(
+                connector.clone(),
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            );
+        }
+        let low_node = node_id_for(&workflow, &low_connector);
+        let medium_node = node_id_for(&workflow, &medium_connector);
+        let high_node = node_id_for(&workflow, &high_connector);
+        workflow.add_link(
+            evaluation_id,
+            LinkAdapter::Evaluation,
+            None,
+            None,
+            Some(LinkTarget {
+                true_target_id: high_node.clone(),
+                false_target_id: low_node.clone(),
+                generated_target_id: None,
+                score_targets: vec![
+                    (0.0..=0.33, low_node.clone()),
+                    (0.34..=0.66, medium_node.clone()),
+                    (0.67..=1.0, high_node.clone()),
+                ],
+                loop_limits: HashMap::new(),
+            }),
+            None,
+            None,
+            None,
+        );
+
+        let history = workflow.execute(Some(1), false).await.unwrap();
+
+        assert!(low.requests().is_empty());
+        assert!(high.requests().is_empty());
+        assert_eq!(medium.requests().len(), 1);
+        let routed = history.iter().find(|entry| entry.branch.is_some()).unwrap();
+        assert_eq!(routed.branch.as_deref(), Some("score"));
+        assert_eq!(routed.target_id.as_deref(), Some(medium_node.as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_condition_loop_stops_after_max_iterations() {
+        let retried = spawn_mock_server(|_| (200, json!({"ok": false}).to_string())).await;
+        let finished = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &retried.url);
+        let fetch_connector = add_rest_connector(&mut nibble, "Fetch", &retried.url);
+        let publish_connector = add_rest_connector(&mut nibble, "Publish", &finished.url);
+        let condition_id = nibble
+            .add_condition(
+                "BeforeMidnight",
+                ConditionType::TimeBased {
+                    comparison_time: chrono::NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
+                    comparison_type: TimeComparisonType::Before,
+                },
+                |_| true,
+                None,
+                false,
+            )
+            .unwrap()
+            .adapter
+            .id
+            .clone();
+
+        let mut workflow = Arc::new(nibble).create_shared_workflow("RetryLoop", false);
+        workflow
+            .add_node(
+                fetch_connector.clone(),
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .add_node(
+                publish_connector.clone(),
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            );
+        let fetch_node = node_id_for(&workflow, &fetch_connector);
+        let publish_node = node_id_for(&workflow, &publish_connector);
+        let mut loop_limits = HashMap::new();
+        loop_limits.insert(fetch_node.clone(), 3);
+        workflow.add_link(
+            condition_id,
+            LinkAdapter::Condition,
+            None,
+            None,
+            Some(LinkTarget {
+                true_target_id: publish_node.clone(),
+                false_target_id: fetch_node.clone(),
+                generated_target_id: None,
+                score_targets: vec![],
+                loop_limits,
+            }),
+            None,
+            None,
+            None,
+        );
+
+        let history = workflow.execute(Some(1), false).await.unwrap();
+
+        assert_eq!(retried.requests().len(), 4);
+        assert!(finished.requests().is_empty());
+        let last = history.last().unwrap();
+        assert_eq!(last.description.as_deref(), Some("Loop limit reached"));
+        assert_eq!(last.target_id.as_deref(), Some(fetch_node.as_str()));
+        assert!(last.error.as_deref().unwrap().contains("3 iterations"));
+    }
+
+    #[tokio::test]
+    async fn test_cycle_between_plain_targets_is_rejected() {
+        let server = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &server.url);
+        let fetch_connector = add_rest_connector(&mut nibble, "Fetch", &server.url);
+        let publish_connector = add_rest_connector(&mut nibble, "Publish", &server.url);
+        let condition_id = nibble
+            .add_condition(
+                "AfterMidnight",
+                ConditionType::TimeBased {
+                    comparison_time: chrono::NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
+                    comparison_type: TimeComparisonType::After,
+                },
+                |_| true,
+                None,
+                false,
+            )
+            .unwrap()
+            .adapter
+            .id
+            .clone();
+
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Cycle", false);
+        for connector_id in [&fetch_connector, &publish_connector] {
+            workflow.add_node(
+                connector_id.clone(),
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            );
+        }
+        let fetch_node = node_id_for(&workflow, &fetch_connector);
+        let publish_node = node_id_for(&workflow, &publish_connector);
+        workflow.add_link(
+            condition_id.clone(),
+            LinkAdapter::Condition,
+            None,
+            None,
+            Some(LinkTarget {
+                true_target_id: fetch_node.clone(),
+                false_target_id: fetch_node.clone(),
+                generated_target_id: None,
+                score_targets: vec![],
+                loop_limits: HashMap::new(),
+            }),
+            None,
+            None,
+            None,
+        );
+        assert!(workflow.execute(Some(1), false).await.is_ok());
+        let requests = server.requests().len();
+
+        // Each link plain-targets the node the other link loops back through,
+        // so neither link can run before the other.
+        let first_link = workflow.links.keys().next().unwrap().clone();
+        let target = workflow
+            .links
+            .get_mut(&first_link)
+            .unwrap()
+            .target
+            .as_mut()
+            .unwrap();
+        target.false_target_id = publish_node.clone();
+        target.loop_limits = HashMap::from([(publish_node.clone(), 3)]);
+        workflow.add_link(
+            condition_id,
+            LinkAdapter::Condition,
+            None,
+            None,
+            Some(LinkTarget {
+                true_target_id: publish_node.clone(),
+                false_target_id: fetch_node.clone(),
+                generated_target_id: None,
+                score_targets: vec![],
+                loop_limits: HashMap::from([(fetch_node.clone(), 3)]),
+            }),
+            None,
+            None,
+            None,
+        );
+
+        let error = workflow.execute(Some(1), false).await.unwrap_err();
+        assert!(error.to_string().contains("Cyclic dependency"));
+        assert_eq!(server.requests().len(), requests);
+    }
+
+    #[tokio::test]
+    async fn test_execute_runs_inside_spawned_task() {
+        let server = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &server.url);
+        let connector_id = add_rest_connector(&mut nibble, "Spawned", &server.url);
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Spawned", false);
+        workflow.add_node(
+            connector_id,
+            NodeAdapter::OffChainConnector,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+
+        let history = tokio::spawn(async move { workflow.execute(Some(1), false).await })
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].result, Some(json!({"ok": true})));
+    }
+
+    #[tokio::test]
+    async fn test_tool_agent_dispatches_tool_calls_to_named_adapter() {
+        let llm = spawn_mock_server(|_| {
+            (
+                200,
+                json!({
+                    "choices": [{
+                        "message": {
+                            "role": "assistant",
+                            "content": "On it",
+                            "tool_calls": [{
+                                "id": "call_1",
+                                "type": "function",
+                                "function": {
+                                    "name": "PostMeme",
+                                    "arguments": "{\"caption\": \"gm\"}"
+                                }
+                            }]
+                        },
+                        "finish_reason": "tool_calls"
+                    }]
+                })
+                .to_string(),
+            )
+        })
+        .await;
+        let api = spawn_mock_server(|_| (200, json!({"posted": true}).to_string())).await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &api.url);
+        add_rest_connector(&mut nibble, "PostMeme", &api.url);
+        let agent_id = nibble
+            .add_agent(
+                "Poster",
+                "role",
+                "personality",
+                "system",
+                false,
+                false,
+                mock_openai_model(&llm.url, "gpt-4o"),
+                false,
+                None,
+                None,
+                None,
+                vec![],
+            )
+            .unwrap()
+            .adapter
+            .id
+            .clone();
+
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Tools", false);
+        workflow.add_node(
+            agent_id,
+            NodeAdapter::ToolAgent,
+            None,
+            Some(json!("Post a meme")),
+            None,
+            None,
+            None,
+        );
+
+        let history = workflow.execute(Some(1), false).await.unwrap();
+
+        let result = history[0].result.clone().unwrap();
+        assert_eq!(result["text"], "On it");
+        assert_eq!(result["tool_calls"][0]["name"], "PostMeme");
+        assert_eq!(result["tool_calls"][0]["result"], json!({"posted": true}));
+        let requests = api.requests();
+        assert_eq!(requests.len(), 1);
+        let body: Value = serde_json::from_str(&requests[0].body).unwrap();
+        assert_eq!(body, json!({"caption": "gm"}));
+    }
+
+    #[tokio::test]
+    async fn test_agent_consumes_upstream_context_over_node_context() {
+        let judge = spawn_mock_server(|_| {
+            (
+                200,
+                json!({"result": {"decision": true, "data": {"draft": "gm frens"}}}).to_string(),
+            )
+        })
+        .await;
+        let llm = spawn_mock_server(|_| {
+            (
+                200,
+                json!({"choices": [{"message": {"role": "assistant", "content": "wagmi"}}]})
+                    .to_string(),
+            )
+        })
+        .await;
+        let fallback = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &judge.url);
+        let fallback_connector = add_rest_connector(&mut nibble, "Fallback", &fallback.url);
+        let agent_id = nibble
+            .add_agent(
+                "Replier",
+                "role",
+                "personality",
+                "system",
+                false,
+                false,
+                mock_openai_model(&llm.url, "gpt-4o"),
+                false,
+                None,
+                None,
+                None,
+                vec![],
+            )
+            .unwrap()
+            .adapter
+            .id
+            .clone();
+        let evaluation_id = nibble
+            .add_evaluation(
+                "Draft",
+                EvaluationType::LLMJudge {
+                    model_type: mock_llm_model(&judge.url, "object"),
+                    prompt: "Draft a greeting".to_string(),
+                    response_type: EvaluationResponseType::BooleanWithData { expected: true },
+                },
+                false,
+            )
+            .unwrap()
+            .adapter
+            .id
+            .clone();
+
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Chain", false);
+        workflow
+            .add_node(
+                agent_id.clone(),
+                NodeAdapter::Agent,
+                None,
+                Some(json!("Hardcoded prompt")),
+                None,
+                None,
+                None,
+            )
+            .add_node(
+                fallback_connector.clone(),
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            );
+        let true_target_id = node_id_for(&workflow, &agent_id);
+        let false_target_id = node_id_for(&workflow, &fallback_connector);
+        workflow.add_link(
+            evaluation_id,
+            LinkAdapter::Evaluation,
+            None,
+            None,
+            Some(LinkTarget {
+                true_target_id,
+                false_target_id,
+                generated_target_id: None,
+                score_targets: vec![],
+                loop_limits: HashMap::new(),
+            }),
+            None,
+            None,
+            None,
+        );
+
+        workflow.execute(Some(1), false).await.unwrap();
+
+        let requests = llm.requests();
+        assert_eq!(requests.len(), 1);
+        let body: Value = serde_json::from_str(&requests[0].body).unwrap();
+        let prompt = body["messages"].as_array().unwrap().last().unwrap()["content"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert!(prompt.starts_with("Context:\n```json\n"));
+        let embedded = prompt
+            .trim_start_matches("Context:\n```json\n")
+            .trim_end_matches("\n```");
+        assert_eq!(
+            serde_json::from_str::<Value>(embedded).unwrap(),
+            json!({"draft": "gm frens"})
+        );
+        assert!(!requests[0].body.contains("Hardcoded prompt"));
+        assert!(fallback.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_runtime_budget_stops_unbounded_execution() {
+        let server = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &server.url);
+        let connector_id = add_rest_connector(&mut nibble, "Poll", &server.url);
+
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Budgeted", false);
+        workflow
+            .add_node(
+                connector_id,
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .set_runtime_budget(Some(Duration::from_millis(200)));
+
+        let history = timeout(Duration::from_secs(5), workflow.execute(None, false))
+            .await
+            .expect("runtime budget should end an unbounded execution")
+            .unwrap();
+
+        let marker = history.last().unwrap();
+        assert_eq!(marker.element_id, workflow.id);
+        assert_eq!(marker.element_type, "Workflow");
+        assert_eq!(
+            marker.description.as_deref(),
+            Some("Runtime budget exhausted")
+        );
+        assert!(history.len() > 1);
+        assert_eq!(server.requests().len(), history.len() - 1);
+    }
+
+    #[tokio::test]
+    async fn test_skip_node_policy_continues_past_failed_node() {
+        let failing = spawn_mock_server(|_| (500, "boom".to_string())).await;
+        let healthy = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &healthy.url);
+        let failing_connector = add_rest_connector(&mut nibble, "Failing", &failing.url);
+        let healthy_connector = add_rest_connector(&mut nibble, "Healthy", &healthy.url);
+
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Skipping", false);
+        workflow
+            .add_node(
+                failing_connector.clone(),
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .add_node(
+                healthy_connector,
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .set_error_policy(ErrorPolicy::SkipNode);
+        let failing_node = node_id_for(&workflow, &failing_connector);
+
+        let history = workflow.execute(Some(1), false).await.unwrap();
+
+        assert_eq!(failing.requests().len(), 1);
+        assert_eq!(healthy.requests().len(), 1);
+        let fired: Vec<_> = history
+            .iter()
+            .filter(|entry| entry.element_type == "ErrorPolicy")
+            .collect();
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].element_id, failing_node);
+        assert_eq!(fired[0].description.as_deref(), Some("SkipNode"));
+        assert!(fired[0].error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_route_to_error_node_policy_runs_handler_with_failure() {
+        let failing = spawn_mock_server(|_| (500, "boom".to_string())).await;
+        let handler = spawn_mock_server(|_| (200, json!({"handled": true}).to_string())).await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &handler.url);
+        let failing_connector = add_rest_connector(&mut nibble, "Failing", &failing.url);
+        let handler_connector = add_rest_connector(&mut nibble, "Handler", &handler.url);
+
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Routing", false);
+        workflow
+            .add_node(
+                failing_connector.clone(),
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .add_node(
+                handler_connector.clone(),
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            );
+        let failing_node = node_id_for(&workflow, &failing_connector);
+        let handler_node = node_id_for(&workflow, &handler_connector);
+        workflow.set_error_policy(ErrorPolicy::RouteToErrorNode(handler_node.clone()));
+
+        let history = workflow.execute(Some(1), false).await.unwrap();
+
+        let requests = handler.requests();
+        assert_eq!(requests.len(), 1);
+        let body: Value = serde_json::from_str(&requests[0].body).unwrap();
+        assert_eq!(body["element_id"], json!(failing_node));
+        assert!(body["error"].is_string());
+        let fired = history
+            .iter()
+            .find(|entry| entry.element_type == "ErrorPolicy")
+            .unwrap();
+        assert_eq!(fired.target_id.as_deref(), Some(handler_node.as_str()));
+        assert_eq!(
+            fired.description,
+            Some(format!("RouteToErrorNode({})", handler_node))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failures_record_underlying_error_in_history() {
+        let llm = spawn_mock_server(|_| (500, "model overloaded".to_string())).await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        let agent_id = nibble
+            .add_agent(
+                "Flaky",
+                "role",
+                "personality",
+                "system",
+                false,
+                false,
+                mock_llm_model(&llm.url, "string"),
+                false,
+                None,
+                None,
+                None,
+                vec![],
+            )
+            .unwrap()
+            .adapter
+            .id
+            .clone();
+
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Failures", false);
+        workflow.add_node(
+            agent_id,
+            NodeAdapter::Agent,
+            None,
+            Some(json!("gm")),
+            None,
+            None,
+            None,
+        );
+        let history = workflow.execute(Some(1), false).await.unwrap();
+        assert!(history[0].result.is_none());
+        assert!(history[0]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("model overloaded"));
+    }
+
+    #[tokio::test]
+    async fn test_missing_adapter_fails_before_any_node_runs() {
+        let server = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        let connector = add_rest_connector(&mut nibble, "Ping", &server.url);
+
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Missing", false);
+        workflow.add_node(
+            connector,
+            NodeAdapter::OffChainConnector,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        workflow.add_node(
+            "0xmissing".to_string(),
+            NodeAdapter::Agent,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        let error = workflow.execute(Some(1), false).await.unwrap_err();
+
+        assert!(matches!(error, NpcError::Validation(_)));
+        assert!(error.to_string().contains("0xmissing"));
+        assert!(server.requests().is_empty());
+        assert!(workflow.execution_history.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_disabled_node_is_skipped_and_greyed_in_dot() {
+        let enabled = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+        let disabled = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &enabled.url);
+        let enabled_connector = add_rest_connector(&mut nibble, "Enabled", &enabled.url);
+        let disabled_connector = add_rest_connector(&mut nibble, "Disabled", &disabled.url);
+
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Toggled", false);
+        workflow
+            .add_node(
+                enabled_connector.clone(),
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .add_node(
+                disabled_connector.clone(),
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            );
+        let enabled_node = node_id_for(&workflow, &enabled_connector);
+        let disabled_node = node_id_for(&workflow, &disabled_connector);
+        workflow.set_enabled(&disabled_node, false);
+
+        let history = workflow.execute(Some(1), false).await.unwrap();
+
+        assert_eq!(enabled.requests().len(), 1);
+        assert!(disabled.requests().is_empty());
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].element_id, enabled_node);
+
+        let dot = workflow.to_dot();
+        let line_for = |node_id: &str| {
+            dot.lines()
+                .find(|line| line.trim_start().starts_with(&format!("\"{}\"", node_id)))
+                .unwrap()
+                .to_string()
+        };
+        assert!(!line_for(&enabled_node).contains("lightgrey"));
+        assert!(line_for(&disabled_node).contains("fillcolor=lightgrey"));
+    }
+
+    #[tokio::test]
+    async fn test_join_node_receives_outputs_of_all_inbound_links() {
+        let likes = spawn_mock_server(|_| {
+            (
+                200,
+                json!({"result": {"decision": true, "data": {"likes": 42}}}).to_string(),
+            )
+        })
+        .await;
+        let mentions = spawn_mock_server(|_| {
+            (
+                200,
+                json!({"result": {"decision": true, "data": {"mentions": 7}}}).to_string(),
+            )
+        })
+        .await;
+        let report = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+        let ignored = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &report.url);
+        let report_connector = add_rest_connector(&mut nibble, "Report", &report.url);
+        let ignored_connector = add_rest_connector(&mut nibble, "Ignored", &ignored.url);
+        let mut evaluation_ids = Vec::new();
+        for (name, server) in [("Likes", &likes), ("Mentions", &mentions)] {
+            evaluation_ids.push(
+                nibble
+                    .add_evaluation(
+                        name,
+                        EvaluationType::LLMJudge {
+                            model_type: mock_llm_model(&server.url, "object"),
+                            prompt: format!("Count {}", name),
+                            response_type: EvaluationResponseType::BooleanWithData {
+                                expected: true,
+                            },
+                        },
+                        false,
+                    )
+                    .unwrap()
+                    .adapter
+                    .id
+                    .clone(),
+            );
+        }
+
+        let mut workflow = Arc::new(nibble).create_shared_workflow("FanIn", false);
+        workflow
+            .add_node(
+                report_connector.clone(),
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .add_node(
+                ignored_connector.clone(),
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            );
+        let report_node = node_id_for(&workflow, &report_connector);
+        let ignored_node = node_id_for(&workflow, &ignored_connector);
+        for evaluation_id in &evaluation_ids {
+            workflow.add_link(
+                evaluation_id.clone(),
+                LinkAdapter::Evaluation,
+                None,
+                None,
+                Some(LinkTarget {
+                    true_target_id: report_node.clone(),
+                    false_target_id: ignored_node.clone(),
+                    generated_target_id: None,
+                    score_targets: vec![],
+                    loop_limits: HashMap::new(),
+                }),
+                None,
+                None,
+                None,
+            );
+        }
+        let link_id_for = |evaluation_id: &str| {
+            workflow
+                .links
+                .values()
+                .find(|link| link.adapter_id == evaluation_id)
+                .map(|link| link.id.clone())
+                .unwrap()
+        };
+        let likes_link = link_id_for(&evaluation_ids[0]);
+        let mentions_link = link_id_for(&evaluation_ids[1]);
+
+        workflow.execute(Some(1), false).await.unwrap();
+
+        let requests = report.requests();
+        assert_eq!(requests.len(), 1);
+        let body: Value = serde_json::from_str(&requests[0].body).unwrap();
+        assert_eq!(
+            body,
+            json!({
+                likes_link: {"likes": 42},
+                mentions_link: {"mentions": 7},
+            })
+        );
+        assert!(ignored.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_execution_summary_aggregates_history() {
+        let failing = spawn_mock_server(|_| (500, "boom".to_string())).await;
+        let healthy = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &healthy.url);
+        let failing_connector = add_rest_connector(&mut nibble, "Failing", &failing.url);
+        let healthy_connector = add_rest_connector(&mut nibble, "Healthy", &healthy.url);
+
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Summary", false);
+        workflow
+            .add_node(
+                failing_connector,
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .add_node(
+                healthy_connector,
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .set_error_policy(ErrorPolicy::SkipNode);
+
+        let history = workflow.execute(Some(1), false).await.unwrap();
+        let summary = workflow.execution_summary();
+
+        assert_eq!(summary.total_elements, 3);
+        assert_eq!(
+            summary.by_adapter["OffChainConnector"],
+            AdapterOutcomes {
+                successes: 1,
+                failures: 1
+            }
+        );
+        assert_eq!(summary.by_adapter["ErrorPolicy"].failures, 1);
+        assert!(summary.total_duration.is_some());
+        assert!(history
+            .iter()
+            .filter(|entry| entry.element_type == "OffChainConnector")
+            .all(|entry| entry.duration.is_some()));
+        let slowest = summary.slowest_node.unwrap();
+        assert!(workflow.nodes.contains_key(&slowest.element_id));
+        assert_eq!(slowest.element_type, "OffChainConnector");
+    }
+
+    #[test]
+    fn test_named_node_helpers_resolve_adapter_ids() {
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        let connector_id = add_rest_connector(&mut nibble, "Poster", "http://127.0.0.1:1");
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Named", false);
+
+        workflow
+            .add_offchain_connector_node("Poster", None, None, None, None, None)
+            .unwrap();
+        let error = workflow
+            .add_agent_node("MemeMaster", None, None, None, None, None)
+            .err()
+            .unwrap();
+
+        assert_eq!(workflow.nodes.len(), 1);
+        assert_eq!(
+            workflow.nodes.values().next().unwrap().adapter_id,
+            connector_id
+        );
+        assert!(matches!(error, NpcError::Validation(_)));
+        assert!(error
+            .to_string()
+            .contains("No Agent adapter named MemeMaster"));
+    }
+
+    #[tokio::test]
+    async fn test_execute_while_repeats_until_predicate_fails() {
+        let server = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        let connector = add_rest_connector(&mut nibble, "Pages", &server.url);
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Pages", false);
+        workflow.add_node(
+            connector.clone(),
+            NodeAdapter::OffChainConnector,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        let node = node_id_for(&workflow, &connector);
+
+        workflow
+            .execute_while(
+                |history| {
+                    history
+                        .iter()
+                        .filter(|entry| entry.element_id == node)
+                        .count()
+                        < 3
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_execute_while_stops_at_max_iterations() {
+        let server = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        let connector = add_rest_connector(&mut nibble, "Pages", &server.url);
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Pages", false);
+        workflow.add_node(
+            connector,
+            NodeAdapter::OffChainConnector,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+
+        workflow.execute_while(|_| true, Some(2)).await.unwrap();
+
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_resume_skips_completed_elements() {
+        let first = spawn_mock_server(|_| (200, json!({"step": 1}).to_string())).await;
+        let second = spawn_mock_server(|_| (200, json!({"step": 2}).to_string())).await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        let first_connector = add_rest_connector(&mut nibble, "First", &first.url);
+        let second_connector = add_rest_connector(&mut nibble, "Second", &second.url);
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Resume", false);
+        for connector in [&first_connector, &second_connector] {
+            workflow.add_node(
+                connector.clone(),
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            );
+        }
+        let first_node = node_id_for(&workflow, &first_connector);
+        let second_node = node_id_for(&workflow, &second_connector);
+        let seeded = ExecutionHistory {
+            element_id: first_node.clone(),
+            element_type: "OffChainConnector".to_string(),
+            result: Some(json!({"step": 1})),
+            description: None,
+            timestamp: chrono::Utc::now(),
+            error: None,
+            branch: None,
+            target_id: None,
+            duration: None,
+        };
+        let failed = ExecutionHistory {
+            element_id: second_node.clone(),
+            result: None,
+            error: Some("connection reset".to_string()),
+            ..seeded.clone()
+        };
+
+        let history = workflow.resume(vec![seeded, failed]).await.unwrap();
+
+        assert!(first.requests().is_empty());
+        assert_eq!(second.requests().len(), 1);
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[2].element_id, second_node);
+        assert!(history[2].error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_independent_nodes_run_in_insertion_order() {
+        let server = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &server.url);
+        let connector_ids: Vec<String> = ["First", "Second", "Third", "Fourth"]
+            .iter()
+            .map(|name| add_rest_connector(&mut nibble, name, &server.url))
+            .collect();
+
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Ordering", false);
+        let mut node_ids = Vec::new();
+        for id in &connector_ids {
+            workflow.add_node(
+                id.clone(),
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            );
+            node_ids.push(node_id_for(&workflow, id));
+        }
+
+        let history = workflow.execute(Some(3), false).await.unwrap();
+
+        let order: Vec<String> = history
+            .iter()
+            .map(|entry| entry.element_id.clone())
+            .collect();
+        let expected: Vec<String> = node_ids
+            .iter()
+            .cycle()
+            .take(3 * node_ids.len())
+            .cloned()
+            .collect();
+        assert_eq!(order, expected);
+    }
+
+    #[tokio::test]
+    async fn test_link_runs_before_targets_added_earlier() {
+        let accepted = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+        let rejected = spawn_mock_server(|_| (200, json!({"ok": false}).to_string())).await;
+
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", &accepted.url);
+        let accept_connector = add_rest_connector(&mut nibble, "Accept", &accepted.url);
+        let reject_connector = add_rest_connector(&mut nibble, "Reject", &rejected.url);
+        let condition_id = nibble
+            .add_condition(
+                "AfterMidnight",
+                ConditionType::TimeBased {
+                    comparison_time: chrono::NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
+                    comparison_type: TimeComparisonType::After,
+                },
+                |_| true,
+                None,
+                false,
+            )
+            .unwrap()
+            .adapter
+            .id
+            .clone();
+
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Edges", false);
+        workflow
+            .add_node(
+                accept_connector.clone(),
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .add_node(
+                reject_connector.clone(),
+                NodeAdapter::OffChainConnector,
+                None,
+                None,
+                None,
+                None,
+                None,
+            );
+        let accept_node = node_id_for(&workflow, &accept_connector);
+        let reject_node = node_id_for(&workflow, &reject_connector);
+        workflow.add_link(
+            condition_id,
+            LinkAdapter::Condition,
+            None,
+            None,
+            Some(LinkTarget {
+                true_target_id: accept_node.clone(),
+                false_target_id: reject_node.clone(),
+                generated_target_id: None,
+                score_targets: vec![],
+                loop_limits: HashMap::new(),
+            }),
+            None,
+            None,
+            None,
+        );
+        let link_id = workflow.links.keys().next().unwrap().clone();
+
+        let history = workflow.execute(Some(1), false).await.unwrap();
+
+        assert_eq!(history[0].element_id, link_id);
+        assert!(history
+            .iter()
+            .all(|entry| entry.element_id == link_id || entry.element_id == accept_node));
+        assert_eq!(accepted.requests().len(), 1);
+        assert!(rejected.requests().is_empty());
+    }
+}
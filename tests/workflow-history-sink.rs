@@ -0,0 +1,94 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{add_rest_connector, node_id_for, offline_nibble, spawn_mock_server};
+    use async_trait::async_trait;
+    use npc_workbench::workflow::{ExecutionHistory, FileHistorySink, HistorySink, NodeAdapter};
+    use serde_json::{json, Value};
+    use std::{
+        error::Error,
+        sync::{Arc, Mutex},
+    };
+
+    #[derive(Default)]
+    struct RecordingSink {
+        batches: Mutex<Vec<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl HistorySink for RecordingSink {
+        async fn append(
+            &self,
+            _workflow_id: &str,
+            entries: &[ExecutionHistory],
+        ) -> Result<(), Box<dyn Error + Send + Sync>> {
+            self.batches.lock().unwrap().push(
+                entries
+                    .iter()
+                    .map(|entry| entry.element_id.clone())
+                    .collect(),
+            );
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_history_sink_receives_each_entry_once() {
+        let server = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        let connector = add_rest_connector(&mut nibble, "Ping", &server.url);
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Ping", false);
+        workflow.add_node(
+            connector.clone(),
+            NodeAdapter::OffChainConnector,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        let node = node_id_for(&workflow, &connector);
+        let sink = Arc::new(RecordingSink::default());
+        workflow.set_history_sink(Some(sink.clone()));
+
+        let history = workflow.execute(Some(2), false).await.unwrap();
+
+        let batches = sink.batches.lock().unwrap();
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches.concat().len(), history.len());
+        assert!(batches.iter().all(|batch| batch.contains(&node)));
+    }
+
+    #[tokio::test]
+    async fn test_file_history_sink_appends_json_lines() {
+        let server = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        let connector = add_rest_connector(&mut nibble, "Ping", &server.url);
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Ping", false);
+        workflow.add_node(
+            connector,
+            NodeAdapter::OffChainConnector,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        let path = std::env::temp_dir().join(format!("npc-history-{}.jsonl", workflow.id));
+        workflow.set_history_sink(Some(Arc::new(FileHistorySink::new(&path))));
+
+        workflow.execute(Some(2), false).await.unwrap();
+
+        let lines: Vec<Value> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(lines.len(), workflow.execution_history.len());
+        assert!(lines
+            .iter()
+            .all(|line| line["workflow_id"] == json!(workflow.id)));
+    }
+}
@@ -0,0 +1,47 @@
+mod common;
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use crate::common::{add_rest_connector, offline_nibble, spawn_mock_server};
+    use npc_workbench::{metrics, workflow::NodeAdapter};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_execute_counts_workflows_and_node_failures() {
+        let server = spawn_mock_server(|_| (500, "boom".to_string())).await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        let connector = add_rest_connector(&mut nibble, "Broken", &server.url);
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Broken", false);
+        workflow.add_node(
+            connector,
+            NodeAdapter::OffChainConnector,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        let executed = metrics::metrics().workflows_executed_total.get();
+        let failures = metrics::metrics()
+            .node_failures_total
+            .with_label_values(&["OffChainConnector"])
+            .get();
+
+        workflow.execute(Some(1), false).await.unwrap();
+
+        assert_eq!(
+            metrics::metrics().workflows_executed_total.get(),
+            executed + 1
+        );
+        assert_eq!(
+            metrics::metrics()
+                .node_failures_total
+                .with_label_values(&["OffChainConnector"])
+                .get(),
+            failures + 1
+        );
+        let rendered = metrics::render().unwrap();
+        assert!(rendered.contains("workflows_executed_total"));
+        assert!(rendered.contains("node_failures_total{adapter_type=\"OffChainConnector\"}"));
+    }
+}
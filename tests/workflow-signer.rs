@@ -0,0 +1,302 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{
+        mock_llm_model, node_id_for, offline_nibble, spawn_mock_server, MockServer,
+    };
+    use ethers::{
+        abi::Abi,
+        signers::{LocalWallet, Signer},
+        types::{transaction::eip2718::TypedTransaction, Address, Chain, U256},
+        utils::{hex, rlp::Rlp},
+    };
+    use npc_workbench::{
+        adapters::nodes::connectors::on_chain::{BatchMode, GasOptions, OnChainTransaction},
+        nibble::{Nibble, NonceManager},
+        workflow::{NodeAdapter, Workflow},
+    };
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+
+    fn ping_abi() -> Abi {
+        serde_json::from_value(json!([{
+            "type": "function",
+            "name": "ping",
+            "inputs": [],
+            "outputs": [],
+            "stateMutability": "nonpayable"
+        }]))
+        .unwrap()
+    }
+
+    async fn nonce_rpc() -> MockServer {
+        spawn_mock_server(|request| {
+            let rpc: Value = serde_json::from_str(&request.body).unwrap();
+            (
+                200,
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": rpc["id"],
+                    "error": {"code": -32000, "message": "offline"}
+                })
+                .to_string(),
+            )
+        })
+        .await
+    }
+
+    fn ping_workflow(nibble: &mut Nibble) -> Workflow {
+        let connector = nibble
+            .add_onchain_connector(
+                "Ping",
+                Some(Address::repeat_byte(0x11)),
+                false,
+                None,
+                Some(ping_abi()),
+                Chain::PolygonAmoy,
+                Some(GasOptions {
+                    max_fee_per_gas: Some(U256::from(44u64)),
+                    max_priority_fee_per_gas: Some(U256::from(25u64)),
+                    gas_limit: Some(U256::from(100_000)),
+                    nonce: None,
+                }),
+                vec![],
+                BatchMode::SequentialAbort,
+            )
+            .unwrap()
+            .adapter
+            .id
+            .clone();
+        let mut workflow = Arc::new(nibble.clone()).create_shared_workflow("Signer", false);
+        workflow.add_node(
+            connector,
+            NodeAdapter::OnChainConnector,
+            None,
+            Some(json!({"method_name": "ping"})),
+            None,
+            None,
+            None,
+        );
+        workflow
+    }
+
+    fn signing_addresses(rpc: &MockServer) -> Vec<Value> {
+        rpc.requests()
+            .iter()
+            .map(|request| serde_json::from_str::<Value>(&request.body).unwrap())
+            .filter(|body| body["method"] == "eth_getTransactionCount")
+            .map(|body| body["params"][0].clone())
+            .collect()
+    }
+
+    fn address_json(wallet: &LocalWallet) -> Value {
+        json!(format!("{:?}", wallet.address()))
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_signer_signs_with_chosen_wallet() {
+        let rpc = nonce_rpc().await;
+        let mut nibble = offline_nibble(&rpc.url, "http://127.0.0.1:5001");
+        let owner = nibble.owner_wallet.clone();
+        let mut workflow = ping_workflow(&mut nibble);
+        let signer = LocalWallet::new(&mut rand::thread_rng());
+
+        workflow
+            .execute_with_signer(signer.clone(), Some(1), false)
+            .await
+            .unwrap();
+        workflow.execute(Some(1), false).await.unwrap();
+
+        assert_eq!(
+            signing_addresses(&rpc),
+            vec![address_json(&signer), address_json(&owner)]
+        );
+        assert!(workflow.signer.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_agent_wallet_context_resolves_agent_node() {
+        let rpc = nonce_rpc().await;
+        let mut nibble = offline_nibble(&rpc.url, "http://127.0.0.1:5001");
+        let agent = nibble
+            .add_agent(
+                "Treasurer",
+                "role",
+                "personality",
+                "system",
+                false,
+                false,
+                mock_llm_model("http://127.0.0.1:1", "string"),
+                false,
+                None,
+                None,
+                None,
+                vec![],
+            )
+            .unwrap()
+            .adapter
+            .clone();
+        let mut workflow = ping_workflow(&mut nibble);
+        workflow.add_node(
+            agent.id.clone(),
+            NodeAdapter::Agent,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        let agent_node = node_id_for(&workflow, &agent.id);
+        let connector_node = workflow
+            .nodes
+            .values()
+            .find(|node| matches!(node.adapter_type, NodeAdapter::OnChainConnector))
+            .unwrap()
+            .id
+            .clone();
+        workflow.nodes.get_mut(&connector_node).unwrap().context = Some(json!({
+            "agent_wallet": agent_node,
+            "method_name": "ping"
+        }));
+        workflow.set_enabled(&agent_node, false);
+
+        workflow.execute(Some(1), false).await.unwrap();
+
+        assert_eq!(signing_addresses(&rpc), vec![address_json(&agent.wallet)]);
+    }
+
+    #[tokio::test]
+    async fn test_execute_keeps_nonces_of_other_addresses() {
+        let rpc = spawn_mock_server(|request| {
+            let rpc: Value = serde_json::from_str(&request.body).unwrap();
+            let mut reply = match rpc["method"].as_str() {
+                Some("eth_getTransactionCount") => json!({"result": "0x5"}),
+                _ => json!({"error": {"code": -32000, "message": "offline"}}),
+            };
+            reply["jsonrpc"] = json!("2.0");
+            reply["id"] = rpc["id"].clone();
+            (200, reply.to_string())
+        })
+        .await;
+        let mut nibble = offline_nibble(&rpc.url, "http://127.0.0.1:5001");
+        let other = Address::repeat_byte(0x44);
+        let mut workflow = ping_workflow(&mut nibble);
+
+        assert_eq!(
+            nibble
+                .nonce_manager
+                .next_nonce(&nibble.provider, other)
+                .await
+                .unwrap(),
+            U256::from(5)
+        );
+        workflow.execute(Some(1), false).await.unwrap();
+
+        assert_eq!(
+            nibble
+                .nonce_manager
+                .next_nonce(&nibble.provider, other)
+                .await
+                .unwrap(),
+            U256::from(6)
+        );
+        assert_eq!(
+            signing_addresses(&rpc),
+            vec![
+                json!(format!("{:?}", other)),
+                address_json(&nibble.owner_wallet)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fixed_nonce_increments_across_sequential_batch() {
+        let rpc = nonce_rpc().await;
+        let mut nibble = offline_nibble(&rpc.url, "http://127.0.0.1:5001");
+        let ping = OnChainTransaction {
+            method_name: "ping".to_string(),
+            params: vec![],
+            value: None,
+        };
+        let connector = nibble
+            .add_onchain_connector(
+                "Pings",
+                Some(Address::repeat_byte(0x11)),
+                false,
+                None,
+                Some(ping_abi()),
+                Chain::PolygonAmoy,
+                Some(GasOptions {
+                    max_fee_per_gas: Some(U256::from(44u64)),
+                    max_priority_fee_per_gas: Some(U256::from(25u64)),
+                    gas_limit: Some(U256::from(100_000)),
+                    nonce: Some(U256::from(7)),
+                }),
+                vec![ping.clone(), ping],
+                BatchMode::SequentialContinue,
+            )
+            .unwrap()
+            .adapter
+            .clone();
+
+        connector
+            .execute_onchain_connector(
+                nibble.provider.clone(),
+                nibble.owner_wallet.clone(),
+                None,
+                None,
+                &NonceManager::default(),
+                &nibble.fee_mode,
+                1,
+            )
+            .await
+            .unwrap();
+
+        let nonces: Vec<U256> = rpc
+            .requests()
+            .iter()
+            .map(|request| serde_json::from_str::<Value>(&request.body).unwrap())
+            .filter(|body| body["method"] == "eth_sendRawTransaction")
+            .map(|body| {
+                let raw = body["params"][0].as_str().unwrap().to_string();
+                let (tx, _) =
+                    TypedTransaction::decode_signed(&Rlp::new(&hex::decode(&raw[2..]).unwrap()))
+                        .unwrap();
+                *tx.nonce().unwrap()
+            })
+            .collect();
+        assert_eq!(nonces, vec![U256::from(7), U256::from(8)]);
+    }
+
+    #[test]
+    fn test_all_or_nothing_rejects_caller_dependent_methods() {
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        let transfer = OnChainTransaction {
+            method_name: "transfer".to_string(),
+            params: vec![
+                json!("0x1111111111111111111111111111111111111111"),
+                json!("1"),
+            ],
+            value: None,
+        };
+
+        let error = nibble
+            .add_onchain_connector(
+                "Transfers",
+                Some(Address::repeat_byte(0x11)),
+                false,
+                None,
+                Some(ping_abi()),
+                Chain::PolygonAmoy,
+                None,
+                vec![transfer],
+                BatchMode::AllOrNothing,
+            )
+            .err()
+            .unwrap();
+
+        assert!(error.to_string().contains("msg.sender"));
+        assert!(nibble.onchain_connectors.is_empty());
+    }
+}
@@ -0,0 +1,137 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{add_rest_connector, node_id_for, offline_nibble, spawn_mock_server};
+    use npc_workbench::workflow::{NodeAdapter, SubflowManager, SubflowStatus, Workflow};
+    use serde_json::json;
+    use std::sync::Arc;
+    use tokio::{
+        sync::{mpsc, Mutex},
+        time::{sleep, timeout, Duration},
+    };
+
+    fn subflow() -> Arc<Mutex<Workflow>> {
+        let nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        Arc::new(Mutex::new(
+            Arc::new(nibble).create_shared_workflow("Auth", false),
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_blocking_subflow_status_is_completed() {
+        let manager = SubflowManager::new();
+        let subflow = subflow();
+        let id = subflow.lock().await.id.clone();
+
+        assert_eq!(manager.status(&id), None);
+
+        let result = manager
+            .execute_subflow(subflow, Some(1), false, true, None)
+            .await;
+
+        assert!(matches!(result, Some(Ok(_))));
+        assert_eq!(manager.status(&id), Some(SubflowStatus::Completed));
+        assert!(manager.list_active().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_non_blocking_subflow_is_listed_until_it_finishes() {
+        let manager = SubflowManager::new();
+        let subflow = subflow();
+        let id = subflow.lock().await.id.clone();
+        let (report_sender, mut report_receiver) = mpsc::channel(1);
+
+        manager
+            .execute_subflow(subflow.clone(), Some(1), false, false, Some(report_sender))
+            .await;
+        let guard = subflow.lock().await;
+        sleep(Duration::from_millis(20)).await;
+
+        assert_eq!(manager.status(&id), Some(SubflowStatus::Queued));
+        assert_eq!(manager.list_active(), vec![id.clone()]);
+
+        drop(guard);
+        timeout(Duration::from_secs(5), report_receiver.recv())
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(manager.status(&id), Some(SubflowStatus::Completed));
+        assert!(manager.list_active().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_non_blocking_subflow_merges_every_repetition_into_history() {
+        let server = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        let connector = add_rest_connector(&mut nibble, "Ping", &server.url);
+        let mut subflow = Arc::new(nibble.clone()).create_shared_workflow("Ping", false);
+        subflow.add_node(
+            connector.clone(),
+            NodeAdapter::OffChainConnector,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        let ping_node = node_id_for(&subflow, &connector);
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Parent", false);
+        workflow.add_node(
+            "0xf2".to_string(),
+            NodeAdapter::SubFlow {
+                subflow: Box::new(subflow),
+                blocking: false,
+                repetitions: Some(3),
+                count_successes: false,
+            },
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+
+        let history = workflow.execute(Some(1), false).await.unwrap();
+
+        assert_eq!(server.requests().len(), 3);
+        assert_eq!(
+            history
+                .iter()
+                .filter(|entry| entry.element_id == ping_node)
+                .count(),
+            3
+        );
+        assert!(history
+            .iter()
+            .any(|entry| entry.element_type == "Subflow" && entry.error.is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_stops_non_blocking_subflow() {
+        let manager = SubflowManager::new();
+        let subflow = subflow();
+        let id = subflow.lock().await.id.clone();
+        let (report_sender, mut report_receiver) = mpsc::channel(1);
+
+        manager
+            .execute_subflow(subflow.clone(), Some(1), false, false, Some(report_sender))
+            .await;
+        let guard = subflow.lock().await;
+        sleep(Duration::from_millis(20)).await;
+
+        assert!(manager.cancel(&id));
+        assert_eq!(manager.status(&id), Some(SubflowStatus::Cancelled));
+        assert!(manager.list_active().is_empty());
+
+        drop(guard);
+        let report = timeout(Duration::from_secs(5), report_receiver.recv())
+            .await
+            .unwrap();
+
+        assert!(report.is_none());
+        assert_eq!(manager.status(&id), Some(SubflowStatus::Cancelled));
+        assert!(!manager.cancel(&id));
+    }
+}
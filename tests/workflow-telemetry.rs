@@ -0,0 +1,124 @@
+mod common;
+
+#[cfg(all(test, feature = "otel"))]
+mod tests {
+    use crate::common::{add_rest_connector, offline_nibble, spawn_mock_server};
+    use npc_workbench::workflow::NodeAdapter;
+    use serde_json::json;
+    use std::{
+        collections::HashMap,
+        fmt::Debug,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, Mutex,
+        },
+    };
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+        Event, Metadata, Subscriber,
+    };
+
+    #[derive(Debug, Clone)]
+    struct RecordedSpan {
+        name: String,
+        parent: Option<u64>,
+        fields: HashMap<String, String>,
+    }
+
+    struct FieldVisitor<'a>(&'a mut HashMap<String, String>);
+
+    impl Visit for FieldVisitor<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+
+    #[derive(Default, Clone)]
+    struct RecordingSubscriber {
+        next_id: Arc<AtomicU64>,
+        spans: Arc<Mutex<HashMap<u64, RecordedSpan>>>,
+        stack: Arc<Mutex<Vec<u64>>>,
+    }
+
+    impl Subscriber for RecordingSubscriber {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, attributes: &Attributes<'_>) -> Id {
+            let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+            let parent = match attributes.parent() {
+                Some(parent) => Some(parent.into_u64()),
+                None if attributes.is_contextual() => self.stack.lock().unwrap().last().copied(),
+                None => None,
+            };
+            let mut fields = HashMap::new();
+            attributes.record(&mut FieldVisitor(&mut fields));
+            self.spans.lock().unwrap().insert(
+                id,
+                RecordedSpan {
+                    name: attributes.metadata().name().to_string(),
+                    parent,
+                    fields,
+                },
+            );
+            Id::from_u64(id)
+        }
+
+        fn record(&self, span: &Id, values: &Record<'_>) {
+            if let Some(recorded) = self.spans.lock().unwrap().get_mut(&span.into_u64()) {
+                values.record(&mut FieldVisitor(&mut recorded.fields));
+            }
+        }
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, _event: &Event<'_>) {}
+
+        fn enter(&self, span: &Id) {
+            self.stack.lock().unwrap().push(span.into_u64());
+        }
+
+        fn exit(&self, span: &Id) {
+            let mut stack = self.stack.lock().unwrap();
+            if let Some(position) = stack.iter().rposition(|id| *id == span.into_u64()) {
+                stack.remove(position);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_emits_workflow_and_node_spans() {
+        let subscriber = RecordingSubscriber::default();
+        let _guard = tracing::subscriber::set_default(subscriber.clone());
+        let server = spawn_mock_server(|_| (200, json!({"ok": true}).to_string())).await;
+        let mut nibble = offline_nibble("http://127.0.0.1:8545", "http://127.0.0.1:5001");
+        let connector = add_rest_connector(&mut nibble, "Ping", &server.url);
+        let mut workflow = Arc::new(nibble).create_shared_workflow("Traced", false);
+        workflow.add_node(
+            connector,
+            NodeAdapter::OffChainConnector,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+
+        workflow.execute(Some(1), false).await.unwrap();
+
+        let spans = subscriber.spans.lock().unwrap();
+        let (workflow_span, _) = spans
+            .iter()
+            .find(|(_, span)| span.name == "workflow")
+            .unwrap();
+        let node_span = spans.values().find(|span| span.name == "element").unwrap();
+        assert_eq!(node_span.parent, Some(*workflow_span));
+        assert_eq!(node_span.fields["adapter_type"], "OffChainConnector");
+        assert_eq!(node_span.fields["element.kind"], "node");
+        assert_eq!(node_span.fields["success"], "true");
+        assert!(node_span.fields.contains_key("duration_ms"));
+    }
+}